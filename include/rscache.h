@@ -0,0 +1,49 @@
+/*
+ * C API of rs-cache, available when the crate is built with the `ffi`
+ * feature:
+ *
+ *     cargo rustc --release --features ffi --crate-type cdylib
+ *
+ * See the `ffi` module of the crate for the documentation of every function.
+ */
+
+#ifndef RSCACHE_H
+#define RSCACHE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returned when an archive could not be read. */
+#define RSCACHE_ERROR (-1)
+/* Returned when reading an archive panicked. */
+#define RSCACHE_PANIC (-2)
+
+/* An opened cache, only ever used through a pointer. */
+typedef struct Cache Cache;
+
+/* Opens the cache in `path`, returns NULL on failure. */
+Cache *rscache_open(const char *path);
+
+/*
+ * Reads the encoded archive into `buf` and returns its length, or one of the
+ * error codes above. Nothing is copied when `buf_len` is too small.
+ */
+int64_t rscache_read(const Cache *cache, uint8_t index_id, uint32_t archive_id,
+                     uint8_t *buf, size_t buf_len);
+
+/* Same as `rscache_read` but the archive is decompressed first. */
+int64_t rscache_read_decoded(const Cache *cache, uint8_t index_id,
+                             uint32_t archive_id, uint8_t *buf, size_t buf_len);
+
+/* Releases a cache opened with `rscache_open`, NULL is ignored. */
+void rscache_free(Cache *cache);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RSCACHE_H */
@@ -0,0 +1,167 @@
+//! Archives that hold more than one file.
+//!
+//! The files of a group are stored in chunks, every chunk holds a part of
+//! every file. The sizes of the parts trail the archive, followed by a single
+//! byte with the amount of chunks.
+
+use std::vec;
+
+use crate::{definition::ArchiveFileData, error::ArchiveGroupError};
+
+/// The files of a single archive, in the order of the reference table.
+///
+/// A fallible version of [`runefs::ArchiveFileGroup`], malformed archives
+/// return an error instead of panicking.
+///
+/// # Examples
+///
+/// ```
+/// use rscache::archive::ArchiveFileGroup;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// // Two files of 2 and 1 bytes in a single chunk.
+/// let buffer = [1, 2, 3, 0, 0, 0, 2, 255, 255, 255, 255, 1];
+/// let group = ArchiveFileGroup::try_from_buffer(&buffer, 2)?;
+///
+/// assert_eq!(group.files()[0].data, [1, 2]);
+/// assert_eq!(group.files()[1].data, [3]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveFileGroup(Vec<ArchiveFileData>);
+
+impl ArchiveFileGroup {
+    /// Creates a group from its files, which are encoded in the given order.
+    #[inline]
+    pub fn new(files: Vec<ArchiveFileData>) -> Self {
+        Self(files)
+    }
+
+    /// Splits a decoded archive buffer into `entry_count` files.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidChunkCount` if the archive is too short for the chunk
+    /// sizes it says it has and `OutOfRange` if a chunk size points outside of
+    /// the archive.
+    pub fn try_from_buffer(buffer: &[u8], entry_count: usize) -> crate::Result<Self> {
+        if entry_count <= 1 {
+            return Ok(Self(vec![ArchiveFileData {
+                id: 0,
+                data: buffer.to_vec(),
+            }]));
+        }
+
+        let len = buffer.len();
+        let chunks = buffer.last().copied().unwrap_or_default() as usize;
+        let table_len = chunks * entry_count * 4;
+        let invalid_chunk_count = ArchiveGroupError::InvalidChunkCount {
+            chunks,
+            entry_count,
+            len,
+        };
+        if chunks == 0 || table_len + 1 > len {
+            return Err(invalid_chunk_count.into());
+        }
+
+        let data_len = len - 1 - table_len;
+        let table = &buffer[data_len..len - 1];
+        let mut sizes = table
+            .chunks_exact(4)
+            .map(|size| i32::from_be_bytes([size[0], size[1], size[2], size[3]]));
+
+        let mut files: Vec<ArchiveFileData> = (0..entry_count as u32)
+            .map(|id| ArchiveFileData {
+                id,
+                data: Vec::new(),
+            })
+            .collect();
+        let mut read_ptr = 0_usize;
+        for _ in 0..chunks {
+            let mut chunk_size = 0_i64;
+            for file in &mut files {
+                chunk_size += sizes.next().unwrap_or_default() as i64;
+
+                let end = read_ptr as i64 + chunk_size;
+                if chunk_size < 0 || end > data_len as i64 {
+                    return Err(ArchiveGroupError::OutOfRange {
+                        offset: read_ptr,
+                        size: chunk_size,
+                        len: data_len,
+                    }
+                    .into());
+                }
+
+                let end = end as usize;
+                file.data.extend_from_slice(&buffer[read_ptr..end]);
+                read_ptr = end;
+            }
+        }
+
+        Ok(Self(files))
+    }
+
+    /// The files of the archive.
+    #[inline]
+    pub fn files(&self) -> &[ArchiveFileData] {
+        &self.0
+    }
+
+    /// Encodes the files into a decoded archive buffer, the inverse of
+    /// [`try_from_buffer`](ArchiveFileGroup::try_from_buffer).
+    ///
+    /// All files are stored in a single chunk. A group with a single file is
+    /// stored as just that file, without any chunk sizes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::{archive::ArchiveFileGroup, definition::ArchiveFileData};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let group = ArchiveFileGroup::new(vec![
+    ///     ArchiveFileData { id: 0, data: vec![1, 2] },
+    ///     ArchiveFileData { id: 1, data: vec![3] },
+    /// ]);
+    /// let buffer = group.encode();
+    ///
+    /// assert_eq!(buffer, [1, 2, 3, 0, 0, 0, 2, 255, 255, 255, 255, 1]);
+    /// let decoded = ArchiveFileGroup::try_from_buffer(&buffer, 2)?;
+    /// assert_eq!(decoded.files()[1].data, [3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        if let [file] = self.0.as_slice() {
+            return file.data.clone();
+        }
+
+        let len = self.0.iter().map(|file| file.data.len() + 4).sum::<usize>();
+        let mut buffer = Vec::with_capacity(len + 1);
+        for file in &self.0 {
+            buffer.extend(&file.data);
+        }
+
+        // The sizes are stored as the difference with the previous file.
+        let mut last = 0_i32;
+        for file in &self.0 {
+            let size = file.data.len() as i32;
+            buffer.extend(size.wrapping_sub(last).to_be_bytes());
+            last = size;
+        }
+        buffer.push(1);
+
+        buffer
+    }
+}
+
+impl IntoIterator for ArchiveFileGroup {
+    type Item = ArchiveFileData;
+    type IntoIter = vec::IntoIter<ArchiveFileData>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
@@ -0,0 +1,154 @@
+//! Command line companion of the library, built with the `cli` feature flag.
+//!
+//! ```text
+//! cargo run --features cli --bin rscache-cli -- ./data/osrs_cache info
+//! ```
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use rscache::{codec::EncodedExt, definition::osrs::ItemDefinition, Cache};
+
+const USAGE: &str = "\
+usage: rscache-cli <cache> <command> [args]
+
+commands:
+    info                            protocol and size of every index
+    read <index> <archive> [flags]  write an archive to stdout
+        --decode                    decode the archive first
+        --output <file>             write to a file instead
+    dump <dir>                      dump every archive into a directory
+    item <id>                       print an OSRS item definition as JSON
+    verify                          check every archive against its crc";
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            let mut source = err.source();
+            while let Some(err) = source {
+                eprintln!("  caused by: {}", err);
+                source = err.source();
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<ExitCode> {
+    let (path, command, args) = match args {
+        [path, command, args @ ..] => (path, command.as_str(), args),
+        _ => return Err(format!("missing command\n\n{}", USAGE).into()),
+    };
+    let cache = Cache::new(path)?;
+
+    match (command, args) {
+        ("info", []) => info(&cache)?,
+        ("read", [index_id, archive_id, flags @ ..]) => {
+            read(&cache, index_id.parse()?, archive_id.parse()?, flags)?
+        }
+        ("dump", [dir]) => dump(&cache, PathBuf::from(dir))?,
+        ("item", [id]) => item(&cache, id.parse()?)?,
+        ("verify", []) => return Ok(verify(&cache)),
+        _ => return Err(format!("invalid command `{}`\n\n{}", command, USAGE).into()),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn info(cache: &Cache) -> Result<()> {
+    let stats = cache.stats()?;
+
+    println!("protocol: {}", cache.protocol());
+    println!("{:>5} {:>9} {:>12}", "index", "archives", "bytes");
+    for index in &stats {
+        println!(
+            "{:>5} {:>9} {:>12}",
+            index.index_id, index.archive_count, index.total_bytes
+        );
+    }
+    println!("total: {} bytes", stats.total_bytes());
+
+    Ok(())
+}
+
+fn read(cache: &Cache, index_id: u8, archive_id: u32, flags: &[String]) -> Result<()> {
+    let mut decode = false;
+    let mut output = None;
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--decode" => decode = true,
+            "--output" => output = Some(flags.next().ok_or("--output needs a file")?),
+            _ => return Err(format!("unknown flag `{}`", flag).into()),
+        }
+    }
+
+    let buffer = cache.read(index_id, archive_id)?;
+    let buffer = if decode {
+        buffer.decode_ref()?.to_vec()
+    } else {
+        buffer.to_vec()
+    };
+
+    match output {
+        Some(file) => fs::write(file, buffer)?,
+        None => io::stdout().lock().write_all(&buffer)?,
+    }
+
+    Ok(())
+}
+
+fn dump(cache: &Cache, dir: PathBuf) -> Result<()> {
+    let skipped = cache.dump_all(&dir, |progress| {
+        if progress.dumped % 1000 == 0 || progress.dumped == progress.total {
+            eprintln!("{}/{}", progress.dumped, progress.total);
+        }
+    })?;
+    for archive in skipped {
+        eprintln!(
+            "skipped {}/{}: {}",
+            archive.index_id, archive.archive_id, archive.error
+        );
+    }
+
+    Ok(())
+}
+
+fn item(cache: &Cache, id: u32) -> Result<()> {
+    let item = ItemDefinition::fetch(cache, id)?.ok_or(format!("item {} not found", id))?;
+    println!("{}", serde_json::to_string_pretty(&item)?);
+
+    Ok(())
+}
+
+fn verify(cache: &Cache) -> ExitCode {
+    let report = cache.verify();
+    for issue in report.issues() {
+        println!("{}/{}: {}", issue.index_id, issue.archive_id, issue.kind);
+    }
+    println!(
+        "{} archives checked, {} issues",
+        report.archives_checked(),
+        report.issues().len()
+    );
+
+    if report.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,148 @@
+//! Configurable construction of a [`Cache`].
+
+use std::{path::Path, sync::OnceLock};
+
+use crate::{
+    observer::{CacheObserver, Observer},
+    util::xtea::XteaKeyStore,
+    Backend, Cache, Protocol,
+};
+
+/// Builds a [`Cache`] with options that go beyond [`Cache::new`].
+///
+/// Created with [`Cache::builder`].
+///
+/// # Examples
+///
+/// ```
+/// use rscache::{Backend, Cache, Protocol};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// let cache = Cache::builder()
+///     .backend(Backend::Heap)
+///     .decoded_cache(64 * 1024 * 1024)
+///     .lazy_metadata(false)
+///     .protocol(Protocol::Osrs)
+///     .open("./data/osrs_cache")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CacheBuilder {
+    backend: Backend,
+    decoded_cache: Option<usize>,
+    lazy_metadata: bool,
+    observer: Observer,
+    xtea_keys: XteaKeyStore,
+    protocol: Option<Protocol>,
+}
+
+impl Default for CacheBuilder {
+    fn default() -> Self {
+        Self {
+            backend: Backend::default(),
+            decoded_cache: None,
+            lazy_metadata: true,
+            observer: Observer::default(),
+            xtea_keys: XteaKeyStore::new(),
+            protocol: None,
+        }
+    }
+}
+
+impl CacheBuilder {
+    /// Creates a builder with the same options as [`Cache::new`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How the data files are brought into memory, see [`Backend`].
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Keeps recently decoded archives in memory, up to `bytes` in total. See
+    /// [`Cache::with_decoded_cache`].
+    pub fn decoded_cache(mut self, bytes: usize) -> Self {
+        self.decoded_cache = Some(bytes);
+        self
+    }
+
+    /// Only decode reference tables when they are first needed, enabled by
+    /// default.
+    ///
+    /// Disabling it decodes every reference table up front, so a corrupt table
+    /// is reported when the cache is opened instead of on first use.
+    pub fn lazy_metadata(mut self, lazy: bool) -> Self {
+        self.lazy_metadata = lazy;
+        self
+    }
+
+    /// Reports every read and decoded archive to `observer`, see
+    /// [`Cache::with_observer`].
+    pub fn observer<O: CacheObserver + 'static>(mut self, observer: O) -> Self {
+        self.observer = Observer::new(observer);
+        self
+    }
+
+    /// The XTEA keys of the regions, available through [`Cache::xtea_keys`].
+    pub fn xtea_keys(mut self, keys: XteaKeyStore) -> Self {
+        self.xtea_keys = keys;
+        self
+    }
+
+    /// Overrides the protocol detected by [`Cache::protocol`].
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Opens the cache in the given directory.
+    ///
+    /// # Errors
+    ///
+    /// See [`Cache::new`]. When metadata is not lazy, errors while decoding
+    /// the reference tables are returned as well.
+    pub fn open<P: AsRef<Path>>(self, path: P) -> crate::Result<Cache> {
+        let cache = Cache::new_with_backend(path, self.backend)?;
+
+        self.finish(cache)
+    }
+
+    /// Creates the cache from buffers that are already in memory, the backend
+    /// is ignored.
+    ///
+    /// # Errors
+    ///
+    /// See [`Cache::from_buffers`] and [`open`](CacheBuilder::open).
+    pub fn from_buffers<D, I, B>(self, data: D, indices: I) -> crate::Result<Cache>
+    where
+        D: AsRef<[u8]> + Send + Sync + 'static,
+        I: IntoIterator<Item = (u8, B)>,
+        B: AsRef<[u8]>,
+    {
+        let cache = Cache::from_buffers(data, indices)?;
+
+        self.finish(cache)
+    }
+
+    fn finish(self, mut cache: Cache) -> crate::Result<Cache> {
+        if let Some(bytes) = self.decoded_cache {
+            cache = cache.with_decoded_cache(bytes);
+        }
+        cache.observer = self.observer;
+        cache.xtea_keys = self.xtea_keys;
+        if let Some(protocol) = self.protocol {
+            cache.protocol = OnceLock::from(protocol);
+        }
+
+        if !self.lazy_metadata {
+            for index_id in cache.present_indices() {
+                cache.index_metadata(index_id)?;
+            }
+        }
+
+        Ok(cache)
+    }
+}
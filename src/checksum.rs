@@ -23,6 +23,7 @@
 //! # }
 //! ```
 
+use std::collections::HashSet;
 use std::iter::IntoIterator;
 use std::slice::Iter;
 
@@ -34,23 +35,126 @@ use runefs::{
 };
 
 #[cfg(feature = "rs3")]
-use num_bigint::{BigInt, Sign};
+pub use crate::crypto::RsaKeys;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "rs3")]
+#[cfg(feature = "whirlpool")]
 use whirlpool::{Digest, Whirlpool};
 
 /// Each entry in the checksum is mapped to an [`Index`](runefs::Index).
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(not(feature = "rs3"), derive(Default))]
+#[cfg_attr(not(feature = "whirlpool"), derive(Default))]
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Entry {
     pub(crate) crc: u32,
     pub(crate) version: u32,
-    #[cfg(feature = "rs3")]
+    #[cfg(feature = "whirlpool")]
     pub(crate) hash: Vec<u8>,
 }
 
+impl Entry {
+    /// The crc of the encoded reference table.
+    #[inline]
+    pub const fn crc(&self) -> u32 {
+        self.crc
+    }
+
+    /// The version of the reference table, 0 if it doesn't have one.
+    #[inline]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The whirlpool digest of the reference table.
+    ///
+    /// Always present for RS3, OSRS checksums only have it when they were made
+    /// with [`Checksum::new_with_whirlpool`], otherwise it's empty.
+    #[cfg(feature = "whirlpool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "whirlpool")))]
+    #[inline]
+    pub fn hash(&self) -> &[u8] {
+        &self.hash
+    }
+}
+
+/// Indices which are skipped by default, their reference tables are broken
+/// in some revisions.
+pub const DEFAULT_SKIP_INDICES: [u8; 1] = [47];
+
+/// Options to control how a [`Checksum`] is generated.
+///
+/// Skipped indices still get an entry, but with a crc and version of 0, which
+/// is what the client expects for indices it doesn't use. Indices with an
+/// empty reference table are always skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::checksum::ChecksumOptions;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let checksum = ChecksumOptions::new()
+///     .skip_index(16)
+///     .build(&cache)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ChecksumOptions {
+    skip_indices: HashSet<u8>,
+    #[cfg(feature = "whirlpool")]
+    whirlpool: bool,
+}
+
+impl Default for ChecksumOptions {
+    fn default() -> Self {
+        Self {
+            skip_indices: DEFAULT_SKIP_INDICES.into_iter().collect(),
+            #[cfg(feature = "whirlpool")]
+            whirlpool: cfg!(feature = "rs3"),
+        }
+    }
+}
+
+impl ChecksumOptions {
+    /// Creates the default options, which skip [`DEFAULT_SKIP_INDICES`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an index to skip on top of the indices that are already skipped.
+    pub fn skip_index(mut self, index_id: u8) -> Self {
+        self.skip_indices.insert(index_id);
+        self
+    }
+
+    /// Replaces the indices to skip.
+    pub fn skip_indices<I: IntoIterator<Item = u8>>(mut self, index_ids: I) -> Self {
+        self.skip_indices = index_ids.into_iter().collect();
+        self
+    }
+
+    /// Whether the whirlpool digest of every reference table is computed,
+    /// defaults to `true` for RS3 and `false` for OSRS.
+    #[cfg(feature = "whirlpool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "whirlpool")))]
+    pub fn whirlpool(mut self, whirlpool: bool) -> Self {
+        self.whirlpool = whirlpool;
+        self
+    }
+
+    /// Generates the checksum, same as [`Checksum::with_options`].
+    ///
+    /// # Errors
+    ///
+    /// Decoding of a index buffer fails, this is considered a bug.
+    pub fn build(&self, cache: &Cache) -> crate::Result<Checksum> {
+        Checksum::with_options(cache, self)
+    }
+}
+
 /// Validator for the `Cache`.
 ///
 /// Used to validate cache index files. It contains a list of entries, one entry for each index file.
@@ -71,56 +175,89 @@ impl Checksum {
     /// 
     /// Decoding of a index buffer fails, this is considered a bug.
     pub fn new(cache: &Cache) -> crate::Result<Self> {
+        Self::with_options(cache, &ChecksumOptions::default())
+    }
+
+    /// Generate a checksum which also holds the whirlpool digest of every
+    /// reference table, even for OSRS caches.
+    ///
+    /// Some server frameworks validate OSRS caches with whirlpool as well, RS3
+    /// checksums made with [`new`](Checksum::new) already contain the digests.
+    ///
+    /// # Errors
+    ///
+    /// Decoding of a index buffer fails, this is considered a bug.
+    #[cfg(feature = "whirlpool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "whirlpool")))]
+    pub fn new_with_whirlpool(cache: &Cache) -> crate::Result<Self> {
+        Self::with_options(cache, &ChecksumOptions::default().whirlpool(true))
+    }
+
+    /// Generate a checksum with the given options, see [`ChecksumOptions`].
+    ///
+    /// # Errors
+    ///
+    /// Decoding of a index buffer fails, this is considered a bug.
+    pub fn with_options(cache: &Cache, options: &ChecksumOptions) -> crate::Result<Self> {
+        let entries = Self::entries(cache, options)?;
+
         Ok(Self {
-            index_count: cache.indices.count(),
-            entries: Self::entries(cache)?,
+            index_count: entries.len() + 1,
+            entries,
         })
     }
 
-    fn entries(cache: &Cache) -> crate::Result<Vec<Entry>> {
-        let entries: Vec<Entry> = (0..cache.indices.count())
-            .filter_map(|idx_id| cache.read(REFERENCE_TABLE_ID, idx_id as u32).ok())
-            .enumerate()
-            .map(|(idx_id, buffer)| -> crate::Result<Entry> {
-                if buffer.is_empty() || idx_id == 47 {
-                    Ok(Entry::default())
-                } else {
-                    // let (buffer, size) = if with_rsa {
-                    //     be_u8(buffer.as_slice())?
-                    // } else {
-                    //     (buffer.as_slice(), (buffer.len() / 8) as u8)
-                    // };
-
-                    #[cfg(feature = "rs3")]
-                    let hash = {
-                        let mut hasher = Whirlpool::new();
-                        hasher.update(&buffer);
-                        hasher.finalize().as_slice().to_vec()
-                    };
-
-                    let checksum = crc32fast::hash(&buffer);
-
-                    let data = buffer.decode()?;
-                    let (_, version) = cond(data[0] >= 6, be_u32)(&data[1..5])?;
-                    let version = version.unwrap_or(0);
-
-                    Ok(Entry {
-                        crc: checksum,
-                        version,
-                        #[cfg(feature = "rs3")]
-                        hash,
-                    })
+    /// One entry for every index listed in the reference table, indices that
+    /// are missing from the cache get a zeroed entry so the positions of the
+    /// other entries stay the same.
+    fn entries(cache: &Cache, options: &ChecksumOptions) -> crate::Result<Vec<Entry>> {
+        let index_count = cache
+            .indices
+            .get(&REFERENCE_TABLE_ID)
+            .map_or(0, |reference| reference.archive_refs.len())
+            .min(REFERENCE_TABLE_ID as usize);
+
+        (0..index_count as u8)
+            .map(|idx_id| -> crate::Result<Entry> {
+                if options.skip_indices.contains(&idx_id) || cache.indices.get(&idx_id).is_none() {
+                    return Ok(Entry::default());
                 }
-            })
-            .filter_map(crate::Result::ok)
-            .collect();
 
-        Ok(entries)
+                let buffer = cache.read(REFERENCE_TABLE_ID, idx_id as u32)?;
+                if buffer.is_empty() {
+                    return Ok(Entry::default());
+                }
+
+                #[cfg(feature = "whirlpool")]
+                let hash = if options.whirlpool {
+                    let mut hasher = Whirlpool::new();
+                    hasher.update(&buffer);
+                    hasher.finalize().as_slice().to_vec()
+                } else {
+                    Vec::new()
+                };
+
+                let checksum = crc32fast::hash(&buffer);
+
+                let data = buffer.decode()?;
+                let (_, version) = cond(data[0] >= 6, be_u32)(&data[1..5])?;
+                let version = version.unwrap_or(0);
+
+                Ok(Entry {
+                    crc: checksum,
+                    version,
+                    #[cfg(feature = "whirlpool")]
+                    hash,
+                })
+            })
+            .collect()
     }
 
-    /// Consumes the `Checksum` and encodes it into a byte buffer.
+    /// Encodes the `Checksum` into a byte buffer.
+    ///
+    /// The checksum is kept, so the same instance can validate the crcs of the
+    /// client after the buffer is sent.
     ///
-    /// 
     /// Note: It defaults to OSRS. RS3 clients use RSA to encrypt
     /// network traffic, which includes the checksum. When encoding for RS3 clients
     /// use [`RsaChecksum`](RsaChecksum) instead.
@@ -130,10 +267,10 @@ impl Checksum {
     /// # Errors
     ///
     /// Encoding of the formatted buffer fails, this is considered a bug.
-    pub fn encode(self) -> crate::Result<Buffer<Encoded>> {
+    pub fn encode(&self) -> crate::Result<Buffer<Encoded>> {
         let mut buffer = Vec::with_capacity(self.entries.len() * 8);
 
-        for entry in self.entries {
+        for entry in &self.entries {
             buffer.extend(u32::to_be_bytes(entry.crc));
             buffer.extend(u32::to_be_bytes(entry.version));
         }
@@ -201,6 +338,70 @@ impl Checksum {
         Ok(())
     }
 
+    /// Same as [`validate`](Checksum::validate) but collects every mismatch
+    /// instead of stopping at the first one.
+    ///
+    /// When the lengths don't match the overlapping crcs are still compared, the
+    /// `InvalidLength` error is then the first error in the list.
+    ///
+    /// # Errors
+    ///
+    /// Returns every `ValidateError` that was found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// # let client_crcs = vec![0; 21];
+    /// let checksum = cache.checksum()?;
+    ///
+    /// if let Err(errors) = checksum.validate_report(&client_crcs) {
+    ///     for error in errors {
+    ///         println!("{}", error);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_report<'b, I>(&self, crcs: I) -> Result<(), Vec<ValidateError>>
+    where
+        I: IntoIterator<Item = &'b u32>,
+        <I as IntoIterator>::IntoIter: ExactSizeIterator,
+    {
+        let crcs = crcs.into_iter();
+        let mut errors = Vec::new();
+
+        if self.entries.len() != crcs.len() {
+            errors.push(ValidateError::InvalidLength {
+                expected: self.entries.len(),
+                actual: crcs.len(),
+            });
+        }
+        for (index, (internal, external)) in self
+            .entries
+            .iter()
+            .map(|entry| &entry.crc)
+            .zip(crcs)
+            .enumerate()
+        {
+            if internal != external {
+                errors.push(ValidateError::InvalidCrc {
+                    idx: index,
+                    internal: *internal,
+                    external: *external,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     #[allow(missing_docs)]
     #[inline]
     pub const fn index_count(&self) -> usize {
@@ -212,35 +413,12 @@ impl Checksum {
     pub fn iter(&self) -> Iter<'_, Entry> {
         self.entries.iter()
     }
-}
 
-/// A struct that holds both keys for RSA encryption.
-#[cfg(feature = "rs3")]
-#[cfg_attr(docsrs, doc(cfg(feature = "rs3")))]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
-pub struct RsaKeys<'a> {
-    pub(crate) exponent: &'a [u8],
-    pub(crate) modulus: &'a [u8],
-}
-
-#[cfg(feature = "rs3")]
-#[cfg_attr(docsrs, doc(cfg(feature = "rs3")))]
-impl<'a> RsaKeys<'a> {
-    /// Generate a RSA key set with the given keys.
-    pub const fn new(exponent: &'a [u8], modulus: &'a [u8]) -> Self {
-        Self { exponent, modulus }
-    }
-
-    /// Encrypts the given hash.
-    // TODO: maybe make this panic if the exponent or modulus not line up
-    pub fn encrypt(&self, hash: &[u8]) -> Vec<u8> {
-        let exp = BigInt::parse_bytes(self.exponent, 10).unwrap_or_default();
-        let mud = BigInt::parse_bytes(self.modulus, 10).unwrap_or_default();
-        BigInt::from_bytes_be(Sign::Plus, hash)
-            .modpow(&exp, &mud)
-            .to_bytes_be()
-            .1
+    /// The entry of a single index, e.g. to answer a request for the crc of
+    /// one index.
+    #[inline]
+    pub fn entry(&self, index_id: u8) -> Option<&Entry> {
+        self.entries.get(index_id as usize)
     }
 }
 
@@ -286,7 +464,7 @@ impl<'a> RsaChecksum<'a> {
     }
 
     /// Same as [`Checksum::encode`](Checksum::encode) but for RS3.
-    pub fn encode(self) -> crate::Result<Buffer<Encoded>> {
+    pub fn encode(&self) -> crate::Result<Buffer<Encoded>> {
         let index_count = self.checksum.index_count - 1;
         let mut buffer = vec![0; 81 * index_count];
 
@@ -311,13 +489,6 @@ impl<'a> RsaChecksum<'a> {
     }
 }
 
-#[cfg(feature = "rs3")]
-impl<'a> From<(&'a [u8], &'a [u8])> for RsaKeys<'a> {
-    fn from(keys: (&'a [u8], &'a [u8])) -> Self {
-        RsaKeys::new(keys.0, keys.1)
-    }
-}
-
 impl IntoIterator for Checksum {
     type Item = Entry;
     type IntoIter = std::vec::IntoIter<Entry>;
@@ -360,7 +531,7 @@ impl<'a> IntoIterator for &'a RsaChecksum<'a> {
     }
 }
 
-#[cfg(feature = "rs3")]
+#[cfg(feature = "whirlpool")]
 impl Default for Entry {
     #[inline]
     fn default() -> Self {
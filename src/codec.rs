@@ -0,0 +1,627 @@
+//! Additions to the [`runefs`] codec.
+//!
+//! # Example
+//!
+//! ```
+//! # use rscache::Cache;
+//! use rscache::codec::{Compression, EncodedExt};
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! # let cache = Cache::new("./data/osrs_cache")?;
+//! let encoded = cache.read(2, 10)?;
+//! let decoded = encoded.decode_with_metadata()?;
+//!
+//! // `encoded` can still be served to a client.
+//! assert_eq!(decoded.compression(), Compression::Bzip2);
+//! assert_eq!(decoded.version(), Some(918));
+//! # assert!(!encoded.is_empty() && !decoded.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io::{Read, Write},
+    ops::{Deref, RangeInclusive},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{error::CodecError, util::xtea};
+pub use runefs::codec::{Buffer, Decoded, Encoded};
+
+/// The compression type of an encoded buffer, stored in its first byte.
+///
+/// [`Deflate`](Compression::Deflate) is never used by the client, it exists so
+/// raw deflate streams used by other tooling and some RS3 sub-formats can go
+/// through the same [`Buffer`] pipeline. Those buffers are decoded with
+/// [`EncodedExt`] as the [`runefs`] codec doesn't know about them.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Bzip2,
+    Gzip,
+    Lzma,
+    Deflate,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = CodecError;
+
+    fn try_from(compression: u8) -> Result<Self, Self::Error> {
+        match compression {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Bzip2),
+            2 => Ok(Self::Gzip),
+            3 => Ok(Self::Lzma),
+            4 => Ok(Self::Deflate),
+            _ => Err(CodecError::UnknownCompression(compression)),
+        }
+    }
+}
+
+impl From<Compression> for u8 {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::None => 0,
+            Compression::Bzip2 => 1,
+            Compression::Gzip => 2,
+            Compression::Lzma => 3,
+            Compression::Deflate => 4,
+        }
+    }
+}
+
+impl Compression {
+    /// Whether buffers with this compression can be decoded.
+    ///
+    /// Every compression type is always part of the enum, only decoding lzma
+    /// depends on the `lzma` feature flag, which is enabled by `rs3`.
+    pub const fn is_supported(self) -> bool {
+        match self {
+            Self::Lzma => cfg!(feature = "lzma"),
+            Self::None | Self::Bzip2 | Self::Gzip | Self::Deflate => true,
+        }
+    }
+
+    /// The level the client compresses with, which is also the level every
+    /// archive in the cache was compressed with.
+    ///
+    /// Returns `None` for uncompressed buffers and for lzma, which can't be
+    /// encoded yet.
+    pub const fn default_level(self) -> Option<u32> {
+        match self {
+            Self::None | Self::Lzma => None,
+            Self::Bzip2 => Some(1),
+            Self::Gzip | Self::Deflate => Some(6),
+        }
+    }
+
+    /// The levels that can be encoded with, from fastest to smallest.
+    ///
+    /// Returns `None` for uncompressed buffers and for lzma, which can't be
+    /// encoded yet.
+    pub const fn levels(self) -> Option<RangeInclusive<u32>> {
+        match self {
+            Self::None | Self::Lzma => None,
+            Self::Bzip2 => Some(1..=9),
+            Self::Gzip | Self::Deflate => Some(0..=9),
+        }
+    }
+}
+
+/// Length of the header of an uncompressed buffer, the compression type and
+/// the length of the data.
+pub const HEADER_LEN: usize = 5;
+/// Length of the header of a compressed buffer, which also stores the
+/// decompressed length.
+pub const COMPRESSED_HEADER_LEN: usize = 9;
+/// Length of the version trailer of archives outside of the reference table.
+pub const VERSION_LEN: usize = 2;
+
+/// The header that precedes the data of every encoded buffer.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Header {
+    pub compression: Compression,
+    /// Length of the (compressed) data that follows the header.
+    pub len: usize,
+    /// The version trailer, archives in the reference table don't have one.
+    pub version: Option<u16>,
+}
+
+impl Header {
+    /// Parses the header and version trailer of an encoded buffer.
+    ///
+    /// Only the compression type and length are read, these are never
+    /// encrypted so the buffer doesn't have to be deciphered first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compression type is unknown or if the buffer is
+    /// shorter than the header says it is.
+    pub fn parse(buffer: &[u8]) -> Result<Self, CodecError> {
+        if buffer.len() < HEADER_LEN {
+            return Err(CodecError::Truncated {
+                expected: HEADER_LEN,
+                actual: buffer.len(),
+            });
+        }
+
+        let compression = Compression::try_from(buffer[0])?;
+        let len = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+        let header_len = if compression == Compression::None {
+            HEADER_LEN
+        } else {
+            COMPRESSED_HEADER_LEN
+        };
+
+        let end = header_len + len;
+        if buffer.len() < end {
+            return Err(CodecError::Truncated {
+                expected: end,
+                actual: buffer.len(),
+            });
+        }
+
+        let version = match &buffer[end..] {
+            [high, low, ..] => Some(u16::from_be_bytes([*high, *low])),
+            _ => None,
+        };
+
+        Ok(Self {
+            compression,
+            len,
+            version,
+        })
+    }
+
+    /// Length of the encoded buffer without its version trailer.
+    #[inline]
+    pub const fn encoded_len(&self) -> usize {
+        match self.compression {
+            Compression::None => HEADER_LEN + self.len,
+            _ => COMPRESSED_HEADER_LEN + self.len,
+        }
+    }
+}
+
+/// A decoded buffer together with the settings it was encoded with.
+///
+/// Returned by [`EncodedExt::decode_with_metadata`].
+#[derive(Debug)]
+pub struct DecodedBuffer {
+    compression: Compression,
+    version: Option<u16>,
+    buffer: Buffer<Decoded>,
+}
+
+impl DecodedBuffer {
+    /// The compression type the buffer was encoded with.
+    #[inline]
+    pub const fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// The version trailer of the encoded buffer, if it had one.
+    #[inline]
+    pub const fn version(&self) -> Option<u16> {
+        self.version
+    }
+
+    /// Length of the decoded data.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn into_inner(self) -> Buffer<Decoded> {
+        self.buffer
+    }
+
+    /// The options to encode this buffer with the settings it was decoded from.
+    pub fn encode_options(&self) -> EncodeOptions {
+        EncodeOptions {
+            compression: self.compression,
+            version: self.version,
+            ..EncodeOptions::default()
+        }
+    }
+
+    /// Encodes the buffer with the settings it was decoded from.
+    ///
+    /// # Errors
+    ///
+    /// See [`EncodeOptions::encode`].
+    pub fn encode(&self) -> crate::Result<Buffer<Encoded>> {
+        self.encode_options().encode(self)
+    }
+}
+
+impl Deref for DecodedBuffer {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.buffer.as_slice()
+    }
+}
+
+/// Configures how data is encoded.
+///
+/// The defaults match the client, so encoding an archive that was just decoded
+/// with the same compression type and version produces the same bytes. This
+/// keeps the crc in the reference table valid when patching a cache.
+///
+/// # Example
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::codec::{Compression, EncodeOptions};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let original = cache.read(2, 10)?;
+/// let data = cache.read(2, 10)?.decode()?;
+///
+/// let encoded = EncodeOptions::new(Compression::Bzip2)
+///     .version(918)
+///     .encode(&data)?;
+/// # assert_eq!(encoded.as_slice(), original.as_slice());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct EncodeOptions {
+    compression: Compression,
+    level: Option<u32>,
+    keep_header: bool,
+    version: Option<u16>,
+    xtea_keys: Option<[u32; 4]>,
+}
+
+impl EncodeOptions {
+    /// Creates the default options for the given compression type.
+    pub fn new(compression: Compression) -> Self {
+        Self {
+            compression,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the compression level, defaults to [`Compression::default_level`].
+    ///
+    /// Higher levels trade encoding time for smaller archives, but only the
+    /// default level produces the same bytes as the client. The level is
+    /// ignored for uncompressed buffers.
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Keeps the `BZh` header of bzip2 data.
+    ///
+    /// The client expects the header to be stripped, only keep it when the
+    /// data is read by other tooling. Gzip data always has a header.
+    pub fn keep_header(mut self, keep_header: bool) -> Self {
+        self.keep_header = keep_header;
+        self
+    }
+
+    /// Appends a version trailer, archives in the reference table don't have one.
+    pub fn version(mut self, version: u16) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Enciphers everything after the header with XTEA, like the location
+    /// archives of the client. Keys that are all zero don't encipher anything.
+    pub fn xtea_keys(mut self, keys: [u32; 4]) -> Self {
+        self.xtea_keys = Some(keys);
+        self
+    }
+
+    /// Encodes `data` with these options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if compressing fails or if the compression type is
+    /// [`Compression::Lzma`], which can't be encoded yet. Levels outside of
+    /// [`Compression::levels`] return `InvalidLevel`.
+    pub fn encode(&self, data: &[u8]) -> crate::Result<Buffer<Encoded>> {
+        if let (Some(level), Some(levels)) = (self.level, self.compression.levels()) {
+            if !levels.contains(&level) {
+                return Err(CodecError::InvalidLevel {
+                    compression: self.compression,
+                    level,
+                }
+                .into());
+            }
+        }
+
+        let level = self.level.or_else(|| self.compression.default_level());
+        let compressed = match self.compression {
+            Compression::None => None,
+            Compression::Bzip2 => {
+                let level = bzip2::Compression::new(level.unwrap_or(1));
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                let compressed = encoder.finish()?;
+
+                Some(if self.keep_header {
+                    compressed
+                } else {
+                    compressed[4..].to_vec()
+                })
+            }
+            Compression::Gzip => Some(gzip(data, level.unwrap_or(6))?),
+            Compression::Deflate => {
+                let level = flate2::Compression::new(level.unwrap_or(6));
+                let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                Some(encoder.finish()?)
+            }
+            Compression::Lzma => return Err(CodecError::Unsupported(self.compression).into()),
+        };
+
+        let mut buffer = Vec::with_capacity(data.len() + 11);
+        buffer.push(u8::from(self.compression));
+        match compressed {
+            Some(compressed) => {
+                buffer.extend(&(compressed.len() as u32).to_be_bytes());
+                buffer.extend(&(data.len() as u32).to_be_bytes());
+                buffer.extend(compressed);
+            }
+            None => {
+                buffer.extend(&(data.len() as u32).to_be_bytes());
+                buffer.extend(data);
+            }
+        }
+        if let Some(keys) = self.xtea_keys.filter(|keys| keys != &[0; 4]) {
+            xtea::encipher_in_place(&mut buffer[HEADER_LEN..], &keys);
+        }
+        if let Some(version) = self.version {
+            buffer.extend(&version.to_be_bytes());
+        }
+
+        Ok(Buffer::from(buffer))
+    }
+}
+
+/// Encodes `data` with `options`, decodes it again and checks that nothing
+/// changed on the way.
+///
+/// Besides the data itself the compression type, version trailer and both
+/// lengths in the header are compared, these are easy to get off by the 4
+/// bytes of the stripped bzip2 header. Bzip2 buffers that keep their header
+/// can't be decoded by the client and always fail the check.
+///
+/// # Errors
+///
+/// Returns `RoundTripMismatch` naming the first part that changed, or the
+/// error of encoding or decoding if either fails.
+///
+/// # Examples
+///
+/// ```
+/// use rscache::codec::{self, Compression, EncodeOptions};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// let options = EncodeOptions::new(Compression::Bzip2)
+///     .version(918)
+///     .xtea_keys([1, 2, 3, 4]);
+///
+/// codec::roundtrip_check(&options, b"location data")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn roundtrip_check(options: &EncodeOptions, data: &[u8]) -> crate::Result<()> {
+    let mismatch = |field| CodecError::RoundTripMismatch {
+        compression: options.compression,
+        field,
+    };
+
+    let mut encoded = options.encode(data)?.to_vec();
+    let header = Header::parse(&encoded)?;
+    if header.compression != options.compression {
+        return Err(mismatch("compression").into());
+    }
+    if header.version != options.version {
+        return Err(mismatch("version").into());
+    }
+    let trailer_len = options.version.map_or(0, |_| VERSION_LEN);
+    if header.encoded_len() + trailer_len != encoded.len() {
+        return Err(mismatch("length").into());
+    }
+
+    if let Some(keys) = options.xtea_keys.filter(|keys| keys != &[0; 4]) {
+        xtea::decipher_in_place(&mut encoded[HEADER_LEN..header.encoded_len()], &keys);
+    }
+    if header.compression != Compression::None {
+        let len = u32::from_be_bytes([encoded[5], encoded[6], encoded[7], encoded[8]]);
+        if len as usize != data.len() {
+            return Err(mismatch("decompressed length").into());
+        }
+    }
+
+    let decoded = Buffer::<Encoded>::from(encoded).decode_ref()?;
+    if decoded.as_slice() != data {
+        return Err(mismatch("data").into());
+    }
+
+    Ok(())
+}
+
+/// Compresses `data` with a gzip header identical to the one Java's
+/// `GZIPOutputStream` writes, unlike `flate2` which fills in the flags and os.
+fn gzip(data: &[u8], level: u32) -> std::io::Result<Vec<u8>> {
+    let mut buffer = vec![0x1F, 0x8B, 0x08, 0, 0, 0, 0, 0, 0, 0];
+    let mut encoder =
+        flate2::write::DeflateEncoder::new(&mut buffer, flate2::Compression::new(level));
+    encoder.write_all(data)?;
+    encoder.finish()?;
+
+    buffer.extend(&crc32fast::hash(data).to_le_bytes());
+    buffer.extend(&(data.len() as u32).to_le_bytes());
+
+    Ok(buffer)
+}
+
+/// Adds decoding by reference and version trailer handling onto an encoded
+/// [`Buffer`].
+pub trait EncodedExt {
+    /// Decodes the buffer without consuming it.
+    ///
+    /// Useful when the encoded bytes are still needed afterwards, e.g. to serve
+    /// them to a client, without reading the archive from the cache twice.
+    ///
+    /// The buffer is decoded from a copy of its bytes, XTEA keys that were set
+    /// with `with_xtea_keys` can't be read back from it and are not used. Use
+    /// [`decode_ref_with_keys`](EncodedExt::decode_ref_with_keys) for
+    /// encrypted buffers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer could not be decoded.
+    fn decode_ref(&self) -> crate::Result<Buffer<Decoded>>;
+
+    /// Same as [`decode_ref`](EncodedExt::decode_ref) but deciphers the buffer
+    /// with the given XTEA keys first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer could not be decoded.
+    fn decode_ref_with_keys(&self, keys: &[u32; 4]) -> crate::Result<Buffer<Decoded>>;
+
+    /// Decodes the buffer without consuming it and keeps its compression type
+    /// and version, so it can be re-encoded with the same settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is invalid or if the buffer could not be
+    /// decoded.
+    fn decode_with_metadata(&self) -> crate::Result<DecodedBuffer>;
+
+    /// The buffer without its version trailer, as it is sent to clients.
+    ///
+    /// Buffers without a trailer, like reference tables, are returned whole,
+    /// as are buffers whose header can't be parsed.
+    fn without_version(&self) -> &[u8];
+
+    /// Removes the version trailer and returns it, `None` if the buffer had
+    /// none.
+    fn strip_version(&mut self) -> Option<u16>;
+}
+
+impl EncodedExt for Buffer<Encoded> {
+    fn decode_ref(&self) -> crate::Result<Buffer<Decoded>> {
+        if self.first() == Some(&u8::from(Compression::Deflate)) {
+            return inflate(self);
+        }
+        ensure_supported(self)?;
+
+        Ok(Buffer::<Encoded>::from(self.as_slice()).decode()?)
+    }
+
+    fn decode_ref_with_keys(&self, keys: &[u32; 4]) -> crate::Result<Buffer<Decoded>> {
+        if self.first() == Some(&u8::from(Compression::Deflate)) {
+            let header = Header::parse(self.as_slice())?;
+            let mut buffer = self.to_vec();
+            xtea::decipher_in_place(&mut buffer[HEADER_LEN..header.encoded_len()], keys);
+
+            return inflate(&buffer);
+        }
+        ensure_supported(self)?;
+
+        Ok(Buffer::<Encoded>::from(self.as_slice())
+            .with_xtea_keys(*keys)
+            .decode()?)
+    }
+
+    fn decode_with_metadata(&self) -> crate::Result<DecodedBuffer> {
+        let header = Header::parse(self.as_slice())?;
+
+        Ok(DecodedBuffer {
+            compression: header.compression,
+            version: header.version,
+            buffer: self.decode_ref()?,
+        })
+    }
+
+    fn without_version(&self) -> &[u8] {
+        match Header::parse(self.as_slice()) {
+            Ok(header) => &self[..header.encoded_len()],
+            Err(_) => self.as_slice(),
+        }
+    }
+
+    fn strip_version(&mut self) -> Option<u16> {
+        let version = Header::parse(self.as_slice()).ok()?.version;
+        if version.is_some() {
+            *self = Buffer::from(self.without_version().to_vec());
+        }
+
+        version
+    }
+}
+
+/// Returns `DecodeUnsupported` if the compression of the buffer can't be
+/// decoded with the enabled features.
+pub(crate) fn ensure_supported(buffer: &[u8]) -> crate::Result<()> {
+    let compression = buffer.first().copied().map(Compression::try_from);
+    match compression {
+        Some(Ok(compression)) if !compression.is_supported() => {
+            Err(CodecError::DecodeUnsupported(compression).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Wraps already decoded data in a decoded [`Buffer`].
+pub(crate) fn decoded(data: &[u8]) -> crate::Result<Buffer<Decoded>> {
+    let mut buffer = Vec::with_capacity(HEADER_LEN + data.len());
+    buffer.push(u8::from(Compression::None));
+    buffer.extend(&(data.len() as u32).to_be_bytes());
+    buffer.extend(data);
+
+    Ok(Buffer::from(buffer).decode()?)
+}
+
+/// Decodes a [`Compression::Deflate`] buffer.
+///
+/// The inflated data is passed through the [`runefs`] codec as an uncompressed
+/// buffer, which is the only way to construct a decoded [`Buffer`]. No more
+/// than the decompressed length of the header is inflated.
+fn inflate(buffer: &[u8]) -> crate::Result<Buffer<Decoded>> {
+    let header = Header::parse(buffer)?;
+    let compressed = &buffer[COMPRESSED_HEADER_LEN..COMPRESSED_HEADER_LEN + header.len];
+    let decompressed_len =
+        u32::from_be_bytes([buffer[5], buffer[6], buffer[7], buffer[8]]) as usize;
+
+    let mut data = Vec::with_capacity(HEADER_LEN + decompressed_len.min(buffer.len() * 2));
+    data.push(u8::from(Compression::None));
+    data.extend(&[0; 4]);
+    let mut decoder = flate2::read::DeflateDecoder::new(compressed).take(decompressed_len as u64);
+    decoder.read_to_end(&mut data)?;
+
+    let len = data.len() - HEADER_LEN;
+    if len != decompressed_len || decoder.into_inner().read(&mut [0])? != 0 {
+        return Err(CodecError::LengthMismatch {
+            expected: decompressed_len,
+        }
+        .into());
+    }
+    data[1..5].copy_from_slice(&(len as u32).to_be_bytes());
+
+    Ok(Buffer::from(data).decode()?)
+}
@@ -0,0 +1,96 @@
+//! RSA helpers for the login protocol.
+//!
+//! The client encrypts the secure part of the login block with the server's
+//! public key, servers decrypt it with their private key. Both directions are
+//! the same modular exponentiation, only the exponent in [`RsaKeys`] differs.
+//!
+//! # Example
+//!
+//! ```
+//! use rscache::crypto::{self, RsaKeys};
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! let public_keys = RsaKeys::new(b"17", b"3233");
+//! let private_keys = RsaKeys::new(b"2753", b"3233");
+//!
+//! let encrypted = crypto::rsa_encrypt(&[65], &public_keys)?;
+//! let decrypted = crypto::rsa_decrypt(&encrypted, &private_keys)?;
+//! assert_eq!(decrypted, [65]);
+//! # Ok(())
+//! # }
+//! ```
+
+use num_bigint::{BigInt, BigUint, Sign};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::RsaError;
+
+/// A struct that holds both keys for RSA encryption.
+///
+/// The exponent and modulus are stored as decimal strings, the format in
+/// which they are usually distributed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct RsaKeys<'a> {
+    pub(crate) exponent: &'a [u8],
+    pub(crate) modulus: &'a [u8],
+}
+
+impl<'a> RsaKeys<'a> {
+    /// Generate a RSA key set with the given keys.
+    pub const fn new(exponent: &'a [u8], modulus: &'a [u8]) -> Self {
+        Self { exponent, modulus }
+    }
+
+    /// Encrypts the given hash.
+    // TODO: maybe make this panic if the exponent or modulus not line up
+    pub fn encrypt(&self, hash: &[u8]) -> Vec<u8> {
+        let exp = BigInt::parse_bytes(self.exponent, 10).unwrap_or_default();
+        let mud = BigInt::parse_bytes(self.modulus, 10).unwrap_or_default();
+        BigInt::from_bytes_be(Sign::Plus, hash)
+            .modpow(&exp, &mud)
+            .to_bytes_be()
+            .1
+    }
+
+    fn modpow(&self, block: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let exponent = BigUint::parse_bytes(self.exponent, 10).ok_or(RsaError::InvalidExponent)?;
+        let modulus = BigUint::parse_bytes(self.modulus, 10)
+            .filter(|modulus| *modulus > BigUint::from(1_u8))
+            .ok_or(RsaError::InvalidModulus)?;
+
+        let block = BigUint::from_bytes_be(block);
+        if block >= modulus {
+            return Err(RsaError::BlockTooLarge);
+        }
+
+        Ok(block.modpow(&exponent, &modulus).to_bytes_be())
+    }
+}
+
+impl<'a> From<(&'a [u8], &'a [u8])> for RsaKeys<'a> {
+    fn from(keys: (&'a [u8], &'a [u8])) -> Self {
+        RsaKeys::new(keys.0, keys.1)
+    }
+}
+
+/// Encrypts a block with the public key.
+///
+/// # Errors
+///
+/// Returns an `RsaError` if the keys aren't valid decimal numbers or if the
+/// block isn't smaller than the modulus.
+pub fn rsa_encrypt(block: &[u8], public_keys: &RsaKeys<'_>) -> crate::Result<Vec<u8>> {
+    Ok(public_keys.modpow(block)?)
+}
+
+/// Decrypts a block with the private key, e.g. the RSA block of a login packet.
+///
+/// # Errors
+///
+/// Returns an `RsaError` if the keys aren't valid decimal numbers or if the
+/// block isn't smaller than the modulus.
+pub fn rsa_decrypt(block: &[u8], private_keys: &RsaKeys<'_>) -> crate::Result<Vec<u8>> {
+    Ok(private_keys.modpow(block)?)
+}
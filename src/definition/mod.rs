@@ -6,3 +6,144 @@ pub mod osrs;
 #[cfg(feature = "rs3")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rs3")))]
 pub mod rs3;
+
+pub use runefs::ArchiveFileData;
+
+use crate::{error::DefinitionError, Cache, Protocol};
+
+/// Decodes a definition from a single file of the cache.
+///
+/// Both OSRS and RS3 definitions implement this trait, ids are always `u32` so
+/// definitions with big ids, like RS3 items or models, work the same way.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::definition::{osrs::ItemDefinition, ArchiveFileData, DecodeContext, Definition};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// let file = ArchiveFileData {
+///     id: 1042,
+///     data: vec![2, b'B', b'l', b'u', b'e', 0, 0],
+/// };
+/// let ctx = DecodeContext::new(2, 10);
+///
+/// let item_def = ItemDefinition::new(file.id, &file, &ctx)?;
+/// assert_eq!(item_def.name, "Blue");
+/// # Ok(())
+/// # }
+/// ```
+pub trait Definition: Sized {
+    /// Decodes the definition with the given id from its file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be parsed.
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self>;
+
+    /// Decodes a definition from an archive that only holds a single file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer could not be parsed.
+    fn from_buffer(id: u32, buffer: &[u8], ctx: &DecodeContext) -> crate::Result<Self> {
+        let file = ArchiveFileData {
+            id: 0,
+            data: buffer.to_vec(),
+        };
+
+        Self::new(id, &file, ctx)
+    }
+}
+
+/// Where the file of a definition comes from and how it should be decoded.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct DecodeContext {
+    pub index_id: u8,
+    pub archive_id: u32,
+    /// The version of the archive in the reference table of its index.
+    pub version: u32,
+    /// The client revision the cache was made for, decides how opcodes that
+    /// changed over time are read.
+    pub revision: Revision,
+    /// The protocol of the cache the file was read from, decides the format of
+    /// opcodes that differ between OSRS and RS3. `None` decodes the file in the
+    /// format of the game the definition belongs to.
+    pub protocol: Option<Protocol>,
+    /// Stop decoding at an unknown opcode instead of returning an error.
+    ///
+    /// The length of the value behind an unknown opcode can't be known, so the
+    /// definition only has the values that came before it.
+    pub lenient: bool,
+}
+
+impl DecodeContext {
+    #[allow(missing_docs)]
+    pub const fn new(index_id: u8, archive_id: u32) -> Self {
+        Self {
+            index_id,
+            archive_id,
+            version: 0,
+            revision: Revision(0),
+            protocol: None,
+            lenient: false,
+        }
+    }
+
+    /// Creates the context of a file read from `cache`.
+    pub(crate) fn with_cache(cache: &Cache, index_id: u8, archive_id: u32) -> Self {
+        Self {
+            protocol: Some(cache.protocol()),
+            ..Self::new(index_id, archive_id)
+        }
+    }
+
+    /// Returns `UnknownOpcode` unless the context is lenient, in which case the
+    /// decoder should stop.
+    pub(crate) fn unknown_opcode(
+        &self,
+        kind: &'static str,
+        id: u32,
+        opcode: u8,
+    ) -> crate::Result<()> {
+        if self.lenient {
+            return Ok(());
+        }
+
+        Err(DefinitionError::UnknownOpcode { kind, id, opcode }.into())
+    }
+}
+
+/// The revision of the client a cache was made for.
+///
+/// A few opcodes of the OSRS definitions changed their format over time, the
+/// associated constants are the revisions in which they did. Decoders read
+/// those opcodes the way the revision of the [`DecodeContext`] wrote them. The
+/// default revision is older than all of the changes.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::{definition::Revision, loader::osrs::ObjectLoader};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let obj_loader = ObjectLoader::with_revision(&cache, Revision(180))?;
+///
+/// assert!(Revision(180) < Revision::OBJECT_SOUND_RETAIN);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Revision(pub u32);
+
+impl Revision {
+    /// Npc head icons are a set of sprites instead of a single id, see
+    /// [`NpcModelData::head_icon_sprites`](osrs::NpcModelData::head_icon_sprites).
+    pub const NPC_HEAD_ICON_SPRITES: Self = Self(210);
+    /// Object ambient sounds have an extra byte, see
+    /// [`ObjectDefinition::ambient_sound_retain`](osrs::ObjectDefinition::ambient_sound_retain).
+    pub const OBJECT_SOUND_RETAIN: Self = Self(220);
+}
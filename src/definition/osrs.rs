@@ -1,33 +1,86 @@
+mod encode;
+pub mod equipment;
+mod font_metrics_def;
+mod graphics_defaults_def;
 #[allow(clippy::too_many_lines)]
 mod item_def;
 mod loc_def;
 mod map_def;
+mod model_def;
 mod npc_def;
 #[allow(clippy::too_many_lines)]
 mod obj_def;
+mod overlay_def;
+mod sprite_def;
+mod texture_def;
+mod transform;
+mod underlay_def;
 
+pub use font_metrics_def::*;
+pub use graphics_defaults_def::*;
 pub use item_def::*;
 pub use loc_def::*;
 pub use map_def::*;
+pub use model_def::*;
 pub use npc_def::*;
 pub use obj_def::*;
+pub use overlay_def::*;
+pub use sprite_def::*;
+pub use texture_def::*;
+pub use transform::TransformVar;
+pub use underlay_def::*;
 
-use std::collections::HashMap;
+pub use super::{DecodeContext, Definition, Revision};
 
-use crate::Cache;
-use runefs::{ArchiveFileGroup, IndexMetadata, REFERENCE_TABLE_ID};
+use std::collections::HashMap;
 
-/// Marker trait for definitions.
-pub trait Definition: Sized {
-    fn new(id: u16, buffer: &[u8]) -> crate::Result<Self>;
-}
+use crate::{archive::ArchiveFileGroup, Cache};
+use runefs::error::{Error as RuneFsError, ReadError};
 
 /// Adds definition fetching from the cache to every struct that implements `Definition`.
 ///
 /// The main difference between `fetch_from_index` and `fetch_from_archive`:
 /// - `fetch_from_index` will get only 1 definition from each archive making it a 1:1 relation.
 /// - `fetch_from_archive` will get multiple definitions from each archive making it a N:1 relation
-/// where N is atleast 1.
+///   where N is atleast 1.
+///
+/// This trait is the intended way to load user-defined definitions, it is
+/// implemented for every [`Definition`]. Definitions can also be fetched from
+/// a single file, from an archive by its name or from an archive that is
+/// encrypted with XTEA. The `fetch_group` methods return the files of an
+/// archive without decoding them, for definitions that need more than
+/// [`Definition::new`] to be decoded.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::definition::{
+///     osrs::{DecodeContext, Definition, FetchDefinition},
+///     ArchiveFileData,
+/// };
+///
+/// struct Sound {
+///     len: usize,
+/// }
+///
+/// impl Definition for Sound {
+///     fn new(_: u32, file: &ArchiveFileData, _: &DecodeContext) -> Result<Self, rscache::Error> {
+///         Ok(Self { len: file.data.len() })
+///     }
+/// }
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let group = Sound::fetch_group(&cache, 2, 10)?;
+/// let sounds = Sound::decode_group(&group, &DecodeContext::new(2, 10))?;
+/// # assert_eq!(sounds.len(), group.files().len());
+///
+/// let sound = Sound::fetch_from_file(&cache, 2, 10, 1042)?.unwrap();
+/// # assert_eq!(sound.len, sounds[&1042].len);
+/// # Ok(())
+/// # }
+/// ```
 pub trait FetchDefinition: Definition {
     // TODO: finish documentation with example.
     /// Fetches multiple definitions from every archive in the index.
@@ -37,17 +90,38 @@ pub trait FetchDefinition: Definition {
     /// # Errors
     ///
     /// Can return multiple errors: if reading, decoding or parsing definition buffers fail.
-    fn fetch_from_index<D>(cache: &Cache, index_id: u8) -> crate::Result<HashMap<u16, D>>
+    fn fetch_from_index<D>(cache: &Cache, index_id: u8) -> crate::Result<HashMap<u32, D>>
     where
         D: Definition,
     {
-        let buffer = cache.read(REFERENCE_TABLE_ID, index_id as u32)?.decode()?;
-        let archives = IndexMetadata::from_buffer(buffer)?;
+        Self::fetch_from_index_with_revision(cache, index_id, Revision::default())
+    }
+
+    /// Fetches multiple definitions from every archive in the index, decoding
+    /// them the way the given client revision wrote them.
+    ///
+    /// # Errors
+    ///
+    /// Can return multiple errors: if reading, decoding or parsing definition buffers fail.
+    fn fetch_from_index_with_revision<D>(
+        cache: &Cache,
+        index_id: u8,
+        revision: Revision,
+    ) -> crate::Result<HashMap<u32, D>>
+    where
+        D: Definition,
+    {
+        let archives = cache.index_metadata(index_id)?;
         let mut definitions = HashMap::new();
-        for archive in &archives {
+        for archive in archives {
             let buffer = cache.read(index_id, archive.id)?.decode()?;
+            let ctx = DecodeContext {
+                version: archive.version,
+                revision,
+                ..DecodeContext::with_cache(cache, index_id, archive.id)
+            };
 
-            definitions.insert(archive.id as u16, D::new(archive.id as u16, &buffer)?);
+            definitions.insert(archive.id, D::from_buffer(archive.id, &buffer, &ctx)?);
         }
 
         Ok(definitions)
@@ -76,7 +150,7 @@ pub trait FetchDefinition: Definition {
     /// let index_id = 2; // Config index.
     /// let archive_id = 10; // Archive containing item definitions.
     ///
-    /// let item_defs: HashMap<u16, ItemDefinition>
+    /// let item_defs: HashMap<u32, ItemDefinition>
     ///     = ItemDefinition::fetch_from_archive(&cache, index_id, archive_id)?;
     /// # Ok(())
     /// # }
@@ -85,27 +159,220 @@ pub trait FetchDefinition: Definition {
         cache: &Cache,
         index_id: u8,
         archive_id: u32,
-    ) -> crate::Result<HashMap<u16, D>>
+    ) -> crate::Result<HashMap<u32, D>>
+    where
+        D: Definition,
+    {
+        Self::fetch_from_archive_with_revision(cache, index_id, archive_id, Revision::default())
+    }
+
+    /// Fetches multiple definitions from a single archive, decoding them the
+    /// way the given client revision wrote them.
+    ///
+    /// # Errors
+    ///
+    /// Can return multiple errors: if reading, decoding or parsing definition buffers fail.
+    fn fetch_from_archive_with_revision<D>(
+        cache: &Cache,
+        index_id: u8,
+        archive_id: u32,
+        revision: Revision,
+    ) -> crate::Result<HashMap<u32, D>>
     where
         D: Definition,
     {
-        let buffer = cache.read(REFERENCE_TABLE_ID, index_id as u32)?.decode()?;
-        let archives = IndexMetadata::from_buffer(buffer)?;
-        let entry_count = archives[archive_id as usize - 1].entry_count;
-        let buffer = cache.read(index_id, archive_id)?.decode()?;
+        let (group, ctx) = read_group(cache, index_id, archive_id, None)?;
 
-        let archive_group = ArchiveFileGroup::from_buffer(&buffer, entry_count);
+        <D as FetchDefinition>::decode_group(&group, &DecodeContext { revision, ..ctx })
+    }
 
-        let mut definitions = HashMap::new();
-        for archive_file in archive_group {
-            definitions.insert(
-                archive_file.id as u16,
-                D::new(archive_file.id as u16, &archive_file.data)?,
-            );
-        }
+    /// Fetches the definitions from a single archive that is encrypted with
+    /// XTEA.
+    ///
+    /// # Errors
+    ///
+    /// Can return multiple errors: if reading, deciphering, decoding or parsing
+    /// definition buffers fail.
+    fn fetch_from_archive_with_keys(
+        cache: &Cache,
+        index_id: u8,
+        archive_id: u32,
+        keys: &[u32; 4],
+    ) -> crate::Result<HashMap<u32, Self>> {
+        let (group, ctx) = read_group(cache, index_id, archive_id, Some(keys))?;
 
-        Ok(definitions)
+        Self::decode_group(&group, &ctx)
+    }
+
+    /// Fetches the definitions from a single archive that is looked up by its
+    /// name, see [`Cache::archive_by_name`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `NameHash` if no archive has the name, or any error of
+    /// [`fetch_from_archive`](FetchDefinition::fetch_from_archive).
+    fn fetch_from_name<T: AsRef<str>>(
+        cache: &Cache,
+        index_id: u8,
+        name: T,
+    ) -> crate::Result<HashMap<u32, Self>> {
+        let archive_id = cache.archive_by_name(index_id, name)?.id;
+
+        Self::fetch_from_archive(cache, index_id, archive_id)
+    }
+
+    /// Fetches the definition in a single file of an archive, or `None` if the
+    /// archive has no such file.
+    ///
+    /// The other files of the archive are not decoded.
+    ///
+    /// # Errors
+    ///
+    /// Can return multiple errors: if reading, decoding or parsing the
+    /// definition buffer fails.
+    fn fetch_from_file(
+        cache: &Cache,
+        index_id: u8,
+        archive_id: u32,
+        file_id: u32,
+    ) -> crate::Result<Option<Self>> {
+        fetch_file(cache, index_id, archive_id, file_id, Revision::default())
+    }
+
+    /// Reads a single archive and splits it into its files, without decoding
+    /// them.
+    ///
+    /// The files are numbered `0..entry_count`, like
+    /// [`ArchiveFileGroup::try_from_buffer`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is not in the reference table, or if
+    /// reading, decoding or splitting the archive fails.
+    fn fetch_group(
+        cache: &Cache,
+        index_id: u8,
+        archive_id: u32,
+    ) -> crate::Result<ArchiveFileGroup> {
+        read_group(cache, index_id, archive_id, None).map(|(group, _)| group)
+    }
+
+    /// Reads a single archive that is encrypted with XTEA and splits it into
+    /// its files, see [`fetch_group`](FetchDefinition::fetch_group).
+    ///
+    /// # Errors
+    ///
+    /// See [`fetch_group`](FetchDefinition::fetch_group).
+    fn fetch_group_with_keys(
+        cache: &Cache,
+        index_id: u8,
+        archive_id: u32,
+        keys: &[u32; 4],
+    ) -> crate::Result<ArchiveFileGroup> {
+        read_group(cache, index_id, archive_id, Some(keys)).map(|(group, _)| group)
+    }
+
+    /// Reads a single archive that is looked up by its name and splits it into
+    /// its files, see [`fetch_group`](FetchDefinition::fetch_group).
+    ///
+    /// # Errors
+    ///
+    /// Returns `NameHash` if no archive has the name, or any error of
+    /// [`fetch_group`](FetchDefinition::fetch_group).
+    fn fetch_group_by_name<T: AsRef<str>>(
+        cache: &Cache,
+        index_id: u8,
+        name: T,
+    ) -> crate::Result<ArchiveFileGroup> {
+        let archive_id = cache.archive_by_name(index_id, name)?.id;
+
+        Self::fetch_group(cache, index_id, archive_id)
+    }
+
+    /// Decodes every file of a group, keyed by file id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing a definition fails.
+    fn decode_group(
+        group: &ArchiveFileGroup,
+        ctx: &DecodeContext,
+    ) -> crate::Result<HashMap<u32, Self>> {
+        group
+            .files()
+            .iter()
+            .map(|file| Ok((file.id, Self::new(file.id, file, ctx)?)))
+            .collect()
     }
 }
 
 impl<D: Definition> FetchDefinition for D {}
+
+/// Decodes the definition in file `file_id` of an archive, without decoding
+/// the other files, or returns `None` if the archive has no such file.
+pub(crate) fn fetch_file<D: Definition>(
+    cache: &Cache,
+    index_id: u8,
+    archive_id: u32,
+    file_id: u32,
+    revision: Revision,
+) -> crate::Result<Option<D>> {
+    let (group, ctx) = read_group(cache, index_id, archive_id, None)?;
+    let ctx = DecodeContext { revision, ..ctx };
+
+    group
+        .into_iter()
+        .find(|file| file.id == file_id)
+        .map(|file| D::new(file_id, &file, &ctx))
+        .transpose()
+}
+
+/// Reads an archive, deciphering it if `keys` are given, and splits it into its
+/// files. The context has the version of the archive and the default revision.
+fn read_group(
+    cache: &Cache,
+    index_id: u8,
+    archive_id: u32,
+    keys: Option<&[u32; 4]>,
+) -> crate::Result<(ArchiveFileGroup, DecodeContext)> {
+    let archive = cache
+        .index_metadata(index_id)?
+        .get(archive_id)
+        .ok_or(RuneFsError::Read(ReadError::ArchiveNotFound {
+            idx: index_id,
+            arc: archive_id,
+        }))?;
+    let buffer = match keys {
+        Some(keys) => cache.read_decoded_shared_with_keys(index_id, archive_id, keys)?,
+        None => cache.read_decoded_shared(index_id, archive_id)?,
+    };
+    let ctx = DecodeContext {
+        version: archive.version,
+        ..DecodeContext::with_cache(cache, index_id, archive_id)
+    };
+
+    let group = ArchiveFileGroup::try_from_buffer(&buffer, archive.entry_count)?;
+
+    Ok((group, ctx))
+}
+
+/// Decodes the definition of an index that stores one definition per archive,
+/// or returns `None` if the index has no archive `archive_id`.
+pub(crate) fn fetch_archive<D: Definition>(
+    cache: &Cache,
+    index_id: u8,
+    archive_id: u32,
+    revision: Revision,
+) -> crate::Result<Option<D>> {
+    let Some(archive) = cache.index_metadata(index_id)?.get(archive_id) else {
+        return Ok(None);
+    };
+    let buffer = cache.read_decoded_shared(index_id, archive_id)?;
+    let ctx = DecodeContext {
+        version: archive.version,
+        revision,
+        ..DecodeContext::with_cache(cache, index_id, archive_id)
+    };
+
+    D::from_buffer(archive_id, &buffer, &ctx).map(Some)
+}
@@ -0,0 +1,71 @@
+use crate::extension::WriteExt;
+
+/// Writes `opcode` followed by the value, if there is one.
+pub(crate) fn write_u8_opcode(buffer: &mut Vec<u8>, opcode: u8, value: Option<u8>) {
+    if let Some(value) = value {
+        buffer.write_u8(opcode);
+        buffer.write_u8(value);
+    }
+}
+
+/// Writes `opcode` followed by the value, if there is one.
+pub(crate) fn write_u16_opcode(buffer: &mut Vec<u8>, opcode: u8, value: Option<u16>) {
+    if let Some(value) = value {
+        buffer.write_u8(opcode);
+        buffer.write_u16(value);
+    }
+}
+
+/// Writes the pairs of a color or texture replacement, prefixed by their
+/// amount.
+pub(crate) fn write_replacements(buffer: &mut Vec<u8>, find: &[u16], replace: &[u16]) {
+    buffer.write_u8(find.len().min(replace.len()) as u8);
+    for (&find, &replace) in find.iter().zip(replace) {
+        buffer.write_u16(find);
+        buffer.write_u16(replace);
+    }
+}
+
+/// Writes `opcode` followed by the values, prefixed by their amount, if there
+/// are any.
+pub(crate) fn write_u16_list(buffer: &mut Vec<u8>, opcode: u8, values: &[u16]) {
+    if values.is_empty() {
+        return;
+    }
+
+    buffer.write_u8(opcode);
+    buffer.write_u8(values.len() as u8);
+    for &value in values {
+        buffer.write_u16(value);
+    }
+}
+
+/// Writes the transform table of an npc or object, if it has one.
+///
+/// The table is written with the first opcode, or with the second one when it
+/// has a default. Missing variables are written as `u16::MAX`.
+pub(crate) fn write_transform(
+    buffer: &mut Vec<u8>,
+    opcodes: (u8, u8),
+    (varbit_id, varp_id): (Option<u16>, Option<u16>),
+    default: Option<u16>,
+    configs: &[u16],
+) {
+    if configs.is_empty() {
+        return;
+    }
+
+    match default {
+        Some(_) => buffer.write_u8(opcodes.1),
+        None => buffer.write_u8(opcodes.0),
+    }
+    buffer.write_u16(varbit_id.unwrap_or(u16::MAX));
+    buffer.write_u16(varp_id.unwrap_or(u16::MAX));
+    if let Some(default) = default {
+        buffer.write_u16(default);
+    }
+    buffer.write_u8((configs.len() - 1) as u8);
+    for &config in configs {
+        buffer.write_u16(config);
+    }
+}
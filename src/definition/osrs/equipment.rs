@@ -0,0 +1,190 @@
+//! Equipment bonuses of items.
+//!
+//! The bonuses of equipable items are stored in the params of their
+//! [`ItemDefinition`], the keys of those params are listed here.
+//!
+//! # Examples
+//!
+//! ```
+//! use rscache::definition::osrs::{equipment, ItemDefinition};
+//!
+//! let mut item = ItemDefinition::default();
+//! item.wear_pos1 = Some(3);
+//! item.params.insert(equipment::SLASH_ATTACK, "82".to_owned());
+//! item.params.insert(equipment::ATTACK_SPEED, "4".to_owned());
+//!
+//! let stats = item.equipment_stats().unwrap();
+//! assert_eq!(stats.slot, Some(equipment::EquipmentSlot::Weapon));
+//! assert_eq!(stats.attack.slash, 82);
+//! assert_eq!(stats.attack_speed, Some(4));
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::ItemDefinition;
+
+pub const STAB_ATTACK: u32 = 0;
+pub const SLASH_ATTACK: u32 = 1;
+pub const CRUSH_ATTACK: u32 = 2;
+pub const MAGIC_ATTACK: u32 = 3;
+pub const RANGED_ATTACK: u32 = 4;
+pub const STAB_DEFENCE: u32 = 5;
+pub const SLASH_DEFENCE: u32 = 6;
+pub const CRUSH_DEFENCE: u32 = 7;
+pub const MAGIC_DEFENCE: u32 = 8;
+pub const RANGED_DEFENCE: u32 = 9;
+pub const MELEE_STRENGTH: u32 = 10;
+pub const PRAYER: u32 = 11;
+/// Attack speed in game ticks, only set on weapons.
+pub const ATTACK_SPEED: u32 = 14;
+pub const RANGED_STRENGTH: u32 = 189;
+/// Magic damage in tenths of a percent.
+pub const MAGIC_DAMAGE: u32 = 299;
+
+const STAT_PARAMS: [u32; 15] = [
+    STAB_ATTACK,
+    SLASH_ATTACK,
+    CRUSH_ATTACK,
+    MAGIC_ATTACK,
+    RANGED_ATTACK,
+    STAB_DEFENCE,
+    SLASH_DEFENCE,
+    CRUSH_DEFENCE,
+    MAGIC_DEFENCE,
+    RANGED_DEFENCE,
+    MELEE_STRENGTH,
+    PRAYER,
+    ATTACK_SPEED,
+    RANGED_STRENGTH,
+    MAGIC_DAMAGE,
+];
+
+/// The equipment slot an item is worn in.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum EquipmentSlot {
+    Head,
+    Cape,
+    Amulet,
+    Weapon,
+    Body,
+    Shield,
+    Legs,
+    Hands,
+    Feet,
+    Ring,
+    Ammo,
+}
+
+impl EquipmentSlot {
+    /// The slot of a wear position, `None` for the positions that only hide
+    /// body parts like the arms, hair and jaw.
+    pub const fn from_wear_pos(wear_pos: u8) -> Option<Self> {
+        match wear_pos {
+            0 => Some(Self::Head),
+            1 => Some(Self::Cape),
+            2 => Some(Self::Amulet),
+            3 => Some(Self::Weapon),
+            4 => Some(Self::Body),
+            5 => Some(Self::Shield),
+            7 => Some(Self::Legs),
+            9 => Some(Self::Hands),
+            10 => Some(Self::Feet),
+            12 => Some(Self::Ring),
+            13 => Some(Self::Ammo),
+            _ => None,
+        }
+    }
+
+    /// The wear position of the slot, which is also its index in the
+    /// equipment container.
+    pub const fn wear_pos(self) -> u8 {
+        match self {
+            Self::Head => 0,
+            Self::Cape => 1,
+            Self::Amulet => 2,
+            Self::Weapon => 3,
+            Self::Body => 4,
+            Self::Shield => 5,
+            Self::Legs => 7,
+            Self::Hands => 9,
+            Self::Feet => 10,
+            Self::Ring => 12,
+            Self::Ammo => 13,
+        }
+    }
+}
+
+/// A bonus for every attack style.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+pub struct StyleBonuses {
+    pub stab: i32,
+    pub slash: i32,
+    pub crush: i32,
+    pub magic: i32,
+    pub ranged: i32,
+}
+
+/// The bonuses an item gives when it is equipped.
+///
+/// Created with [`ItemDefinition::equipment_stats`], bonuses the item has no
+/// param for are 0.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+pub struct EquipmentStats {
+    pub slot: Option<EquipmentSlot>,
+    pub attack: StyleBonuses,
+    pub defence: StyleBonuses,
+    pub melee_strength: i32,
+    pub ranged_strength: i32,
+    pub magic_damage: i32,
+    pub prayer: i32,
+    pub attack_speed: Option<u8>,
+}
+
+impl ItemDefinition {
+    /// The equipment bonuses of this item.
+    ///
+    /// Returns `None` if the item has neither a wear position nor any of the
+    /// bonus params.
+    pub fn equipment_stats(&self) -> Option<EquipmentStats> {
+        let has_stats = STAT_PARAMS.iter().any(|key| self.params.contains_key(key));
+        if self.wear_pos1.is_none() && !has_stats {
+            return None;
+        }
+
+        let param = |key| self.int_param(key).unwrap_or_default();
+
+        Some(EquipmentStats {
+            slot: self.wear_pos1.and_then(EquipmentSlot::from_wear_pos),
+            attack: StyleBonuses {
+                stab: param(STAB_ATTACK),
+                slash: param(SLASH_ATTACK),
+                crush: param(CRUSH_ATTACK),
+                magic: param(MAGIC_ATTACK),
+                ranged: param(RANGED_ATTACK),
+            },
+            defence: StyleBonuses {
+                stab: param(STAB_DEFENCE),
+                slash: param(SLASH_DEFENCE),
+                crush: param(CRUSH_DEFENCE),
+                magic: param(MAGIC_DEFENCE),
+                ranged: param(RANGED_DEFENCE),
+            },
+            melee_strength: param(MELEE_STRENGTH),
+            ranged_strength: param(RANGED_STRENGTH),
+            magic_damage: param(MAGIC_DAMAGE),
+            prayer: param(PRAYER),
+            attack_speed: self
+                .int_param(ATTACK_SPEED)
+                .and_then(|speed| u8::try_from(speed).ok()),
+        })
+    }
+
+    /// The param with the given key, if it is an integer.
+    pub fn int_param(&self, key: u32) -> Option<i32> {
+        self.params.get(&key)?.parse().ok()
+    }
+}
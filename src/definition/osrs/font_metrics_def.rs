@@ -0,0 +1,59 @@
+use std::{io, io::BufReader};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+};
+
+/// The amount of glyphs in a font, one for every cp1252 character.
+pub const GLYPH_COUNT: usize = 256;
+
+/// Contains the glyph metrics of a font fetched from the cache through the
+/// [FontMetricsLoader](../../loader/osrs/struct.FontMetricsLoader.html).
+///
+/// The id is the same as the id of the sprite that holds the glyphs of the font.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct FontMetricsDefinition {
+    pub id: u32,
+    /// The horizontal advance of every glyph, indexed by cp1252 character.
+    pub advances: Vec<u8>,
+    pub ascent: u8,
+}
+
+impl FontMetricsDefinition {
+    /// Returns the advance of a single glyph.
+    #[inline]
+    pub fn advance(&self, glyph: u8) -> u8 {
+        self.advances[glyph as usize]
+    }
+
+    /// Returns the width of `text` in pixels, every byte is treated as a glyph.
+    #[inline]
+    pub fn text_width(&self, text: &[u8]) -> u32 {
+        text.iter().map(|&glyph| self.advance(glyph) as u32).sum()
+    }
+}
+
+impl Definition for FontMetricsDefinition {
+    fn new(id: u32, file: &ArchiveFileData, _: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let font_metrics_def = decode_buffer(id, &mut reader)?;
+
+        Ok(font_metrics_def)
+    }
+}
+
+fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<FontMetricsDefinition> {
+    let mut advances = vec![0; GLYPH_COUNT];
+    io::Read::read_exact(reader, &mut advances)?;
+
+    Ok(FontMetricsDefinition {
+        id,
+        advances,
+        ascent: reader.read_u8()?,
+    })
+}
@@ -0,0 +1,79 @@
+use std::io::BufReader;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+};
+
+/// Contains the sprite ids the client uses for its built-in interface graphics,
+/// fetched from the cache through the
+/// [DefaultsLoader](../../loader/osrs/struct.DefaultsLoader.html).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct GraphicsDefaultsDefinition {
+    pub id: u32,
+    pub compass: Option<u32>,
+    pub map_edge: Option<u32>,
+    pub map_scenes: Option<u32>,
+    pub head_icons_pk: Option<u32>,
+    pub head_icons_prayer: Option<u32>,
+    pub head_icons_hint: Option<u32>,
+    pub map_markers: Option<u32>,
+    pub cross_sprites: Option<u32>,
+    pub map_dots: Option<u32>,
+    pub scroll_bars: Option<u32>,
+    pub mod_icons: Option<u32>,
+}
+
+impl Definition for GraphicsDefaultsDefinition {
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let graphics_defaults_def = decode_buffer(id, &mut reader, ctx)?;
+
+        Ok(graphics_defaults_def)
+    }
+}
+
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<GraphicsDefaultsDefinition> {
+    let mut graphics_defaults_def = GraphicsDefaultsDefinition {
+        id,
+        ..GraphicsDefaultsDefinition::default()
+    };
+
+    loop {
+        let opcode = reader.read_u8()?;
+
+        match opcode {
+            0 => break,
+            1 => {
+                reader.read_u24()?;
+            }
+            2 => {
+                graphics_defaults_def.compass = reader.read_nullable_smart()?;
+                graphics_defaults_def.map_edge = reader.read_nullable_smart()?;
+                graphics_defaults_def.map_scenes = reader.read_nullable_smart()?;
+                graphics_defaults_def.head_icons_pk = reader.read_nullable_smart()?;
+                graphics_defaults_def.head_icons_prayer = reader.read_nullable_smart()?;
+                graphics_defaults_def.head_icons_hint = reader.read_nullable_smart()?;
+                graphics_defaults_def.map_markers = reader.read_nullable_smart()?;
+                graphics_defaults_def.cross_sprites = reader.read_nullable_smart()?;
+                graphics_defaults_def.map_dots = reader.read_nullable_smart()?;
+                graphics_defaults_def.scroll_bars = reader.read_nullable_smart()?;
+                graphics_defaults_def.mod_icons = reader.read_nullable_smart()?;
+            }
+            _ => {
+                ctx.unknown_opcode("graphics defaults", id, opcode)?;
+                break;
+            }
+        }
+    }
+
+    Ok(graphics_defaults_def)
+}
@@ -1,23 +1,40 @@
-use std::{collections::HashMap, io, io::BufReader};
+use std::{collections::HashMap, io::BufReader};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Definition;
-use crate::{extension::ReadExt, util};
+use crate::{
+    definition::{
+        osrs::encode::{write_replacements, write_u16_opcode, write_u8_opcode},
+        ArchiveFileData, DecodeContext, Definition,
+    },
+    error::DefinitionError,
+    extension::{ReadExt, WriteExt},
+    util, Protocol,
+};
 
 /// Contains all the information about a certain item fetched from the cache through
 /// the [ItemLoader](../../loader/osrs/struct.ItemLoader.html).
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
 pub struct ItemDefinition {
-    pub id: u16,
+    pub id: u32,
     pub name: String,
+    pub examine: String,
     pub stackable: bool,
     pub cost: i32,
     pub members_only: bool,
+    pub wear_pos1: Option<u8>,
+    pub wear_pos2: Option<u8>,
+    pub wear_pos3: Option<u8>,
     pub options: [String; 5],
     pub interface_options: [String; 5],
+    /// The options of the menus under the interface options, keyed by their
+    /// index in the menu.
+    pub interface_sub_options: [HashMap<u8, String>; 5],
+    /// Weight in grams.
+    pub weight: i16,
+    pub category: Option<u16>,
     pub tradable: bool,
     pub noted_id: Option<u16>,
     pub noted_template: Option<u16>,
@@ -26,6 +43,8 @@ pub struct ItemDefinition {
     pub team: u8,
     pub bought_link: Option<u16>,
     pub bought_tempalte: Option<u16>,
+    pub placeholder_id: Option<u16>,
+    pub placeholder_template: Option<u16>,
     pub shift_click_drop_index: Option<u8>,
     pub params: HashMap<u32, String>,
     pub inventory_model_data: InventoryModelData,
@@ -70,16 +89,293 @@ pub struct CharacterModelData {
     pub female_head_model2: Option<u16>,
 }
 
+impl ItemDefinition {
+    /// The id of the item that is shown for a stack of `count` items.
+    ///
+    /// Items like coins and arrows look different depending on the size of
+    /// the stack, the client picks the last variant whose count is reached.
+    /// Returns the id of this item if it has no variant for the count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// use rscache::loader::osrs::ItemLoader;
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let item_loader = ItemLoader::new(&cache)?;
+    /// let coins = item_loader.load(995).unwrap();
+    ///
+    /// assert_eq!(coins.stack_variant(1), 995);
+    /// assert_eq!(coins.stack_variant(100), 1001);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stack_variant(&self, count: u32) -> u16 {
+        let id = self.id as u16;
+        let (Some(stack_ids), Some(stack_count)) = (self.stack_ids, self.stack_count) else {
+            return id;
+        };
+        if count <= 1 {
+            return id;
+        }
+
+        stack_ids
+            .iter()
+            .zip(stack_count)
+            .rfind(|&(_, stack_count)| stack_count != 0 && count >= stack_count as u32)
+            .map_or(id, |(&stack_id, _)| stack_id)
+    }
+
+    /// Encodes the definition into the opcode stream it is decoded from.
+    ///
+    /// Fields that still have the value the decoder starts out with are left
+    /// out, so the buffer can be smaller than the one in the cache but decodes
+    /// into the same definition. The unused opcode 9 is never written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::definition::osrs::{DecodeContext, Definition, ItemDefinition};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let ctx = DecodeContext::new(2, 10);
+    /// let mut item = ItemDefinition::from_buffer(30_000, &[0], &ctx)?;
+    /// item.name = "Golden gnome".to_owned();
+    /// item.cost = 1_000;
+    ///
+    /// let decoded = ItemDefinition::from_buffer(30_000, &item.encode(), &ctx)?;
+    /// assert_eq!(decoded, item);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let model = &self.inventory_model_data;
+        let character = &self.character_model_data;
+
+        if model.inventory_model != 0 {
+            buffer.write_u8(1);
+            buffer.write_u16(model.inventory_model);
+        }
+        if !self.name.is_empty() {
+            buffer.write_u8(2);
+            buffer.write_string(&self.name);
+        }
+        if !self.examine.is_empty() {
+            buffer.write_u8(3);
+            buffer.write_string(&self.examine);
+        }
+        let view = [
+            (4, model.zoom2d, 2000),
+            (5, model.x_an2d, 0),
+            (6, model.y_an2d, 0),
+            (7, model.x_offset2d, 0),
+            (8, model.y_offset2d, 0),
+        ];
+        for (opcode, value, default) in view {
+            if value != default {
+                buffer.write_u8(opcode);
+                buffer.write_u16(value);
+            }
+        }
+        // Noted items are made stackable by their template opcode.
+        if self.stackable && self.noted_template.is_none() {
+            buffer.write_u8(11);
+        }
+        if self.cost != 0 {
+            buffer.write_u8(12);
+            buffer.write_i32(self.cost);
+        }
+        write_u8_opcode(&mut buffer, 13, self.wear_pos1);
+        write_u8_opcode(&mut buffer, 14, self.wear_pos2);
+        if self.members_only {
+            buffer.write_u8(16);
+        }
+        if let Some(male_model10) = character.male_model10 {
+            buffer.write_u8(23);
+            buffer.write_u16(male_model10);
+            buffer.write_u8(character.male_model_offset);
+        }
+        write_u16_opcode(&mut buffer, 24, character.male_model1);
+        if let Some(female_model10) = character.female_model10 {
+            buffer.write_u8(25);
+            buffer.write_u16(female_model10);
+            buffer.write_u8(character.female_model_offset);
+        }
+        write_u16_opcode(&mut buffer, 26, character.female_model1);
+        write_u8_opcode(&mut buffer, 27, self.wear_pos3);
+
+        let defaults = Self::default_options();
+        for (i, option) in self.options.iter().enumerate() {
+            if *option != defaults.0[i] {
+                buffer.write_u8(30 + i as u8);
+                buffer.write_string(option);
+            }
+        }
+        for (i, option) in self.interface_options.iter().enumerate() {
+            if *option != defaults.1[i] {
+                buffer.write_u8(35 + i as u8);
+                buffer.write_string(option);
+            }
+        }
+
+        if !model.color_find.is_empty() {
+            buffer.write_u8(40);
+            write_replacements(&mut buffer, &model.color_find, &model.color_replace);
+        }
+        if !model.texture_find.is_empty() {
+            buffer.write_u8(41);
+            write_replacements(&mut buffer, &model.texture_find, &model.texture_replace);
+        }
+        write_u8_opcode(&mut buffer, 42, self.shift_click_drop_index);
+        for (option, sub_options) in self.interface_sub_options.iter().enumerate() {
+            if sub_options.is_empty() {
+                continue;
+            }
+
+            let mut sub_options: Vec<_> = sub_options.iter().collect();
+            sub_options.sort_unstable_by_key(|&(&sub_option, _)| sub_option);
+
+            buffer.write_u8(43);
+            buffer.write_u8(option as u8);
+            for (&sub_option, text) in sub_options {
+                buffer.write_u8(sub_option + 1);
+                buffer.write_string(text);
+            }
+            buffer.write_u8(0);
+        }
+
+        if self.tradable {
+            buffer.write_u8(65);
+        }
+        if self.weight != 0 {
+            buffer.write_u8(75);
+            buffer.write_i16(self.weight);
+        }
+        write_u16_opcode(&mut buffer, 78, character.male_model12);
+        write_u16_opcode(&mut buffer, 79, character.female_model12);
+        write_u16_opcode(&mut buffer, 90, character.male_head_model1);
+        write_u16_opcode(&mut buffer, 91, character.female_head_model1);
+        write_u16_opcode(&mut buffer, 92, character.male_head_model2);
+        write_u16_opcode(&mut buffer, 93, character.female_head_model2);
+        write_u16_opcode(&mut buffer, 94, self.category);
+        if model.z_an2d != 0 {
+            buffer.write_u8(95);
+            buffer.write_u16(model.z_an2d);
+        }
+        write_u16_opcode(&mut buffer, 97, self.noted_id);
+        write_u16_opcode(&mut buffer, 98, self.noted_template);
+
+        if self.stack_ids.is_some() || self.stack_count.is_some() {
+            let stack_ids = self.stack_ids.unwrap_or_default();
+            let stack_count = self.stack_count.unwrap_or_default();
+            for (i, (stack_id, count)) in stack_ids.into_iter().zip(stack_count).enumerate() {
+                // The first variant is always written so the arrays decode as `Some`.
+                if i == 0 || stack_id != 0 || count != 0 {
+                    buffer.write_u8(100 + i as u8);
+                    buffer.write_u16(stack_id);
+                    buffer.write_u16(count);
+                }
+            }
+        }
+
+        let resize = [
+            (110, model.resize_x),
+            (111, model.resize_y),
+            (112, model.resize_z),
+        ];
+        for (opcode, value) in resize {
+            if value != 128 {
+                buffer.write_u8(opcode);
+                buffer.write_u16(value);
+            }
+        }
+        if model.ambient != 0 {
+            buffer.write_u8(113);
+            buffer.write_i8(model.ambient);
+        }
+        if model.contrast != 0 {
+            buffer.write_u8(114);
+            buffer.write_i8(model.contrast);
+        }
+        if self.team != 0 {
+            buffer.write_u8(115);
+            buffer.write_u8(self.team);
+        }
+        write_u16_opcode(&mut buffer, 139, self.bought_link);
+        write_u16_opcode(&mut buffer, 140, self.bought_tempalte);
+        write_u16_opcode(&mut buffer, 148, self.placeholder_id);
+        write_u16_opcode(&mut buffer, 149, self.placeholder_template);
+        if !self.params.is_empty() {
+            buffer.write_u8(249);
+            util::write_parameters(&mut buffer, &self.params);
+        }
+
+        buffer.write_u8(0);
+        buffer
+    }
+
+    /// The options and interface options an item has when its definition
+    /// doesn't set them.
+    fn default_options() -> ([String; 5], [String; 5]) {
+        (
+            [
+                "".to_string(),
+                "".to_string(),
+                "Take".to_string(),
+                "".to_string(),
+                "".to_string(),
+            ],
+            [
+                "".to_string(),
+                "".to_string(),
+                "".to_string(),
+                "".to_string(),
+                "Drop".to_string(),
+            ],
+        )
+    }
+
+    /// Turns this noted item into the definition the client shows, with the
+    /// model of the note `template` and the name, cost and members flag of the
+    /// `unnoted` item.
+    pub(crate) fn apply_note(&mut self, template: &Self, unnoted: &Self) {
+        self.inventory_model_data = template.inventory_model_data.clone();
+        self.name = unnoted.name.clone();
+        self.cost = unnoted.cost;
+        self.members_only = unnoted.members_only;
+        self.stackable = true;
+    }
+
+    /// Turns this placeholder into the definition the client shows, with the
+    /// model of the placeholder `template` and the name of the item it holds
+    /// the place of. Placeholders are worth nothing.
+    pub(crate) fn apply_placeholder(&mut self, template: &Self, item: &Self) {
+        self.inventory_model_data = template.inventory_model_data.clone();
+        self.name = item.name.clone();
+        self.members_only = item.members_only;
+        self.stackable = item.stackable;
+        self.cost = 0;
+    }
+}
+
 impl Definition for ItemDefinition {
-    fn new(id: u16, buffer: &[u8]) -> crate::Result<Self> {
-        let mut reader = BufReader::new(buffer);
-        let item_def = decode_buffer(id, &mut reader)?;
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let item_def = decode_buffer(id, &mut reader, ctx)?;
 
         Ok(item_def)
     }
 }
 
-fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefinition> {
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<ItemDefinition> {
+    let (options, interface_options) = ItemDefinition::default_options();
     let mut item_def = ItemDefinition {
         id,
         inventory_model_data: InventoryModelData {
@@ -89,20 +385,8 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
             zoom2d: 2000,
             ..InventoryModelData::default()
         },
-        options: [
-            "".to_string(),
-            "".to_string(),
-            "Take".to_string(),
-            "".to_string(),
-            "".to_string(),
-        ],
-        interface_options: [
-            "".to_string(),
-            "".to_string(),
-            "".to_string(),
-            "".to_string(),
-            "Drop".to_string(),
-        ],
+        options,
+        interface_options,
         ..ItemDefinition::default()
     };
 
@@ -112,11 +396,14 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
         match opcode {
             0 => break,
             1 => {
-                item_def.inventory_model_data.inventory_model = reader.read_u16()?;
+                item_def.inventory_model_data.inventory_model = read_model_id(reader, id, ctx)?;
             }
             2 => {
                 item_def.name = reader.read_string()?;
             }
+            3 => {
+                item_def.examine = reader.read_string()?;
+            }
             4 => {
                 item_def.inventory_model_data.zoom2d = reader.read_u16()?;
             }
@@ -132,26 +419,40 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
             8 => {
                 item_def.inventory_model_data.y_offset2d = reader.read_u16()?;
             }
+            9 => {
+                // Read by the client but never used.
+                reader.read_string()?;
+            }
             11 => {
                 item_def.stackable = true;
             }
             12 => {
                 item_def.cost = reader.read_i32()?;
             }
+            13 => {
+                item_def.wear_pos1 = Some(reader.read_u8()?);
+            }
+            14 => {
+                item_def.wear_pos2 = Some(reader.read_u8()?);
+            }
             16 => item_def.members_only = true,
             23 => {
-                item_def.character_model_data.male_model10 = Some(reader.read_u16()?);
+                item_def.character_model_data.male_model10 = Some(read_model_id(reader, id, ctx)?);
                 item_def.character_model_data.male_model_offset = reader.read_u8()?;
             }
             24 => {
-                item_def.character_model_data.male_model1 = Some(reader.read_u16()?);
+                item_def.character_model_data.male_model1 = Some(read_model_id(reader, id, ctx)?);
             }
             25 => {
-                item_def.character_model_data.female_model10 = Some(reader.read_u16()?);
+                item_def.character_model_data.female_model10 =
+                    Some(read_model_id(reader, id, ctx)?);
                 item_def.character_model_data.female_model_offset = reader.read_u8()?;
             }
             26 => {
-                item_def.character_model_data.female_model1 = Some(reader.read_u16()?);
+                item_def.character_model_data.female_model1 = Some(read_model_id(reader, id, ctx)?);
+            }
+            27 => {
+                item_def.wear_pos3 = Some(reader.read_u8()?);
             }
             30..=34 => {
                 item_def.options[opcode as usize - 30] = reader.read_string()?;
@@ -192,26 +493,53 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
             42 => {
                 item_def.shift_click_drop_index = Some(reader.read_u8()?);
             }
+            43 => {
+                let option = reader.read_u8()? as usize;
+                loop {
+                    let sub_option = reader.read_u8()?;
+                    if sub_option == 0 {
+                        break;
+                    }
+                    let text = reader.read_string()?;
+                    // The client ignores sub options that don't fit its menus.
+                    if let Some(sub_options) = item_def.interface_sub_options.get_mut(option) {
+                        if sub_option <= 20 {
+                            sub_options.insert(sub_option - 1, text);
+                        }
+                    }
+                }
+            }
             65 => {
                 item_def.tradable = true;
             }
+            75 => {
+                item_def.weight = reader.read_i16()?;
+            }
             78 => {
-                item_def.character_model_data.male_model12 = Some(reader.read_u16()?);
+                item_def.character_model_data.male_model12 = Some(read_model_id(reader, id, ctx)?);
             }
             79 => {
-                item_def.character_model_data.female_model12 = Some(reader.read_u16()?);
+                item_def.character_model_data.female_model12 =
+                    Some(read_model_id(reader, id, ctx)?);
             }
             90 => {
-                item_def.character_model_data.male_head_model1 = Some(reader.read_u16()?);
+                item_def.character_model_data.male_head_model1 =
+                    Some(read_model_id(reader, id, ctx)?);
             }
             91 => {
-                item_def.character_model_data.female_head_model1 = Some(reader.read_u16()?);
+                item_def.character_model_data.female_head_model1 =
+                    Some(read_model_id(reader, id, ctx)?);
             }
             92 => {
-                item_def.character_model_data.male_head_model2 = Some(reader.read_u16()?);
+                item_def.character_model_data.male_head_model2 =
+                    Some(read_model_id(reader, id, ctx)?);
             }
             93 => {
-                item_def.character_model_data.female_head_model2 = Some(reader.read_u16()?);
+                item_def.character_model_data.female_head_model2 =
+                    Some(read_model_id(reader, id, ctx)?);
+            }
+            94 => {
+                item_def.category = Some(reader.read_u16()?);
             }
             95 => {
                 item_def.inventory_model_data.z_an2d = reader.read_u16()?;
@@ -224,20 +552,9 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
                 item_def.stackable = true;
             }
             100..=109 => {
-                item_def.stack_ids = Some([0; 10]);
-                item_def.stack_count = Some([0; 10]);
-                match item_def.stack_ids {
-                    Some(mut stack_ids) => {
-                        stack_ids[opcode as usize - 100] = reader.read_u16()?;
-                    }
-                    _ => unreachable!(),
-                }
-                match item_def.stack_count {
-                    Some(mut stack_count) => {
-                        stack_count[opcode as usize - 100] = reader.read_u16()?;
-                    }
-                    _ => unreachable!(),
-                }
+                let index = opcode as usize - 100;
+                item_def.stack_ids.get_or_insert([0; 10])[index] = reader.read_u16()?;
+                item_def.stack_count.get_or_insert([0; 10])[index] = reader.read_u16()?;
             }
             110 => {
                 item_def.inventory_model_data.resize_x = reader.read_u16()?;
@@ -263,15 +580,36 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
             140 => {
                 item_def.bought_tempalte = Some(reader.read_u16()?);
             }
-            148 | 149 => {
-                reader.read_u16()?;
+            148 => {
+                item_def.placeholder_id = Some(reader.read_u16()?);
+            }
+            149 => {
+                item_def.placeholder_template = Some(reader.read_u16()?);
             }
             249 => {
                 item_def.params = util::read_parameters(reader)?;
             }
-            _ => unreachable!(),
+            _ => {
+                ctx.unknown_opcode("item", id, opcode)?;
+                break;
+            }
         }
     }
 
     Ok(item_def)
 }
+
+/// Reads a model id, RS3 caches store them as big smarts.
+fn read_model_id(
+    reader: &mut BufReader<&[u8]>,
+    id: u32,
+    ctx: &DecodeContext,
+) -> crate::Result<u16> {
+    if ctx.protocol != Some(Protocol::Rs3) {
+        return Ok(reader.read_u16()?);
+    }
+
+    let model_id = reader.read_smart()?;
+
+    u16::try_from(model_id).map_err(|_| DefinitionError::ModelIdOverflow { id, model_id }.into())
+}
@@ -2,15 +2,18 @@ use nom::number::complete::be_u8;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Definition;
-use runefs::parse::{be_u16_smart, be_u32_smart_compat};
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    parse::{be_u16_smart, be_u32_smart_compat},
+    util::coords::RegionId,
+};
 
 /// Contains all the information about a certain location fetched from the cache through
 /// the [LocationLoader](../../loader/osrs/struct.LocationLoader.html).
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct LocationDefinition {
-    pub id: u16,
+    pub id: u32,
     pub region_x: u16,
     pub region_y: u16,
     pub data: Vec<Location>,
@@ -21,6 +24,19 @@ impl LocationDefinition {
     pub const fn region_base_coords(&self) -> (u16, u16) {
         (self.region_x << 6, self.region_y << 6)
     }
+
+    /// Iterates over the locations in `data` with their coordinates relative
+    /// to the region.
+    pub fn entries(&self) -> impl Iterator<Item = LocationEntry> + '_ {
+        self.data.iter().map(move |location| LocationEntry {
+            id: location.id,
+            x: location.pos.0.wrapping_sub(self.region_x) as u8,
+            y: location.pos.1.wrapping_sub(self.region_y) as u8,
+            plane: location.pos.2 as u8,
+            loc_type: location.loc_type,
+            rotation: location.orientation,
+        })
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -32,23 +48,50 @@ pub struct Location {
     pub pos: (u16, u16, u16),
 }
 
+/// A single object placed in a region.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct LocationEntry {
+    /// Id of the [`ObjectDefinition`](super::ObjectDefinition) that is placed.
+    pub id: u32,
+    /// X coordinate within the region, `0..64`.
+    pub x: u8,
+    /// Y coordinate within the region, `0..64`.
+    pub y: u8,
+    pub plane: u8,
+    pub loc_type: u8,
+    pub rotation: u8,
+}
+
+impl LocationEntry {
+    /// Returns the absolute `(x, y)` coordinates given the region the entry belongs to.
+    #[inline]
+    pub const fn absolute_coords(&self, region_base_coords: (u16, u16)) -> (u16, u16) {
+        (
+            region_base_coords.0 + self.x as u16,
+            region_base_coords.1 + self.y as u16,
+        )
+    }
+}
+
 impl Definition for LocationDefinition {
-    fn new(id: u16, buffer: &[u8]) -> crate::Result<Self> {
-        let loc_def = decode_buffer(id, buffer)?;
+    fn new(id: u32, file: &ArchiveFileData, _: &DecodeContext) -> crate::Result<Self> {
+        let loc_def = decode_buffer(id, &file.data)?;
 
         Ok(loc_def)
     }
 }
 
-fn decode_buffer(id: u16, mut buffer: &[u8]) -> crate::Result<LocationDefinition> {
+fn decode_buffer(id: u32, mut buffer: &[u8]) -> crate::Result<LocationDefinition> {
+    let region = RegionId(id as u16);
     let mut loc_def = LocationDefinition {
         id,
-        region_x: (id >> 8) & 0xFF,
-        region_y: id & 0xFF,
+        region_x: region.x().into(),
+        region_y: region.y().into(),
         ..LocationDefinition::default()
     };
 
-    let mut id = -1;
+    let mut id: i32 = -1;
 
     loop {
         let (buf, id_offset) = be_u32_smart_compat(buffer)?;
@@ -58,20 +101,19 @@ fn decode_buffer(id: u16, mut buffer: &[u8]) -> crate::Result<LocationDefinition
             break;
         }
 
-        id += id_offset as i32;
+        id = id.wrapping_add(id_offset as i32);
 
-        let mut pos = 0;
+        let mut pos: u16 = 0;
 
         loop {
             let (buf, pos_offset) = be_u16_smart(buffer)?;
-            let pos_offset = pos_offset;
             buffer = buf;
 
             if pos_offset == 0 {
                 break;
             }
 
-            pos += pos_offset - 1;
+            pos = pos.wrapping_add(pos_offset - 1);
 
             let local_x = pos >> 6 & 0x3F;
             let local_y = pos & 0x3F;
@@ -3,8 +3,11 @@ use std::{io, io::BufReader};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Definition;
-use crate::extension::ReadExt;
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+    util::coords::RegionId,
+};
 
 const X: usize = 64;
 const Y: usize = 64;
@@ -32,13 +35,65 @@ pub struct MapData {
     pub underlay_id: u8,
 }
 
+/// A single tile with its attributes parsed, see [`MapDefinition::tile`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Tile {
+    /// `None` if the client generates the height of this tile. The cache
+    /// stores a height of 0 as 1, so 0 never comes from the cache.
+    pub height: Option<u8>,
+    pub settings: u8,
+    pub overlay: Option<Overlay>,
+    pub underlay_id: Option<u8>,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Overlay {
+    pub id: u8,
+    pub path: u8,
+    pub rotation: u8,
+}
+
+impl Tile {
+    /// Returns `true` if the tile can't be walked on.
+    #[inline]
+    pub const fn is_blocked(&self) -> bool {
+        self.settings & 1 == 1
+    }
+
+    /// Returns `true` if the tile is a bridge, only used on plane 1.
+    #[inline]
+    pub const fn is_bridge(&self) -> bool {
+        self.settings & 2 == 2
+    }
+}
+
+impl From<&MapData> for Tile {
+    fn from(map_data: &MapData) -> Self {
+        // Overlay opcodes start at 2 and underlay ids at 1, so 0 means the
+        // attribute wasn't set.
+        let overlay = Overlay {
+            id: map_data.overlay_id as u8,
+            path: map_data.overlay_path,
+            rotation: map_data.overlay_rotation,
+        };
+
+        Self {
+            height: (map_data.height != 0).then_some(map_data.height),
+            settings: map_data.settings,
+            overlay: (map_data.attr_opcode != 0).then_some(overlay),
+            underlay_id: (map_data.underlay_id != 0).then_some(map_data.underlay_id),
+        }
+    }
+}
+
 impl Definition for MapDefinition {
-    fn new(id: u16, buffer: &[u8]) -> crate::Result<Self> {
-        let x = id >> 8;
-        let y = id & 0xFF;
+    fn new(id: u32, file: &ArchiveFileData, _: &DecodeContext) -> crate::Result<Self> {
+        let region = RegionId(id as u16);
 
-        let mut reader = BufReader::new(buffer);
-        let map_def = decode_buffer(x, y, &mut reader)?;
+        let mut reader = BufReader::new(file.data.as_slice());
+        let map_def = decode_buffer(region.x().into(), region.y().into(), &mut reader)?;
 
         Ok(map_def)
     }
@@ -49,6 +104,28 @@ impl MapDefinition {
         &self.data[z][x][y]
     }
 
+    /// Returns the parsed attributes of a single tile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the region.
+    #[inline]
+    pub fn tile(&self, x: usize, y: usize, plane: usize) -> Tile {
+        Tile::from(self.map_data(x, y, plane))
+    }
+
+    /// Iterates over all tiles as `(x, y, plane, tile)`.
+    pub fn tiles(&self) -> impl Iterator<Item = (usize, usize, usize, Tile)> + '_ {
+        self.data.iter().enumerate().flat_map(|(plane, rows)| {
+            rows.iter().enumerate().flat_map(move |(x, column)| {
+                column
+                    .iter()
+                    .enumerate()
+                    .map(move |(y, map_data)| (x, y, plane, Tile::from(map_data)))
+            })
+        })
+    }
+
     #[inline]
     pub const fn region_base_coords(&self) -> (u16, u16) {
         (self.region_x << 6, self.region_y << 6)
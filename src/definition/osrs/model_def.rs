@@ -0,0 +1,321 @@
+use std::io;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    error::DefinitionError,
+    extension::ReadExt,
+};
+
+const NEW_FORMAT_FOOTER_LEN: usize = 23;
+const OLD_FORMAT_FOOTER_LEN: usize = 18;
+
+/// Contains the geometry and colors of a model fetched from the cache.
+///
+/// Faces are triangles that index into the vertices, their colors are in the
+/// 16-bit HSL format of the client. Optional per face data is empty when the
+/// model doesn't have it.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ModelDefinition {
+    pub id: u32,
+    pub vertices_x: Vec<i32>,
+    pub vertices_y: Vec<i32>,
+    pub vertices_z: Vec<i32>,
+    pub vertex_skins: Vec<u8>,
+    pub faces_a: Vec<u16>,
+    pub faces_b: Vec<u16>,
+    pub faces_c: Vec<u16>,
+    pub face_colors: Vec<u16>,
+    pub face_render_types: Vec<u8>,
+    pub face_priorities: Vec<u8>,
+    pub face_alphas: Vec<u8>,
+    pub face_skins: Vec<u8>,
+    pub face_textures: Vec<Option<u16>>,
+    /// The priority of every face, unless the faces have their own.
+    pub priority: u8,
+}
+
+impl ModelDefinition {
+    #[inline]
+    pub fn vertex_count(&self) -> usize {
+        self.vertices_x.len()
+    }
+
+    #[inline]
+    pub fn face_count(&self) -> usize {
+        self.faces_a.len()
+    }
+}
+
+impl Definition for ModelDefinition {
+    fn new(id: u32, file: &ArchiveFileData, _: &DecodeContext) -> crate::Result<Self> {
+        let buffer = file.data.as_slice();
+        let model_def = match buffer {
+            [.., 0xFF, 0xFF] => decode_new_format(id, buffer)?,
+            [.., 0xFF, 0xFE] => {
+                return Err(DefinitionError::UnsupportedModelFormat { id, format: 2 }.into())
+            }
+            [.., 0xFF, 0xFD] => {
+                return Err(DefinitionError::UnsupportedModelFormat { id, format: 3 }.into())
+            }
+            _ => decode_old_format(id, buffer)?,
+        };
+
+        Ok(model_def)
+    }
+}
+
+/// The offsets of the sections that the old and new format have in common.
+struct Sections {
+    vertex_flags: usize,
+    vertices_x: usize,
+    vertices_y: usize,
+    vertices_z: usize,
+    vertex_skins: Option<usize>,
+    face_compression: usize,
+    face_indices: usize,
+    face_colors: usize,
+    face_priorities: Option<usize>,
+    face_alphas: Option<usize>,
+    face_skins: Option<usize>,
+}
+
+fn decode_new_format(id: u32, buffer: &[u8]) -> io::Result<ModelDefinition> {
+    let mut footer = section(buffer, buffer.len().saturating_sub(NEW_FORMAT_FOOTER_LEN))?;
+    let vertex_count = footer.read_u16()? as usize;
+    let face_count = footer.read_u16()? as usize;
+    let texture_face_count = footer.read_u8()? as usize;
+    let flags = footer.read_u8()?;
+    let priority = footer.read_u8()?;
+    let has_face_alphas = footer.read_u8()? == 1;
+    let has_face_skins = footer.read_u8()? == 1;
+    let has_face_textures = footer.read_u8()? == 1;
+    let has_vertex_skins = footer.read_u8()? == 1;
+    let vertices_x_len = footer.read_u16()? as usize;
+    let vertices_y_len = footer.read_u16()? as usize;
+    let vertices_z_len = footer.read_u16()? as usize;
+    let face_indices_len = footer.read_u16()? as usize;
+    let texture_coords_len = footer.read_u16()? as usize;
+
+    let mut offset = texture_face_count;
+    let mut next = |len: usize| {
+        let start = offset;
+        offset += len;
+        start
+    };
+    let vertex_flags = next(vertex_count);
+    let face_render_types = (flags & 1 == 1).then(|| next(face_count));
+    let face_compression = next(face_count);
+    let face_priorities = (priority == 255).then(|| next(face_count));
+    let face_skins = has_face_skins.then(|| next(face_count));
+    let vertex_skins = has_vertex_skins.then(|| next(vertex_count));
+    let face_alphas = has_face_alphas.then(|| next(face_count));
+    let face_indices = next(face_indices_len);
+    let face_textures = has_face_textures.then(|| next(face_count * 2));
+    // Texture coordinates, which are not needed to know the texture of a face.
+    next(texture_coords_len);
+    let face_colors = next(face_count * 2);
+    let vertices_x = next(vertices_x_len);
+    let vertices_y = next(vertices_y_len);
+    let vertices_z = next(vertices_z_len);
+
+    let sections = Sections {
+        vertex_flags,
+        vertices_x,
+        vertices_y,
+        vertices_z,
+        vertex_skins,
+        face_compression,
+        face_indices,
+        face_colors,
+        face_priorities,
+        face_alphas,
+        face_skins,
+    };
+    let mut model_def = decode_sections(id, buffer, vertex_count, face_count, &sections)?;
+    model_def.priority = priority;
+
+    if let Some(offset) = face_render_types {
+        model_def.face_render_types = section(buffer, offset)?
+            .get(..face_count)
+            .ok_or_else(eof)?
+            .to_vec();
+    }
+    if let Some(offset) = face_textures {
+        let mut reader = section(buffer, offset)?;
+        model_def.face_textures = (0..face_count)
+            .map(|_| Ok(reader.read_u16()?.checked_sub(1)))
+            .collect::<io::Result<_>>()?;
+    }
+
+    Ok(model_def)
+}
+
+fn decode_old_format(id: u32, buffer: &[u8]) -> io::Result<ModelDefinition> {
+    let mut footer = section(buffer, buffer.len().saturating_sub(OLD_FORMAT_FOOTER_LEN))?;
+    let vertex_count = footer.read_u16()? as usize;
+    let face_count = footer.read_u16()? as usize;
+    let texture_face_count = footer.read_u8()? as usize;
+    let has_render_info = footer.read_u8()? == 1;
+    let priority = footer.read_u8()?;
+    let has_face_alphas = footer.read_u8()? == 1;
+    let has_face_skins = footer.read_u8()? == 1;
+    let has_vertex_skins = footer.read_u8()? == 1;
+    let vertices_x_len = footer.read_u16()? as usize;
+    let vertices_y_len = footer.read_u16()? as usize;
+    let vertices_z_len = footer.read_u16()? as usize;
+    let face_indices_len = footer.read_u16()? as usize;
+
+    let mut offset = 0;
+    let mut next = |len: usize| {
+        let start = offset;
+        offset += len;
+        start
+    };
+    let vertex_flags = next(vertex_count);
+    let face_compression = next(face_count);
+    let face_priorities = (priority == 255).then(|| next(face_count));
+    let face_skins = has_face_skins.then(|| next(face_count));
+    let render_info = has_render_info.then(|| next(face_count));
+    let vertex_skins = has_vertex_skins.then(|| next(vertex_count));
+    let face_alphas = has_face_alphas.then(|| next(face_count));
+    let face_indices = next(face_indices_len);
+    let face_colors = next(face_count * 2);
+    next(texture_face_count * 6);
+    let vertices_x = next(vertices_x_len);
+    let vertices_y = next(vertices_y_len);
+    let vertices_z = next(vertices_z_len);
+
+    let sections = Sections {
+        vertex_flags,
+        vertices_x,
+        vertices_y,
+        vertices_z,
+        vertex_skins,
+        face_compression,
+        face_indices,
+        face_colors,
+        face_priorities,
+        face_alphas,
+        face_skins,
+    };
+    let mut model_def = decode_sections(id, buffer, vertex_count, face_count, &sections)?;
+    model_def.priority = priority;
+
+    if let Some(offset) = render_info {
+        let render_info = section(buffer, offset)?.get(..face_count).ok_or_else(eof)?;
+
+        model_def.face_render_types = render_info.iter().map(|info| info & 1).collect();
+        // Textured faces store their texture in place of their color.
+        model_def.face_textures = render_info
+            .iter()
+            .zip(&mut model_def.face_colors)
+            .map(|(info, color)| (info & 2 == 2).then(|| std::mem::replace(color, 127)))
+            .collect();
+    }
+
+    Ok(model_def)
+}
+
+fn decode_sections(
+    id: u32,
+    buffer: &[u8],
+    vertex_count: usize,
+    face_count: usize,
+    sections: &Sections,
+) -> io::Result<ModelDefinition> {
+    let mut model_def = ModelDefinition {
+        id,
+        ..ModelDefinition::default()
+    };
+
+    let mut flags = section(buffer, sections.vertex_flags)?;
+    let mut xs = section(buffer, sections.vertices_x)?;
+    let mut ys = section(buffer, sections.vertices_y)?;
+    let mut zs = section(buffer, sections.vertices_z)?;
+    let (mut x, mut y, mut z) = (0, 0, 0);
+    for _ in 0..vertex_count {
+        let flag = flags.read_u8()?;
+        if flag & 1 != 0 {
+            x += xs.read_i16_smart()? as i32;
+        }
+        if flag & 2 != 0 {
+            y += ys.read_i16_smart()? as i32;
+        }
+        if flag & 4 != 0 {
+            z += zs.read_i16_smart()? as i32;
+        }
+
+        model_def.vertices_x.push(x);
+        model_def.vertices_y.push(y);
+        model_def.vertices_z.push(z);
+    }
+
+    let mut colors = section(buffer, sections.face_colors)?;
+    for _ in 0..face_count {
+        model_def.face_colors.push(colors.read_u16()?);
+    }
+
+    let bytes = |offset: Option<usize>, len: usize| -> io::Result<Vec<u8>> {
+        match offset {
+            Some(offset) => Ok(section(buffer, offset)?
+                .get(..len)
+                .ok_or_else(eof)?
+                .to_vec()),
+            None => Ok(Vec::new()),
+        }
+    };
+    model_def.vertex_skins = bytes(sections.vertex_skins, vertex_count)?;
+    model_def.face_priorities = bytes(sections.face_priorities, face_count)?;
+    model_def.face_alphas = bytes(sections.face_alphas, face_count)?;
+    model_def.face_skins = bytes(sections.face_skins, face_count)?;
+
+    // Faces reuse the vertices of the previous face where they can. Only the
+    // low 16 bits are kept, so adding up the deltas may wrap.
+    let mut compression = section(buffer, sections.face_compression)?;
+    let mut indices = section(buffer, sections.face_indices)?;
+    let (mut a, mut b, mut c, mut last) = (0, 0, 0, 0);
+    for _ in 0..face_count {
+        match compression.read_u8()? {
+            1 => {
+                a = (indices.read_i16_smart()? as i32).wrapping_add(last);
+                b = (indices.read_i16_smart()? as i32).wrapping_add(a);
+                c = (indices.read_i16_smart()? as i32).wrapping_add(b);
+                last = c;
+            }
+            2 => {
+                b = c;
+                c = (indices.read_i16_smart()? as i32).wrapping_add(last);
+                last = c;
+            }
+            3 => {
+                a = c;
+                c = (indices.read_i16_smart()? as i32).wrapping_add(last);
+                last = c;
+            }
+            4 => {
+                std::mem::swap(&mut a, &mut b);
+                c = (indices.read_i16_smart()? as i32).wrapping_add(last);
+                last = c;
+            }
+            _ => {}
+        }
+
+        model_def.faces_a.push(a as u16);
+        model_def.faces_b.push(b as u16);
+        model_def.faces_c.push(c as u16);
+    }
+
+    Ok(model_def)
+}
+
+fn section(buffer: &[u8], offset: usize) -> io::Result<&[u8]> {
+    buffer.get(offset..).ok_or_else(eof)
+}
+
+fn eof() -> io::Error {
+    io::ErrorKind::UnexpectedEof.into()
+}
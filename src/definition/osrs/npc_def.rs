@@ -1,27 +1,48 @@
-use std::{collections::HashMap, io, io::BufReader};
+use std::{
+    collections::HashMap,
+    io::{self, BufReader},
+};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Definition;
-use crate::{extension::ReadExt, util};
+use crate::{
+    definition::{
+        osrs::{
+            encode::{write_replacements, write_transform, write_u16_list, write_u16_opcode},
+            transform, TransformVar,
+        },
+        ArchiveFileData, DecodeContext, Definition, Revision,
+    },
+    extension::{ReadExt, WriteExt},
+    util,
+};
 
 /// Contains all the information about a certain npc fetched from the cache through
 /// the [NpcLoader](../../loader/osrs/struct.NpcLoader.html).
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
 pub struct NpcDefinition {
-    pub id: u16,
+    pub id: u32,
     pub name: String,
     pub size: usize,
     pub actions: [String; 5],
     pub visible_on_minimap: bool,
     pub combat_level: Option<u16>,
+    pub category: Option<u16>,
+    /// Attack, defence, strength, hitpoints, ranged and magic levels.
+    pub stats: [u16; 6],
     pub configs: Vec<u16>,
+    /// The npc to transform into when the value of the transform variable is
+    /// outside of `configs`.
+    pub default_config: Option<u16>,
     pub varbit_id: Option<u16>,
     pub varp_index: Option<u16>,
     pub interactable: bool,
     pub pet: bool,
+    pub follower: bool,
+    /// Options of a follower are shown below the options of other npcs.
+    pub low_priority_follower_options: bool,
     pub params: HashMap<u32, String>,
     pub model_data: NpcModelData,
     pub animation_data: NpcAnimationData,
@@ -42,8 +63,13 @@ pub struct NpcModelData {
     pub ambient: u8,
     pub contrast: u8,
     pub head_icon: Option<u16>,
+    /// The sprite archive and sprite index of each head icon, read instead of
+    /// `head_icon` from [`Revision::NPC_HEAD_ICON_SPRITES`] on. `None` for
+    /// slots without an icon.
+    pub head_icon_sprites: Vec<Option<(u32, u16)>>,
     pub rotate_speed: u16,
     pub rotate_flag: bool,
+    pub height: Option<u16>,
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -56,19 +82,204 @@ pub struct NpcAnimationData {
     pub rotate_180: Option<u16>,
     pub rotate_90_left: Option<u16>,
     pub rotate_90_right: Option<u16>,
+    pub running: Option<u16>,
+    pub running_rotate_180: Option<u16>,
+    pub running_rotate_90_left: Option<u16>,
+    pub running_rotate_90_right: Option<u16>,
+    pub crawling: Option<u16>,
+    pub crawling_rotate_180: Option<u16>,
+    pub crawling_rotate_90_left: Option<u16>,
+    pub crawling_rotate_90_right: Option<u16>,
+}
+
+impl NpcDefinition {
+    /// The variable that decides which npc this npc transforms into, `None` if
+    /// it doesn't transform.
+    pub const fn transform_var(&self) -> Option<TransformVar> {
+        TransformVar::new(self.varbit_id, self.varp_index)
+    }
+
+    /// The id of the npc this npc transforms into when its
+    /// [`transform_var`](NpcDefinition::transform_var) has the given value.
+    ///
+    /// Returns `None` if the npc is hidden for that value.
+    pub fn transform(&self, value: i32) -> Option<u16> {
+        transform::transform(&self.configs, self.default_config, value)
+    }
+
+    /// Encodes the definition into the opcode stream it is decoded from.
+    ///
+    /// Fields that still have the value the decoder starts out with are left
+    /// out. Walking, running and crawling animations are written with their
+    /// rotations in one opcode when all of them are set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::definition::osrs::{DecodeContext, Definition, NpcDefinition};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let ctx = DecodeContext::new(2, 9);
+    /// let mut npc = NpcDefinition::from_buffer(20_000, &[0], &ctx)?;
+    /// npc.name = "Gnome child".to_owned();
+    /// npc.combat_level = Some(3);
+    ///
+    /// let decoded = NpcDefinition::from_buffer(20_000, &npc.encode(), &ctx)?;
+    /// assert_eq!(decoded, npc);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let model = &self.model_data;
+        let animation = &self.animation_data;
+
+        write_u16_list(&mut buffer, 1, &model.models);
+        if !self.name.is_empty() {
+            buffer.write_u8(2);
+            buffer.write_string(&self.name);
+        }
+        if self.size != 0 {
+            buffer.write_u8(12);
+            buffer.write_u8(self.size as u8);
+        }
+        write_u16_opcode(&mut buffer, 13, animation.standing);
+        write_movement(
+            &mut buffer,
+            (14, 17),
+            animation.walking,
+            [
+                animation.rotate_180,
+                animation.rotate_90_right,
+                animation.rotate_90_left,
+            ],
+        );
+        write_u16_opcode(&mut buffer, 15, animation.rotate_left);
+        write_u16_opcode(&mut buffer, 16, animation.rotate_right);
+        write_u16_opcode(&mut buffer, 18, self.category);
+        for (i, action) in self.actions.iter().enumerate() {
+            if !action.is_empty() {
+                buffer.write_u8(30 + i as u8);
+                buffer.write_string(action);
+            }
+        }
+
+        if !model.recolor_find.is_empty() {
+            buffer.write_u8(40);
+            write_replacements(&mut buffer, &model.recolor_find, &model.recolor_replace);
+        }
+        if !model.retexture_find.is_empty() {
+            buffer.write_u8(41);
+            write_replacements(&mut buffer, &model.retexture_find, &model.retexture_replace);
+        }
+        write_u16_list(&mut buffer, 60, &model.chat_head_models);
+        for (i, &stat) in self.stats.iter().enumerate() {
+            if stat != 0 {
+                buffer.write_u8(74 + i as u8);
+                buffer.write_u16(stat);
+            }
+        }
+
+        if !self.visible_on_minimap {
+            buffer.write_u8(93);
+        }
+        write_u16_opcode(&mut buffer, 95, self.combat_level);
+        let scale = [(97, model.width_scale), (98, model.height_scale)];
+        for (opcode, value) in scale {
+            if value != 128 {
+                buffer.write_u8(opcode);
+                buffer.write_u16(value);
+            }
+        }
+        if model.render_priority {
+            buffer.write_u8(99);
+        }
+        if model.ambient != 0 {
+            buffer.write_u8(100);
+            buffer.write_u8(model.ambient);
+        }
+        if model.contrast != 0 {
+            buffer.write_u8(101);
+            buffer.write_u8(model.contrast);
+        }
+        if model.head_icon_sprites.is_empty() {
+            write_u16_opcode(&mut buffer, 102, model.head_icon);
+        } else {
+            buffer.write_u8(102);
+            write_head_icon_sprites(&mut buffer, &model.head_icon_sprites);
+        }
+        if model.rotate_speed != 32 {
+            buffer.write_u8(103);
+            buffer.write_u16(model.rotate_speed);
+        }
+        write_transform(
+            &mut buffer,
+            (106, 118),
+            (self.varbit_id, self.varp_index),
+            self.default_config,
+            &self.configs,
+        );
+        if !self.interactable {
+            buffer.write_u8(107);
+        }
+        if !model.rotate_flag {
+            buffer.write_u8(109);
+        }
+        if self.pet {
+            buffer.write_u8(111);
+        }
+        write_movement(
+            &mut buffer,
+            (114, 115),
+            animation.running,
+            [
+                animation.running_rotate_180,
+                animation.running_rotate_90_right,
+                animation.running_rotate_90_left,
+            ],
+        );
+        write_movement(
+            &mut buffer,
+            (116, 117),
+            animation.crawling,
+            [
+                animation.crawling_rotate_180,
+                animation.crawling_rotate_90_right,
+                animation.crawling_rotate_90_left,
+            ],
+        );
+        if self.follower {
+            buffer.write_u8(122);
+        }
+        if self.low_priority_follower_options {
+            buffer.write_u8(123);
+        }
+        write_u16_opcode(&mut buffer, 124, model.height);
+        if !self.params.is_empty() {
+            buffer.write_u8(249);
+            util::write_parameters(&mut buffer, &self.params);
+        }
+
+        buffer.write_u8(0);
+        buffer
+    }
 }
 
 impl Definition for NpcDefinition {
-    fn new(id: u16, buffer: &[u8]) -> crate::Result<Self> {
-        let mut reader = BufReader::new(buffer);
-        let npc_def = decode_buffer(id, &mut reader)?;
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let npc_def = decode_buffer(id, &mut reader, ctx)?;
 
         Ok(npc_def)
     }
 }
 
 #[allow(clippy::too_many_lines)]
-fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<NpcDefinition> {
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<NpcDefinition> {
     let mut npc_def = NpcDefinition {
         id,
         interactable: true,
@@ -118,6 +329,9 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<NpcDefini
                 npc_def.animation_data.rotate_90_right = Some(reader.read_u16()?);
                 npc_def.animation_data.rotate_90_left = Some(reader.read_u16()?);
             }
+            18 => {
+                npc_def.category = Some(reader.read_u16()?);
+            }
             30..=34 => {
                 npc_def.actions[opcode as usize - 30] = reader.read_string()?;
             }
@@ -144,7 +358,10 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<NpcDefini
                     npc_def.model_data.chat_head_models.push(reader.read_u16()?);
                 }
             }
-            93 => npc_def.visible_on_minimap = true,
+            74..=79 => {
+                npc_def.stats[opcode as usize - 74] = reader.read_u16()?;
+            }
+            93 => npc_def.visible_on_minimap = false,
             95 => {
                 npc_def.combat_level = Some(reader.read_u16()?);
             }
@@ -161,6 +378,9 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<NpcDefini
             101 => {
                 npc_def.model_data.contrast = reader.read_u8()?;
             }
+            102 if ctx.revision >= Revision::NPC_HEAD_ICON_SPRITES => {
+                npc_def.model_data.head_icon_sprites = read_head_icon_sprites(reader)?;
+            }
             102 => {
                 npc_def.model_data.head_icon = Some(reader.read_u16()?);
             }
@@ -169,14 +389,14 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<NpcDefini
             }
             106 => {
                 let varbit_id = reader.read_u16()?;
-                npc_def.varbit_id = if varbit_id == std::u16::MAX {
+                npc_def.varbit_id = if varbit_id == u16::MAX {
                     None
                 } else {
                     Some(varbit_id)
                 };
 
                 let varp_index = reader.read_u16()?;
-                npc_def.varp_index = if varp_index == std::u16::MAX {
+                npc_def.varp_index = if varp_index == u16::MAX {
                     None
                 } else {
                     Some(varp_index)
@@ -191,23 +411,45 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<NpcDefini
             107 => npc_def.interactable = false,
             109 => npc_def.model_data.rotate_flag = false,
             111 => npc_def.pet = true,
+            114 => {
+                npc_def.animation_data.running = Some(reader.read_u16()?);
+            }
+            115 => {
+                npc_def.animation_data.running = Some(reader.read_u16()?);
+                npc_def.animation_data.running_rotate_180 = Some(reader.read_u16()?);
+                npc_def.animation_data.running_rotate_90_right = Some(reader.read_u16()?);
+                npc_def.animation_data.running_rotate_90_left = Some(reader.read_u16()?);
+            }
+            116 => {
+                npc_def.animation_data.crawling = Some(reader.read_u16()?);
+            }
+            117 => {
+                npc_def.animation_data.crawling = Some(reader.read_u16()?);
+                npc_def.animation_data.crawling_rotate_180 = Some(reader.read_u16()?);
+                npc_def.animation_data.crawling_rotate_90_right = Some(reader.read_u16()?);
+                npc_def.animation_data.crawling_rotate_90_left = Some(reader.read_u16()?);
+            }
             118 => {
                 let varbit_id = reader.read_u16()?;
-                npc_def.varbit_id = if varbit_id == std::u16::MAX {
+                npc_def.varbit_id = if varbit_id == u16::MAX {
                     None
                 } else {
                     Some(varbit_id)
                 };
 
                 let varp_index = reader.read_u16()?;
-                npc_def.varp_index = if varp_index == std::u16::MAX {
+                npc_def.varp_index = if varp_index == u16::MAX {
                     None
                 } else {
                     Some(varp_index)
                 };
 
-                // should append var at end
-                let _var = reader.read_u16()?;
+                let default_config = reader.read_u16()?;
+                npc_def.default_config = if default_config == u16::MAX {
+                    None
+                } else {
+                    Some(default_config)
+                };
 
                 npc_def.configs = Vec::new();
                 let len = reader.read_u8()?;
@@ -215,12 +457,86 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<NpcDefini
                     npc_def.configs.push(reader.read_u16()?);
                 }
             }
+            122 => npc_def.follower = true,
+            123 => npc_def.low_priority_follower_options = true,
+            124 => {
+                npc_def.model_data.height = Some(reader.read_u16()?);
+            }
             249 => {
                 npc_def.params = util::read_parameters(reader)?;
             }
-            _ => unreachable!(),
+            _ => {
+                ctx.unknown_opcode("npc", id, opcode)?;
+                break;
+            }
         }
     }
 
     Ok(npc_def)
 }
+
+/// Writes a movement animation on its own with the first opcode, or together
+/// with its rotations with the second one when they are all set.
+fn write_movement(
+    buffer: &mut Vec<u8>,
+    opcodes: (u8, u8),
+    animation: Option<u16>,
+    rotations: [Option<u16>; 3],
+) {
+    let Some(animation) = animation else {
+        return;
+    };
+
+    match rotations {
+        [Some(rotate_180), Some(rotate_90_right), Some(rotate_90_left)] => {
+            buffer.write_u8(opcodes.1);
+            buffer.write_u16(animation);
+            buffer.write_u16(rotate_180);
+            buffer.write_u16(rotate_90_right);
+            buffer.write_u16(rotate_90_left);
+        }
+        _ => {
+            buffer.write_u8(opcodes.0);
+            buffer.write_u16(animation);
+        }
+    }
+}
+
+/// Reads the head icons that are present in a bit set, followed by their
+/// sprite archive and sprite index.
+fn read_head_icon_sprites(reader: &mut BufReader<&[u8]>) -> io::Result<Vec<Option<(u32, u16)>>> {
+    let present = reader.read_u8()?;
+    let len = u8::BITS - present.leading_zeros();
+
+    let mut sprites = Vec::with_capacity(len as usize);
+    for slot in 0..len {
+        if present & (1 << slot) == 0 {
+            sprites.push(None);
+            continue;
+        }
+
+        let archive_id = reader.read_nullable_smart()?;
+        let sprite_id = reader.read_nullable_smart_u16()?;
+        sprites.push(archive_id.zip(sprite_id));
+    }
+
+    Ok(sprites)
+}
+
+/// Writes head icons in the format of [`read_head_icon_sprites`].
+///
+/// Only the first 8 slots fit in the bit set.
+fn write_head_icon_sprites(buffer: &mut Vec<u8>, sprites: &[Option<(u32, u16)>]) {
+    let sprites = &sprites[..sprites.len().min(u8::BITS as usize)];
+    let present = sprites
+        .iter()
+        .enumerate()
+        .filter(|(_, sprite)| sprite.is_some())
+        .fold(0_u8, |present, (slot, _)| present | (1 << slot));
+
+    buffer.write_u8(present);
+    for &(archive_id, sprite_id) in sprites.iter().flatten() {
+        buffer.write_nullable_smart(Some(archive_id));
+        buffer.write_nullable_smart_u16(Some(sprite_id));
+    }
+}
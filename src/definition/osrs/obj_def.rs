@@ -1,17 +1,29 @@
-use std::{collections::HashMap, io, io::BufReader};
+use std::{collections::HashMap, io::BufReader};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Definition;
-use crate::{extension::ReadExt, util};
+use crate::{
+    definition::{
+        osrs::{
+            encode::{
+                write_replacements, write_transform, write_u16_list, write_u16_opcode,
+                write_u8_opcode,
+            },
+            transform, TransformVar,
+        },
+        ArchiveFileData, DecodeContext, Definition, Revision,
+    },
+    extension::{ReadExt, WriteExt},
+    util,
+};
 
 /// Contains all the information about a certain object fetched from the cache through
 /// the [ObjectLoader](../../loader/osrs/struct.ObjectLoader.html).
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
 pub struct ObjectDefinition {
-    pub id: u16,
+    pub id: u32,
     pub name: String,
     pub config_id: Option<u16>,
     pub map_area_id: Option<u16>,
@@ -25,10 +37,22 @@ pub struct ObjectDefinition {
     pub interact_type: u8,
     pub rotated: bool,
     pub ambient_sound_id: u16,
+    /// The distance in tiles the ambient sound can be heard from.
+    pub ambient_sound_distance: u8,
+    /// Read after the distance from [`Revision::OBJECT_SOUND_RETAIN`] on.
+    pub ambient_sound_retain: Option<u8>,
+    /// Sounds of which a random one is played every
+    /// `ambient_sound_min_ticks..=ambient_sound_max_ticks` client ticks.
+    pub ambient_sound_ids: Vec<u16>,
+    pub ambient_sound_min_ticks: u16,
+    pub ambient_sound_max_ticks: u16,
     pub blocks_projectile: bool,
     pub wall_or_door: Option<u8>,
     pub contoured_ground: Option<u8>,
     pub config_change_dest: Vec<u16>,
+    /// The object to transform into when the value of the transform variable
+    /// is outside of `config_change_dest`.
+    pub config_change_default: Option<u16>,
     pub params: HashMap<u32, String>,
     pub model_data: ObjectModelData,
 }
@@ -58,17 +82,241 @@ pub struct ObjectModelData {
     pub blocking_mask: u8,
 }
 
+impl ObjectDefinition {
+    /// The variable that decides which object this object transforms into,
+    /// `None` if it doesn't transform.
+    ///
+    /// The client reads the varbit before the varp, so the varbit id is the one
+    /// in `model_data.varp_id` and the varp id is `config_id`.
+    pub const fn transform_var(&self) -> Option<TransformVar> {
+        TransformVar::new(self.model_data.varp_id, self.config_id)
+    }
+
+    /// The id of the object this object transforms into when its
+    /// [`transform_var`](ObjectDefinition::transform_var) has the given value.
+    ///
+    /// Returns `None` if the object is hidden for that value.
+    pub fn transform(&self, value: i32) -> Option<u16> {
+        transform::transform(&self.config_change_dest, self.config_change_default, value)
+    }
+
+    /// Encodes the definition into the opcode stream it is decoded from.
+    ///
+    /// Fields that still have the value the decoder starts out with are left
+    /// out, as are `wall_or_door` and `supports_items` when they are the
+    /// values the decoder derives for them. The unused opcode 23 is never
+    /// written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::definition::osrs::{DecodeContext, Definition, ObjectDefinition};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let ctx = DecodeContext::new(2, 6);
+    /// let mut obj = ObjectDefinition::from_buffer(50_000, &[0], &ctx)?;
+    /// obj.name = "Gnome statue".to_owned();
+    /// obj.actions[0] = "Admire".to_owned();
+    ///
+    /// let decoded = ObjectDefinition::from_buffer(50_000, &obj.encode(), &ctx)?;
+    /// assert_eq!(decoded, obj);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let model = &self.model_data;
+
+        if model.types.is_empty() {
+            write_u16_list(&mut buffer, 5, &model.models);
+        } else {
+            buffer.write_u8(1);
+            buffer.write_u8(model.models.len().min(model.types.len()) as u8);
+            for (&model_id, &kind) in model.models.iter().zip(&model.types) {
+                buffer.write_u16(model_id);
+                buffer.write_u8(kind);
+            }
+        }
+        if !self.name.is_empty() {
+            buffer.write_u8(2);
+            buffer.write_string(&self.name);
+        }
+        let size = [(14, model.size_x), (15, model.size_y)];
+        for (opcode, value) in size {
+            if value != 1 {
+                buffer.write_u8(opcode);
+                buffer.write_u8(value);
+            }
+        }
+        // Opcode 17 also makes the object not block projectiles.
+        if self.interact_type == 0 {
+            buffer.write_u8(17);
+        } else if !self.blocks_projectile {
+            buffer.write_u8(18);
+        }
+        if self.wall_or_door != Some(self.derived_wall_or_door()) {
+            write_u8_opcode(&mut buffer, 19, self.wall_or_door);
+        }
+        if self.contoured_ground == Some(0) {
+            buffer.write_u8(21);
+        }
+        if model.merge_normals {
+            buffer.write_u8(22);
+        }
+        if self.animation_id != 0 {
+            buffer.write_u8(24);
+            buffer.write_u16(self.animation_id);
+        }
+        if self.interact_type == 1 {
+            buffer.write_u8(27);
+        }
+        if model.decord_displacement != 16 {
+            buffer.write_u8(28);
+            buffer.write_u8(model.decord_displacement);
+        }
+        if model.ambient != 0 {
+            buffer.write_u8(29);
+            buffer.write_u8(model.ambient);
+        }
+        for (i, action) in self.actions.iter().enumerate() {
+            if !action.is_empty() {
+                buffer.write_u8(30 + i as u8);
+                buffer.write_string(action);
+            }
+        }
+        if model.contrast != 0 {
+            buffer.write_u8(39);
+            buffer.write_u8(model.contrast);
+        }
+
+        if !model.recolor_find.is_empty() {
+            buffer.write_u8(40);
+            write_replacements(&mut buffer, &model.recolor_find, &model.recolor_replace);
+        }
+        if !model.retexture_find.is_empty() {
+            buffer.write_u8(41);
+            write_replacements(&mut buffer, &model.retexture_find, &model.retexture_replace);
+        }
+        if self.rotated {
+            buffer.write_u8(62);
+        }
+        if self.shadow {
+            buffer.write_u8(64);
+        }
+        let model_size = [
+            (65, model.model_size_x),
+            (66, model.model_size_z),
+            (67, model.model_size_y),
+        ];
+        for (opcode, value) in model_size {
+            if value != 128 {
+                buffer.write_u8(opcode);
+                buffer.write_u16(value);
+            }
+        }
+        if self.map_scene_id != 0 {
+            buffer.write_u8(68);
+            buffer.write_u16(self.map_scene_id);
+        }
+        if model.blocking_mask != 0 {
+            buffer.write_u8(69);
+            buffer.write_u8(model.blocking_mask);
+        }
+        let offset = [
+            (70, model.offset_x),
+            (71, model.offset_z),
+            (72, model.offset_y),
+        ];
+        for (opcode, value) in offset {
+            if value != 0 {
+                buffer.write_u8(opcode);
+                buffer.write_u16(value);
+            }
+        }
+        if self.obstruct_ground {
+            buffer.write_u8(73);
+        }
+        if !self.solid {
+            buffer.write_u8(74);
+        }
+        if self.supports_items != Some(u8::from(self.interact_type != 0)) {
+            write_u8_opcode(&mut buffer, 75, self.supports_items);
+        }
+        write_transform(
+            &mut buffer,
+            (77, 92),
+            (model.varp_id, self.config_id),
+            self.config_change_default,
+            &self.config_change_dest,
+        );
+
+        // Both sound opcodes set the distance and retain, 78 is only needed
+        // for them when there are no random sounds.
+        let random_sounds = !self.ambient_sound_ids.is_empty()
+            || self.ambient_sound_min_ticks != 0
+            || self.ambient_sound_max_ticks != 0;
+        let sound_range = self.ambient_sound_distance != 0 || self.ambient_sound_retain.is_some();
+        if self.ambient_sound_id != 0 || (sound_range && !random_sounds) {
+            buffer.write_u8(78);
+            buffer.write_u16(self.ambient_sound_id);
+            buffer.write_u8(self.ambient_sound_distance);
+            if let Some(retain) = self.ambient_sound_retain {
+                buffer.write_u8(retain);
+            }
+        }
+        if random_sounds {
+            buffer.write_u8(79);
+            buffer.write_u16(self.ambient_sound_min_ticks);
+            buffer.write_u16(self.ambient_sound_max_ticks);
+            buffer.write_u8(self.ambient_sound_distance);
+            if let Some(retain) = self.ambient_sound_retain {
+                buffer.write_u8(retain);
+            }
+            buffer.write_u8(self.ambient_sound_ids.len() as u8);
+            for &sound_id in &self.ambient_sound_ids {
+                buffer.write_u16(sound_id);
+            }
+        }
+        if let Some(contoured_ground @ 1..) = self.contoured_ground {
+            buffer.write_u8(81);
+            buffer.write_u8(contoured_ground);
+        }
+        write_u16_opcode(&mut buffer, 82, self.map_area_id);
+        if !self.params.is_empty() {
+            buffer.write_u8(249);
+            util::write_parameters(&mut buffer, &self.params);
+        }
+
+        buffer.write_u8(0);
+        buffer
+    }
+
+    /// The `wall_or_door` value the decoder gives objects that don't set it.
+    fn derived_wall_or_door(&self) -> u8 {
+        let model = &self.model_data;
+        let has_model =
+            !model.models.is_empty() && (model.types.is_empty() || model.types[0] == 10);
+        let has_action = self.actions.iter().any(|action| !action.is_empty());
+
+        u8::from(has_model || has_action)
+    }
+}
+
 impl Definition for ObjectDefinition {
-    fn new(id: u16, buffer: &[u8]) -> crate::Result<Self> {
-        let mut reader = BufReader::new(buffer);
-        let mut obj_def = decode_buffer(id, &mut reader)?;
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let mut obj_def = decode_buffer(id, &mut reader, ctx)?;
         post(&mut obj_def);
 
         Ok(obj_def)
     }
 }
 
-fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ObjectDefinition> {
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<ObjectDefinition> {
     let mut obj_def = ObjectDefinition {
         id,
         interact_type: 2,
@@ -206,13 +454,13 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ObjectDef
             }
             77 => {
                 let varp_id = reader.read_u16()?;
-                obj_def.model_data.varp_id = if varp_id == std::u16::MAX {
+                obj_def.model_data.varp_id = if varp_id == u16::MAX {
                     None
                 } else {
                     Some(varp_id)
                 };
                 let config_id = reader.read_u16()?;
-                obj_def.config_id = if config_id == std::u16::MAX {
+                obj_def.config_id = if config_id == u16::MAX {
                     None
                 } else {
                     Some(config_id)
@@ -225,15 +473,22 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ObjectDef
             }
             78 => {
                 obj_def.ambient_sound_id = reader.read_u16()?;
-                reader.read_u8()?;
+                obj_def.ambient_sound_distance = reader.read_u8()?;
+                if ctx.revision >= Revision::OBJECT_SOUND_RETAIN {
+                    obj_def.ambient_sound_retain = Some(reader.read_u8()?);
+                }
             }
             79 => {
-                reader.read_u16()?;
-                reader.read_u16()?;
-                reader.read_u8()?;
+                obj_def.ambient_sound_min_ticks = reader.read_u16()?;
+                obj_def.ambient_sound_max_ticks = reader.read_u16()?;
+                obj_def.ambient_sound_distance = reader.read_u8()?;
+                if ctx.revision >= Revision::OBJECT_SOUND_RETAIN {
+                    obj_def.ambient_sound_retain = Some(reader.read_u8()?);
+                }
                 let len = reader.read_u8()?;
+                obj_def.ambient_sound_ids = Vec::with_capacity(len as usize);
                 for _ in 0..len {
-                    reader.read_u16()?;
+                    obj_def.ambient_sound_ids.push(reader.read_u16()?);
                 }
             }
             81 => {
@@ -244,20 +499,24 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ObjectDef
             }
             92 => {
                 let varp_id = reader.read_u16()?;
-                obj_def.model_data.varp_id = if varp_id == std::u16::MAX {
+                obj_def.model_data.varp_id = if varp_id == u16::MAX {
                     None
                 } else {
                     Some(varp_id)
                 };
 
                 let config_id = reader.read_u16()?;
-                obj_def.config_id = if config_id == std::u16::MAX {
+                obj_def.config_id = if config_id == u16::MAX {
                     None
                 } else {
                     Some(config_id)
                 };
-                // should append var at end
-                let _var = reader.read_u16()?;
+                let config_change_default = reader.read_u16()?;
+                obj_def.config_change_default = if config_change_default == u16::MAX {
+                    None
+                } else {
+                    Some(config_change_default)
+                };
 
                 let len = reader.read_u8()?;
                 obj_def.config_change_dest = Vec::new();
@@ -269,7 +528,10 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ObjectDef
                 obj_def.params = util::read_parameters(reader)?;
             }
             23 => { /* skip */ }
-            _ => unreachable!(),
+            _ => {
+                ctx.unknown_opcode("object", id, opcode)?;
+                break;
+            }
         }
     }
 
@@ -278,18 +540,7 @@ fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<ObjectDef
 
 fn post(obj_def: &mut ObjectDefinition) {
     if obj_def.wall_or_door.is_none() {
-        obj_def.wall_or_door = Some(0);
-        if !obj_def.model_data.models.is_empty()
-            && (obj_def.model_data.types.is_empty() || obj_def.model_data.types[0] == 10)
-        {
-            obj_def.wall_or_door = Some(1);
-        }
-
-        for var in 0..5 {
-            if !obj_def.actions[var].is_empty() {
-                obj_def.wall_or_door = Some(1);
-            }
-        }
+        obj_def.wall_or_door = Some(obj_def.derived_wall_or_door());
     }
 
     if obj_def.supports_items.is_none() {
@@ -0,0 +1,70 @@
+use std::io::BufReader;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+};
+
+/// The color of an overlay that has no color of its own.
+pub const TRANSPARENT_RGB: u32 = 0xFF00FF;
+
+/// Contains the color and texture of a floor overlay fetched from the cache
+/// through the [OverlayLoader](../../loader/osrs/struct.OverlayLoader.html).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct OverlayDefinition {
+    pub id: u32,
+    pub rgb: u32,
+    pub texture: Option<u8>,
+    pub hide_underlay: bool,
+    /// The color used on the minimap instead of `rgb`.
+    pub secondary_rgb: Option<u32>,
+}
+
+impl Definition for OverlayDefinition {
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let overlay_def = decode_buffer(id, &mut reader, ctx)?;
+
+        Ok(overlay_def)
+    }
+}
+
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<OverlayDefinition> {
+    let mut overlay_def = OverlayDefinition {
+        id,
+        hide_underlay: true,
+        ..OverlayDefinition::default()
+    };
+
+    loop {
+        let opcode = reader.read_u8()?;
+
+        match opcode {
+            0 => break,
+            1 => {
+                overlay_def.rgb = reader.read_u24()?;
+            }
+            2 => {
+                overlay_def.texture = Some(reader.read_u8()?);
+            }
+            5 => overlay_def.hide_underlay = false,
+            7 => {
+                overlay_def.secondary_rgb = Some(reader.read_u24()?);
+            }
+            _ => {
+                ctx.unknown_opcode("overlay", id, opcode)?;
+                break;
+            }
+        }
+    }
+
+    Ok(overlay_def)
+}
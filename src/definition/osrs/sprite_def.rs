@@ -0,0 +1,157 @@
+use std::io::{self, Read};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+};
+
+const FLAG_VERTICAL: u8 = 0b01;
+const FLAG_ALPHA: u8 = 0b10;
+
+/// Contains all the frames of a sprite fetched from the cache through the
+/// [SpriteLoader](../../loader/osrs/struct.SpriteLoader.html).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SpriteDefinition {
+    pub id: u32,
+    pub max_width: u16,
+    pub max_height: u16,
+    pub frames: Vec<SpriteFrame>,
+}
+
+/// A single image of a sprite.
+///
+/// The offsets position the frame within the `max_width` by `max_height` area
+/// of its sprite.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SpriteFrame {
+    pub offset_x: u16,
+    pub offset_y: u16,
+    pub width: u16,
+    pub height: u16,
+    /// ARGB pixels in row-major order, transparent pixels are `0`.
+    pub pixels: Vec<u32>,
+}
+
+impl SpriteFrame {
+    /// Returns the ARGB pixel at `x`, `y` or `None` if it is out of bounds.
+    #[inline]
+    pub fn pixel(&self, x: u16, y: u16) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels
+            .get(y as usize * self.width as usize + x as usize)
+            .copied()
+    }
+
+    /// Converts the pixels to RGBA bytes, 4 per pixel.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&pixel| {
+                let [a, r, g, b] = pixel.to_be_bytes();
+                [r, g, b, a]
+            })
+            .collect()
+    }
+}
+
+impl Definition for SpriteDefinition {
+    fn new(id: u32, file: &ArchiveFileData, _: &DecodeContext) -> crate::Result<Self> {
+        let sprite_def = decode_buffer(id, &file.data)?;
+
+        Ok(sprite_def)
+    }
+}
+
+fn decode_buffer(id: u32, buffer: &[u8]) -> io::Result<SpriteDefinition> {
+    let eof = || io::Error::from(io::ErrorKind::UnexpectedEof);
+
+    // The frame count is stored at the very end of the buffer, the frame
+    // dimensions and the palette are stored right before it. The dimensions
+    // have to fit in the buffer, which bounds the frames that are allocated.
+    let count_offset = buffer.len().checked_sub(2).ok_or_else(eof)?;
+    let frame_count = (&buffer[count_offset..]).read_u16()? as usize;
+    let footer_offset = count_offset
+        .checked_sub(5 + frame_count * 8)
+        .ok_or_else(eof)?;
+
+    let mut reader = &buffer[footer_offset..];
+    let max_width = reader.read_u16()?;
+    let max_height = reader.read_u16()?;
+    let palette_len = reader.read_u8()? as usize;
+
+    let offsets_x = read_u16s(&mut reader, frame_count)?;
+    let offsets_y = read_u16s(&mut reader, frame_count)?;
+    let widths = read_u16s(&mut reader, frame_count)?;
+    let heights = read_u16s(&mut reader, frame_count)?;
+
+    let palette_offset = footer_offset.checked_sub(palette_len * 3).ok_or_else(eof)?;
+    let mut reader = &buffer[palette_offset..footer_offset];
+    let mut palette = vec![0; palette_len + 1];
+    for color in palette.iter_mut().skip(1) {
+        // Black is transparent, so the client uses the closest color instead.
+        *color = reader.read_u24()?.max(1);
+    }
+
+    let mut reader = &buffer[..palette_offset];
+    let mut frames = Vec::with_capacity(frame_count);
+    for frame in 0..frame_count {
+        let width = widths[frame];
+        let height = heights[frame];
+        let len = width as usize * height as usize;
+        let flags = reader.read_u8()?;
+        // Every pixel has an index byte, so a frame can't have more pixels
+        // than there are bytes left.
+        if len > reader.len() {
+            return Err(eof());
+        }
+
+        let mut indices = vec![0; len];
+        reader.read_exact(&mut indices)?;
+        let mut alphas = vec![0xFF; len];
+        if flags & FLAG_ALPHA != 0 {
+            reader.read_exact(&mut alphas)?;
+        }
+
+        let mut pixels = vec![0; len];
+        for (i, (&index, &alpha)) in indices.iter().zip(&alphas).enumerate() {
+            let i = if flags & FLAG_VERTICAL != 0 {
+                let (x, y) = (i / height as usize, i % height as usize);
+                y * width as usize + x
+            } else {
+                i
+            };
+
+            let color = *palette.get(index as usize).ok_or_else(eof)?;
+            if index != 0 {
+                pixels[i] = ((alpha as u32) << 24) | color;
+            }
+        }
+
+        frames.push(SpriteFrame {
+            offset_x: offsets_x[frame],
+            offset_y: offsets_y[frame],
+            width,
+            height,
+            pixels,
+        });
+    }
+
+    Ok(SpriteDefinition {
+        id,
+        max_width,
+        max_height,
+        frames,
+    })
+}
+
+fn read_u16s(reader: &mut &[u8], count: usize) -> io::Result<Vec<u16>> {
+    (0..count).map(|_| reader.read_u16()).collect()
+}
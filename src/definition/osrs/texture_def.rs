@@ -0,0 +1,59 @@
+use std::{io, io::BufReader};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+};
+
+/// Contains the information about a texture fetched from the cache through the
+/// [TextureLoader](../../loader/osrs/struct.TextureLoader.html).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct TextureDefinition {
+    pub id: u32,
+    /// The average color of the texture in the 16-bit HSL format of the client.
+    pub average_hsl: u16,
+    pub opaque: bool,
+    pub sprite_ids: Vec<u16>,
+    pub animation_direction: u8,
+    pub animation_speed: u8,
+}
+
+impl Definition for TextureDefinition {
+    fn new(id: u32, file: &ArchiveFileData, _: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let texture_def = decode_buffer(id, &mut reader)?;
+
+        Ok(texture_def)
+    }
+}
+
+fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<TextureDefinition> {
+    let average_hsl = reader.read_u16()?;
+    let opaque = reader.read_u8()? == 1;
+
+    let len = reader.read_u8()? as usize;
+    let mut sprite_ids = Vec::with_capacity(len);
+    for _ in 0..len {
+        sprite_ids.push(reader.read_u16()?);
+    }
+    // Blending information of the sprites, which is unused.
+    for _ in 0..len.saturating_sub(1) * 2 {
+        reader.read_u8()?;
+    }
+    for _ in 0..len {
+        reader.read_u32()?;
+    }
+
+    Ok(TextureDefinition {
+        id,
+        average_hsl,
+        opaque,
+        sprite_ids,
+        animation_direction: reader.read_u8()?,
+        animation_speed: reader.read_u8()?,
+    })
+}
@@ -0,0 +1,36 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The player variable that decides what a transforming npc or object turns
+/// into.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum TransformVar {
+    /// A varbit, a range of bits within a varp.
+    Varbit(u16),
+    /// A whole varp.
+    Varp(u16),
+}
+
+impl TransformVar {
+    pub(crate) const fn new(varbit_id: Option<u16>, varp_id: Option<u16>) -> Option<Self> {
+        match (varbit_id, varp_id) {
+            (Some(varbit_id), _) => Some(Self::Varbit(varbit_id)),
+            (None, Some(varp_id)) => Some(Self::Varp(varp_id)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Picks the id for `value` from the transform table like the client does,
+/// values outside of the table fall back to `default`. `u16::MAX` means the
+/// definition is hidden.
+pub(crate) fn transform(configs: &[u16], default: Option<u16>, value: i32) -> Option<u16> {
+    let id = usize::try_from(value)
+        .ok()
+        .and_then(|index| configs.get(index))
+        .copied()
+        .or(default)?;
+
+    (id != u16::MAX).then_some(id)
+}
@@ -0,0 +1,55 @@
+use std::io::BufReader;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+};
+
+/// Contains the color of a floor underlay fetched from the cache through the
+/// [UnderlayLoader](../../loader/osrs/struct.UnderlayLoader.html).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct UnderlayDefinition {
+    pub id: u32,
+    pub rgb: u32,
+}
+
+impl Definition for UnderlayDefinition {
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let underlay_def = decode_buffer(id, &mut reader, ctx)?;
+
+        Ok(underlay_def)
+    }
+}
+
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<UnderlayDefinition> {
+    let mut underlay_def = UnderlayDefinition {
+        id,
+        ..UnderlayDefinition::default()
+    };
+
+    loop {
+        let opcode = reader.read_u8()?;
+
+        match opcode {
+            0 => break,
+            1 => {
+                underlay_def.rgb = reader.read_u24()?;
+            }
+            _ => {
+                ctx.unknown_opcode("underlay", id, opcode)?;
+                break;
+            }
+        }
+    }
+
+    Ok(underlay_def)
+}
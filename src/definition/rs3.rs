@@ -1,17 +1,41 @@
 #[allow(clippy::too_many_lines)]
 mod item_def;
+mod quest_def;
 
 pub use item_def::*;
+pub use quest_def::*;
 
-use crate::Cache;
-use runefs::{IndexMetadata, ArchiveFileGroup, REFERENCE_TABLE_ID};
+pub use super::{DecodeContext, Definition};
+
+use crate::{archive::ArchiveFileGroup, Cache};
+use runefs::error::{Error as RuneFsError, ReadError};
 use std::collections::HashMap;
 
 pub(crate) const ID_BLOCK_SIZE: usize = 256;
 
-/// Marker trait for definitions.
-pub trait Definition: Sized {
-    fn new(id: u32, buffer: &[u8]) -> crate::Result<Self>;
+/// Splits a definition id into the id of the archive (group) that holds it and
+/// the id of its file within that archive.
+///
+/// RS3 configs are stored in groups of 256 definitions per archive.
+///
+/// # Examples
+///
+/// ```
+/// use rscache::definition::rs3;
+///
+/// assert_eq!(rs3::group_ids(1042), (4, 18));
+/// assert_eq!(rs3::definition_id(4, 18), 1042);
+/// ```
+#[inline]
+pub const fn group_ids(id: u32) -> (u32, u32) {
+    (id / ID_BLOCK_SIZE as u32, id % ID_BLOCK_SIZE as u32)
+}
+
+/// The inverse of [`group_ids`], combines an archive (group) id and a file id
+/// into a definition id.
+#[inline]
+pub const fn definition_id(archive_id: u32, file_id: u32) -> u32 {
+    archive_id * ID_BLOCK_SIZE as u32 + file_id
 }
 
 /// Adds definition fetching from the cache to every struct that implements `Definition`.
@@ -28,23 +52,67 @@ pub trait FetchDefinition: Definition {
     where
         D: Definition,
     {
-        let buffer = cache.read(REFERENCE_TABLE_ID, index_id as u32)?.decode()?;
-        let archives = IndexMetadata::from_buffer(buffer)?;
+        let archives = cache.index_metadata(index_id)?;
 
         let mut definitions = std::collections::HashMap::new();
-        let mut base_id = 0;
 
-        for archive in &archives {
-            let buffer = cache.read(index_id, archive.id as u32)?.decode()?;
+        for archive in archives {
+            let buffer = cache.read(index_id, archive.id)?.decode()?;
+            let ctx = DecodeContext {
+                version: archive.version,
+                ..DecodeContext::with_cache(cache, index_id, archive.id)
+            };
 
-            let archive_group = ArchiveFileGroup::from_buffer(&buffer, archive.entry_count);
+            let archive_group = ArchiveFileGroup::try_from_buffer(&buffer, archive.entry_count)?;
 
             for archive_file in archive_group {
-                let id = base_id + archive.valid_ids[archive_file.id as usize] as usize;
-                definitions.insert(id as u32, D::new(id as u32, &archive_file.data)?);
+                let file_id = archive.valid_ids[archive_file.id as usize];
+                let id = definition_id(archive.id, file_id);
+                definitions.insert(id, D::new(id, &archive_file, &ctx)?);
             }
+        }
+
+        Ok(definitions)
+    }
+
+    /// Fetches multiple definitions from a single archive.
+    ///
+    /// Used for configs that aren't split into groups, the definition ids are
+    /// the file ids within the archive.
+    ///
+    /// # Errors
+    ///
+    /// Can return multiple errors: if reading, decoding or parsing definition buffers fail.
+    fn fetch_from_archive<D>(
+        cache: &Cache,
+        index_id: u8,
+        archive_id: u32,
+    ) -> crate::Result<HashMap<u32, D>>
+    where
+        D: Definition,
+    {
+        let archives = cache.index_metadata(index_id)?;
+        let archive = archives
+            .iter()
+            .find(|archive| archive.id == archive_id)
+            .ok_or(RuneFsError::Read(ReadError::ArchiveNotFound {
+                idx: index_id,
+                arc: archive_id,
+            }))?;
+        let buffer = cache.read(index_id, archive_id)?.decode()?;
+        let ctx = DecodeContext {
+            version: archive.version,
+            ..DecodeContext::with_cache(cache, index_id, archive_id)
+        };
+
+        let archive_group = ArchiveFileGroup::try_from_buffer(&buffer, archive.entry_count)?;
 
-            base_id += ID_BLOCK_SIZE;
+        let mut definitions = HashMap::new();
+        for archive_file in archive_group {
+            definitions.insert(
+                archive_file.id,
+                D::new(archive_file.id, &archive_file, &ctx)?,
+            );
         }
 
         Ok(definitions)
@@ -1,10 +1,13 @@
-use std::{io, io::BufReader};
+use std::io::{self, BufReader};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::Definition;
-use crate::{extension::ReadExt, util};
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+    util, Protocol,
+};
 
 /// Contains all the information about a certain item fetched from the cache through
 /// the [ItemLoader](../../loader/rs3/struct.ItemLoader.html).
@@ -58,15 +61,19 @@ pub struct ModelData {
 }
 
 impl Definition for ItemDefinition {
-    fn new(id: u32, buffer: &[u8]) -> crate::Result<Self> {
-        let mut reader = BufReader::new(buffer);
-        let item_def = decode_buffer(id, &mut reader)?;
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let item_def = decode_buffer(id, &mut reader, ctx)?;
 
         Ok(item_def)
     }
 }
 
-fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefinition> {
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<ItemDefinition> {
     let mut item_def = ItemDefinition {
         id,
         options: [
@@ -92,7 +99,7 @@ fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
         match opcode {
             0 => break,
             1 => {
-                item_def.model_data.id = reader.read_smart()?;
+                item_def.model_data.id = read_model_id(reader, ctx)?;
             }
             2 => {
                 item_def.name = reader.read_string()?;
@@ -124,16 +131,16 @@ fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
             }
             16 => item_def.members_only = true,
             23 => {
-                item_def.model_data.male_equip1 = reader.read_smart()?;
+                item_def.model_data.male_equip1 = read_model_id(reader, ctx)?;
             }
             24 => {
-                item_def.model_data.male_equip2 = reader.read_smart()?;
+                item_def.model_data.male_equip2 = read_model_id(reader, ctx)?;
             }
             25 => {
-                item_def.model_data.female_equip1 = reader.read_smart()?;
+                item_def.model_data.female_equip1 = read_model_id(reader, ctx)?;
             }
             26 => {
-                item_def.model_data.female_equip2 = reader.read_smart()?;
+                item_def.model_data.female_equip2 = read_model_id(reader, ctx)?;
             }
             27 => {
                 item_def.equip_hide_slot2 = reader.read_u8()?;
@@ -178,10 +185,10 @@ fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
                 item_def.unnoted = true;
             }
             78 => {
-                item_def.model_data.male_equip_id = reader.read_smart()?;
+                item_def.model_data.male_equip_id = read_model_id(reader, ctx)?;
             }
             79 => {
-                item_def.model_data.female_equip_id = reader.read_smart()?;
+                item_def.model_data.female_equip_id = read_model_id(reader, ctx)?;
             }
             97 => {
                 item_def.noted_id = Some(reader.read_u16()?);
@@ -191,20 +198,9 @@ fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
                 item_def.stackable = true;
             }
             100..=109 => {
-                item_def.stack_ids = Some([0; 10]);
-                item_def.stack_count = Some([0; 10]);
-                match item_def.stack_ids {
-                    Some(mut stack_ids) => {
-                        stack_ids[opcode as usize - 100] = reader.read_u16()?;
-                    }
-                    _ => unreachable!(),
-                }
-                match item_def.stack_count {
-                    Some(mut stack_count) => {
-                        stack_count[opcode as usize - 100] = reader.read_u16()?;
-                    }
-                    _ => unreachable!(),
-                }
+                let index = opcode as usize - 100;
+                item_def.stack_ids.get_or_insert([0; 10])[index] = reader.read_u16()?;
+                item_def.stack_count.get_or_insert([0; 10])[index] = reader.read_u16()?;
             }
             115 => {
                 item_def.team = reader.read_u8()?;
@@ -263,11 +259,20 @@ fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<ItemDefin
                 reader.read_smart()?;
             }
             _ => {
-                println!("{} {}", id, opcode);
-                unreachable!()
+                ctx.unknown_opcode("item", id, opcode)?;
+                break;
             }
         }
     }
 
     Ok(item_def)
 }
+
+/// Reads a model id, OSRS caches store them as shorts.
+fn read_model_id(reader: &mut BufReader<&[u8]>, ctx: &DecodeContext) -> io::Result<u32> {
+    if ctx.protocol == Some(Protocol::Osrs) {
+        return reader.read_u16().map(u32::from);
+    }
+
+    reader.read_smart()
+}
@@ -0,0 +1,198 @@
+use std::{collections::HashMap, io, io::BufReader};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::{ArchiveFileData, DecodeContext, Definition},
+    extension::ReadExt,
+    util,
+};
+
+/// Contains all the information about a certain quest fetched from the cache through
+/// the [QuestLoader](../../loader/rs3/struct.QuestLoader.html).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct QuestDefinition {
+    pub id: u32,
+    pub name: String,
+    /// Name as it is shown in the quest list, usually equal to `name`.
+    pub list_name: String,
+    pub progress_varps: Vec<QuestProgress>,
+    pub progress_varbits: Vec<QuestProgress>,
+    pub quest_type: u8,
+    pub difficulty: u8,
+    pub members_only: bool,
+    /// Quest points rewarded on completion.
+    pub quest_points: u8,
+    /// Quest points needed before the quest can be started.
+    pub quest_point_requirement: u16,
+    /// Ids of the quests that have to be completed first.
+    pub quest_requirements: Vec<u16>,
+    pub skill_requirements: Vec<SkillRequirement>,
+    pub varp_requirements: Vec<VarRequirement>,
+    pub varbit_requirements: Vec<VarRequirement>,
+    pub graphic_id: Option<u32>,
+    pub params: HashMap<u32, String>,
+}
+
+/// A variable that tracks the progress of a quest, the quest is started once
+/// the variable reaches `start` and completed once it reaches `end`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct QuestProgress {
+    pub id: u16,
+    pub start: i32,
+    pub end: i32,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SkillRequirement {
+    pub skill_id: u8,
+    pub level: u8,
+}
+
+/// A requirement on a variable which falls outside of the regular quest and skill
+/// requirements, `description` is shown to the player in the quest journal.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct VarRequirement {
+    pub id: u32,
+    pub min: i32,
+    pub max: i32,
+    pub description: String,
+}
+
+impl Definition for QuestDefinition {
+    fn new(id: u32, file: &ArchiveFileData, ctx: &DecodeContext) -> crate::Result<Self> {
+        let mut reader = BufReader::new(file.data.as_slice());
+        let quest_def = decode_buffer(id, &mut reader, ctx)?;
+
+        Ok(quest_def)
+    }
+}
+
+fn decode_buffer(
+    id: u32,
+    reader: &mut BufReader<&[u8]>,
+    ctx: &DecodeContext,
+) -> crate::Result<QuestDefinition> {
+    let mut quest_def = QuestDefinition {
+        id,
+        ..QuestDefinition::default()
+    };
+
+    loop {
+        let opcode = reader.read_u8()?;
+
+        match opcode {
+            0 => break,
+            1 => {
+                quest_def.name = reader.read_string()?;
+            }
+            2 => {
+                quest_def.list_name = reader.read_string()?;
+            }
+            3 => {
+                quest_def.progress_varps = read_progress(reader)?;
+            }
+            4 => {
+                quest_def.progress_varbits = read_progress(reader)?;
+            }
+            5 => {
+                reader.read_u16()?;
+            }
+            6 => {
+                quest_def.quest_type = reader.read_u8()?;
+            }
+            7 => {
+                quest_def.difficulty = reader.read_u8()?;
+            }
+            8 => quest_def.members_only = true,
+            9 => {
+                quest_def.quest_points = reader.read_u8()?;
+            }
+            10 => {
+                let len = reader.read_u8()?;
+                for _ in 0..len {
+                    reader.read_i32()?;
+                }
+            }
+            12 => {
+                reader.read_i32()?;
+            }
+            13 => {
+                let len = reader.read_u8()? as usize;
+                quest_def.quest_requirements = Vec::with_capacity(len);
+                for _ in 0..len {
+                    quest_def.quest_requirements.push(reader.read_u16()?);
+                }
+            }
+            14 => {
+                let len = reader.read_u8()? as usize;
+                quest_def.skill_requirements = Vec::with_capacity(len);
+                for _ in 0..len {
+                    quest_def.skill_requirements.push(SkillRequirement {
+                        skill_id: reader.read_u8()?,
+                        level: reader.read_u8()?,
+                    });
+                }
+            }
+            15 => {
+                quest_def.quest_point_requirement = reader.read_u16()?;
+            }
+            17 => {
+                quest_def.graphic_id = Some(reader.read_smart()?);
+            }
+            18 => {
+                quest_def.varp_requirements = read_var_requirements(reader)?;
+            }
+            19 => {
+                quest_def.varbit_requirements = read_var_requirements(reader)?;
+            }
+            249 => {
+                quest_def.params = util::read_parameters(reader)?;
+            }
+            _ => {
+                ctx.unknown_opcode("quest", id, opcode)?;
+                break;
+            }
+        }
+    }
+
+    if quest_def.list_name.is_empty() {
+        quest_def.list_name = quest_def.name.clone();
+    }
+
+    Ok(quest_def)
+}
+
+fn read_progress(reader: &mut BufReader<&[u8]>) -> io::Result<Vec<QuestProgress>> {
+    let len = reader.read_u8()? as usize;
+    let mut progress = Vec::with_capacity(len);
+    for _ in 0..len {
+        progress.push(QuestProgress {
+            id: reader.read_u16()?,
+            start: reader.read_i32()?,
+            end: reader.read_i32()?,
+        });
+    }
+
+    Ok(progress)
+}
+
+fn read_var_requirements(reader: &mut BufReader<&[u8]>) -> io::Result<Vec<VarRequirement>> {
+    let len = reader.read_u8()? as usize;
+    let mut requirements = Vec::with_capacity(len);
+    for _ in 0..len {
+        requirements.push(VarRequirement {
+            id: reader.read_u32()?,
+            min: reader.read_i32()?,
+            max: reader.read_i32()?,
+            description: reader.read_string()?,
+        });
+    }
+
+    Ok(requirements)
+}
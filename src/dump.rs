@@ -0,0 +1,248 @@
+//! Dumping every archive of the cache to a directory tree, and building a
+//! cache from such a tree.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rscache::Cache;
+//! # fn main() -> Result<(), rscache::Error> {
+//! # let cache = Cache::new("./data/osrs_cache")?;
+//! let skipped = cache.dump_all("./dump", |progress| {
+//!     println!("{}/{}", progress.dumped, progress.total);
+//! })?;
+//! for archive in skipped {
+//!     eprintln!("{}/{}: {}", archive.index_id, archive.archive_id, archive.error);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::{
+    archive::ArchiveFileGroup,
+    metadata::ArchiveMetadata,
+    util::{coords::RegionId, djd2},
+    writer::CacheFiles,
+    Cache, Error,
+};
+
+/// The index of the map archives, its location archives are encrypted with
+/// XTEA.
+const MAP_INDEX_ID: u8 = 5;
+
+/// Reported by [`Cache::dump_all`] after every archive.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct DumpProgress {
+    pub index_id: u8,
+    pub archive_id: u32,
+    /// The amount of archives that were dumped so far, this one included.
+    pub dumped: usize,
+    /// The amount of archives in all indices.
+    pub total: usize,
+}
+
+/// An archive that [`Cache::dump_all`] could not dump.
+#[derive(Debug)]
+pub struct SkippedArchive {
+    pub index_id: u8,
+    pub archive_id: u32,
+    /// Why the archive was skipped, e.g. a decoding error or `MissingXteaKeys`.
+    pub error: Error,
+}
+
+impl Cache {
+    /// Builds a dat2/idx cache in `path` from a directory of encoded archives
+    /// and opens it.
+    ///
+    /// Archives are read from `dir/{index}/{archive}.dat`, as they are stored
+    /// in the data file with their version trailer, which is the layout of
+    /// OpenRS2 flat file exports. The reference tables have to be in
+    /// `dir/255`. Other files and directories are ignored, so the `.bin` files
+    /// of [`dump_all`](Cache::dump_all) can be in the same tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading the directory tree or writing the cache
+    /// fails, or any error of [`Cache::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rscache::Cache;
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let cache = Cache::import("./openrs2/cache", "./data/imported_cache")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn import<P: AsRef<Path>, Q: AsRef<Path>>(dir: P, path: Q) -> crate::Result<Self> {
+        let mut files = CacheFiles::default();
+        for (index_id, index_dir) in numbered_entries::<u8>(dir.as_ref(), None)? {
+            files.indices.entry(index_id).or_default();
+
+            for (archive_id, file) in numbered_entries::<u32>(&index_dir, Some("dat"))? {
+                files.insert(index_id, archive_id, &fs::read(file)?)?;
+            }
+        }
+        files.write(path.as_ref())?;
+
+        Self::new(path)
+    }
+
+    /// Writes every archive of every index into `dir`, calling `progress`
+    /// after each archive.
+    ///
+    /// Decoded archives are written to `dir/{index}/{archive}.bin`. Archives
+    /// with more than one file are also split into
+    /// `dir/{index}/{archive}/{file}.bin`. Location archives are deciphered
+    /// with the [`xtea_keys`](Cache::xtea_keys) of the cache.
+    ///
+    /// Archives that can't be read or decoded, and location archives without
+    /// keys, are skipped and returned together with the reason.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reference table can't be read, or if writing the
+    /// files fails.
+    pub fn dump_all<P, F>(&self, dir: P, mut progress: F) -> crate::Result<Vec<SkippedArchive>>
+    where
+        P: AsRef<Path>,
+        F: FnMut(DumpProgress),
+    {
+        let indices = self
+            .present_indices()
+            .into_iter()
+            .map(|index_id| Ok((index_id, self.index_metadata(index_id)?)))
+            .collect::<crate::Result<Vec<_>>>()?;
+        let total = indices.iter().map(|(_, metadata)| metadata.len()).sum();
+        let locations = location_regions();
+
+        let mut dumped = 0;
+        let mut skipped = Vec::new();
+        for (index_id, metadata) in indices {
+            let dir = dir.as_ref().join(index_id.to_string());
+            fs::create_dir_all(&dir)?;
+
+            for archive in metadata {
+                let region_id = locations
+                    .get(&archive.name_hash)
+                    .filter(|_| index_id == MAP_INDEX_ID);
+
+                match self.decode_for_dump(index_id, archive, region_id.copied()) {
+                    Ok((decoded, group)) => dump_archive(&dir, archive, &decoded, group)?,
+                    Err(error) => skipped.push(SkippedArchive {
+                        index_id,
+                        archive_id: archive.id,
+                        error,
+                    }),
+                }
+
+                dumped += 1;
+                progress(DumpProgress {
+                    index_id,
+                    archive_id: archive.id,
+                    dumped,
+                    total,
+                });
+            }
+        }
+
+        Ok(skipped)
+    }
+
+    /// Reads and decodes an archive and splits it into its files if it has
+    /// more than one. Location archives are deciphered with the keys of their
+    /// region.
+    fn decode_for_dump(
+        &self,
+        index_id: u8,
+        archive: &ArchiveMetadata,
+        region_id: Option<u16>,
+    ) -> crate::Result<(Arc<[u8]>, Option<ArchiveFileGroup>)> {
+        let decoded = match region_id {
+            Some(region_id) => {
+                let keys = self
+                    .xtea_keys()
+                    .get(region_id)
+                    .ok_or(Error::MissingXteaKeys { region_id })?;
+
+                self.read_decoded_shared_with_keys(index_id, archive.id, keys)?
+            }
+            None => self.read_decoded_shared(index_id, archive.id)?,
+        };
+        let group = (archive.entry_count > 1)
+            .then(|| ArchiveFileGroup::try_from_buffer(&decoded, archive.entry_count))
+            .transpose()?;
+
+        Ok((decoded, group))
+    }
+}
+
+fn dump_archive(
+    dir: &Path,
+    archive: &ArchiveMetadata,
+    decoded: &[u8],
+    group: Option<ArchiveFileGroup>,
+) -> crate::Result<()> {
+    fs::write(dir.join(format!("{}.bin", archive.id)), decoded)?;
+
+    if let Some(group) = group {
+        let dir = dir.join(archive.id.to_string());
+        fs::create_dir_all(&dir)?;
+
+        for (file, file_id) in group.into_iter().zip(&archive.valid_ids) {
+            fs::write(dir.join(format!("{}.bin", file_id)), file.data)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The regions of the location archives in the map index, by the hash of
+/// their name.
+fn location_regions() -> HashMap<i32, u16> {
+    (0..=u16::MAX)
+        .map(|id| {
+            let region = RegionId(id);
+
+            (djd2::hash(format!("l{}_{}", region.x(), region.y())), id)
+        })
+        .collect()
+}
+
+/// The entries of `dir` that are named after a number, sorted by that number.
+///
+/// Only files with the given extension are returned, or only directories
+/// without one.
+fn numbered_entries<T: FromStr + Ord>(
+    dir: &Path,
+    extension: Option<&str>,
+) -> crate::Result<Vec<(T, PathBuf)>> {
+    let mut entries: Vec<(T, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = match extension {
+            Some(extension) if path.extension() == Some(OsStr::new(extension)) => path.file_stem(),
+            None if path.is_dir() => path.file_name(),
+            _ => continue,
+        };
+
+        let id: Option<T> = name
+            .and_then(OsStr::to_str)
+            .and_then(|name| name.parse().ok());
+        if let Some(id) = id {
+            entries.push((id, path));
+        }
+    }
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(entries)
+}
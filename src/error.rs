@@ -1,6 +1,12 @@
 //! Error management.
+//!
+//! Errors of this crate carry their details as fields so they can be matched
+//! on, errors from other crates are returned through
+//! [`source`](std::error::Error::source).
 
-use runefs::Error as RuneFsError;
+pub use runefs::error::{Error as RuneFsError, ReadError};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::io;
 use thiserror::Error;
 
@@ -8,18 +14,54 @@ pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 /// Super error type for all cache errors.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Wrapper for the std::io::Error type.
-    #[error(transparent)]
+    #[error("failed to read or write cache files")]
     Io(#[from] io::Error),
     #[error(transparent)]
     NameHash(#[from] NameHashMismatch),
-    #[error("unknown parser error")]
+    #[error("failed to parse a buffer")]
     Parse(#[from] nom::Err<()>),
     #[error(transparent)]
     Validate(#[from] ValidateError),
-    #[error(transparent)]
+    #[error("failed to read from the cache file system")]
     RuneFs(#[from] RuneFsError),
+    #[error(transparent)]
+    Sector(#[from] SectorError),
+    #[error(transparent)]
+    Huffman(#[from] HuffmanError),
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+    #[error(transparent)]
+    Js5(#[from] Js5Error),
+    #[error(transparent)]
+    Write(#[from] WriteError),
+    #[error(transparent)]
+    Definition(#[from] DefinitionError),
+    #[error(transparent)]
+    ArchiveGroup(#[from] ArchiveGroupError),
+    #[error(transparent)]
+    Metadata(#[from] MetadataError),
+    /// The id of an index file could not be taken from its file name.
+    #[error("expected an index file named like \"{expected}\" but found \"{found}\"")]
+    InvalidIndexFile { expected: String, found: String },
+    /// A location archive is encrypted and there are no XTEA keys for its
+    /// region.
+    #[error("no xtea keys for the location archive of region {region_id}")]
+    MissingXteaKeys { region_id: u16 },
+    #[cfg(feature = "rsa")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rsa")))]
+    #[error(transparent)]
+    Rsa(#[from] RsaError),
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    #[error("failed to export to json")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    #[error("failed to export to csv")]
+    Csv(#[from] csv::Error),
 }
 
 #[derive(Error, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -30,7 +72,28 @@ pub struct NameHashMismatch {
     pub(crate) idx: u8,
 }
 
+impl NameHashMismatch {
+    /// The djd2 hash of the name.
+    #[inline]
+    pub const fn hash(&self) -> i32 {
+        self.hash
+    }
+
+    /// The name that was looked up.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The index the name was looked up in.
+    #[inline]
+    pub const fn index_id(&self) -> u8 {
+        self.idx
+    }
+}
+
 #[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
 pub enum ValidateError {
     #[error("expected crc length of {expected} but was {actual}")]
     InvalidLength {
@@ -44,3 +107,201 @@ pub enum ValidateError {
         external: u32,
     },
 }
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum SectorError {
+    #[error("sector belongs to archive {actual} but expected archive {expected}")]
+    ArchiveMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    #[error("sector is chunk {actual} of its archive but expected chunk {expected}")]
+    ChunkMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    #[error("sector belongs to index {actual} but expected index {expected}")]
+    IndexMismatch {
+        expected: u8,
+        actual: u8,
+    },
+    /// The archive ended before all of its bytes were read, usually because
+    /// the cache is truncated.
+    #[error("archive should be {expected} bytes long but only {actual} bytes could be read")]
+    LengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// An index or sector header points to a sector past the end of the data
+    /// file.
+    #[error("sector {sector} at offset {offset} is past the end of the data file")]
+    SectorOutOfBounds { sector: usize, offset: u64 },
+    /// The data file is too large to address a new sector, sector numbers are
+    /// stored in 24 bits.
+    #[error("sector {sector} can't be addressed with 24 bits")]
+    SectorOverflow { sector: u64 },
+    /// The sector chain of an archive points back to a sector that holds an
+    /// earlier chunk of the archive.
+    #[error("sector chain loops back to sector {sector}")]
+    CyclicChain { sector: usize },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum HuffmanError {
+    #[error("invalid huffman tree node {0}")]
+    InvalidKey(usize),
+    #[error("expected {expected} decompressed bytes but the message ended after {actual}")]
+    Truncated {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum CodecError {
+    #[error("unknown compression type {0}")]
+    UnknownCompression(u8),
+    #[error("encoding with {0:?} compression is not supported")]
+    Unsupported(crate::codec::Compression),
+    /// The compression can't be decoded with the enabled features, see
+    /// [`Compression::is_supported`](crate::codec::Compression::is_supported).
+    #[error("decoding {0:?} compressed buffers requires the lzma feature")]
+    DecodeUnsupported(crate::codec::Compression),
+    #[error("{compression:?} can't be encoded with level {level}")]
+    InvalidLevel {
+        compression: crate::codec::Compression,
+        level: u32,
+    },
+    #[error("expected a buffer of at least {expected} bytes but was {actual}")]
+    Truncated {
+        expected: usize,
+        actual: usize,
+    },
+    #[error("decompressed data does not match the length of {expected} bytes in the header")]
+    LengthMismatch {
+        expected: usize,
+    },
+    /// Returned by [`roundtrip_check`](crate::codec::roundtrip_check).
+    #[error("{compression:?} round trip changed the {field}")]
+    RoundTripMismatch {
+        compression: crate::codec::Compression,
+        field: &'static str,
+    },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum Js5Error {
+    #[error("server rejected the handshake with response code {0}")]
+    HandshakeRejected(u8),
+    #[error("expected a block marker but was {0}")]
+    InvalidBlockMarker(u8),
+    #[error("unknown request opcode {0}")]
+    InvalidOpcode(u8),
+    /// JS5 requests and responses only have room for 16 bit archive ids.
+    #[error("archive id {0} does not fit in a JS5 request")]
+    ArchiveIdOverflow(u32),
+    #[error("received archive {index_id}/{archive_id} which was not requested")]
+    UnexpectedArchive {
+        index_id: u8,
+        archive_id: u32,
+    },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum WriteError {
+    #[error("archive {archive_id} is not in the reference table of index {index_id}")]
+    UnknownArchive {
+        index_id: u8,
+        archive_id: u32,
+    },
+    #[error("archive of {len} bytes is too large for the 24 bit length of its index entry")]
+    ArchiveTooLarge {
+        len: usize,
+    },
+    #[error("archive {archive_id} of index {index_id} has no version trailer")]
+    MissingVersion {
+        index_id: u8,
+        archive_id: u32,
+    },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum ArchiveGroupError {
+    #[error("archive of {len} bytes is too short for {chunks} chunks of {entry_count} files")]
+    InvalidChunkCount {
+        chunks: usize,
+        entry_count: usize,
+        len: usize,
+    },
+    #[error("chunk of {size} bytes at {offset} is out of range of the {len} bytes of file data")]
+    OutOfRange {
+        offset: usize,
+        size: i64,
+        len: usize,
+    },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum MetadataError {
+    #[error("reference table protocol {0} is not supported")]
+    UnsupportedProtocol(u8),
+    #[error("{value} does not fit in an id of reference table protocol {protocol}")]
+    IdOverflow {
+        protocol: u8,
+        value: u32,
+    },
+    #[error("archive {archive_id} has a whirlpool digest of {len} bytes instead of 64")]
+    InvalidWhirlpool {
+        archive_id: u32,
+        len: usize,
+    },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum DefinitionError {
+    #[error("unknown opcode {opcode} in {kind} definition {id}")]
+    UnknownOpcode {
+        kind: &'static str,
+        id: u32,
+        opcode: u8,
+    },
+    /// Models of type 2 and 3, which end with `0xFF 0xFE` and `0xFF 0xFD`,
+    /// can't be decoded yet.
+    #[error("model {id} is stored in the unsupported format {format}")]
+    UnsupportedModelFormat {
+        id: u32,
+        format: u8,
+    },
+    /// An RS3 model id that doesn't fit in the 16 bits of an OSRS definition.
+    #[error("model {model_id} of definition {id} does not fit in 16 bits")]
+    ModelIdOverflow {
+        id: u32,
+        model_id: u32,
+    },
+}
+
+#[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum RsaError {
+    #[error("rsa exponent is not a valid decimal number")]
+    InvalidExponent,
+    #[error("rsa modulus is not a valid decimal number larger than 1")]
+    InvalidModulus,
+    #[error("rsa block is not smaller than the modulus")]
+    BlockTooLarge,
+}
+
+impl From<SectorError> for io::Error {
+    fn from(err: SectorError) -> Self {
+        Self::new(io::ErrorKind::InvalidData, err)
+    }
+}
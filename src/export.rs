@@ -0,0 +1,129 @@
+//! Bulk export of definitions to JSON lines or CSV.
+//!
+//! Every [`Loader`] whose definitions can be serialized gets the [`Export`]
+//! methods, definitions are written in order of their id.
+//!
+//! # Example
+//!
+//! ```
+//! # use rscache::Cache;
+//! use rscache::{export::Export, loader::osrs::ItemLoader};
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! # let cache = Cache::new("./data/osrs_cache")?;
+//! let item_loader = ItemLoader::new(&cache)?;
+//!
+//! let mut items = Vec::new();
+//! item_loader.export_json(&mut items)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, io::Write};
+
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::Value;
+
+use crate::loader::Loader;
+
+/// Streams all definitions of a loader out to a writer.
+pub trait Export: Loader
+where
+    Self::Definition: Serialize,
+{
+    /// Writes every definition as a single line of JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serializing or writing fails.
+    fn export_json<W: Write>(&self, mut writer: W) -> crate::Result<()> {
+        for (_, definition) in self.iter() {
+            serde_json::to_writer(&mut writer, definition)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Writes every definition as a CSV record, preceded by a header.
+    ///
+    /// The columns are the fields of the definition in declaration order.
+    /// Fields that don't hold a single value, like lists or nested structs,
+    /// are written as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serializing or writing fails.
+    fn export_csv<W: Write>(&self, writer: W) -> crate::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        let mut has_header = false;
+
+        for (_, definition) in self.iter() {
+            // Parsed into a list instead of a `Value`, its map doesn't keep
+            // the order of the fields.
+            let json = serde_json::to_vec(definition)?;
+            let fields = if json.first() == Some(&b'{') {
+                serde_json::from_slice::<Fields>(&json)?.0
+            } else {
+                vec![("value".to_owned(), serde_json::from_slice(&json)?)]
+            };
+
+            if !has_header {
+                writer.write_record(fields.iter().map(|(name, _)| name))?;
+                has_header = true;
+            }
+
+            let record = fields
+                .iter()
+                .map(|(_, value)| match value {
+                    Value::Null => String::new(),
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                })
+                .collect::<Vec<_>>();
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl<L> Export for L
+where
+    L: Loader,
+    L::Definition: Serialize,
+{
+}
+
+/// The fields of a JSON object in the order they were written.
+struct Fields(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Fields {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldsVisitor;
+
+        impl<'de> Visitor<'de> for FieldsVisitor {
+            type Value = Fields;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Fields, A::Error> {
+                let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+
+                Ok(Fields(fields))
+            }
+        }
+
+        deserializer.deserialize_map(FieldsVisitor)
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::io::{self, Read};
 
+use crate::{parse, util::charset};
+
 /// Adds easy byte reading onto a [`Read`] instance.
 ///
 /// [`Read`]: https://doc.rust-lang.org/std/io/trait.Read.html
@@ -11,6 +13,13 @@ pub trait ReadExt: Read {
     fn read_u16(&mut self) -> io::Result<u16>;
     fn read_i16(&mut self) -> io::Result<i16>;
     fn read_smart_u16(&mut self) -> io::Result<u16>;
+    /// Reads an unsigned smart, a byte for values below `128` or a short for
+    /// values up to `32767`.
+    fn read_usmart(&mut self) -> io::Result<u16>;
+    fn read_i16_smart(&mut self) -> io::Result<i16>;
+    /// Reads a smart that is stored as its value plus one, `0` means there is
+    /// no value.
+    fn read_nullable_smart_u16(&mut self) -> io::Result<Option<u16>>;
     fn read_u24(&mut self) -> io::Result<u32>;
     fn read_i24(&mut self) -> io::Result<i32>;
     fn read_u32(&mut self) -> io::Result<u32>;
@@ -20,6 +29,12 @@ pub trait ReadExt: Read {
     fn read_u128(&mut self) -> io::Result<u128>;
     fn read_i128(&mut self) -> io::Result<i128>;
     fn read_smart(&mut self) -> io::Result<u32>;
+    /// Reads a big smart where [`SMART_CONTINUATION`](parse::SMART_CONTINUATION)
+    /// means there is no value.
+    fn read_nullable_smart(&mut self) -> io::Result<Option<u32>>;
+    /// Reads smarts and adds them up for as long as they are
+    /// [`SMART_CONTINUATION`](parse::SMART_CONTINUATION).
+    fn read_smart_compat(&mut self) -> io::Result<u32>;
     fn read_string(&mut self) -> io::Result<String>;
 }
 
@@ -61,6 +76,27 @@ impl<T: Read> ReadExt for T {
             Ok(value - 0xC000)
         }
     }
+
+    fn read_usmart(&mut self) -> io::Result<u16> {
+        let (bytes, len) = read_smart_bytes(self, 1)?;
+
+        parse::be_u16_smart(&bytes[..len])
+            .map(|(_, value)| value)
+            .map_err(invalid_data)
+    }
+
+    fn read_i16_smart(&mut self) -> io::Result<i16> {
+        let (bytes, len) = read_smart_bytes(self, 1)?;
+
+        parse::be_i16_smart(&bytes[..len])
+            .map(|(_, value)| value)
+            .map_err(invalid_data)
+    }
+
+    fn read_nullable_smart_u16(&mut self) -> io::Result<Option<u16>> {
+        Ok(self.read_usmart()?.checked_sub(1))
+    }
+
     fn read_u24(&mut self) -> io::Result<u32> {
         let mut buffer = [0; 3];
         self.read_exact(&mut buffer)?;
@@ -105,30 +141,31 @@ impl<T: Read> ReadExt for T {
         Ok(self.read_u128()? as i128)
     }
 
-    // clean this up.
-    // can't find a way to peek the first byte, even
-    // an iterator reads the first byte...
     fn read_smart(&mut self) -> io::Result<u32> {
-        let byte = self.read_u8()?;
+        let (bytes, len) = read_smart_bytes(self, 2)?;
 
-        if (byte as i64 ^ 0xffffffff) as i8 <= -1 {
-            let value = self.read_u8()?;
-            let mut arr = [0; 2];
-            arr[0] = byte;
-            arr[1] = value;
+        parse::be_u32_smart(&bytes[..len])
+            .map(|(_, value)| value)
+            .map_err(invalid_data)
+    }
 
-            return Ok(u16::from_be_bytes(arr) as u32);
-        }
+    fn read_nullable_smart(&mut self) -> io::Result<Option<u32>> {
+        let value = self.read_smart()?;
 
-        let mut buffer = [0; 3];
-        self.read_exact(&mut buffer)?;
-        let mut arr = [0; 4];
-        arr[0] = byte;
-        arr[1] = buffer[0];
-        arr[2] = buffer[1];
-        arr[3] = buffer[2];
+        Ok((value != parse::SMART_CONTINUATION).then_some(value))
+    }
 
-        Ok(u32::from_be_bytes(arr) & 0x7fffffff)
+    fn read_smart_compat(&mut self) -> io::Result<u32> {
+        let mut total = 0_u32;
+
+        loop {
+            let value = self.read_usmart()? as u32;
+            total = total.wrapping_add(value);
+
+            if value != parse::SMART_CONTINUATION {
+                return Ok(total);
+            }
+        }
     }
 
     fn read_string(&mut self) -> io::Result<String> {
@@ -141,6 +178,114 @@ impl<T: Read> ReadExt for T {
                 break;
             }
         }
-        Ok(String::from_utf8_lossy(&bytes[..]).to_string())
+        Ok(charset::decode(&bytes))
+    }
+}
+
+/// Adds writing in the formats of [`ReadExt`] onto a byte buffer.
+pub trait WriteExt {
+    fn write_u8(&mut self, value: u8);
+    fn write_i8(&mut self, value: i8);
+    fn write_u16(&mut self, value: u16);
+    fn write_i16(&mut self, value: i16);
+    /// Writes an unsigned smart, see [`ReadExt::read_usmart`].
+    ///
+    /// Only values below `0x8000` fit in a smart.
+    fn write_usmart(&mut self, value: u16);
+    /// Writes a smart as its value plus one, `None` is written as `0`.
+    ///
+    /// Only values below `0x7FFF` fit in a smart.
+    fn write_nullable_smart_u16(&mut self, value: Option<u16>);
+    fn write_u24(&mut self, value: u32);
+    fn write_u32(&mut self, value: u32);
+    fn write_i32(&mut self, value: i32);
+    /// Writes a big smart, only values below `0x8000_0000` fit in one.
+    fn write_smart(&mut self, value: u32);
+    /// Writes a big smart where `None` is written as
+    /// [`SMART_CONTINUATION`](parse::SMART_CONTINUATION).
+    fn write_nullable_smart(&mut self, value: Option<u32>);
+    /// Writes a cp1252 string followed by its terminating `0`.
+    fn write_string(&mut self, value: &str);
+}
+
+impl WriteExt for Vec<u8> {
+    fn write_u8(&mut self, value: u8) {
+        self.push(value);
+    }
+
+    fn write_i8(&mut self, value: i8) {
+        self.write_u8(value as u8);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.extend(value.to_be_bytes());
+    }
+
+    fn write_i16(&mut self, value: i16) {
+        self.write_u16(value as u16);
+    }
+
+    fn write_usmart(&mut self, value: u16) {
+        debug_assert!(value < 0x8000, "{} doesn't fit in a smart", value);
+        if value < 0x80 {
+            self.write_u8(value as u8);
+        } else {
+            self.write_u16(value | 0x8000);
+        }
+    }
+
+    fn write_nullable_smart_u16(&mut self, value: Option<u16>) {
+        debug_assert!(
+            value.is_none_or(|value| value < 0x7FFF),
+            "{:?} doesn't fit in a smart",
+            value
+        );
+        self.write_usmart(value.map_or(0, |value| value.wrapping_add(1)));
+    }
+
+    fn write_u24(&mut self, value: u32) {
+        self.extend(&value.to_be_bytes()[1..]);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.extend(value.to_be_bytes());
     }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+
+    fn write_smart(&mut self, value: u32) {
+        debug_assert!(value < 0x8000_0000, "{} doesn't fit in a smart", value);
+        if value <= i16::MAX as u32 {
+            self.write_u16(value as u16);
+        } else {
+            self.write_u32(value | 0x8000_0000);
+        }
+    }
+
+    fn write_nullable_smart(&mut self, value: Option<u32>) {
+        self.write_smart(value.unwrap_or(parse::SMART_CONTINUATION));
+    }
+
+    fn write_string(&mut self, value: &str) {
+        self.extend(charset::encode(value));
+        self.push(0);
+    }
+}
+
+/// Reads the bytes of a smart, which is `len` bytes long or twice that when
+/// the high bit of its first byte is set.
+fn read_smart_bytes<R: Read>(reader: &mut R, len: usize) -> io::Result<([u8; 4], usize)> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes[..1])?;
+
+    let len = if bytes[0] & 0x80 == 0 { len } else { len * 2 };
+    reader.read_exact(&mut bytes[1..len])?;
+
+    Ok((bytes, len))
+}
+
+fn invalid_data(_: nom::Err<()>) -> io::Error {
+    io::ErrorKind::InvalidData.into()
 }
@@ -0,0 +1,139 @@
+//! A minimal C API for servers that are not written in Rust.
+//!
+//! Emulators written in Java, Kotlin or C++ can link against this API to
+//! read the cache instead of reimplementing the file system. Build a shared or
+//! static library with the `ffi` feature enabled:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! The declarations are in `include/rscache.h`, the cache is an opaque
+//! `Cache` that is only passed around by pointer.
+//!
+//! Every function that reads an archive returns its length, [`RSCACHE_ERROR`]
+//! if the archive could not be read or [`RSCACHE_PANIC`] if reading it
+//! panicked. The archive is only copied when the buffer of the caller is large
+//! enough, so a read with a null buffer can be used to find out how large the
+//! buffer has to be.
+//!
+//! ```c
+//! Cache *cache = rscache_open("./data/osrs_cache");
+//! int64_t len = rscache_read(cache, 2, 10, NULL, 0);
+//! uint8_t *buf = malloc(len);
+//! rscache_read(cache, 2, 10, buf, len);
+//! rscache_free(cache);
+//! ```
+
+use std::{
+    ffi::{c_char, CStr},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::Cache;
+
+/// Returned when an archive could not be read.
+pub const RSCACHE_ERROR: i64 = -1;
+/// Returned when reading an archive panicked, panics never unwind into C.
+pub const RSCACHE_PANIC: i64 = -2;
+
+/// Opens the cache in the given directory.
+///
+/// Returns a null pointer if `path` is not valid UTF-8 or the cache could not
+/// be opened, also when opening it panicked. The cache has to be released with
+/// [`rscache_free`].
+///
+/// # Safety
+///
+/// `path` must be a valid, null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rscache_open(path: *const c_char) -> *mut Cache {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+
+    let path = CStr::from_ptr(path);
+    match panic::catch_unwind(|| path.to_str().map(Cache::new)) {
+        Ok(Ok(Ok(cache))) => Box::into_raw(Box::new(cache)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Reads the encoded archive into `buf`.
+///
+/// # Safety
+///
+/// `cache` must come from [`rscache_open`] and `buf` must be null or valid
+/// for writes of `buf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rscache_read(
+    cache: *const Cache,
+    index_id: u8,
+    archive_id: u32,
+    buf: *mut u8,
+    buf_len: usize,
+) -> i64 {
+    let Some(cache) = cache.as_ref() else {
+        return RSCACHE_ERROR;
+    };
+
+    catch_read(|| match cache.read(index_id, archive_id) {
+        Ok(buffer) => copy_into(&buffer, buf, buf_len),
+        Err(_) => RSCACHE_ERROR,
+    })
+}
+
+/// Reads the archive into `buf` after decompressing it.
+///
+/// # Safety
+///
+/// See [`rscache_read`].
+#[no_mangle]
+pub unsafe extern "C" fn rscache_read_decoded(
+    cache: *const Cache,
+    index_id: u8,
+    archive_id: u32,
+    buf: *mut u8,
+    buf_len: usize,
+) -> i64 {
+    let Some(cache) = cache.as_ref() else {
+        return RSCACHE_ERROR;
+    };
+
+    catch_read(|| {
+        match cache
+            .read(index_id, archive_id)
+            .and_then(|buffer| Ok(buffer.decode()?))
+        {
+            Ok(buffer) => copy_into(&buffer, buf, buf_len),
+            Err(_) => RSCACHE_ERROR,
+        }
+    })
+}
+
+/// Releases a cache opened with [`rscache_open`], null pointers are ignored.
+///
+/// # Safety
+///
+/// `cache` must come from [`rscache_open`] and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rscache_free(cache: *mut Cache) {
+    if !cache.is_null() {
+        drop(Box::from_raw(cache));
+    }
+}
+
+/// Runs a read, a panic is turned into [`RSCACHE_PANIC`] instead of unwinding
+/// across the C boundary.
+fn catch_read<F: FnOnce() -> i64>(read: F) -> i64 {
+    panic::catch_unwind(AssertUnwindSafe(read)).unwrap_or(RSCACHE_PANIC)
+}
+
+unsafe fn copy_into(data: &[u8], buf: *mut u8, buf_len: usize) -> i64 {
+    if !buf.is_null() && buf_len >= data.len() {
+        ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len());
+    }
+
+    data.len() as i64
+}
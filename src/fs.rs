@@ -0,0 +1,51 @@
+//! The file system the cache is built on, re-exported from [`runefs`].
+//!
+//! Gives access to the lower level types like [`Dat2`](runefs::Dat2),
+//! [`Indices`](runefs::Indices), [`Sector`](runefs::Sector) and
+//! [`ArchiveRef`](runefs::ArchiveRef) without a separate dependency on a
+//! matching version of `runefs`. The sizes of the file system are in
+//! [`consts`].
+//!
+//! # Examples
+//!
+//! ```
+//! use rscache::fs::{Dat2, Indices, MAIN_DATA};
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! let path = std::path::Path::new("./data/osrs_cache");
+//! let data = Dat2::new(path.join(MAIN_DATA))?;
+//! let indices = Indices::new(path)?;
+//!
+//! let archive = &indices.get(&2).unwrap().archive_refs[&10];
+//! let buffer = data.read(archive)?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use runefs::*;
+
+/// The sizes of sectors, index entries, archive headers and JS5 blocks.
+///
+/// Collected in one place so code that reads the files or frames archives
+/// itself doesn't need magic numbers.
+///
+/// # Examples
+///
+/// ```
+/// use rscache::fs::consts::{DataBlocks, SECTOR_DATA_SIZE, SECTOR_SIZE};
+///
+/// let length = 1200;
+/// let sectors = DataBlocks::new(10, length).len();
+///
+/// assert_eq!(sectors, length.div_ceil(SECTOR_DATA_SIZE));
+/// assert_eq!(sectors * SECTOR_SIZE, 1560);
+/// ```
+pub mod consts {
+    pub use crate::codec::{COMPRESSED_HEADER_LEN, HEADER_LEN, VERSION_LEN};
+    pub use crate::index::ARCHIVE_REF_LEN;
+    pub use crate::net::{BLOCK_LEN, BLOCK_MARKER};
+    pub use crate::sector::{
+        DataBlocks, MAX_SECTOR, SECTOR_DATA_SIZE, SECTOR_EXPANDED_DATA_SIZE,
+        SECTOR_EXPANDED_HEADER_SIZE, SECTOR_HEADER_SIZE, SECTOR_SIZE,
+    };
+}
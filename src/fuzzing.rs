@@ -0,0 +1,69 @@
+//! Parser entry points for the cargo-fuzz targets in `fuzz/`.
+//!
+//! Every function takes untrusted bytes, doesn't do any IO and throws the
+//! parsed result away. Malformed input has to come back as an error, so any
+//! panic or hang found by the fuzzer is a bug in the parser. Sizes read from
+//! the input are checked against its length before anything is allocated.
+//!
+//! Run a target with `cargo fuzz run <target>` from the repository root. The
+//! `fuzzing` tests run every target on a fixed set of random and corrupted
+//! inputs, which catches regressions without cargo-fuzz.
+//!
+//! # Example
+//!
+//! ```
+//! use rscache::fuzzing;
+//!
+//! fuzzing::index_metadata(&[7, 0, 0, 0, 1]);
+//! fuzzing::sector_header(&[0, 1, 2]);
+//! fuzzing::definitions(&[0]);
+//! ```
+
+use crate::{
+    definition::osrs::{
+        DecodeContext, Definition, FontMetricsDefinition, GraphicsDefaultsDefinition,
+        ItemDefinition, LocationDefinition, MapDefinition, ModelDefinition, NpcDefinition,
+        ObjectDefinition, OverlayDefinition, SpriteDefinition, TextureDefinition,
+        UnderlayDefinition,
+    },
+    metadata::IndexMetadata,
+    sector::{SectorHeader, SectorHeaderSize},
+};
+
+/// Parses `data` as a decoded reference table.
+pub fn index_metadata(data: &[u8]) {
+    let _ = IndexMetadata::try_from(data);
+}
+
+/// Parses `data` as a normal and as an expanded sector header.
+pub fn sector_header(data: &[u8]) {
+    let _ = SectorHeader::try_new(data, SectorHeaderSize::Normal);
+    let _ = SectorHeader::try_new(data, SectorHeaderSize::Expanded);
+}
+
+/// Decodes `data` as every OSRS definition, both strict and lenient.
+pub fn definitions(data: &[u8]) {
+    for lenient in [false, true] {
+        let ctx = DecodeContext {
+            lenient,
+            ..DecodeContext::new(0, 0)
+        };
+
+        decode::<FontMetricsDefinition>(data, &ctx);
+        decode::<GraphicsDefaultsDefinition>(data, &ctx);
+        decode::<ItemDefinition>(data, &ctx);
+        decode::<LocationDefinition>(data, &ctx);
+        decode::<MapDefinition>(data, &ctx);
+        decode::<ModelDefinition>(data, &ctx);
+        decode::<NpcDefinition>(data, &ctx);
+        decode::<ObjectDefinition>(data, &ctx);
+        decode::<OverlayDefinition>(data, &ctx);
+        decode::<SpriteDefinition>(data, &ctx);
+        decode::<TextureDefinition>(data, &ctx);
+        decode::<UnderlayDefinition>(data, &ctx);
+    }
+}
+
+fn decode<D: Definition>(data: &[u8], ctx: &DecodeContext) {
+    let _ = D::from_buffer(0, data, ctx);
+}
@@ -0,0 +1,223 @@
+//! Index files and the data file they point into.
+//!
+//! Every `main_file_cache.idx*` file is a flat list of 6 byte archive
+//! references, each holding the length of an archive and the first sector of
+//! its chain in the data file.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::{self, Read},
+    ops::Deref,
+    path::Path,
+    sync::OnceLock,
+};
+
+use memmap2::Mmap;
+use runefs::{
+    error::{Error as RuneFsError, ReadError},
+    ArchiveRef, REFERENCE_TABLE_ID,
+};
+
+use crate::metadata::IndexMetadata;
+
+/// File name prefix of every index file, followed by the index id.
+pub(crate) const IDX_PREFIX: &str = "main_file_cache.idx";
+/// Size of a single archive reference in an index file.
+pub const ARCHIVE_REF_LEN: usize = 6;
+
+/// How the data files of a cache are brought into memory.
+///
+/// Memory maps are the fastest way to read a cache, but some platforms such as
+/// certain containers or network shares don't handle them well.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+pub enum Backend {
+    /// Maps the data files into memory, pages are only read when they are
+    /// needed.
+    #[default]
+    Mmap,
+    /// Reads the data files into an owned buffer up front.
+    Heap,
+}
+
+impl Backend {
+    pub(crate) fn load(self, mut file: File) -> io::Result<Data> {
+        match self {
+            Self::Mmap => Ok(Data::Mapped(unsafe { Mmap::map(&file)? })),
+            Self::Heap => {
+                let mut buffer = Vec::new();
+                file.read_to_end(&mut buffer)?;
+                Ok(Data::Owned(Box::new(buffer)))
+            }
+        }
+    }
+}
+
+/// The bytes of a data file, either mapped from disk or handed over in memory.
+pub(crate) enum Data {
+    Mapped(Mmap),
+    Owned(Box<dyn AsRef<[u8]> + Send + Sync>),
+}
+
+impl Deref for Data {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Mapped(mmap) => mmap,
+            Self::Owned(buffer) => (**buffer).as_ref(),
+        }
+    }
+}
+
+impl fmt::Debug for Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Mapped(_) => "Mapped",
+            Self::Owned(_) => "Owned",
+        };
+
+        f.debug_struct("Data")
+            .field("kind", &kind)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// A single index with the archive references from its index file and the
+/// metadata from its reference table.
+///
+/// The metadata is only decoded when it is first needed, see
+/// [`Cache::index_metadata`](crate::Cache::index_metadata).
+#[derive(Debug)]
+pub(crate) struct Index {
+    pub archive_refs: HashMap<u32, ArchiveRef>,
+    pub metadata: OnceLock<IndexMetadata>,
+}
+
+impl Index {
+    fn from_buffer(id: u8, buffer: &[u8]) -> Self {
+        let archive_refs = buffer
+            .chunks_exact(ARCHIVE_REF_LEN)
+            .enumerate()
+            .map(|(archive_id, buffer)| {
+                let archive_id = archive_id as u32;
+                let length = u32::from_be_bytes([0, buffer[0], buffer[1], buffer[2]]);
+                let sector = u32::from_be_bytes([0, buffer[3], buffer[4], buffer[5]]);
+
+                let archive = ArchiveRef {
+                    id: archive_id,
+                    index_id: id,
+                    sector: sector as usize,
+                    length: length as usize,
+                };
+
+                (archive_id, archive)
+            })
+            .collect();
+
+        Self {
+            archive_refs,
+            metadata: OnceLock::new(),
+        }
+    }
+}
+
+/// Every index of the cache, the reference table included.
+#[derive(Debug, Default)]
+pub(crate) struct Indices(HashMap<u8, Index>);
+
+impl Indices {
+    /// Reads every index file in `path` that is listed in the reference table.
+    pub(crate) fn new(path: &Path) -> crate::Result<Self> {
+        let reference = fs::read(path.join(format!("{}{}", IDX_PREFIX, REFERENCE_TABLE_ID)))?;
+        let index_count = reference.len() / ARCHIVE_REF_LEN;
+
+        let mut buffers = vec![(REFERENCE_TABLE_ID, reference)];
+        for index_id in (0..index_count.min(REFERENCE_TABLE_ID as usize)).map(|id| id as u8) {
+            match fs::read(path.join(format!("{}{}", IDX_PREFIX, index_id))) {
+                Ok(buffer) => buffers.push((index_id, buffer)),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Self::from_buffers(buffers)
+    }
+
+    /// Reads the given index files, their ids are taken from their `idx`
+    /// extensions.
+    pub(crate) fn from_paths<I, P>(paths: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let paths = paths
+            .into_iter()
+            .map(|path| Ok((index_id(path.as_ref())?, path)))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Self::from_paths_with_ids(paths)
+    }
+
+    /// Reads the given index files, which can have any name.
+    pub(crate) fn from_paths_with_ids<I, P>(paths: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = (u8, P)>,
+        P: AsRef<Path>,
+    {
+        let buffers = paths
+            .into_iter()
+            .map(|(id, path)| Ok((id, fs::read(path)?)))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Self::from_buffers(buffers)
+    }
+
+    /// Parses the given index files, the reference table has to be one of them.
+    pub(crate) fn from_buffers<I, B>(buffers: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = (u8, B)>,
+        B: AsRef<[u8]>,
+    {
+        let indices: HashMap<u8, Index> = buffers
+            .into_iter()
+            .map(|(id, buffer)| (id, Index::from_buffer(id, buffer.as_ref())))
+            .collect();
+
+        if !indices.contains_key(&REFERENCE_TABLE_ID) {
+            return Err(RuneFsError::Read(ReadError::IndexNotFound(REFERENCE_TABLE_ID)).into());
+        }
+
+        Ok(Self(indices))
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, index_id: &u8) -> Option<&Index> {
+        self.0.get(index_id)
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self, index_id: &u8) -> Option<&mut Index> {
+        self.0.get_mut(index_id)
+    }
+
+    /// The ids of every loaded index, in no particular order.
+    #[inline]
+    pub(crate) fn ids(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.keys().copied()
+    }
+}
+
+/// Takes the id of an index file from its extension, `main_file_cache.idx2`
+/// has id 2.
+pub(crate) fn index_id(path: &Path) -> crate::Result<u8> {
+    path.extension()
+        .and_then(|extension| extension.to_str()?.strip_prefix("idx")?.parse().ok())
+        .ok_or_else(|| crate::Error::InvalidIndexFile {
+            expected: format!("{}<id>", IDX_PREFIX),
+            found: path.display().to_string(),
+        })
+}
@@ -20,6 +20,10 @@
 //! by the client on login and are user specific. It will only send encoded
 //! packet id's if the packets are game packets.
 //!
+//! Archives can be written back into the cache with a
+//! [`CacheWriter`](crate::writer::CacheWriter), which also keeps the reference
+//! tables up to date.
+//!
 //! Note that this crate is still evolving; both OSRS & RS3 are not fully
 //! supported/implemented and will probably contain bugs or miss core features.
 //! If you require features or find bugs consider [opening an issue].
@@ -33,14 +37,30 @@
 //! system. The map will remain valid even after the `File` is dropped, it's
 //! completely independent of the `File` used to create it. Therefore, the use
 //! of unsafe is not propagated outwards. When the `Cache` is dropped memory
-//! will be subsequently unmapped.
+//! will be subsequently unmapped. Platforms that can't memory map files can
+//! read the data files into memory instead, see [`Backend`].
 //!
 //! # Features
 //!
 //! The cache's protocol defaults to OSRS. In order to use the RS3 protocol you
-//! can enable the `rs3` feature flag. A lot of types derive [serde]'s
-//! `Serialize` and `Deserialize`. The `serde-derive` feature flag can be used
-//! to enable (de)serialization on any compatible types.
+//! can enable the `rs3` feature flag, which adds the RS3 definitions and
+//! loaders. Which protocol a cache uses is detected at runtime, see
+//! [`Cache::protocol`], so a single binary can serve both, loaders read the
+//! opcodes that differ between the two in the format of the detected protocol.
+//! A lot of types derive [serde]'s `Serialize` and `Deserialize`. The
+//! `serde-derive` feature flag can be used to enable (de)serialization on any
+//! compatible types, it also enables bulk exports of loaders through the
+//! `export` module. The `async` feature flag adds [tokio] based reads and a JS5
+//! responder, see [`net`]. RSA helpers for the login protocol are available
+//! through the `rsa` feature flag, which is also enabled by `rs3`. Decoding
+//! lzma compressed archives requires the `lzma` feature flag, also enabled by
+//! `rs3`. The `render` feature flag adds the [`render`] module, which draws map
+//! images from the cache data. The `ffi` feature flag exposes a small C API in
+//! the [`ffi`] module and the `python` feature flag adds Python bindings, see
+//! [`python`].
+//!
+//! The lower level file system is available through [`fs`], a re-export of
+//! [`runefs`] that also holds the sizes of its files in [`fs::consts`].
 //!
 //! # Quick Start
 //!
@@ -112,6 +132,7 @@
 //! [RuneScape 3]: https://www.runescape.com/
 //! [opening an issue]: https://github.com/jimvdl/rs-cache/issues/new
 //! [serde]: https://crates.io/crates/serde
+//! [tokio]: https://crates.io/crates/tokio
 //! [memmap2]: https://crates.io/crates/memmap2
 //! [`Huffman`]: crate::util::Huffman
 //! [`IsaacRand`]: crate::util::IsaacRand
@@ -127,14 +148,56 @@
 
 #[macro_use]
 pub mod util;
+pub mod archive;
+mod builder;
 pub mod checksum;
+pub mod codec;
+#[cfg(feature = "rsa")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rsa")))]
+pub mod crypto;
 pub mod definition;
+pub mod dump;
 pub mod error;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod export;
 pub mod extension;
+#[cfg(feature = "ffi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ffi")))]
+pub mod ffi;
+pub mod fs;
+#[cfg(feature = "fuzzing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fuzzing")))]
+pub mod fuzzing;
+mod index;
 pub mod loader;
+mod lru;
+pub mod metadata;
+pub mod net;
+pub mod observer;
+pub mod parse;
+mod protocol;
+#[cfg(feature = "python")]
+#[cfg_attr(docsrs, doc(cfg(feature = "python")))]
+pub mod python;
+#[cfg(feature = "render")]
+#[cfg_attr(docsrs, doc(cfg(feature = "render")))]
+pub mod render;
+pub mod sector;
+pub mod stats;
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
+pub mod verify;
+pub mod writer;
 
+pub use builder::CacheBuilder;
 #[doc(inline)]
 pub use error::Error;
+pub use index::Backend;
+pub use protocol::Protocol;
+pub use sector::ReadResult;
+
 use error::Result;
 
 use checksum::Checksum;
@@ -142,14 +205,41 @@ use checksum::Checksum;
 use checksum::{RsaChecksum, RsaKeys};
 use runefs::codec::{Buffer, Decoded, Encoded};
 use runefs::error::{Error as RuneFsError, ReadError};
-use runefs::{ArchiveRef, Dat2, Indices, MAIN_DATA};
-use std::{io::Write, path::Path};
+use runefs::{ArchiveRef, MAIN_DATA, REFERENCE_TABLE_ID};
+use index::{Data, Indices};
+use metadata::{IndexMetadata, MetadataSnapshot};
+use observer::{CacheObserver, DecodeEvent, Observer, ReadEvent};
+use lru::DecodedCache;
+use sector::{ArchiveReader, Reader, Sectors};
+use util::{djd2::Dictionary, xtea::XteaKeyStore};
+use writer::CacheFiles;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+
+/// File name of the data file that holds the music indices, if present.
+pub const MUSIC_DATA: &str = "main_file_cache.dat2m";
+/// Indices that are stored in [`MUSIC_DATA`] instead of the main data file.
+pub const MUSIC_INDICES: [u8; 2] = [6, 11];
 
 /// A complete virtual representation of the RuneScape cache file system.
 #[derive(Debug)]
 pub struct Cache {
-    pub(crate) data: Dat2,
+    pub(crate) data: Data,
+    pub(crate) music_data: Option<Data>,
     pub(crate) indices: Indices,
+    name_tables: Vec<OnceLock<HashMap<i32, u32>>>,
+    names: Dictionary,
+    overlays: HashMap<(u8, u32), Vec<u8>>,
+    decoded: Option<DecodedCache>,
+    pub(crate) observer: Observer,
+    pub(crate) xtea_keys: XteaKeyStore,
+    pub(crate) protocol: OnceLock<Protocol>,
 }
 
 impl Cache {
@@ -157,6 +247,10 @@ impl Cache {
     ///
     /// All files are isolated on allocation by keeping them as in-memory files.
     ///
+    /// Newer caches store music in a separate data file, [`MUSIC_DATA`]. When it
+    /// is present reads from the [`MUSIC_INDICES`] are served from it instead
+    /// of the main data file.
+    ///
     /// # Errors
     ///
     /// The bulk of the errors which might occur are mostely I/O related due to
@@ -165,10 +259,204 @@ impl Cache {
     /// Other errors might include protocol changes in newer caches. Any error
     /// unrelated to I/O at this stage should be considered a bug.
     pub fn new<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
-        Ok(Self {
-            data: Dat2::new(path.as_ref().join(MAIN_DATA))?,
-            indices: Indices::new(path)?,
-        })
+        Self::new_with_backend(path, Backend::Mmap)
+    }
+
+    /// Same as [`new`](Cache::new) but lets you pick how the data files are
+    /// brought into memory.
+    ///
+    /// [`Backend::Heap`] reads the data files into memory instead of mapping
+    /// them, for platforms where memory maps are not available or unreliable.
+    ///
+    /// # Errors
+    ///
+    /// See [`new`](Cache::new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::{Backend, Cache};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let cache = Cache::new_with_backend("./data/osrs_cache", Backend::Heap)?;
+    /// let buffer = cache.read(2, 10)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_backend<P: AsRef<Path>>(path: P, backend: Backend) -> crate::Result<Self> {
+        let file = File::open(path.as_ref().join(MAIN_DATA))?;
+        let music_data = match File::open(path.as_ref().join(MUSIC_DATA)) {
+            Ok(file) => Some(backend.load(file)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        let data = backend.load(file)?;
+        let indices = Indices::new(path.as_ref())?;
+
+        Ok(Self::from_parts(data, music_data, indices))
+    }
+
+    /// Creates a cache from buffers that are already in memory.
+    ///
+    /// Useful for caches that are embedded in the binary, downloaded into
+    /// memory or fetched from object storage, nothing is read from disk.
+    /// `data` holds the contents of [`MAIN_DATA`] and `indices` the contents of
+    /// every index file paired with its id, the reference table (255) included.
+    /// A separate music data file is not supported, music has to be in `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IndexNotFound` if the reference table is not one of the
+    /// indices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let path = "./data/osrs_cache";
+    /// let data = std::fs::read(format!("{}/main_file_cache.dat2", path))?;
+    /// let mut indices = Vec::new();
+    /// for index_id in (0..=20).chain([255]) {
+    ///     let index = std::fs::read(format!("{}/main_file_cache.idx{}", path, index_id))?;
+    ///     indices.push((index_id, index));
+    /// }
+    ///
+    /// let cache = Cache::from_buffers(data, indices)?;
+    /// let buffer = cache.read(2, 10)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_buffers<D, I, B>(data: D, indices: I) -> crate::Result<Self>
+    where
+        D: AsRef<[u8]> + Send + Sync + 'static,
+        I: IntoIterator<Item = (u8, B)>,
+        B: AsRef<[u8]>,
+    {
+        let data = Data::Owned(Box::new(data));
+        let indices = Indices::from_buffers(indices)?;
+
+        Ok(Self::from_parts(data, None, indices))
+    }
+
+    /// Creates a cache from a data file and index files that can be anywhere.
+    ///
+    /// The id of every index file is taken from its `idx` extension, like the
+    /// files of a cache directory. Use
+    /// [`from_files_with_ids`](Cache::from_files_with_ids) for files with
+    /// other names. A separate music data file is not supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidIndexFile` if the id of an index file can't be taken
+    /// from its name. Other errors are the same as for [`new`](Cache::new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let path = std::path::Path::new("./data/osrs_cache");
+    /// let indices = (0..=20)
+    ///     .chain([255])
+    ///     .map(|index_id| path.join(format!("main_file_cache.idx{}", index_id)));
+    ///
+    /// let cache = Cache::from_files(path.join("main_file_cache.dat2"), indices)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_files<D, I, P>(data: D, indices: I) -> crate::Result<Self>
+    where
+        D: AsRef<Path>,
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let data = Backend::Mmap.load(File::open(data)?)?;
+        let indices = Indices::from_paths(indices)?;
+
+        Ok(Self::from_parts(data, None, indices))
+    }
+
+    /// Creates a cache from a data file and index files with any name, every
+    /// index file is paired with its id.
+    ///
+    /// # Errors
+    ///
+    /// See [`new`](Cache::new).
+    pub fn from_files_with_ids<D, I, P>(data: D, indices: I) -> crate::Result<Self>
+    where
+        D: AsRef<Path>,
+        I: IntoIterator<Item = (u8, P)>,
+        P: AsRef<Path>,
+    {
+        let data = Backend::Mmap.load(File::open(data)?)?;
+        let indices = Indices::from_paths_with_ids(indices)?;
+
+        Ok(Self::from_parts(data, None, indices))
+    }
+
+    fn from_parts(data: Data, music_data: Option<Data>, indices: Indices) -> Self {
+        Self {
+            data,
+            music_data,
+            indices,
+            name_tables: (0..=u8::MAX).map(|_| OnceLock::new()).collect(),
+            names: Dictionary::new(),
+            overlays: HashMap::new(),
+            decoded: None,
+            observer: Observer::default(),
+            xtea_keys: XteaKeyStore::new(),
+            protocol: OnceLock::new(),
+        }
+    }
+
+    /// Creates a [`CacheBuilder`] to configure the cache before it is opened.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::{Backend, Cache};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let cache = Cache::builder()
+    ///     .backend(Backend::Heap)
+    ///     .open("./data/osrs_cache")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> CacheBuilder {
+        CacheBuilder::new()
+    }
+
+    /// Keeps recently decoded archives in memory, up to `bytes` in total.
+    ///
+    /// Reads through [`read_decoded`](Cache::read_decoded) and
+    /// [`read_decoded_with_keys`](Cache::read_decoded_with_keys) return the
+    /// cached buffer instead of decompressing hot archives over and over. The
+    /// least recently used archives are dropped once the budget is exceeded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let cache = Cache::new("./data/osrs_cache")?.with_decoded_cache(64 * 1024 * 1024);
+    /// let buffer = cache.read_decoded(2, 10)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_decoded_cache(mut self, bytes: usize) -> Self {
+        self.decoded = Some(DecodedCache::new(bytes));
+        self
+    }
+
+    /// Reports every read and decoded archive to `observer`, see
+    /// [`CacheObserver`].
+    ///
+    /// Pass an `Arc` of the observer to keep a handle to it.
+    pub fn with_observer<O: CacheObserver + 'static>(mut self, observer: O) -> Self {
+        self.observer = Observer::new(observer);
+        self
     }
 
     /// Generate a checksum based on the current cache.
@@ -193,37 +481,269 @@ impl Cache {
     /// Retrieves and constructs data corresponding to the given index and
     /// archive.
     ///
+    /// The buffer is returned as it is stored in the data file. Archives
+    /// outside of the reference table end with a 2 byte version trailer which
+    /// the client doesn't expect in a JS5 response, use
+    /// [`without_version`](codec::EncodedExt::without_version) before serving
+    /// them.
+    ///
     /// # Errors
     ///
     /// When trying to retrieve data from an index or an archive that does not
     /// exist the `IndexNotFound` or `ArchiveNotFound` errors are returned,
-    /// respectively.
+    /// respectively. A truncated cache returns a `LengthMismatch` error.
     ///
     /// Any other errors such as sector validation failures or failed parsers
     /// should be considered a bug.
     pub fn read(&self, index_id: u8, archive_id: u32) -> crate::Result<Buffer<Encoded>> {
-        let index = self
-            .indices
-            .get(&index_id)
-            .ok_or(RuneFsError::Read(ReadError::IndexNotFound(index_id)))?;
+        let start = Instant::now();
+        let buffer = match self.overlays.get(&(index_id, archive_id)) {
+            Some(overlay) => overlay.clone(),
+            None => {
+                let archive = self.archive_ref(index_id, archive_id)?;
+                self.archive_reader(archive).read_to_vec()?
+            }
+        };
+        self.report_read(index_id, archive_id, buffer.len(), start);
 
-        let archive = index
-            .archive_refs
-            .get(&archive_id)
-            .ok_or(RuneFsError::Read(ReadError::ArchiveNotFound {
-                idx: index_id,
-                arc: archive_id,
-            }))?;
+        Ok(Buffer::from(buffer))
+    }
 
-        let buffer = self.data.read(archive)?;
+    /// Same as [`read`](Cache::read) but runs on tokio's blocking thread pool.
+    ///
+    /// # Errors
+    ///
+    /// See the error section on [`read`](Cache::read) for more details.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn read_async(
+        self: &Arc<Self>,
+        index_id: u8,
+        archive_id: u32,
+    ) -> crate::Result<Buffer<Encoded>> {
+        let cache = Arc::clone(self);
+        net::spawn_blocking(move || cache.read(index_id, archive_id)).await
+    }
 
-        assert_eq!(buffer.len(), archive.length);
+    /// Reads and decodes the given archive on tokio's blocking thread pool.
+    ///
+    /// Decompression can take a while for large archives, offloading it keeps
+    /// the async executor responsive.
+    ///
+    /// # Errors
+    ///
+    /// See the error section on [`read`](Cache::read) for more details.
+    /// Decoding errors are returned as well.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn read_decoded_async(
+        self: &Arc<Self>,
+        index_id: u8,
+        archive_id: u32,
+    ) -> crate::Result<Buffer<Decoded>> {
+        let cache = Arc::clone(self);
+        net::spawn_blocking(move || cache.read_decoded(index_id, archive_id)).await
+    }
+
+    /// Retrieves and decodes the given archive.
+    ///
+    /// Served from the decoded archive cache when it is enabled, see
+    /// [`with_decoded_cache`](Cache::with_decoded_cache). Cached archives are
+    /// copied into the returned buffer, use
+    /// [`read_decoded_shared`](Cache::read_decoded_shared) to share them
+    /// instead.
+    ///
+    /// # Errors
+    ///
+    /// See the error section on [`read`](Cache::read) for more details.
+    /// Decoding errors are returned as well.
+    pub fn read_decoded(&self, index_id: u8, archive_id: u32) -> crate::Result<Buffer<Decoded>> {
+        self.read_decoded_inner(index_id, archive_id, None)
+    }
+
+    /// Retrieves and decodes an archive that is encrypted with XTEA.
+    ///
+    /// # Errors
+    ///
+    /// See [`read_decoded`](Cache::read_decoded).
+    pub fn read_decoded_with_keys(
+        &self,
+        index_id: u8,
+        archive_id: u32,
+        keys: &[u32; 4],
+    ) -> crate::Result<Buffer<Decoded>> {
+        self.read_decoded_inner(index_id, archive_id, Some(*keys))
+    }
+
+    /// Same as [`read_decoded`](Cache::read_decoded) but the buffer is shared
+    /// with the decoded archive cache, so reading a cached archive doesn't copy
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// See [`read_decoded`](Cache::read_decoded).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let cache = Cache::new("./data/osrs_cache")?.with_decoded_cache(64 * 1024 * 1024);
+    /// let buffer = cache.read_decoded_shared(2, 10)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_decoded_shared(&self, index_id: u8, archive_id: u32) -> crate::Result<Arc<[u8]>> {
+        self.read_decoded_shared_inner(index_id, archive_id, None)
+    }
+
+    /// Same as [`read_decoded_with_keys`](Cache::read_decoded_with_keys) but
+    /// the buffer is shared, see [`read_decoded_shared`](Cache::read_decoded_shared).
+    ///
+    /// # Errors
+    ///
+    /// See [`read_decoded`](Cache::read_decoded).
+    pub fn read_decoded_shared_with_keys(
+        &self,
+        index_id: u8,
+        archive_id: u32,
+        keys: &[u32; 4],
+    ) -> crate::Result<Arc<[u8]>> {
+        self.read_decoded_shared_inner(index_id, archive_id, Some(*keys))
+    }
+
+    fn read_decoded_inner(
+        &self,
+        index_id: u8,
+        archive_id: u32,
+        keys: Option<[u32; 4]>,
+    ) -> crate::Result<Buffer<Decoded>> {
+        let key = (index_id, archive_id, keys);
+        if let Some(buffer) = self.read_cached(&key) {
+            return codec::decoded(&buffer);
+        }
+
+        let buffer = self.decode_uncached(index_id, archive_id, keys)?;
+        if let Some(decoded) = &self.decoded {
+            decoded.insert(key, Arc::from(buffer.as_slice()));
+        }
+
+        Ok(buffer)
+    }
+
+    fn read_decoded_shared_inner(
+        &self,
+        index_id: u8,
+        archive_id: u32,
+        keys: Option<[u32; 4]>,
+    ) -> crate::Result<Arc<[u8]>> {
+        let key = (index_id, archive_id, keys);
+        if let Some(buffer) = self.read_cached(&key) {
+            return Ok(buffer);
+        }
+
+        let buffer = self.decode_uncached(index_id, archive_id, keys)?;
+        let buffer: Arc<[u8]> = Arc::from(buffer.as_slice());
+        if let Some(decoded) = &self.decoded {
+            decoded.insert(key, Arc::clone(&buffer));
+        }
+
+        Ok(buffer)
+    }
+
+    fn read_cached(&self, key: &lru::Key) -> Option<Arc<[u8]>> {
+        let start = Instant::now();
+        let buffer = self.decoded.as_ref()?.get(key)?;
+        self.report_decode(key.0, key.1, buffer.len(), true, start);
+
+        Some(buffer)
+    }
+
+    fn decode_uncached(
+        &self,
+        index_id: u8,
+        archive_id: u32,
+        keys: Option<[u32; 4]>,
+    ) -> crate::Result<Buffer<Decoded>> {
+        let buffer = self.read(index_id, archive_id)?;
+        let start = Instant::now();
+        codec::ensure_supported(&buffer)?;
+        let buffer = match keys {
+            Some(keys) => buffer.with_xtea_keys(keys).decode()?,
+            None => buffer.decode()?,
+        };
+        self.report_decode(index_id, archive_id, buffer.len(), false, start);
 
         Ok(buffer)
     }
 
-    pub(crate) fn read_archive(&self, archive: &ArchiveRef) -> crate::Result<Buffer<Encoded>> {
-        self.read(archive.index_id, archive.id)
+    fn report_read(&self, index_id: u8, archive_id: u32, bytes: usize, start: Instant) {
+        if !self.observer.is_some() {
+            return;
+        }
+
+        self.observer.on_read(ReadEvent {
+            index_id,
+            archive_id,
+            bytes,
+            overlay: self.overlays.contains_key(&(index_id, archive_id)),
+            duration: start.elapsed(),
+        });
+    }
+
+    fn report_decode(
+        &self,
+        index_id: u8,
+        archive_id: u32,
+        bytes: usize,
+        cached: bool,
+        start: Instant,
+    ) {
+        if !self.observer.is_some() {
+            return;
+        }
+
+        self.observer.on_decode(DecodeEvent {
+            index_id,
+            archive_id,
+            bytes,
+            cached,
+            duration: start.elapsed(),
+        });
+    }
+
+    /// Retrieves data corresponding to the given index and archive without
+    /// copying when possible.
+    ///
+    /// Sector headers are interleaved with the archive data, so only archives
+    /// that fit in a single sector can be borrowed straight from the data file.
+    /// Those are by far the most common archives in the cache. Larger archives
+    /// are copied into a buffer as they would be with [`read`](Cache::read).
+    ///
+    /// # Errors
+    ///
+    /// See the error section on [`read`](Cache::read) for more details.
+    pub fn read_borrowed(&self, index_id: u8, archive_id: u32) -> crate::Result<ReadResult<'_>> {
+        let start = Instant::now();
+        let result = self.read_borrowed_inner(index_id, archive_id)?;
+        self.report_read(index_id, archive_id, result.len(), start);
+
+        Ok(result)
+    }
+
+    fn read_borrowed_inner(&self, index_id: u8, archive_id: u32) -> crate::Result<ReadResult<'_>> {
+        if let Some(overlay) = self.overlays.get(&(index_id, archive_id)) {
+            return Ok(ReadResult::Borrowed(overlay));
+        }
+
+        let archive = self.archive_ref(index_id, archive_id)?;
+        let mut reader = self.archive_reader(archive);
+
+        if let Some(data) = reader.single_block()? {
+            return Ok(ReadResult::Borrowed(data));
+        }
+
+        Ok(ReadResult::Owned(Buffer::from(reader.read_to_vec()?)))
     }
 
     /// Retrieves and writes data corresponding to the given index and archive
@@ -238,6 +758,333 @@ impl Cache {
         archive_id: u32,
         writer: &mut W,
     ) -> crate::Result<()> {
+        io::copy(&mut self.reader(index_id, archive_id)?, writer)?;
+
+        Ok(())
+    }
+
+    /// Streams the data corresponding to the given index and archive.
+    ///
+    /// Instead of reading the whole archive into a buffer up front the sector
+    /// chain is walked on demand, so large archives (e.g. music or models) can
+    /// be written to a socket without buffering them twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `IndexNotFound` or `ArchiveNotFound` errors when the archive
+    /// does not exist. Sector validation failures are returned by the reader
+    /// as [`io::Error`]s of kind `InvalidData`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// use std::io::Read;
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let mut reader = cache.reader(2, 10)?;
+    ///
+    /// let mut chunk = [0; 512];
+    /// let len = reader.read(&mut chunk)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reader(&self, index_id: u8, archive_id: u32) -> crate::Result<impl Read + '_> {
+        if let Some(overlay) = self.overlays.get(&(index_id, archive_id)) {
+            return Ok(Reader::Overlay(overlay));
+        }
+
+        let archive = self.archive_ref(index_id, archive_id)?;
+
+        Ok(Reader::Archive(self.archive_reader(archive)))
+    }
+
+    /// Walks the sector chain of an archive in the data file, see [`Sectors`].
+    ///
+    /// Overlays are not stored in sectors, the chain on disk is walked even if
+    /// the archive has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `IndexNotFound` or `ArchiveNotFound` errors when the archive
+    /// does not exist. Errors in the chain itself are yielded by the iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// for sector in cache.sectors(2, 10)? {
+    ///     let (offset, header) = sector?;
+    ///     println!("chunk {} at {}, next sector {}", header.chunk, offset, header.next);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sectors(&self, index_id: u8, archive_id: u32) -> crate::Result<Sectors<'_>> {
+        let archive = self.archive_ref(index_id, archive_id)?;
+
+        Ok(Sectors::new(self.data_file(index_id), archive))
+    }
+
+    /// Computes the crc of an archive the way its reference table does, over
+    /// the encoded buffer without the version trailer.
+    ///
+    /// Overlays are taken into account, so this can be compared to the
+    /// reference table entry after patching an archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive can't be read or its header is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let metadata = cache.index_metadata(2)?;
+    /// let crc = cache.archive_crc(2, 10)?;
+    ///
+    /// assert_eq!(Some(crc), metadata.get(10).map(|archive| archive.crc));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn archive_crc(&self, index_id: u8, archive_id: u32) -> crate::Result<u32> {
+        let buffer = self.read(index_id, archive_id)?;
+        let header = codec::Header::parse(&buffer)?;
+
+        Ok(crc32fast::hash(&buffer[..header.encoded_len()]))
+    }
+
+    /// Reads the version trailer of an archive, the lower 16 bits of the
+    /// version in its reference table.
+    ///
+    /// Returns `None` for archives without a trailer, like the reference
+    /// tables themselves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive can't be read or its header is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let metadata = cache.index_metadata(2)?;
+    /// let version = cache.archive_version(2, 10)?;
+    ///
+    /// assert_eq!(version, metadata.get(10).map(|archive| archive.version as u16));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn archive_version(&self, index_id: u8, archive_id: u32) -> crate::Result<Option<u16>> {
+        let buffer = self.read(index_id, archive_id)?;
+
+        Ok(codec::Header::parse(&buffer)?.version)
+    }
+
+    /// Replaces an archive with `buffer` in memory, without touching the files
+    /// on disk.
+    ///
+    /// Every read of the archive returns the overlay from then on. Unless the
+    /// archive is a reference table itself, its entry in the reference table is
+    /// updated as well and stored as an overlay, so checksums match the
+    /// overlaid data. Statistics and [`verify`](Cache::verify) still look at
+    /// the files on disk.
+    ///
+    /// The buffer is stored as-is, archives outside of the reference table need
+    /// their version trailer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `UnknownArchive` if the archive is not in the reference table of
+    /// its index, `MissingVersion` if it has no version trailer, or any error
+    /// that occurs while decoding the reference table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let mut cache = Cache::new("./data/osrs_cache")?;
+    /// let buffer = cache.read(0, 191)?;
+    ///
+    /// cache.overlay(0, 191, buffer.as_slice())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn overlay<B: Into<Vec<u8>>>(
+        &mut self,
+        index_id: u8,
+        archive_id: u32,
+        buffer: B,
+    ) -> crate::Result<()> {
+        let buffer = buffer.into();
+
+        if index_id != REFERENCE_TABLE_ID {
+            let table = self.read(REFERENCE_TABLE_ID, index_id as u32)?;
+            let table =
+                writer::patch_reference_table(table.to_vec(), index_id, archive_id, &buffer)?;
+            self.overlays
+                .insert((REFERENCE_TABLE_ID, index_id as u32), table.to_vec());
+            self.invalidate_decoded(REFERENCE_TABLE_ID, index_id as u32);
+            self.invalidate_metadata(index_id);
+        }
+        self.overlays.insert((index_id, archive_id), buffer);
+        self.invalidate_decoded(index_id, archive_id);
+
+        Ok(())
+    }
+
+    /// Removes every overlay, reads return the archives on disk again.
+    pub fn clear_overlays(&mut self) {
+        self.overlays.clear();
+        if let Some(decoded) = &mut self.decoded {
+            decoded.clear();
+        }
+        for index_id in 0..=u8::MAX {
+            self.invalidate_metadata(index_id);
+        }
+    }
+
+    fn invalidate_metadata(&mut self, index_id: u8) {
+        if let Some(index) = self.indices.get_mut(&index_id) {
+            index.metadata.take();
+        }
+        self.name_tables[index_id as usize].take();
+    }
+
+    fn invalidate_decoded(&mut self, index_id: u8, archive_id: u32) {
+        if let Some(decoded) = &mut self.decoded {
+            decoded.invalidate(index_id, archive_id);
+        }
+    }
+
+    /// Returns `true` if the cache has a separate music data file.
+    #[inline]
+    pub const fn has_music_data(&self) -> bool {
+        self.music_data.is_some()
+    }
+
+    /// The protocol the definitions of this cache are encoded with.
+    ///
+    /// Detected on first use from the amount of indices and the reference
+    /// tables, so a single binary can serve both OSRS and RS3 caches. The
+    /// detection can be overridden with [`CacheBuilder::protocol`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::{Cache, Protocol};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let cache = Cache::new("./data/osrs_cache")?;
+    /// assert_eq!(cache.protocol(), Protocol::Osrs);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn protocol(&self) -> Protocol {
+        *self.protocol.get_or_init(|| Protocol::detect(self))
+    }
+
+    /// The XTEA keys that were given to [`CacheBuilder::xtea_keys`], empty
+    /// otherwise.
+    #[inline]
+    pub const fn xtea_keys(&self) -> &XteaKeyStore {
+        &self.xtea_keys
+    }
+
+    /// Returns the ids of the indices that were loaded, sorted and without the
+    /// reference table.
+    ///
+    /// Not every cache ships every index file, [`Cache::new`] skips the ones
+    /// that are missing. Reads from a missing index return `IndexNotFound`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let indices = cache.present_indices();
+    /// assert_eq!(indices, (0..=20).collect::<Vec<u8>>());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn present_indices(&self) -> Vec<u8> {
+        let mut ids: Vec<u8> = self
+            .indices
+            .ids()
+            .filter(|&index_id| index_id != REFERENCE_TABLE_ID)
+            .collect();
+        ids.sort_unstable();
+
+        ids
+    }
+
+    /// Writes a new cache into `path` that only holds the given indices and
+    /// their reference tables.
+    ///
+    /// Archives keep their ids, so the new cache can be read like the original
+    /// one. Music indices are moved into the main data file and overlays are
+    /// written instead of the archives on disk. The directory is created if it
+    /// doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IndexNotFound` if one of the indices is not in the cache, or any
+    /// error that occurs while reading the archives or writing the files.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// // Only the configs and maps.
+    /// cache.extract("./data/trimmed_cache", &[2, 5])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extract<P: AsRef<Path>>(&self, path: P, indices: &[u8]) -> crate::Result<()> {
+        let mut files = CacheFiles::default();
+        for &index_id in indices {
+            if index_id == REFERENCE_TABLE_ID {
+                continue;
+            }
+
+            let metadata = self.index_metadata(index_id)?;
+            let table = self.read(REFERENCE_TABLE_ID, index_id as u32)?;
+            files.insert(REFERENCE_TABLE_ID, index_id as u32, &table)?;
+            files.indices.entry(index_id).or_default();
+
+            for archive in metadata {
+                let buffer = self.read(index_id, archive.id)?;
+                files.insert(index_id, archive.id, &buffer)?;
+            }
+        }
+
+        files.write(path.as_ref())
+    }
+
+    pub(crate) fn archive_reader<'a>(&'a self, archive: &'a ArchiveRef) -> ArchiveReader<'a> {
+        ArchiveReader::new(self.data_file(archive.index_id), archive)
+    }
+
+    /// The data file the archives of the index are stored in.
+    fn data_file(&self, index_id: u8) -> &[u8] {
+        match &self.music_data {
+            Some(music_data) if MUSIC_INDICES.contains(&index_id) => music_data,
+            _ => &self.data,
+        }
+    }
+
+    pub(crate) fn archive_ref(&self, index_id: u8, archive_id: u32) -> crate::Result<&ArchiveRef> {
         let index = self
             .indices
             .get(&index_id)
@@ -250,7 +1097,85 @@ impl Cache {
                 idx: index_id,
                 arc: archive_id,
             }))?;
-        Ok(self.data.read_into_writer(archive, writer)?)
+
+        Ok(archive)
+    }
+
+    /// Retrieves the metadata of an index from its reference table.
+    ///
+    /// Reference tables are only decoded when their metadata is first needed,
+    /// tools that only care about a single index don't pay for the others.
+    /// Indices with an empty reference table have empty metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IndexNotFound` if the index does not exist. Errors while
+    /// decoding the reference table should be considered a bug.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let metadata = cache.index_metadata(2)?;
+    /// let items = metadata.get(10).unwrap();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn index_metadata(&self, index_id: u8) -> crate::Result<&IndexMetadata> {
+        let index = self
+            .indices
+            .get(&index_id)
+            .ok_or(RuneFsError::Read(ReadError::IndexNotFound(index_id)))?;
+
+        if let Some(metadata) = index.metadata.get() {
+            return Ok(metadata);
+        }
+
+        let buffer = self.read(REFERENCE_TABLE_ID, index_id as u32)?;
+        let metadata = if buffer.is_empty() {
+            IndexMetadata::default()
+        } else {
+            IndexMetadata::from_buffer(&buffer.decode()?)?
+        };
+
+        Ok(index.metadata.get_or_init(|| metadata))
+    }
+
+    /// Collects the metadata of every index into one snapshot.
+    ///
+    /// With the `serde` feature the snapshot can be stored, e.g. as JSON, next
+    /// to data exported from the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reference table can't be read or parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let snapshot = cache.metadata_snapshot()?;
+    ///
+    /// let items = snapshot.archive(2, 10).unwrap();
+    /// assert_eq!(items.crc, cache.archive_crc(2, 10)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn metadata_snapshot(&self) -> crate::Result<MetadataSnapshot> {
+        let indices = self
+            .present_indices()
+            .into_iter()
+            .map(|index_id| Ok((index_id, self.index_metadata(index_id)?.clone())))
+            .collect::<crate::Result<_>>()?;
+
+        Ok(MetadataSnapshot {
+            protocol: self.protocol(),
+            indices,
+        })
     }
 
     /// Retrieves the huffman table.
@@ -261,43 +1186,89 @@ impl Cache {
         let index_id = 10;
 
         let archive = self.archive_by_name(index_id, "huffman")?;
-        let buffer = self.read_archive(archive)?;
 
-        assert_eq!(buffer.len(), archive.length);
-
-        Ok(buffer.decode()?)
+        self.read_decoded(archive.index_id, archive.id)
     }
 
-    pub(crate) fn archive_by_name<T: AsRef<str>>(
-        &self,
-        index_id: u8,
-        name: T,
-    ) -> crate::Result<&ArchiveRef> {
-        let index = self
-            .indices
-            .get(&index_id)
-            .ok_or(RuneFsError::Read(ReadError::IndexNotFound(index_id)))?;
+    /// Looks up an archive by its name.
+    ///
+    /// The name is hashed with [`djd2`](crate::util::djd2) and matched against
+    /// the name hashes in the index's reference table, if multiple archives
+    /// share the hash the one with the lowest id is returned. The first lookup
+    /// on an index builds a table of all its name hashes, every lookup after
+    /// that is a simple hash map lookup.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IndexNotFound` if the index doesn't exist and `NameHash` if no
+    /// archive with the given name exists in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let archive = cache.archive_by_name(5, "m50_50")?;
+    /// let buffer = cache.read(archive.index_id, archive.id)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn archive_by_name<T: AsRef<str>>(&self, index_id: u8, name: T) -> crate::Result<&ArchiveRef> {
         let hash = util::djd2::hash(&name);
 
-        let archive = index
-            .metadata
-            .iter()
-            .find(|archive| archive.name_hash == hash)
-            .ok_or_else(|| crate::error::NameHashMismatch {
+        let archive_id = *self.name_table(index_id)?.get(&hash).ok_or_else(|| {
+            crate::error::NameHashMismatch {
                 hash,
                 name: name.as_ref().into(),
                 idx: index_id,
-            })?;
+            }
+        })?;
 
-        let archive_ref = index
-            .archive_refs
-            .get(&archive.id)
-            .ok_or(RuneFsError::Read(ReadError::ArchiveNotFound {
-                idx: index_id,
-                arc: archive.id,
-            }))?;
+        self.archive_ref(index_id, archive_id)
+    }
+
+    /// Registers known archive or file names so their hashes can be resolved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let mut cache = Cache::new("./data/osrs_cache")?;
+    /// cache.register_names(["huffman", "m50_50", "l50_50"]);
+    ///
+    /// assert_eq!(cache.resolve_name(1258058669), Some("huffman"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_names<I, T>(&mut self, names: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.names.extend(names);
+    }
+
+    /// Resolves a name hash back to a name registered with
+    /// [`register_names`](Cache::register_names).
+    pub fn resolve_name(&self, hash: i32) -> Option<&str> {
+        self.names.resolve(hash)
+    }
+
+    fn name_table(&self, index_id: u8) -> crate::Result<&HashMap<i32, u32>> {
+        let metadata = self.index_metadata(index_id)?;
+
+        Ok(self.name_tables[index_id as usize].get_or_init(|| {
+            // Archives can share a name hash, the first one wins like it does
+            // in a linear search.
+            let mut table = HashMap::with_capacity(metadata.len());
+            for archive in metadata {
+                table.entry(archive.name_hash).or_insert(archive.id);
+            }
 
-        Ok(archive_ref)
+            table
+        }))
     }
 }
 
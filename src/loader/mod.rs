@@ -9,7 +9,7 @@
 //! use std::{collections::HashMap, io::{ self, BufReader, }};
 //! use rscache::{
 //!     Cache, extension::ReadExt,
-//!     definition::osrs::{ Definition, FetchDefinition },
+//!     definition::{ ArchiveFileData, DecodeContext, osrs::{ Definition, FetchDefinition } },
 //! };
 //! 
 //! fn main() -> Result<(), rscache::Error> {
@@ -25,7 +25,7 @@
 //! }
 //!
 //! // Newtype defining the loader.
-//! struct CustomLoader(HashMap<u16, CustomDefinition>);
+//! struct CustomLoader(HashMap<u32, CustomDefinition>);
 //! 
 //! impl CustomLoader {
 //!     fn new(cache: &Cache) -> Result<Self, rscache::Error> {
@@ -42,7 +42,7 @@
 //!     }
 //!
 //!     // Simple HashMap lookup.
-//!     fn load(&self, id: u16) -> Option<&CustomDefinition> {
+//!     fn load(&self, id: u32) -> Option<&CustomDefinition> {
 //!         self.0.get(&id)
 //!     }
 //! }
@@ -50,20 +50,20 @@
 //! // Your definition with all the required fields. (in this example it's just a ItemDefinition)
 //! #[derive(Default)]
 //! struct CustomDefinition {
-//!     pub id: u16,
+//!     pub id: u32,
 //!     pub name: String,
 //! }
 //!
 //! impl Definition for CustomDefinition {
-//!     fn new(id: u16, buffer: &[u8]) -> Result<Self, rscache::Error> {
-//!         let mut reader = BufReader::new(buffer);
+//!     fn new(id: u32, file: &ArchiveFileData, _: &DecodeContext) -> Result<Self, rscache::Error> {
+//!         let mut reader = BufReader::new(file.data.as_slice());
 //!         let def = decode_buffer(id, &mut reader)?;
 //!
 //!         Ok(def)
 //!     }
 //! }
 //! 
-//! fn decode_buffer(id: u16, reader: &mut BufReader<&[u8]>) -> io::Result<CustomDefinition> {
+//! fn decode_buffer(id: u32, reader: &mut BufReader<&[u8]>) -> io::Result<CustomDefinition> {
 //!     // Parse the buffer into a definition.
 //!     let mut def = CustomDefinition {
 //!         id,
@@ -81,8 +81,8 @@
 //!             // Skipping the rest of the buffer for the sake of the example,
 //!             // every opcode should be parsed into values of the definition.
 //!             _ => { if reader.buffer().len() == 0 { break; } }
-//!             // Should normally be:
-//!             // _ => unreachable!()
+//!             // Should normally return
+//!             // `rscache::error::DefinitionError::UnknownOpcode`.
 //!         }
 //!     }
 //!
@@ -90,9 +90,51 @@
 //! }
 //! ```
 
+use std::hash::Hash;
+
+use crate::Cache;
+
 /// OSRS loaders.
 pub mod osrs;
 /// RS3 loaders.
 #[cfg(feature = "rs3")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rs3")))]
 pub mod rs3;
+
+/// Common interface of all loaders that load their definitions up front.
+///
+/// Makes it possible to write code that works with any loader.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::loader::{osrs::ItemLoader, Loader};
+///
+/// fn count<L: Loader>(cache: &Cache) -> Result<usize, rscache::Error> {
+///     Ok(L::new(cache)?.iter().count())
+/// }
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let item_count = count::<ItemLoader>(&cache)?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Loader: Sized {
+    /// `u32` for both OSRS and RS3 definitions.
+    type Id: Copy + Ord + Hash;
+    type Definition;
+
+    /// Loads all definitions from the cache.
+    ///
+    /// # Errors
+    ///
+    /// Can return multiple errors: if reading, decoding or parsing definition buffers fail.
+    fn new(cache: &Cache) -> crate::Result<Self>;
+
+    fn load(&self, id: Self::Id) -> Option<&Self::Definition>;
+
+    /// Iterates over all definitions in order of their id.
+    fn iter(&self) -> impl Iterator<Item = (&Self::Id, &Self::Definition)>;
+}
@@ -1,40 +1,339 @@
-use std::collections::{
-    hash_map::{self, Entry},
-    HashMap,
-};
+mod collision;
+mod font;
+mod region;
+
+pub use collision::*;
+pub use font::*;
+pub use region::*;
+
+use std::collections::{btree_map, hash_map::Entry, BTreeMap, HashMap};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
     definition::osrs::{
-        Definition, FetchDefinition, ItemDefinition, LocationDefinition, MapDefinition,
-        NpcDefinition, ObjectDefinition,
+        DecodeContext, Definition, FetchDefinition, FontMetricsDefinition,
+        GraphicsDefaultsDefinition, ItemDefinition, LocationDefinition, MapDefinition,
+        NpcDefinition, ObjectDefinition, OverlayDefinition, Revision, SpriteDefinition,
+        TextureDefinition, TransformVar, UnderlayDefinition,
     },
+    util::coords::RegionId,
     Cache,
 };
 
 /// Loads all item definitions from the current cache.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
-pub struct ItemLoader(HashMap<u16, ItemDefinition>);
+pub struct ItemLoader(BTreeMap<u32, ItemDefinition>);
 
 impl_osrs_loader!(ItemLoader, ItemDefinition, index_id: 2, archive_id: 10);
 
+impl ItemLoader {
+    /// Loads an item the way the client shows it.
+    ///
+    /// Noted items and placeholders barely hold any data of their own, the
+    /// client takes their model from a template item and their name from the
+    /// item they belong to. Every other item is returned as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// use rscache::loader::osrs::ItemLoader;
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let item_loader = ItemLoader::new(&cache)?;
+    /// let noted = item_loader.resolve(1043).unwrap();
+    ///
+    /// assert_eq!(noted.name, "Blue partyhat");
+    /// assert!(noted.stackable);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve(&self, id: u32) -> Option<ItemDefinition> {
+        let item = self.load(id)?;
+        let mut resolved = item.clone();
+
+        if let (Some(template), Some(unnoted)) = (item.noted_template, item.noted_id) {
+            let template = self.load(template as u32)?;
+            resolved.apply_note(template, self.load(unnoted as u32)?);
+        } else if let (Some(template), Some(base)) =
+            (item.placeholder_template, item.placeholder_id)
+        {
+            let template = self.load(template as u32)?;
+            resolved.apply_placeholder(template, self.load(base as u32)?);
+        }
+
+        Some(resolved)
+    }
+
+    /// The noted version of an item, resolved like [`resolve`](ItemLoader::resolve).
+    ///
+    /// Returns `None` if the item can't be noted or is already noted.
+    pub fn noted_of(&self, id: u32) -> Option<ItemDefinition> {
+        let item = self.load(id)?;
+        if item.noted_template.is_some() {
+            return None;
+        }
+
+        self.resolve(item.noted_id? as u32)
+    }
+
+    /// The unnoted version of a noted item.
+    ///
+    /// Returns `None` if the item is not noted.
+    pub fn unnoted_of(&self, id: u32) -> Option<ItemDefinition> {
+        let item = self.load(id)?;
+        item.noted_template?;
+
+        self.resolve(item.noted_id? as u32)
+    }
+
+    /// The bank placeholder of an item, resolved like
+    /// [`resolve`](ItemLoader::resolve).
+    ///
+    /// Returns `None` if the item has no placeholder or is a placeholder
+    /// itself.
+    pub fn placeholder_of(&self, id: u32) -> Option<ItemDefinition> {
+        let item = self.load(id)?;
+        if item.placeholder_template.is_some() {
+            return None;
+        }
+
+        self.resolve(item.placeholder_id? as u32)
+    }
+}
+
 /// Loads all npc definitions from the current cache.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
-pub struct NpcLoader(HashMap<u16, NpcDefinition>);
+pub struct NpcLoader(BTreeMap<u32, NpcDefinition>);
 
 impl_osrs_loader!(NpcLoader, NpcDefinition, index_id: 2, archive_id: 9);
 
+impl NpcLoader {
+    /// Loads the npc a player actually sees.
+    ///
+    /// Npcs can transform into other npcs depending on a varbit or varp of the
+    /// player, `value_of` returns the value of that variable. Returns `None`
+    /// if the npc is hidden for the player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// use rscache::{definition::osrs::TransformVar, loader::osrs::NpcLoader};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let npc_loader = NpcLoader::new(&cache)?;
+    /// let npc = npc_loader.resolve(119, |var| match var {
+    ///     TransformVar::Varbit(4153) => 1,
+    ///     _ => 0,
+    /// });
+    ///
+    /// assert_eq!(npc.unwrap().id, 6774);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve<F>(&self, id: u32, value_of: F) -> Option<&NpcDefinition>
+    where
+        F: FnOnce(TransformVar) -> i32,
+    {
+        let npc = self.load(id)?;
+        match npc.transform_var() {
+            Some(var) => self.load(npc.transform(value_of(var))? as u32),
+            None => Some(npc),
+        }
+    }
+}
+
 /// Loads all object definitions from the current cache.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
-pub struct ObjectLoader(HashMap<u16, ObjectDefinition>);
+pub struct ObjectLoader(BTreeMap<u32, ObjectDefinition>);
 
 impl_osrs_loader!(ObjectLoader, ObjectDefinition, index_id: 2, archive_id: 6);
 
+impl ObjectLoader {
+    /// Loads the object a player actually sees.
+    ///
+    /// Doors, farming patches and many other objects transform into other
+    /// objects depending on a varbit or varp of the player, `value_of` returns
+    /// the value of that variable. Returns `None` if the object is hidden for
+    /// the player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// use rscache::{definition::osrs::TransformVar, loader::osrs::ObjectLoader};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let object_loader = ObjectLoader::new(&cache)?;
+    /// let object = object_loader.resolve(1729, |var| match var {
+    ///     TransformVar::Varbit(4621) => 1,
+    ///     _ => 0,
+    /// });
+    ///
+    /// assert_eq!(object.unwrap().id, 33343);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve<F>(&self, id: u32, value_of: F) -> Option<&ObjectDefinition>
+    where
+        F: FnOnce(TransformVar) -> i32,
+    {
+        let object = self.load(id)?;
+        match object.transform_var() {
+            Some(var) => self.load(object.transform(value_of(var))? as u32),
+            None => Some(object),
+        }
+    }
+}
+
+/// Loads the glyph metrics of all fonts from the current cache.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct FontMetricsLoader(BTreeMap<u32, FontMetricsDefinition>);
+
+impl_osrs_loader!(FontMetricsLoader, FontMetricsDefinition, index_id: 13);
+
+/// Loads all floor underlay definitions from the current cache.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct UnderlayLoader(BTreeMap<u32, UnderlayDefinition>);
+
+impl_osrs_loader!(UnderlayLoader, UnderlayDefinition, index_id: 2, archive_id: 1);
+
+/// Loads all floor overlay definitions from the current cache.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct OverlayLoader(BTreeMap<u32, OverlayDefinition>);
+
+impl_osrs_loader!(OverlayLoader, OverlayDefinition, index_id: 2, archive_id: 4);
+
+/// Loads all texture definitions from the current cache.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct TextureLoader(BTreeMap<u32, TextureDefinition>);
+
+impl_osrs_loader!(TextureLoader, TextureDefinition, index_id: 9, archive_id: 0);
+
+/// Loads all sprites from the current cache.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SpriteLoader(BTreeMap<u32, SpriteDefinition>);
+
+impl_osrs_loader!(SpriteLoader, SpriteDefinition, index_id: 8);
+
+/// Loads all fonts with their glyphs from the current cache.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::loader::osrs::FontLoader;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let font_loader = FontLoader::new(&cache)?;
+///
+/// let p12_full = font_loader.load(495).unwrap();
+/// let width = p12_full.text_width("Hello, world!");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct FontLoader(BTreeMap<u32, Font>);
+
+impl FontLoader {
+    const SPRITE_INDEX_ID: u8 = 8;
+
+    /// Make a new `FontLoader`.
+    ///
+    /// Every font in the font metrics index is combined with the sprite of the
+    /// same id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metrics or a sprite could not be read, decoded
+    /// or parsed.
+    pub fn new(cache: &Cache) -> crate::Result<Self> {
+        let mut fonts = BTreeMap::new();
+        for (id, metrics) in FontMetricsLoader::new(cache)? {
+            let buffer = cache.read(Self::SPRITE_INDEX_ID, id)?.decode()?;
+            let ctx = DecodeContext::new(Self::SPRITE_INDEX_ID, id);
+            let glyphs = SpriteDefinition::from_buffer(id, &buffer, &ctx)?;
+
+            fonts.insert(id, Font::new(metrics, glyphs));
+        }
+
+        Ok(Self(fonts))
+    }
+
+    pub fn load(&self, id: u32) -> Option<&Font> {
+        self.0.get(&id)
+    }
+}
+
+impl_loader_trait!(FontLoader, u32, Font);
+impl_iter_for_loader!(FontLoader, u32, Font);
+
+/// Loads the client defaults from the current cache.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::loader::osrs::DefaultsLoader;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let defaults = DefaultsLoader::new(&cache)?;
+///
+/// let compass = defaults.graphics().compass;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct DefaultsLoader {
+    graphics: GraphicsDefaultsDefinition,
+}
+
+impl DefaultsLoader {
+    const INDEX_ID: u8 = 17;
+    const GRAPHICS_ARCHIVE_ID: u32 = 3;
+
+    /// Make a new `DefaultsLoader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a defaults archive could not be read, decoded or
+    /// parsed.
+    pub fn new(cache: &Cache) -> crate::Result<Self> {
+        let buffer = cache
+            .read(Self::INDEX_ID, Self::GRAPHICS_ARCHIVE_ID)?
+            .decode()?;
+        let ctx = DecodeContext::new(Self::INDEX_ID, Self::GRAPHICS_ARCHIVE_ID);
+        let graphics =
+            GraphicsDefaultsDefinition::from_buffer(Self::GRAPHICS_ARCHIVE_ID, &buffer, &ctx)?;
+
+        Ok(Self { graphics })
+    }
+
+    /// The sprite ids of the built-in interface graphics.
+    #[inline]
+    pub const fn graphics(&self) -> &GraphicsDefaultsDefinition {
+        &self.graphics
+    }
+}
+
 /// Loads maps definitions lazily from the current cache.
 #[derive(Debug)]
 pub struct MapLoader<'cache> {
@@ -56,17 +355,18 @@ impl<'cache> MapLoader<'cache> {
     }
 
     pub fn load(&mut self, id: u16) -> crate::Result<&MapDefinition> {
-        if let Entry::Vacant(entry) = self.maps.entry(id) {
-            let x = id >> 8;
-            let y = id & 0xFF;
-
-            let map_archive = self.cache.archive_by_name(5, format!("m{}_{}", x, y))?;
-            let buffer = self.cache.read_archive(map_archive)?.decode()?;
-
-            entry.insert(MapDefinition::new(id, &buffer)?);
+        match self.maps.entry(id) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(fetch_map(self.cache, id)?)),
         }
+    }
 
-        Ok(&self.maps[&id])
+    /// Loads the map definition of a particular region without caching it.
+    ///
+    /// Unlike [`load`](Self::load) this only needs a shared reference, so the
+    /// loader can be shared across threads.
+    pub fn load_uncached(&self, id: u16) -> crate::Result<MapDefinition> {
+        fetch_map(self.cache, id)
     }
 }
 
@@ -95,21 +395,108 @@ impl<'cache> LocationLoader<'cache> {
     /// Also takes a `keys: [u32; 4]` because the location archive is encrypted
     /// with XTEA. The buffer is automatically decoded with the given keys.
     pub fn load(&mut self, id: u16, keys: &[u32; 4]) -> crate::Result<&LocationDefinition> {
-        if let Entry::Vacant(entry) = self.locations.entry(id) {
-            let x = id >> 8;
-            let y = id & 0xFF;
-
-            let loc_archive = self.cache.archive_by_name(5, format!("l{}_{}", x, y))?;
-            let buffer = self
-                .cache
-                .read_archive(loc_archive)?
-                .with_xtea_keys(*keys)
-                .decode()?;
-
-            entry.insert(LocationDefinition::new(id, &buffer)?);
+        match self.locations.entry(id) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(fetch_location(self.cache, id, keys)?)),
         }
+    }
+
+    /// Loads the location data for a particular region without caching it.
+    ///
+    /// Unlike [`load`](Self::load) this only needs a shared reference, so the
+    /// loader can be shared across threads.
+    pub fn load_uncached(&self, id: u16, keys: &[u32; 4]) -> crate::Result<LocationDefinition> {
+        fetch_location(self.cache, id, keys)
+    }
+}
+
+/// Loads regions with their tiles, locations and collision flags.
+///
+/// All object definitions are loaded up front because they are needed to
+/// compute the collision flags of every location in a region.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::loader::osrs::RegionLoader;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let region_loader = RegionLoader::new(&cache)?;
+///
+/// let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+/// let region = region_loader.load(12850, &keys)?;
+///
+/// if region.is_blocked(10, 20, 0) {
+///     // ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RegionLoader<'cache> {
+    cache: &'cache Cache,
+    objects: ObjectLoader,
+}
+
+impl<'cache> RegionLoader<'cache> {
+    /// Make a new `RegionLoader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object definitions could not be loaded.
+    pub fn new(cache: &'cache Cache) -> crate::Result<Self> {
+        Self::with_revision(cache, Revision::default())
+    }
+
+    /// Make a new `RegionLoader` that loads the object definitions the way
+    /// the given client revision wrote them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object definitions could not be loaded.
+    pub fn with_revision(cache: &'cache Cache, revision: Revision) -> crate::Result<Self> {
+        Ok(Self {
+            cache,
+            objects: ObjectLoader::with_revision(cache, revision)?,
+        })
+    }
+
+    /// Loads and combines the map and location data of a region.
+    ///
+    /// The location archive is encrypted with XTEA, so `keys` are required.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the map or location data could not be read or decoded.
+    pub fn load(&self, id: u16, keys: &[u32; 4]) -> crate::Result<Region> {
+        collision::load_region(self.cache, &self.objects, id, keys)
+    }
 
-        Ok(&self.locations[&id])
+    /// The object definitions used to compute collision flags.
+    #[inline]
+    pub const fn objects(&self) -> &ObjectLoader {
+        &self.objects
     }
 }
-    
\ No newline at end of file
+
+fn fetch_map(cache: &Cache, id: u16) -> crate::Result<MapDefinition> {
+    let region = RegionId(id);
+
+    let map_archive = cache.archive_by_name(5, format!("m{}_{}", region.x(), region.y()))?;
+    let buffer = cache.read_decoded_shared(map_archive.index_id, map_archive.id)?;
+    let ctx = DecodeContext::new(map_archive.index_id, map_archive.id);
+
+    MapDefinition::from_buffer(id as u32, &buffer, &ctx)
+}
+
+fn fetch_location(cache: &Cache, id: u16, keys: &[u32; 4]) -> crate::Result<LocationDefinition> {
+    let region = RegionId(id);
+
+    let loc_archive = cache.archive_by_name(5, format!("l{}_{}", region.x(), region.y()))?;
+    let buffer = cache.read_decoded_shared_with_keys(loc_archive.index_id, loc_archive.id, keys)?;
+    let ctx = DecodeContext::new(loc_archive.index_id, loc_archive.id);
+
+    LocationDefinition::from_buffer(id as u32, &buffer, &ctx)
+}
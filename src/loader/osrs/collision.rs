@@ -0,0 +1,198 @@
+use std::{collections::HashMap, io, num::NonZeroUsize, thread};
+
+use super::{fetch_location, fetch_map, flags, region, BorderFlag, ObjectLoader, Region};
+use crate::{
+    util::{coords::Coordinate, xtea::XteaKeyStore},
+    Cache,
+};
+
+/// Collision flags for the whole game world.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use rscache::Cache;
+/// use rscache::{loader::osrs::CollisionMap, util::xtea::XteaKeyStore};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let mut keys = XteaKeyStore::new();
+/// keys.insert(12850, [3030157619, 2364842415, 3297319647, 1973582566]);
+/// let collision_map = CollisionMap::build(&cache, &keys)?;
+///
+/// if collision_map.is_blocked(3222, 3218, 0) == Some(false) {
+///     // ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct CollisionMap {
+    regions: HashMap<u16, Vec<u32>>,
+    skipped: Vec<u16>,
+}
+
+impl CollisionMap {
+    /// Builds the collision flags of every region that has keys in `keys`.
+    ///
+    /// Regions are decoded in parallel on as many threads as are available.
+    /// Regions that are missing from the cache or can't be decrypted with their
+    /// keys are skipped, see [`skipped`](Self::skipped). Regions without keys
+    /// aren't loaded at all, their tiles are reported as unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the object definitions could not be loaded or if
+    /// a thread decoding regions panicked.
+    pub fn build(cache: &Cache, keys: &XteaKeyStore) -> crate::Result<Self> {
+        let objects = ObjectLoader::new(cache)?;
+        let region_keys: Vec<_> = keys.iter().map(|(&id, keys)| (id, *keys)).collect();
+
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk_size = region_keys.len().div_ceil(threads).max(1);
+
+        let results = thread::scope(|scope| {
+            let handles: Vec<_> = region_keys
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let objects = &objects;
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(id, keys)| {
+                                let region = load_region(cache, objects, *id, keys).ok();
+                                (*id, region.map(Region::into_collision))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .map_err(|_| io::Error::other("region decoding thread panicked"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+
+        let mut skipped = Vec::new();
+        let regions = results
+            .into_iter()
+            .flatten()
+            .filter_map(|(id, collision)| match collision {
+                Some((collision, border_flags)) => Some((id, collision, border_flags)),
+                None => {
+                    skipped.push(id);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut collision_map = Self::from_parts(regions);
+        skipped.sort_unstable();
+        collision_map.skipped = skipped;
+
+        Ok(collision_map)
+    }
+
+    /// Combines the collision flags of regions that were already loaded, e.g.
+    /// with a [`RegionLoader`](super::RegionLoader).
+    ///
+    /// The [`border_flags`](Region::border_flags) of every region are merged
+    /// into its neighbours, so walls and objects on region edges block both
+    /// sides.
+    pub fn from_regions<I: IntoIterator<Item = Region>>(regions: I) -> Self {
+        Self::from_parts(regions.into_iter().map(|region| {
+            let id = region.id;
+            let (collision, border_flags) = region.into_collision();
+
+            (id, collision, border_flags)
+        }))
+    }
+
+    fn from_parts<I>(regions: I) -> Self
+    where
+        I: IntoIterator<Item = (u16, Vec<u32>, Vec<BorderFlag>)>,
+    {
+        let mut collision_map = Self::default();
+        let mut border_flags = Vec::new();
+        for (id, collision, flags) in regions {
+            collision_map.regions.insert(id, collision);
+            border_flags.extend(flags);
+        }
+
+        // Flags for regions that weren't loaded have nowhere to go.
+        for BorderFlag { coords, flag } in border_flags {
+            let (x, y) = coords.local();
+            if let Some(collision) = collision_map.regions.get_mut(&coords.region().into()) {
+                collision[region::index(x as usize, y as usize, coords.plane as usize)] |= flag;
+            }
+        }
+
+        collision_map
+    }
+
+    /// Returns the collision flags of a tile in absolute coordinates, see [`flags`].
+    ///
+    /// Returns `None` if the region the tile is in wasn't loaded.
+    pub fn flags(&self, x: u16, y: u16, plane: u8) -> Option<u32> {
+        if plane as usize >= region::PLANES {
+            return None;
+        }
+
+        let coords = Coordinate::new(x, y, plane);
+        let (local_x, local_y) = coords.local();
+
+        self.regions.get(&coords.region().into()).map(|collision| {
+            collision[region::index(local_x as usize, local_y as usize, plane as usize)]
+        })
+    }
+
+    /// Returns `true` if the tile can't be stood on.
+    ///
+    /// Returns `None` if the region the tile is in wasn't loaded, nothing is
+    /// known about its tiles.
+    pub fn is_blocked(&self, x: u16, y: u16, plane: u8) -> Option<bool> {
+        self.flags(x, y, plane)
+            .map(|tile| tile & flags::BLOCKED != 0)
+    }
+
+    /// Returns `true` if the region was loaded.
+    #[inline]
+    pub fn contains_region(&self, region_id: u16) -> bool {
+        self.regions.contains_key(&region_id)
+    }
+
+    /// The ids of regions that were skipped because they couldn't be loaded, sorted.
+    #[inline]
+    pub fn skipped(&self) -> &[u16] {
+        &self.skipped
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+pub(super) fn load_region(
+    cache: &Cache,
+    objects: &ObjectLoader,
+    id: u16,
+    keys: &[u32; 4],
+) -> crate::Result<Region> {
+    let map = fetch_map(cache, id)?;
+    let locations = fetch_location(cache, id, keys)?;
+
+    Ok(Region::new(map, locations, objects))
+}
@@ -0,0 +1,69 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::osrs::{FontMetricsDefinition, SpriteDefinition, SpriteFrame},
+    util::charset,
+};
+
+/// A font with its glyph metrics and the sprite that holds its glyphs.
+///
+/// Glyphs are indexed by cp1252 character. Characters that are not in cp1252
+/// are measured and drawn as `?`. The fonts in the cache have no
+/// kerning tables, glyphs are placed next to each other by their advance.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Font {
+    metrics: FontMetricsDefinition,
+    glyphs: SpriteDefinition,
+}
+
+impl Font {
+    pub(crate) const fn new(metrics: FontMetricsDefinition, glyphs: SpriteDefinition) -> Self {
+        Self { metrics, glyphs }
+    }
+
+    /// The id of the font, which is also the id of its sprite.
+    #[inline]
+    pub const fn id(&self) -> u32 {
+        self.metrics.id
+    }
+
+    /// The distance between the top of a line and the baseline.
+    #[inline]
+    pub const fn ascent(&self) -> u8 {
+        self.metrics.ascent
+    }
+
+    #[inline]
+    pub const fn metrics(&self) -> &FontMetricsDefinition {
+        &self.metrics
+    }
+
+    /// The sprite that holds a frame for every glyph.
+    #[inline]
+    pub const fn glyphs(&self) -> &SpriteDefinition {
+        &self.glyphs
+    }
+
+    /// Returns the pixel data of the glyph of `c`.
+    ///
+    /// The offsets of the frame position the glyph relative to the top left of
+    /// the line.
+    #[inline]
+    pub fn glyph(&self, c: char) -> Option<&SpriteFrame> {
+        self.glyphs.frames.get(charset::encode_char(c) as usize)
+    }
+
+    /// Returns the horizontal advance of `c`.
+    #[inline]
+    pub fn advance(&self, c: char) -> u8 {
+        self.metrics.advance(charset::encode_char(c))
+    }
+
+    /// Returns the width of `text` in pixels as the client would draw it.
+    #[inline]
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.chars().map(|c| self.advance(c) as u32).sum()
+    }
+}
@@ -0,0 +1,279 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    definition::osrs::{LocationDefinition, MapData, MapDefinition},
+    util::coords::{Coordinate, RegionId},
+};
+
+use super::ObjectLoader;
+
+/// Width and height of a region in tiles.
+pub const REGION_SIZE: usize = 64;
+/// Amount of planes in a region.
+pub const PLANES: usize = 4;
+
+/// Collision flags as they are used by the client.
+///
+/// Movement flags block walking into a tile from a direction, projectile
+/// flags are the movement flags shifted left by 9 and block line of sight.
+pub mod flags {
+    pub const WALL_NORTH_WEST: u32 = 0x1;
+    pub const WALL_NORTH: u32 = 0x2;
+    pub const WALL_NORTH_EAST: u32 = 0x4;
+    pub const WALL_EAST: u32 = 0x8;
+    pub const WALL_SOUTH_EAST: u32 = 0x10;
+    pub const WALL_SOUTH: u32 = 0x20;
+    pub const WALL_SOUTH_WEST: u32 = 0x40;
+    pub const WALL_WEST: u32 = 0x80;
+    pub const OBJECT: u32 = 0x100;
+    pub const PROJECTILE_SHIFT: u32 = 9;
+    pub const FLOOR_DECORATION: u32 = 0x40000;
+    pub const FLOOR: u32 = 0x200000;
+
+    /// Any of these flags make a tile impossible to stand on.
+    pub const BLOCKED: u32 = OBJECT | FLOOR_DECORATION | FLOOR;
+}
+
+/// A collision flag that a wall or object on the edge of a region sets on a
+/// tile of the neighbouring region.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct BorderFlag {
+    /// The tile in the neighbouring region, with the plane bridges resolved.
+    pub coords: Coordinate,
+    pub flag: u32,
+}
+
+/// A single region with its tiles, locations and collision flags combined.
+///
+/// Bridges are resolved: tiles and locations below a bridge are moved down
+/// a plane, just like the client does when it builds a scene.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Region {
+    pub id: u16,
+    pub map: MapDefinition,
+    pub locations: LocationDefinition,
+    collision: Vec<u32>,
+    border_flags: Vec<BorderFlag>,
+}
+
+impl Region {
+    /// Combines the map and locations of a region and computes its collision flags.
+    ///
+    /// Every location is looked up in `objects` to find its size and whether it
+    /// blocks movement, locations that are missing from the loader are ignored.
+    pub fn new(map: MapDefinition, locations: LocationDefinition, objects: &ObjectLoader) -> Self {
+        let mut region = Self {
+            id: RegionId::new(map.region_x as u8, map.region_y as u8).into(),
+            map,
+            locations,
+            collision: vec![0; PLANES * REGION_SIZE * REGION_SIZE],
+            border_flags: Vec::new(),
+        };
+
+        for plane in 0..PLANES {
+            for x in 0..REGION_SIZE {
+                for y in 0..REGION_SIZE {
+                    if region.map.map_data(x, y, plane).settings & 1 == 0 {
+                        continue;
+                    }
+                    if let Some(plane) = region.collision_plane(x, y, plane) {
+                        region.flag(x as isize, y as isize, plane, flags::FLOOR);
+                    }
+                }
+            }
+        }
+
+        let entries: Vec<_> = region.locations.entries().collect();
+        for entry in entries {
+            let (x, y) = (entry.x as usize, entry.y as usize);
+            let (id, loc_type, orientation) = (entry.id, entry.loc_type, entry.rotation);
+
+            let plane = match region.collision_plane(x, y, entry.plane as usize) {
+                Some(plane) => plane,
+                None => continue,
+            };
+            let object = match objects.load(id) {
+                Some(object) => object,
+                None => continue,
+            };
+            if object.interact_type == 0 {
+                continue;
+            }
+
+            let blocks_projectile = object.blocks_projectile;
+            let (x, y) = (x as isize, y as isize);
+            match loc_type {
+                0..=3 => region.add_wall(x, y, plane, loc_type, orientation, blocks_projectile),
+                9..=21 => {
+                    let (size_x, size_y) = if orientation & 1 == 1 {
+                        (object.model_data.size_y, object.model_data.size_x)
+                    } else {
+                        (object.model_data.size_x, object.model_data.size_y)
+                    };
+
+                    let mut flag = flags::OBJECT;
+                    if blocks_projectile {
+                        flag |= flags::OBJECT << flags::PROJECTILE_SHIFT;
+                    }
+                    for dx in 0..size_x as isize {
+                        for dy in 0..size_y as isize {
+                            region.flag(x + dx, y + dy, plane, flag);
+                        }
+                    }
+                }
+                22 if object.interact_type == 1 => {
+                    region.flag(x, y, plane, flags::FLOOR_DECORATION);
+                }
+                _ => {}
+            }
+        }
+
+        region
+    }
+
+    /// Returns `true` if the tile on plane 1 is a bridge, which moves everything
+    /// on and below it one plane down.
+    pub fn is_bridge(&self, x: usize, y: usize) -> bool {
+        self.map.map_data(x, y, 1).settings & 2 == 2
+    }
+
+    /// Returns the tile data that is visible on the given plane, with bridges resolved.
+    pub fn map_data(&self, x: usize, y: usize, plane: usize) -> &MapData {
+        if plane < PLANES - 1 && self.is_bridge(x, y) {
+            self.map.map_data(x, y, plane + 1)
+        } else {
+            self.map.map_data(x, y, plane)
+        }
+    }
+
+    #[inline]
+    pub fn underlay_id(&self, x: usize, y: usize, plane: usize) -> u8 {
+        self.map_data(x, y, plane).underlay_id
+    }
+
+    #[inline]
+    pub fn overlay_id(&self, x: usize, y: usize, plane: usize) -> i8 {
+        self.map_data(x, y, plane).overlay_id
+    }
+
+    /// Returns the collision flags of a tile, see [`flags`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the region.
+    pub fn collision_flags(&self, x: usize, y: usize, plane: usize) -> u32 {
+        self.collision[index(x, y, plane)]
+    }
+
+    /// Returns `true` if the tile can't be stood on.
+    #[inline]
+    pub fn is_blocked(&self, x: usize, y: usize, plane: usize) -> bool {
+        self.collision_flags(x, y, plane) & flags::BLOCKED != 0
+    }
+
+    /// The flags that walls and objects on the edge of this region set in the
+    /// regions around it.
+    ///
+    /// They are not part of [`collision_flags`](Self::collision_flags), a
+    /// [`CollisionMap`](super::CollisionMap) merges them into the neighbouring
+    /// regions.
+    #[inline]
+    pub fn border_flags(&self) -> &[BorderFlag] {
+        &self.border_flags
+    }
+
+    #[inline]
+    pub const fn region_base_coords(&self) -> (u16, u16) {
+        self.map.region_base_coords()
+    }
+
+    pub(crate) fn into_collision(self) -> (Vec<u32>, Vec<BorderFlag>) {
+        (self.collision, self.border_flags)
+    }
+
+    pub(crate) fn collision_plane(&self, x: usize, y: usize, plane: usize) -> Option<usize> {
+        if self.is_bridge(x, y) {
+            plane.checked_sub(1)
+        } else {
+            Some(plane)
+        }
+    }
+
+    fn add_wall(
+        &mut self,
+        x: isize,
+        y: isize,
+        plane: usize,
+        loc_type: u8,
+        orientation: u8,
+        blocks_projectile: bool,
+    ) {
+        use flags::*;
+
+        let walls: &[(isize, isize, u32)] = match (loc_type, orientation & 3) {
+            (0, 0) => &[(0, 0, WALL_WEST), (-1, 0, WALL_EAST)],
+            (0, 1) => &[(0, 0, WALL_NORTH), (0, 1, WALL_SOUTH)],
+            (0, 2) => &[(0, 0, WALL_EAST), (1, 0, WALL_WEST)],
+            (0, _) => &[(0, 0, WALL_SOUTH), (0, -1, WALL_NORTH)],
+            (2, 0) => &[
+                (0, 0, WALL_WEST | WALL_NORTH),
+                (-1, 0, WALL_EAST),
+                (0, 1, WALL_SOUTH),
+            ],
+            (2, 1) => &[
+                (0, 0, WALL_NORTH | WALL_EAST),
+                (0, 1, WALL_SOUTH),
+                (1, 0, WALL_WEST),
+            ],
+            (2, 2) => &[
+                (0, 0, WALL_EAST | WALL_SOUTH),
+                (1, 0, WALL_WEST),
+                (0, -1, WALL_NORTH),
+            ],
+            (2, _) => &[
+                (0, 0, WALL_SOUTH | WALL_WEST),
+                (0, -1, WALL_NORTH),
+                (-1, 0, WALL_EAST),
+            ],
+            (_, 0) => &[(0, 0, WALL_NORTH_WEST), (-1, 1, WALL_SOUTH_EAST)],
+            (_, 1) => &[(0, 0, WALL_NORTH_EAST), (1, 1, WALL_SOUTH_WEST)],
+            (_, 2) => &[(0, 0, WALL_SOUTH_EAST), (1, -1, WALL_NORTH_WEST)],
+            (_, _) => &[(0, 0, WALL_SOUTH_WEST), (-1, -1, WALL_NORTH_EAST)],
+        };
+
+        for &(dx, dy, flag) in walls {
+            let mut flag = flag;
+            if blocks_projectile {
+                flag |= flag << PROJECTILE_SHIFT;
+            }
+            self.flag(x + dx, y + dy, plane, flag);
+        }
+    }
+
+    /// Flags a tile, flags of tiles outside of the region are kept as
+    /// [`BorderFlag`]s.
+    fn flag(&mut self, x: isize, y: isize, plane: usize, flag: u32) {
+        if (0..REGION_SIZE as isize).contains(&x) && (0..REGION_SIZE as isize).contains(&y) {
+            self.collision[index(x as usize, y as usize, plane)] |= flag;
+            return;
+        }
+
+        let base = RegionId(self.id).base();
+        let (x, y) = (base.x as isize + x, base.y as isize + y);
+        // Nothing lies past the edge of the world.
+        if let (Ok(x), Ok(y)) = (u16::try_from(x), u16::try_from(y)) {
+            self.border_flags.push(BorderFlag {
+                coords: Coordinate::new(x, y, plane as u8),
+                flag,
+            });
+        }
+    }
+}
+
+#[inline]
+pub(crate) const fn index(x: usize, y: usize, plane: usize) -> usize {
+    (plane * REGION_SIZE + x) * REGION_SIZE + y
+}
@@ -1,16 +1,102 @@
-use std::collections::{hash_map, HashMap};
+use std::collections::{btree_map, BTreeMap};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    definition::rs3::{FetchDefinition, ItemDefinition},
+    definition::rs3::{Definition, FetchDefinition, ItemDefinition, QuestDefinition},
     Cache,
 };
 
 /// Loads all item definitions from the current cache.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
-pub struct ItemLoader(HashMap<u32, ItemDefinition>);
+pub struct ItemLoader(BTreeMap<u32, ItemDefinition>);
 
 impl_rs3_loader!(ItemLoader, ItemDefinition, index_id: 19);
+
+/// Loads all quest definitions from the current cache.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct QuestLoader(BTreeMap<u32, QuestDefinition>);
+
+impl_rs3_loader!(QuestLoader, QuestDefinition, index_id: 2, archive_id: 35);
+
+/// Generic loader for any RS3 config type.
+///
+/// RS3 configs are spread across many archives, each holding a group of 256
+/// definitions. This loader takes care of mapping archive and file ids to
+/// definition ids, see [`group_ids`](crate::definition::rs3::group_ids), so
+/// a new loader only needs a [`Definition`] and the index it lives in.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::{definition::rs3::ItemDefinition, loader::rs3::ConfigGroupLoader};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/rs3_cache")?;
+/// let item_loader = ConfigGroupLoader::<ItemDefinition>::new(&cache, 19)?;
+/// let item = item_loader.load(1042);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ConfigGroupLoader<D>(BTreeMap<u32, D>);
+
+impl<D: Definition> ConfigGroupLoader<D> {
+    /// Loads every definition in the given config index.
+    ///
+    /// # Errors
+    ///
+    /// Can return multiple errors: if reading, decoding or parsing definition
+    /// buffers fail.
+    pub fn new(cache: &Cache, index_id: u8) -> crate::Result<Self> {
+        let map = D::fetch_from_index(cache, index_id)?;
+
+        Ok(Self(map.into_iter().collect()))
+    }
+
+    pub fn load(&self, id: u32) -> Option<&D> {
+        self.0.get(&id)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> btree_map::Iter<'_, u32, D> {
+        self.0.iter()
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> btree_map::IterMut<'_, u32, D> {
+        self.0.iter_mut()
+    }
+}
+
+impl<D> Default for ConfigGroupLoader<D> {
+    #[inline]
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<D> IntoIterator for ConfigGroupLoader<D> {
+    type Item = (u32, D);
+    type IntoIter = btree_map::IntoIter<u32, D>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, D> IntoIterator for &'a ConfigGroupLoader<D> {
+    type Item = (&'a u32, &'a D);
+    type IntoIter = btree_map::Iter<'a, u32, D>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
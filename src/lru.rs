@@ -0,0 +1,103 @@
+//! A least recently used cache of decoded archives.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+/// Index id, archive id and the XTEA keys the archive was decoded with.
+pub(crate) type Key = (u8, u32, Option<[u32; 4]>);
+
+/// Keeps decoded archives around until their total size exceeds the budget,
+/// evicting the least recently used archive first.
+///
+/// Archives are shared, a hit only bumps a reference count.
+#[derive(Debug)]
+pub(crate) struct DecodedCache {
+    budget: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    size: usize,
+    tick: u64,
+    entries: HashMap<Key, (u64, Arc<[u8]>)>,
+    order: BTreeMap<u64, Key>,
+}
+
+impl DecodedCache {
+    pub(crate) fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &Key) -> Option<Arc<[u8]>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        let tick = inner.next_tick();
+        let (last_used, buffer) = inner.entries.get_mut(key)?;
+        let last_used = std::mem::replace(last_used, tick);
+        let buffer = Arc::clone(buffer);
+
+        inner.order.remove(&last_used);
+        inner.order.insert(tick, *key);
+
+        Some(buffer)
+    }
+
+    pub(crate) fn insert(&self, key: Key, buffer: Arc<[u8]>) {
+        if buffer.len() > self.budget {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.remove(&key);
+
+        let tick = inner.next_tick();
+        inner.size += buffer.len();
+        inner.entries.insert(key, (tick, buffer));
+        inner.order.insert(tick, key);
+
+        while inner.size > self.budget {
+            match inner.order.first_key_value() {
+                Some((_, &oldest)) => inner.remove(&oldest),
+                None => break,
+            }
+        }
+    }
+
+    /// Drops the archive, whatever keys it was decoded with.
+    pub(crate) fn invalidate(&mut self, index_id: u8, archive_id: u32) {
+        let inner = self.inner.get_mut().unwrap_or_else(|err| err.into_inner());
+        let keys: Vec<Key> = inner
+            .entries
+            .keys()
+            .filter(|(index, archive, _)| (*index, *archive) == (index_id, archive_id))
+            .copied()
+            .collect();
+
+        for key in keys {
+            inner.remove(&key);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self.inner.get_mut().unwrap_or_else(|err| err.into_inner()) = Inner::default();
+    }
+}
+
+impl Inner {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some((tick, buffer)) = self.entries.remove(key) {
+            self.size -= buffer.len();
+            self.order.remove(&tick);
+        }
+    }
+}
@@ -0,0 +1,550 @@
+//! Reference table (index 255) metadata.
+//!
+//! Every index has a reference table stored in index 255 which describes all
+//! of its archives: their name hashes, crcs, versions and the files they
+//! contain. Unlike the metadata loaded by the underlying file system this
+//! parser keeps every field the reference table stores, including the name
+//! hashes of the files inside of archives.
+//!
+//! # Example
+//!
+//! ```
+//! # use rscache::Cache;
+//! use rscache::metadata::IndexMetadata;
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! # let cache = Cache::new("./data/osrs_cache")?;
+//! let buffer = cache.read(255, 16)?.decode()?;
+//! let metadata = IndexMetadata::from_buffer(&buffer)?;
+//!
+//! let archive = metadata.get(0).unwrap();
+//! let file_id = archive.file_by_name("main");
+//! # assert_eq!(file_id, Some(0));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, slice::Iter};
+
+use nom::{
+    combinator::cond,
+    multi::count,
+    number::complete::{be_i32, be_u16, be_u32, be_u8},
+    IResult,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "whirlpool")]
+use whirlpool::{Digest, Whirlpool};
+
+use crate::{codec::Header, error::MetadataError, parse, util::djd2, Protocol};
+
+/// Archives have a name hash, and so do the files inside of them.
+pub const FLAG_NAMES: u8 = 0x1;
+/// Archives have a 64 byte whirlpool digest.
+pub const FLAG_WHIRLPOOL: u8 = 0x2;
+/// Archives have their compressed and uncompressed lengths stored.
+pub const FLAG_LENGTHS: u8 = 0x4;
+/// Archives have a crc of their uncompressed data.
+pub const FLAG_HASH: u8 = 0x8;
+
+/// Metadata of every archive in a single index.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct IndexMetadata {
+    pub protocol: u8,
+    pub version: u32,
+    pub flags: u8,
+    archives: Vec<ArchiveMetadata>,
+}
+
+/// Metadata of a single archive.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ArchiveMetadata {
+    pub id: u32,
+    pub name_hash: i32,
+    pub crc: u32,
+    pub hash: i32,
+    pub whirlpool: Vec<u8>,
+    /// Length of the encoded archive without its version trailer, `None` if
+    /// the index doesn't store lengths.
+    pub compressed_len: Option<u32>,
+    /// Length of the decoded archive, `None` if the index doesn't store
+    /// lengths.
+    pub decompressed_len: Option<u32>,
+    pub version: u32,
+    pub entry_count: usize,
+    pub valid_ids: Vec<u32>,
+    /// Name hashes of the files in this archive, in the same order as `valid_ids`.
+    ///
+    /// Empty if the index doesn't have names.
+    pub file_name_hashes: Vec<i32>,
+}
+
+/// The metadata of every index of a cache, see
+/// [`Cache::metadata_snapshot`](crate::Cache::metadata_snapshot).
+///
+/// Meant to be stored next to data exported from the cache, so it can later be
+/// checked which archives the export was made from.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct MetadataSnapshot {
+    pub protocol: Protocol,
+    /// The metadata of every index, keyed by index id.
+    pub indices: BTreeMap<u8, IndexMetadata>,
+}
+
+impl IndexMetadata {
+    /// Parses the metadata from a decoded reference table buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns a parser error if the buffer is malformed.
+    pub fn from_buffer(buffer: &[u8]) -> crate::Result<Self> {
+        let (buffer, protocol) = be_u8(buffer)?;
+        let (buffer, version) = cond(protocol >= 6, be_u32)(buffer)?;
+        let (buffer, flags) = be_u8(buffer)?;
+
+        let smart = protocol >= 7;
+        let (buffer, archive_count) = id(smart, buffer)?;
+        let (buffer, ids) = ids(smart, archive_count as usize, buffer)?;
+        let archive_count = ids.len();
+
+        let (buffer, name_hashes) = cond(flags & FLAG_NAMES != 0, count(be_i32, archive_count))(buffer)?;
+        let (buffer, crcs) = count(be_u32, archive_count)(buffer)?;
+        let (buffer, hashes) = cond(flags & FLAG_HASH != 0, count(be_i32, archive_count))(buffer)?;
+        let (buffer, whirlpools) = cond(
+            flags & FLAG_WHIRLPOOL != 0,
+            count(nom::bytes::complete::take(64_usize), archive_count),
+        )(buffer)?;
+        let (buffer, lengths) = cond(
+            flags & FLAG_LENGTHS != 0,
+            count(nom::sequence::pair(be_u32, be_u32), archive_count),
+        )(buffer)?;
+        let (buffer, versions) = count(be_u32, archive_count)(buffer)?;
+
+        // From here on every count has been read from the buffer, so the
+        // capacities are bounded by its length.
+        let (mut buffer, entry_counts) = count(|input| id(smart, input), archive_count)(buffer)?;
+        let mut valid_ids = Vec::with_capacity(entry_counts.len());
+        for &entry_count in &entry_counts {
+            let (buf, archive_valid_ids) = self::ids(smart, entry_count as usize, buffer)?;
+            buffer = buf;
+            valid_ids.push(archive_valid_ids);
+        }
+
+        let mut file_name_hashes = Vec::with_capacity(valid_ids.len());
+        for archive_valid_ids in &valid_ids {
+            let (buf, hashes) = cond(
+                flags & FLAG_NAMES != 0,
+                count(be_i32, archive_valid_ids.len()),
+            )(buffer)?;
+            buffer = buf;
+            file_name_hashes.push(hashes.unwrap_or_default());
+        }
+
+        let archives = ids
+            .into_iter()
+            .zip(valid_ids)
+            .zip(file_name_hashes)
+            .enumerate()
+            .map(|(i, ((id, valid_ids), file_name_hashes))| ArchiveMetadata {
+                id,
+                name_hash: name_hashes.as_ref().map_or(0, |hashes| hashes[i]),
+                crc: crcs[i],
+                hash: hashes.as_ref().map_or(0, |hashes| hashes[i]),
+                whirlpool: whirlpools
+                    .as_ref()
+                    .map_or_else(Vec::new, |whirlpools| whirlpools[i].to_vec()),
+                compressed_len: lengths.as_ref().map(|lengths| lengths[i].0),
+                decompressed_len: lengths.as_ref().map(|lengths| lengths[i].1),
+                version: versions[i],
+                entry_count: entry_counts[i] as usize,
+                valid_ids,
+                file_name_hashes,
+            })
+            .collect();
+
+        Ok(Self {
+            protocol,
+            version: version.unwrap_or(0),
+            flags,
+            archives,
+        })
+    }
+
+    /// Creates the metadata of an index from the metadata of its archives,
+    /// which are sorted by id.
+    ///
+    /// Together with [`encode`](IndexMetadata::encode) this rebuilds a
+    /// reference table, for example when its archive in index 255 is corrupt.
+    pub fn new(protocol: u8, version: u32, flags: u8, mut archives: Vec<ArchiveMetadata>) -> Self {
+        archives.sort_by_key(|archive| archive.id);
+
+        Self {
+            protocol,
+            version,
+            flags,
+            archives,
+        }
+    }
+
+    /// Encodes the metadata into a decoded reference table buffer, the inverse
+    /// of [`from_buffer`](IndexMetadata::from_buffer).
+    ///
+    /// Only the fields the flags ask for are written. The buffer still has to
+    /// be compressed, e.g. with [`EncodeOptions`](crate::codec::EncodeOptions),
+    /// before it is stored in index 255. Before protocol 7 ids are stored in
+    /// two bytes, so the protocol has to be at least 7 for larger ids.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::Cache;
+    /// use rscache::metadata::IndexMetadata;
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let buffer = cache.read(255, 2)?.decode()?;
+    /// let metadata = IndexMetadata::from_buffer(&buffer)?;
+    ///
+    /// assert_eq!(metadata.encode(), buffer.as_slice());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let smart = self.protocol >= 7;
+        let mut buffer = vec![self.protocol];
+        if self.protocol >= 6 {
+            buffer.extend(self.version.to_be_bytes());
+        }
+        buffer.push(self.flags);
+
+        write_id(smart, self.archives.len() as u32, &mut buffer);
+        let ids = self.archives.iter().map(|archive| archive.id);
+        write_ids(smart, ids, &mut buffer);
+
+        if self.flags & FLAG_NAMES != 0 {
+            for archive in &self.archives {
+                buffer.extend(archive.name_hash.to_be_bytes());
+            }
+        }
+        for archive in &self.archives {
+            buffer.extend(archive.crc.to_be_bytes());
+        }
+        if self.flags & FLAG_HASH != 0 {
+            for archive in &self.archives {
+                buffer.extend(archive.hash.to_be_bytes());
+            }
+        }
+        if self.flags & FLAG_WHIRLPOOL != 0 {
+            for archive in &self.archives {
+                let mut whirlpool = [0; 64];
+                let len = archive.whirlpool.len().min(64);
+                whirlpool[..len].copy_from_slice(&archive.whirlpool[..len]);
+                buffer.extend(whirlpool);
+            }
+        }
+        if self.flags & FLAG_LENGTHS != 0 {
+            for archive in &self.archives {
+                buffer.extend(archive.compressed_len.unwrap_or(0).to_be_bytes());
+                buffer.extend(archive.decompressed_len.unwrap_or(0).to_be_bytes());
+            }
+        }
+        for archive in &self.archives {
+            buffer.extend(archive.version.to_be_bytes());
+        }
+
+        for archive in &self.archives {
+            write_id(smart, archive.valid_ids.len() as u32, &mut buffer);
+        }
+        for archive in &self.archives {
+            write_ids(smart, archive.valid_ids.iter().copied(), &mut buffer);
+        }
+        if self.flags & FLAG_NAMES != 0 {
+            for archive in &self.archives {
+                for i in 0..archive.valid_ids.len() {
+                    let hash = archive.file_name_hashes.get(i).copied().unwrap_or(0);
+                    buffer.extend(hash.to_be_bytes());
+                }
+            }
+        }
+
+        buffer
+    }
+
+    /// Returns the metadata of the given archive, if it exists.
+    pub fn get(&self, archive_id: u32) -> Option<&ArchiveMetadata> {
+        self.archives
+            .binary_search_by_key(&archive_id, |archive| archive.id)
+            .ok()
+            .map(|i| &self.archives[i])
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.archives.len()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.archives.is_empty()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, ArchiveMetadata> {
+        self.archives.iter()
+    }
+}
+
+impl MetadataSnapshot {
+    /// Returns the metadata of the given archive, if its index and the archive
+    /// exist.
+    pub fn archive(&self, index_id: u8, archive_id: u32) -> Option<&ArchiveMetadata> {
+        self.indices.get(&index_id)?.get(archive_id)
+    }
+}
+
+impl ArchiveMetadata {
+    /// Looks up the id of a file in this archive by its name.
+    ///
+    /// Always returns `None` if the index doesn't have names.
+    pub fn file_by_name<T: AsRef<str>>(&self, name: T) -> Option<u32> {
+        let hash = djd2::hash(name);
+
+        self.file_name_hashes
+            .iter()
+            .position(|&file_name_hash| file_name_hash == hash)
+            .map(|i| self.valid_ids[i])
+    }
+
+    /// Checks an encoded archive, e.g. one that was just downloaded, against
+    /// the crc and whirlpool digest of this archive.
+    ///
+    /// The version trailer is not part of the crc, so `buffer` may or may not
+    /// have one. The whirlpool digest is only checked when the index has
+    /// digests and the `whirlpool` feature is enabled.
+    pub fn verify(&self, buffer: &[u8]) -> bool {
+        let buffer = match Header::parse(buffer) {
+            Ok(header) => &buffer[..header.encoded_len()],
+            Err(_) => return false,
+        };
+
+        if crc32fast::hash(buffer) != self.crc {
+            return false;
+        }
+
+        #[cfg(feature = "whirlpool")]
+        if !self.whirlpool.is_empty() {
+            let mut hasher = Whirlpool::new();
+            hasher.update(buffer);
+            return hasher.finalize().as_slice() == self.whirlpool.as_slice();
+        }
+
+        true
+    }
+}
+
+impl IntoIterator for IndexMetadata {
+    type Item = ArchiveMetadata;
+    type IntoIter = std::vec::IntoIter<ArchiveMetadata>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.archives.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a IndexMetadata {
+    type Item = &'a ArchiveMetadata;
+    type IntoIter = Iter<'a, ArchiveMetadata>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.archives.iter()
+    }
+}
+
+/// Parses the metadata from a decoded reference table buffer, see
+/// [`IndexMetadata::from_buffer`].
+impl TryFrom<&[u8]> for IndexMetadata {
+    type Error = crate::Error;
+
+    #[inline]
+    fn try_from(buffer: &[u8]) -> crate::Result<Self> {
+        Self::from_buffer(buffer)
+    }
+}
+
+/// Serializes the metadata like [`IndexMetadata::encode`], but checks first
+/// that its ids, counts and digests can be stored in its protocol, which
+/// [`encode`](IndexMetadata::encode) would silently truncate.
+///
+/// # Errors
+///
+/// Returns `UnsupportedProtocol` for protocols other than 5, 6 and 7,
+/// `IdOverflow` if an id or count doesn't fit in the ids of the protocol and
+/// `InvalidWhirlpool` if the index has digests but one of them isn't 64 bytes.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let metadata = cache.index_metadata(2)?;
+/// let buffer = Vec::<u8>::try_from(metadata)?;
+///
+/// assert_eq!(buffer, cache.read(255, 2)?.decode()?.as_slice());
+/// # Ok(())
+/// # }
+/// ```
+impl TryFrom<&IndexMetadata> for Vec<u8> {
+    type Error = MetadataError;
+
+    fn try_from(metadata: &IndexMetadata) -> Result<Self, Self::Error> {
+        let protocol = metadata.protocol;
+        let max_id = match protocol {
+            5 | 6 => u16::MAX as u32,
+            7 => i32::MAX as u32,
+            _ => return Err(MetadataError::UnsupportedProtocol(protocol)),
+        };
+        let check = |value: usize| match u32::try_from(value) {
+            Ok(value) if value <= max_id => Ok(()),
+            _ => Err(MetadataError::IdOverflow {
+                protocol,
+                value: value as u32,
+            }),
+        };
+
+        check(metadata.archives.len())?;
+        for archive in &metadata.archives {
+            check(archive.id as usize)?;
+            check(archive.valid_ids.len())?;
+            for &id in &archive.valid_ids {
+                check(id as usize)?;
+            }
+
+            if metadata.flags & FLAG_WHIRLPOOL != 0 && archive.whirlpool.len() != 64 {
+                return Err(MetadataError::InvalidWhirlpool {
+                    archive_id: archive.id,
+                    len: archive.whirlpool.len(),
+                });
+            }
+        }
+
+        Ok(metadata.encode())
+    }
+}
+
+/// New values for a single archive in a reference table, see [`patch_archive`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct ArchivePatch {
+    pub crc: u32,
+    pub version: u32,
+    pub compressed_len: u32,
+    pub decompressed_len: u32,
+}
+
+/// Overwrites the crc, version and lengths of an archive in a decoded
+/// reference table, without having to re-encode the whole table.
+///
+/// Returns `false` if the archive is not in the table.
+pub(crate) fn patch_archive(
+    buffer: &mut [u8],
+    archive_id: u32,
+    patch: &ArchivePatch,
+) -> crate::Result<bool> {
+    let (input, protocol) = be_u8(&buffer[..])?;
+    let (input, _) = cond(protocol >= 6, be_u32)(input)?;
+    let (input, flags) = be_u8(input)?;
+
+    let smart = protocol >= 7;
+    let (input, archive_count) = id(smart, input)?;
+    let (input, ids) = ids(smart, archive_count as usize, input)?;
+    let offset = buffer.len() - input.len();
+
+    let i = match ids.binary_search(&archive_id) {
+        Ok(i) => i,
+        Err(_) => return Ok(false),
+    };
+    let count = ids.len();
+
+    let mut write = |offset: usize, value: u32| {
+        buffer[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    };
+
+    let mut offset = offset;
+    if flags & FLAG_NAMES != 0 {
+        offset += count * 4;
+    }
+    write(offset + i * 4, patch.crc);
+    offset += count * 4;
+    if flags & FLAG_HASH != 0 {
+        offset += count * 4;
+    }
+    if flags & FLAG_WHIRLPOOL != 0 {
+        offset += count * 64;
+    }
+    if flags & FLAG_LENGTHS != 0 {
+        write(offset + i * 8, patch.compressed_len);
+        write(offset + i * 8 + 4, patch.decompressed_len);
+        offset += count * 8;
+    }
+    write(offset + i * 4, patch.version);
+
+    Ok(true)
+}
+
+/// Size of the smallest id or count, a `u16` or a small smart.
+const MIN_ID_LEN: usize = 2;
+
+/// Reads an id or count, which is a smart from protocol 7 onwards.
+fn id(smart: bool, buffer: &[u8]) -> IResult<&[u8], u32, ()> {
+    if !smart {
+        let (buffer, id) = be_u16(buffer)?;
+        return Ok((buffer, id as u32));
+    }
+
+    parse::be_u32_smart(buffer)
+}
+
+/// Writes an id or count, the inverse of [`id`].
+fn write_id(smart: bool, id: u32, buffer: &mut Vec<u8>) {
+    if !smart || id <= i16::MAX as u32 {
+        buffer.extend((id as u16).to_be_bytes());
+    } else {
+        buffer.extend((id | 0x8000_0000).to_be_bytes());
+    }
+}
+
+/// Writes delta encoded ids, the inverse of [`ids`].
+fn write_ids<I: Iterator<Item = u32>>(smart: bool, ids: I, buffer: &mut Vec<u8>) {
+    let mut last = 0_u32;
+    for id in ids {
+        write_id(smart, id.wrapping_sub(last), buffer);
+        last = id;
+    }
+}
+
+/// Reads `len` delta encoded ids.
+fn ids(smart: bool, len: usize, mut buffer: &[u8]) -> IResult<&[u8], Vec<u32>, ()> {
+    // `len` comes from the buffer itself, every id takes up at least 2 bytes
+    // so a corrupt length can't reserve more than the buffer could hold.
+    let mut ids = Vec::with_capacity(len.min(buffer.len() / MIN_ID_LEN));
+    let mut last = 0_u32;
+
+    for _ in 0..len {
+        let (buf, delta) = id(smart, buffer)?;
+        buffer = buf;
+        last = last.wrapping_add(delta);
+        ids.push(last);
+    }
+
+    Ok((buffer, ids))
+}
@@ -0,0 +1,169 @@
+//! Networking helpers for the update (JS5) protocol.
+//!
+//! Servers can frame archives with [`encode_response`] or answer requests with
+//! `Js5Responder`. [`Js5Session`] keeps track of the requests of a single
+//! connection and answers them in the right order. A cache can be downloaded
+//! from a live server with [`Js5Downloader`].
+//!
+//! # Example
+//!
+//! ```
+//! # use rscache::Cache;
+//! use rscache::net;
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! # let cache = Cache::new("./data/osrs_cache")?;
+//! let (index_id, archive_id) = (255, 10);
+//!
+//! let buffer = cache.read(index_id, archive_id)?;
+//! let response = net::encode_response(index_id, archive_id, &buffer)?;
+//!
+//! // stream.write_all(&response)?;
+//! # assert_eq!(response.len(), 80);
+//! # Ok(())
+//! # }
+//! ```
+
+mod downloader;
+mod session;
+mod update;
+
+pub use downloader::*;
+pub use session::*;
+pub use update::*;
+
+use crate::error::Js5Error;
+
+#[cfg(feature = "async")]
+use std::{io, sync::Arc};
+
+#[cfg(feature = "async")]
+use crate::{checksum::Checksum, codec::EncodedExt, Cache};
+#[cfg(feature = "async")]
+use runefs::{
+    codec::{Buffer, Encoded},
+    REFERENCE_TABLE_ID,
+};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Size of a single block in a JS5 response, block marker included.
+pub const BLOCK_LEN: usize = 512;
+/// Marker that precedes every block in a JS5 response except the first.
+pub const BLOCK_MARKER: u8 = 0xFF;
+
+/// Frames an archive buffer into an OSRS JS5 response.
+///
+/// The response starts with the index id and the archive id, followed by the
+/// archive data. Every [`BLOCK_LEN`] bytes a [`BLOCK_MARKER`] is inserted.
+///
+/// Note: archives outside of the reference table carry a 2 byte version
+/// trailer which should not be sent to the client, strip it before framing
+/// with [`EncodedExt::without_version`](crate::codec::EncodedExt::without_version).
+///
+/// # Errors
+///
+/// Returns [`Js5Error::ArchiveIdOverflow`] if the archive id doesn't fit in
+/// the 2 bytes of the response header.
+pub fn encode_response(index_id: u8, archive_id: u32, buffer: &[u8]) -> crate::Result<Vec<u8>> {
+    let mut response = Vec::with_capacity(3 + buffer.len() + buffer.len() / (BLOCK_LEN - 1) + 1);
+    response.push(index_id);
+    response.extend(&request_archive_id(archive_id)?.to_be_bytes());
+
+    let mut block_len = response.len();
+    for &byte in buffer {
+        if block_len == BLOCK_LEN {
+            response.push(BLOCK_MARKER);
+            block_len = 1;
+        }
+
+        response.push(byte);
+        block_len += 1;
+    }
+
+    Ok(response)
+}
+
+/// Narrows an archive id to the 2 bytes it takes up in JS5 requests and responses.
+pub(crate) fn request_archive_id(archive_id: u32) -> crate::Result<u16> {
+    u16::try_from(archive_id).map_err(|_| Js5Error::ArchiveIdOverflow(archive_id).into())
+}
+
+/// Answers JS5 requests from within a tokio runtime.
+///
+/// Reading from the cache and encoding the checksum happen on the blocking
+/// thread pool so they don't stall the async executor.
+///
+/// # Example
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::net::Js5Responder;
+/// use std::sync::Arc;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), rscache::Error> {
+/// let cache = Arc::new(Cache::new("./data/osrs_cache")?);
+/// let responder = Js5Responder::new(cache).await?;
+///
+/// let mut stream = Vec::new();
+/// responder.respond(255, 10, &mut stream).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+#[derive(Debug)]
+pub struct Js5Responder {
+    cache: Arc<Cache>,
+    checksum: Buffer<Encoded>,
+}
+
+#[cfg(feature = "async")]
+impl Js5Responder {
+    /// Creates a responder and encodes the checksum which is sent for the 255/255 request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checksum could not be generated.
+    pub async fn new(cache: Arc<Cache>) -> crate::Result<Self> {
+        let checksum = {
+            let cache = Arc::clone(&cache);
+            spawn_blocking(move || Checksum::new(&cache)?.encode()).await?
+        };
+
+        Ok(Self { cache, checksum })
+    }
+
+    /// Writes the framed response for the requested archive into `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive can't be read or if writing fails.
+    pub async fn respond<W>(&self, index_id: u8, archive_id: u32, writer: &mut W) -> crate::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let response = if index_id == REFERENCE_TABLE_ID && archive_id == REFERENCE_TABLE_ID as u32 {
+            encode_response(index_id, archive_id, &self.checksum)?
+        } else {
+            let buffer = self.cache.read_async(index_id, archive_id).await?;
+            encode_response(index_id, archive_id, buffer.without_version())?
+        };
+
+        writer.write_all(&response).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> crate::Result<T>
+where
+    F: FnOnce() -> crate::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(io::Error::other)?
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+};
+
+use runefs::{
+    codec::{Buffer, Encoded},
+    REFERENCE_TABLE_ID,
+};
+
+use super::{
+    request_archive_id,
+    session::{LOGGED_OUT, REQUEST_URGENT},
+    BLOCK_LEN, BLOCK_MARKER,
+};
+use crate::{
+    codec::{COMPRESSED_HEADER_LEN, HEADER_LEN},
+    error::Js5Error,
+    metadata::IndexMetadata,
+    writer::CacheFiles,
+};
+
+/// Opcode of the JS5 handshake.
+pub const HANDSHAKE_OPCODE: u8 = 15;
+/// Handshake response code when the server accepted the revision.
+pub const HANDSHAKE_OK: u8 = 0;
+/// The amount of requests that are sent before waiting on their responses.
+pub const MAX_PENDING_REQUESTS: usize = 20;
+
+/// Downloads archives from a live OSRS server over the update (JS5) protocol.
+///
+/// [`download`](Js5Downloader::download) bootstraps a complete dat2/idx cache
+/// from the server, single archives can be requested with
+/// [`request`](Js5Downloader::request).
+///
+/// # Example
+///
+/// ```no_run
+/// use rscache::net::Js5Downloader;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// let revision = 215;
+/// let downloader = Js5Downloader::connect("oldschool1.runescape.com:43594", revision)?;
+///
+/// downloader.download("./data/osrs_cache")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Js5Downloader<S = TcpStream> {
+    stream: S,
+}
+
+impl Js5Downloader {
+    /// Connects to the server and performs the handshake.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if connecting fails or if the server rejects the
+    /// revision, which happens when the client is out of date.
+    pub fn connect<A: ToSocketAddrs>(addr: A, revision: u32) -> crate::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+
+        Self::new(stream, revision)
+    }
+}
+
+impl<S: Read + Write> Js5Downloader<S> {
+    /// Performs the handshake over an already connected stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server rejects the revision or if the stream
+    /// fails.
+    pub fn new(mut stream: S, revision: u32) -> crate::Result<Self> {
+        let mut handshake = vec![HANDSHAKE_OPCODE];
+        handshake.extend(&revision.to_be_bytes());
+        stream.write_all(&handshake)?;
+        stream.flush()?;
+
+        let mut response = [0; 1];
+        stream.read_exact(&mut response)?;
+        if response[0] != HANDSHAKE_OK {
+            return Err(Js5Error::HandshakeRejected(response[0]).into());
+        }
+
+        stream.write_all(&[LOGGED_OUT, 0, 0, 0])?;
+        stream.flush()?;
+
+        Ok(Self { stream })
+    }
+
+    /// Requests a single archive.
+    ///
+    /// The server strips the version trailer, so the buffer doesn't have one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream fails, if the archive id doesn't fit in a
+    /// request or if the server responds with a different archive.
+    pub fn request(&mut self, index_id: u8, archive_id: u32) -> crate::Result<Buffer<Encoded>> {
+        self.request_all(&[(index_id, archive_id)])
+            .map(|mut buffers| buffers.remove(0))
+    }
+
+    /// Requests multiple archives, the buffers are returned in the same order.
+    ///
+    /// Up to [`MAX_PENDING_REQUESTS`] requests are sent at once before their
+    /// responses are read.
+    ///
+    /// # Errors
+    ///
+    /// See [`request`](Js5Downloader::request).
+    pub fn request_all(&mut self, requests: &[(u8, u32)]) -> crate::Result<Vec<Buffer<Encoded>>> {
+        let mut buffers = Vec::with_capacity(requests.len());
+
+        for requests in requests.chunks(MAX_PENDING_REQUESTS) {
+            let mut request = Vec::with_capacity(requests.len() * 4);
+            for &(index_id, archive_id) in requests {
+                request.extend(&[REQUEST_URGENT, index_id]);
+                request.extend(&request_archive_id(archive_id)?.to_be_bytes());
+            }
+            self.stream.write_all(&request)?;
+            self.stream.flush()?;
+
+            let mut responses = HashMap::with_capacity(requests.len());
+            for _ in requests {
+                let (index_id, archive_id, buffer) = self.read_response()?;
+                if !requests.contains(&(index_id, archive_id)) {
+                    return Err(Js5Error::UnexpectedArchive {
+                        index_id,
+                        archive_id,
+                    }
+                    .into());
+                }
+                responses.insert((index_id, archive_id), buffer);
+            }
+
+            for request in requests {
+                let buffer = responses
+                    .remove(request)
+                    .ok_or(Js5Error::UnexpectedArchive {
+                        index_id: request.0,
+                        archive_id: request.1,
+                    })?;
+                buffers.push(Buffer::from(buffer));
+            }
+        }
+
+        Ok(buffers)
+    }
+
+    /// Downloads every archive of every index and writes them as a dat2/idx
+    /// cache into `path`, which is created if it doesn't exist.
+    ///
+    /// Archives get their version trailer back from the reference tables, so
+    /// the written cache is the same as the one the client would write.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any request fails, if a reference table can't be
+    /// decoded or if writing the files fails.
+    pub fn download<P: AsRef<Path>>(mut self, path: P) -> crate::Result<()> {
+        let checksum = self
+            .request(REFERENCE_TABLE_ID, REFERENCE_TABLE_ID as u32)?
+            .decode()?;
+        let index_count = (checksum.len() / 8) as u8;
+
+        let reference_requests: Vec<_> = (0..index_count)
+            .map(|index_id| (REFERENCE_TABLE_ID, index_id as u32))
+            .collect();
+        let reference_tables = self.request_all(&reference_requests)?;
+
+        let mut files = CacheFiles::default();
+        for (index_id, buffer) in reference_tables.into_iter().enumerate() {
+            let index_id = index_id as u8;
+            files.insert(REFERENCE_TABLE_ID, index_id as u32, &buffer)?;
+            files.indices.entry(index_id).or_default();
+            if buffer.is_empty() {
+                continue;
+            }
+
+            let metadata = IndexMetadata::from_buffer(&buffer.decode()?)?;
+            let requests: Vec<_> = metadata
+                .iter()
+                .map(|archive| (index_id, archive.id))
+                .collect();
+            let buffers = self.request_all(&requests)?;
+
+            for (archive, buffer) in metadata.iter().zip(buffers) {
+                let mut buffer = buffer.as_slice().to_vec();
+                buffer.extend(&(archive.version as u16).to_be_bytes());
+                files.insert(index_id, archive.id, &buffer)?;
+            }
+        }
+
+        files.write(path.as_ref())
+    }
+
+    fn read_response(&mut self) -> crate::Result<(u8, u32, Vec<u8>)> {
+        let mut header = [0; 8];
+        self.stream.read_exact(&mut header)?;
+
+        let index_id = header[0];
+        let archive_id = u16::from_be_bytes([header[1], header[2]]) as u32;
+        let compression = header[3];
+        let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let header_len = if compression == 0 {
+            HEADER_LEN
+        } else {
+            COMPRESSED_HEADER_LEN
+        };
+        let len = header_len + len;
+
+        // The length comes from the server, the buffer only grows as the
+        // blocks actually arrive.
+        let mut buffer = Vec::with_capacity(len.min(BLOCK_LEN));
+        buffer.extend(&header[3..]);
+
+        let mut block_len = header.len();
+        while buffer.len() < len {
+            if block_len == BLOCK_LEN {
+                let mut marker = [0; 1];
+                self.stream.read_exact(&mut marker)?;
+                if marker[0] != BLOCK_MARKER {
+                    return Err(Js5Error::InvalidBlockMarker(marker[0]).into());
+                }
+                block_len = 1;
+            }
+
+            let start = buffer.len();
+            let chunk = (BLOCK_LEN - block_len).min(len - start);
+            buffer.resize(start + chunk, 0);
+            self.stream.read_exact(&mut buffer[start..])?;
+            block_len += chunk;
+        }
+
+        Ok((index_id, archive_id, buffer))
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+
+use runefs::{
+    codec::{Buffer, Encoded},
+    REFERENCE_TABLE_ID,
+};
+
+use super::encode_response;
+use crate::{checksum::Checksum, codec::EncodedExt, error::Js5Error, Cache};
+
+pub(super) const REQUEST_PREFETCH: u8 = 0;
+pub(super) const REQUEST_URGENT: u8 = 1;
+pub(super) const LOGGED_IN: u8 = 2;
+pub(super) const LOGGED_OUT: u8 = 3;
+pub(super) const ENCRYPTION_KEY: u8 = 4;
+pub(super) const CONNECTED: u8 = 6;
+pub(super) const DISCONNECT: u8 = 7;
+
+/// Set on the compression type of responses to prefetch requests.
+pub const PREFETCH_FLAG: u8 = 0x80;
+
+/// The server side of a single JS5 connection, after the handshake.
+///
+/// Bytes read from the client are passed to
+/// [`receive`](Js5Session::receive), responses are taken out with
+/// [`poll_response`](Js5Session::poll_response) until it returns `None`. The
+/// session doesn't do any IO itself, so it works with blocking and async
+/// streams alike.
+///
+/// The client sends urgent requests for archives it needs right now and
+/// prefetch requests for archives it downloads in the background. Urgent
+/// requests are always answered first, and a prefetch request that is
+/// requested again as urgent moves to the urgent queue. Requests for archives
+/// that are already queued are ignored. A response is always returned
+/// completely, so the blocks of two responses never interleave.
+///
+/// # Example
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::net::Js5Session;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let mut session = Js5Session::new(&cache)?;
+///
+/// // A prefetch request for 255/2 followed by an urgent request for 255/10.
+/// session.receive(&[0, 255, 0, 2, 1, 255, 0, 10])?;
+///
+/// while let Some(response) = session.poll_response()? {
+///     // stream.write_all(&response)?;
+/// #   assert_eq!(response[0], 255);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Js5Session<'a> {
+    cache: &'a Cache,
+    checksum: Buffer<Encoded>,
+    urgent: VecDeque<(u8, u32)>,
+    prefetch: VecDeque<(u8, u32)>,
+    partial: Vec<u8>,
+    logged_in: bool,
+    key: u8,
+    closed: bool,
+}
+
+impl<'a> Js5Session<'a> {
+    /// Creates a session and encodes the checksum which is sent for the
+    /// 255/255 request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checksum could not be generated.
+    pub fn new(cache: &'a Cache) -> crate::Result<Self> {
+        Ok(Self {
+            cache,
+            checksum: Checksum::new(cache)?.encode()?,
+            urgent: VecDeque::new(),
+            prefetch: VecDeque::new(),
+            partial: Vec::with_capacity(4),
+            logged_in: false,
+            key: 0,
+            closed: false,
+        })
+    }
+
+    /// Handles bytes read from the client.
+    ///
+    /// Every request is 4 bytes long, a request that is split over multiple
+    /// reads is buffered until it is complete. Bytes received after the client
+    /// disconnected are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidOpcode` if the client sends an unknown request, the
+    /// session should be closed in that case.
+    pub fn receive(&mut self, bytes: &[u8]) -> crate::Result<()> {
+        for &byte in bytes {
+            if self.closed {
+                break;
+            }
+
+            self.partial.push(byte);
+            if self.partial.len() == 4 {
+                let request = [
+                    self.partial[0],
+                    self.partial[1],
+                    self.partial[2],
+                    self.partial[3],
+                ];
+                self.partial.clear();
+                self.handle(request)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the next framed response, or `None` if no requests are
+    /// outstanding.
+    ///
+    /// Archives are sent without their version trailer, see
+    /// [`EncodedExt::without_version`]. Responses to prefetch requests have the
+    /// [`PREFETCH_FLAG`] set on their compression type. When the client sent
+    /// an encryption key every byte is xored with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the requested archive can't be read, the request is
+    /// dropped in that case.
+    pub fn poll_response(&mut self) -> crate::Result<Option<Vec<u8>>> {
+        let (request, prefetch) = match self.urgent.pop_front() {
+            Some(request) => (request, false),
+            None => match self.prefetch.pop_front() {
+                Some(request) => (request, true),
+                None => return Ok(None),
+            },
+        };
+
+        let mut response = self.encode(request)?;
+        if prefetch {
+            if let Some(compression) = response.get_mut(3) {
+                *compression |= PREFETCH_FLAG;
+            }
+        }
+        if self.key != 0 {
+            response.iter_mut().for_each(|byte| *byte ^= self.key);
+        }
+
+        Ok(Some(response))
+    }
+
+    /// The amount of requests that haven't been responded to.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.urgent.len() + self.prefetch.len()
+    }
+
+    /// Returns `true` if the client told the server it is logged in to the
+    /// game.
+    #[inline]
+    pub const fn is_logged_in(&self) -> bool {
+        self.logged_in
+    }
+
+    /// Returns `true` if the client disconnected, no responses are returned
+    /// after that.
+    #[inline]
+    pub const fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn handle(&mut self, request: [u8; 4]) -> crate::Result<()> {
+        let [opcode, index_id, high, low] = request;
+        let archive = (index_id, u16::from_be_bytes([high, low]) as u32);
+
+        match opcode {
+            REQUEST_PREFETCH => {
+                if !self.urgent.contains(&archive) && !self.prefetch.contains(&archive) {
+                    self.prefetch.push_back(archive);
+                }
+            }
+            REQUEST_URGENT => {
+                self.prefetch.retain(|&queued| queued != archive);
+                if !self.urgent.contains(&archive) {
+                    self.urgent.push_back(archive);
+                }
+            }
+            LOGGED_IN => self.logged_in = true,
+            LOGGED_OUT => self.logged_in = false,
+            ENCRYPTION_KEY => self.key = index_id,
+            CONNECTED => {}
+            DISCONNECT => {
+                self.urgent.clear();
+                self.prefetch.clear();
+                self.closed = true;
+            }
+            _ => return Err(Js5Error::InvalidOpcode(opcode).into()),
+        }
+
+        Ok(())
+    }
+
+    fn encode(&self, (index_id, archive_id): (u8, u32)) -> crate::Result<Vec<u8>> {
+        if index_id == REFERENCE_TABLE_ID && archive_id == REFERENCE_TABLE_ID as u32 {
+            return encode_response(index_id, archive_id, &self.checksum);
+        }
+
+        let buffer = self.cache.read(index_id, archive_id)?;
+
+        encode_response(index_id, archive_id, buffer.without_version())
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    io::{Read, Write},
+    slice::Iter,
+};
+
+use runefs::{
+    codec::{Buffer, Encoded},
+    REFERENCE_TABLE_ID,
+};
+
+use super::Js5Downloader;
+use crate::{metadata::IndexMetadata, Cache};
+
+/// A single archive that changed on the server.
+#[derive(Debug)]
+pub struct UpdatedArchive {
+    pub index_id: u8,
+    pub archive_id: u32,
+    /// The archive as it would be stored in the cache, version trailer included.
+    pub buffer: Buffer<Encoded>,
+}
+
+/// The archives that differ between the local cache and a live server, see
+/// [`Cache::update_from_remote`].
+#[derive(Debug, Default)]
+pub struct CacheUpdate {
+    archives: Vec<UpdatedArchive>,
+}
+
+impl CacheUpdate {
+    /// Returns `true` if the local cache is up to date.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.archives.is_empty()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.archives.len()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, UpdatedArchive> {
+        self.archives.iter()
+    }
+}
+
+impl IntoIterator for CacheUpdate {
+    type Item = UpdatedArchive;
+    type IntoIter = std::vec::IntoIter<UpdatedArchive>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.archives.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CacheUpdate {
+    type Item = &'a UpdatedArchive;
+    type IntoIter = Iter<'a, UpdatedArchive>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.archives.iter()
+    }
+}
+
+impl Cache {
+    /// Fetches every archive that changed on the server since this cache was
+    /// written.
+    ///
+    /// Reference tables are compared by crc first, only the indices whose
+    /// reference table changed are looked at. Within those indices an archive
+    /// is fetched when its crc or version differs from the local reference
+    /// table, or when it doesn't exist locally. The changed reference tables
+    /// are part of the update as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails or if a reference table can't be
+    /// decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use rscache::Cache;
+    /// use rscache::net::Js5Downloader;
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// # let cache = Cache::new("./data/osrs_cache")?;
+    /// let mut downloader = Js5Downloader::connect("oldschool1.runescape.com:43594", 215)?;
+    /// let update = cache.update_from_remote(&mut downloader)?;
+    ///
+    /// for archive in &update {
+    ///     println!("{}/{} changed", archive.index_id, archive.archive_id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update_from_remote<S>(
+        &self,
+        downloader: &mut Js5Downloader<S>,
+    ) -> crate::Result<CacheUpdate>
+    where
+        S: Read + Write,
+    {
+        let checksum = downloader
+            .request(REFERENCE_TABLE_ID, REFERENCE_TABLE_ID as u32)?
+            .decode()?;
+
+        let mut update = CacheUpdate::default();
+        for (index_id, entry) in checksum.chunks_exact(8).enumerate() {
+            let index_id = index_id as u8;
+            let remote_crc = u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]);
+
+            let local = self.read(REFERENCE_TABLE_ID, index_id as u32).ok();
+            if local
+                .as_ref()
+                .is_some_and(|local| crc32fast::hash(local) == remote_crc)
+            {
+                continue;
+            }
+
+            let local = match local {
+                Some(local) if !local.is_empty() => IndexMetadata::from_buffer(&local.decode()?)?,
+                _ => IndexMetadata::default(),
+            };
+            let reference_table = downloader.request(REFERENCE_TABLE_ID, index_id as u32)?;
+            let remote = if reference_table.is_empty() {
+                IndexMetadata::default()
+            } else {
+                IndexMetadata::from_buffer(
+                    &Buffer::<Encoded>::from(reference_table.as_slice()).decode()?,
+                )?
+            };
+
+            let changed: Vec<_> = remote
+                .iter()
+                .filter(|archive| {
+                    !local.get(archive.id).is_some_and(|local| {
+                        local.crc == archive.crc && local.version == archive.version
+                    })
+                })
+                .collect();
+            let requests: Vec<_> = changed
+                .iter()
+                .map(|archive| (index_id, archive.id))
+                .collect();
+            let buffers = downloader.request_all(&requests)?;
+
+            update.archives.push(UpdatedArchive {
+                index_id: REFERENCE_TABLE_ID,
+                archive_id: index_id as u32,
+                buffer: reference_table,
+            });
+            for (archive, buffer) in changed.into_iter().zip(buffers) {
+                let mut buffer = buffer.as_slice().to_vec();
+                buffer.extend(&(archive.version as u16).to_be_bytes());
+
+                update.archives.push(UpdatedArchive {
+                    index_id,
+                    archive_id: archive.id,
+                    buffer: Buffer::from(buffer),
+                });
+            }
+        }
+
+        Ok(update)
+    }
+}
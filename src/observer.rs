@@ -0,0 +1,127 @@
+//! Hooks that report what the cache reads and decodes, e.g. to feed
+//! Prometheus counters in a long running server.
+//!
+//! # Example
+//!
+//! ```
+//! use std::sync::{
+//!     atomic::{AtomicUsize, Ordering},
+//!     Arc,
+//! };
+//!
+//! use rscache::{
+//!     observer::{CacheObserver, ReadEvent},
+//!     Cache,
+//! };
+//!
+//! #[derive(Default)]
+//! struct Metrics {
+//!     bytes_read: AtomicUsize,
+//! }
+//!
+//! impl CacheObserver for Metrics {
+//!     fn on_read(&self, event: &ReadEvent) {
+//!         self.bytes_read.fetch_add(event.bytes, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! let metrics = Arc::new(Metrics::default());
+//! let cache = Cache::new("./data/osrs_cache")?.with_observer(Arc::clone(&metrics));
+//!
+//! cache.read(2, 10)?;
+//! assert!(metrics.bytes_read.load(Ordering::Relaxed) > 0);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, sync::Arc, time::Duration};
+
+/// Receives an event for every archive the cache reads or decodes.
+///
+/// Both methods do nothing by default. A cache can be shared between threads,
+/// so observers are `Send + Sync` and are called on the thread that did the
+/// read. Failed reads and decodes are not reported.
+pub trait CacheObserver: Send + Sync {
+    /// Called after an archive was read with [`Cache::read`](crate::Cache::read)
+    /// or [`Cache::read_borrowed`](crate::Cache::read_borrowed), which
+    /// includes the reads of every decoded archive.
+    fn on_read(&self, _event: &ReadEvent) {}
+
+    /// Called after an archive was decoded with
+    /// [`Cache::read_decoded`](crate::Cache::read_decoded) or
+    /// [`Cache::read_decoded_with_keys`](crate::Cache::read_decoded_with_keys).
+    fn on_decode(&self, _event: &DecodeEvent) {}
+}
+
+impl<T: CacheObserver + ?Sized> CacheObserver for Arc<T> {
+    fn on_read(&self, event: &ReadEvent) {
+        (**self).on_read(event);
+    }
+
+    fn on_decode(&self, event: &DecodeEvent) {
+        (**self).on_decode(event);
+    }
+}
+
+/// An archive that was read, see [`CacheObserver::on_read`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ReadEvent {
+    pub index_id: u8,
+    pub archive_id: u32,
+    /// Length of the encoded archive.
+    pub bytes: usize,
+    /// `true` if the archive came from an overlay instead of the data file.
+    pub overlay: bool,
+    pub duration: Duration,
+}
+
+/// An archive that was decoded, see [`CacheObserver::on_decode`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct DecodeEvent {
+    pub index_id: u8,
+    pub archive_id: u32,
+    /// Length of the decoded archive.
+    pub bytes: usize,
+    /// `true` if the archive was served from the decoded archive cache instead
+    /// of being decoded again.
+    pub cached: bool,
+    /// Time spent deciphering and decompressing, the read before it is
+    /// reported on its own.
+    pub duration: Duration,
+}
+
+/// The observer of a cache, if it has one.
+#[derive(Clone, Default)]
+pub(crate) struct Observer(Option<Arc<dyn CacheObserver>>);
+
+impl Observer {
+    pub(crate) fn new<O: CacheObserver + 'static>(observer: O) -> Self {
+        Self(Some(Arc::new(observer)))
+    }
+
+    #[inline]
+    pub(crate) const fn is_some(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub(crate) fn on_read(&self, event: ReadEvent) {
+        if let Some(observer) = &self.0 {
+            observer.on_read(&event);
+        }
+    }
+
+    pub(crate) fn on_decode(&self, event: DecodeEvent) {
+        if let Some(observer) = &self.0 {
+            observer.on_decode(&event);
+        }
+    }
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Observer").field(&self.is_some()).finish()
+    }
+}
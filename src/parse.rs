@@ -0,0 +1,138 @@
+//! Parsers for the variable length values used throughout the cache.
+//!
+//! Smarts take less space for small values by storing them in fewer bytes,
+//! the high bit of the first byte tells which size is used. The same parsers
+//! are used by the reference tables, the definitions and
+//! [`ReadExt`](crate::extension::ReadExt) so they can't drift apart.
+//!
+//! # Examples
+//!
+//! ```
+//! use rscache::parse::{be_u16_smart, be_u32_smart};
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! let (rest, value) = be_u16_smart(&[0x05, 0x00, 0x80])?;
+//! assert_eq!(value, 5);
+//!
+//! let (_, value) = be_u32_smart(rest)?;
+//! assert_eq!(value, 128);
+//! # Ok(())
+//! # }
+//! ```
+
+use nom::{
+    combinator::peek,
+    number::complete::{be_u16, be_u32, be_u8},
+    IResult,
+};
+
+/// The value of a big smart that signals another smart follows.
+pub const SMART_CONTINUATION: u32 = 32767;
+
+/// Parses an unsigned smart, a byte for values below `128` or a short for
+/// values up to `32767`.
+///
+/// # Errors
+///
+/// Returns an error if the input ends early.
+pub fn be_u16_smart(input: &[u8]) -> IResult<&[u8], u16, ()> {
+    let (_, first) = peek(be_u8)(input)?;
+
+    if first < 128 {
+        let (input, value) = be_u8(input)?;
+        Ok((input, value as u16))
+    } else {
+        let (input, value) = be_u16(input)?;
+        Ok((input, value - 0x8000))
+    }
+}
+
+/// Parses a signed smart, a byte for values from `-64` to `63` or a short for
+/// values from `-16384` to `16383`.
+///
+/// # Errors
+///
+/// Returns an error if the input ends early.
+pub fn be_i16_smart(input: &[u8]) -> IResult<&[u8], i16, ()> {
+    let (_, first) = peek(be_u8)(input)?;
+
+    if first < 128 {
+        let (input, value) = be_u8(input)?;
+        Ok((input, value as i16 - 64))
+    } else {
+        let (input, value) = be_u16(input)?;
+        Ok((input, (value as i32 - 0xC000) as i16))
+    }
+}
+
+/// Parses a big smart, a short for values below `32768` or an int for values
+/// up to `i32::MAX`.
+///
+/// Ids and counts in reference tables are stored like this from protocol 7
+/// onwards.
+///
+/// # Errors
+///
+/// Returns an error if the input ends early.
+pub fn be_u32_smart(input: &[u8]) -> IResult<&[u8], u32, ()> {
+    let (_, first) = peek(be_u8)(input)?;
+
+    if first & 0x80 == 0 {
+        let (input, value) = be_u16(input)?;
+        Ok((input, value as u32))
+    } else {
+        let (input, value) = be_u32(input)?;
+        Ok((input, value & 0x7FFF_FFFF))
+    }
+}
+
+/// Parses unsigned smarts and adds them up for as long as they are
+/// [`SMART_CONTINUATION`], so values above `32767` can still be stored.
+///
+/// # Errors
+///
+/// Returns an error if the input ends early.
+pub fn be_u32_smart_compat(mut input: &[u8]) -> IResult<&[u8], u32, ()> {
+    let mut total = 0_u32;
+
+    loop {
+        let (rest, value) = be_u16_smart(input)?;
+        input = rest;
+        total = total.wrapping_add(value as u32);
+
+        if value as u32 != SMART_CONTINUATION {
+            return Ok((input, total));
+        }
+    }
+}
+
+/// Parses a variable length int, stored in groups of 7 bits with the high bit
+/// set on every byte except the last.
+///
+/// # Errors
+///
+/// Returns an error if the input ends early.
+pub fn var_u32(input: &[u8]) -> IResult<&[u8], u32, ()> {
+    let (input, value) = var_u64(input)?;
+
+    Ok((input, value as u32))
+}
+
+/// Parses a variable length long, see [`var_u32`].
+///
+/// # Errors
+///
+/// Returns an error if the input ends early.
+pub fn var_u64(mut input: &[u8]) -> IResult<&[u8], u64, ()> {
+    let mut value = 0_u64;
+
+    loop {
+        let (rest, byte) = be_u8(input)?;
+        input = rest;
+        value = (value << 7) | (byte & 0x7F) as u64;
+
+        if byte & 0x80 == 0 {
+            return Ok((input, value));
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! The protocol a cache is encoded with.
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use runefs::REFERENCE_TABLE_ID;
+
+use crate::{metadata::FLAG_WHIRLPOOL, Cache};
+
+/// OSRS caches have a little over 20 indices, RS3 caches have well over 30.
+const RS3_MIN_INDEX_COUNT: usize = 30;
+
+/// The game a cache belongs to, which decides how its definitions are encoded.
+///
+/// The protocol of a cache is detected at runtime, see [`Cache::protocol`].
+/// `Default` returns the protocol of the enabled feature flags, `Rs3` with the
+/// `rs3` feature and `Osrs` otherwise.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Protocol {
+    /// Old School RuneScape.
+    #[cfg_attr(not(feature = "rs3"), default)]
+    Osrs,
+    /// RuneScape 3.
+    #[cfg_attr(feature = "rs3", default)]
+    Rs3,
+}
+
+impl Protocol {
+    /// Detects the protocol from the amount of indices in the reference table,
+    /// falling back to the reference table of the first index. RS3 reference
+    /// tables hold whirlpool digests, OSRS reference tables never do.
+    pub(crate) fn detect(cache: &Cache) -> Self {
+        let index_count = cache
+            .indices
+            .get(&REFERENCE_TABLE_ID)
+            .map_or(0, |reference| reference.archive_refs.len());
+        if index_count >= RS3_MIN_INDEX_COUNT {
+            return Self::Rs3;
+        }
+
+        let whirlpool = cache
+            .present_indices()
+            .first()
+            .and_then(|&index_id| cache.index_metadata(index_id).ok())
+            .is_some_and(|metadata| metadata.flags & FLAG_WHIRLPOOL != 0);
+        if whirlpool {
+            Self::Rs3
+        } else {
+            Self::Osrs
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Osrs => f.write_str("OSRS"),
+            Self::Rs3 => f.write_str("RS3"),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Python bindings, built with [PyO3].
+//!
+//! A lot of cache data analysis happens in Python notebooks, so the cache and
+//! the most used loaders are exposed as the `rscache` Python module. Build it
+//! with [maturin] and the `python` feature enabled:
+//!
+//! ```text
+//! maturin develop --features python,pyo3/extension-module
+//! ```
+//!
+//! Definitions are returned as dictionaries with the same fields as their
+//! serialized Rust counterparts.
+//!
+//! ```python
+//! import rscache
+//!
+//! cache = rscache.Cache("./data/osrs_cache")
+//! items = cache.items()
+//! print(items[1042]["name"])
+//! ```
+//!
+//! [PyO3]: https://crates.io/crates/pyo3
+//! [maturin]: https://www.maturin.rs/
+
+// The impls generated by `#[pymethods]` of PyO3 0.20 are inside a constant.
+#![allow(non_local_definitions)]
+
+use pyo3::{
+    create_exception,
+    exceptions::PyException,
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+use pythonize::pythonize;
+use serde::Serialize;
+
+use crate::loader::osrs::{ItemLoader, NpcLoader, ObjectLoader};
+
+create_exception!(
+    rscache,
+    CacheError,
+    PyException,
+    "Failed to read from the cache."
+);
+
+/// A cache that can be used from Python.
+#[pyclass(name = "Cache", frozen)]
+pub struct PyCache(crate::Cache);
+
+#[pymethods]
+impl PyCache {
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        Ok(Self(crate::Cache::new(path).map_err(to_py_err)?))
+    }
+
+    /// Reads the encoded archive.
+    fn read<'py>(&self, py: Python<'py>, index_id: u8, archive_id: u32) -> PyResult<&'py PyBytes> {
+        let buffer = self.0.read(index_id, archive_id).map_err(to_py_err)?;
+
+        Ok(PyBytes::new(py, &buffer))
+    }
+
+    /// Reads and decompresses the archive.
+    fn read_decoded<'py>(
+        &self,
+        py: Python<'py>,
+        index_id: u8,
+        archive_id: u32,
+    ) -> PyResult<&'py PyBytes> {
+        let buffer = self
+            .0
+            .read(index_id, archive_id)
+            .and_then(|buffer| Ok(buffer.decode()?))
+            .map_err(to_py_err)?;
+
+        Ok(PyBytes::new(py, &buffer))
+    }
+
+    /// Loads all item definitions, keyed by id.
+    fn items<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let loader = ItemLoader::new(&self.0).map_err(to_py_err)?;
+
+        definitions(py, &loader)
+    }
+
+    /// Loads all npc definitions, keyed by id.
+    fn npcs<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let loader = NpcLoader::new(&self.0).map_err(to_py_err)?;
+
+        definitions(py, &loader)
+    }
+
+    /// Loads all object definitions, keyed by id.
+    fn objects<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let loader = ObjectLoader::new(&self.0).map_err(to_py_err)?;
+
+        definitions(py, &loader)
+    }
+}
+
+/// The `rscache` Python module.
+#[pymodule]
+fn rscache(py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyCache>()?;
+    module.add("CacheError", py.get_type::<CacheError>())?;
+
+    Ok(())
+}
+
+fn definitions<'a, 'py, L, D>(py: Python<'py>, loader: &'a L) -> PyResult<&'py PyDict>
+where
+    &'a L: IntoIterator<Item = (&'a u32, &'a D)>,
+    D: Serialize + 'a,
+{
+    let dict = PyDict::new(py);
+    for (id, definition) in loader {
+        dict.set_item(id, pythonize(py, definition)?)?;
+    }
+
+    Ok(dict)
+}
+
+fn to_py_err(err: crate::Error) -> PyErr {
+    CacheError::new_err(err.to_string())
+}
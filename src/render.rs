@@ -0,0 +1,122 @@
+//! Rendering of cache data into images.
+//!
+//! Maps are rendered by the [`MapImageRenderer`] and inventory icons by the
+//! [`ItemIconRenderer`]. Images are plain RGBA buffers, they can be handed to
+//! any image library to be encoded.
+
+mod item;
+mod map;
+
+pub use item::*;
+pub use map::*;
+
+/// An image with 4 bytes per pixel in RGBA order, row by row from the top left.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates a fully transparent image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    #[inline]
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The RGBA bytes of the image.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    /// Returns the RGBA pixel at `x`, `y` or `None` if it is out of bounds.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        Some([
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ])
+    }
+
+    /// Sets the pixel at `x`, `y` to the ARGB color `argb`, pixels that are out
+    /// of bounds are ignored.
+    pub(crate) fn set_argb(&mut self, x: i32, y: i32, argb: u32) {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return;
+        }
+
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        let [a, r, g, b] = argb.to_be_bytes();
+        self.pixels[i..i + 4].copy_from_slice(&[r, g, b, a]);
+    }
+}
+
+/// Converts a color in the 16-bit HSL format of the client to 24-bit RGB.
+///
+/// The format packs 6 bits of hue, 3 bits of saturation and 7 bits of lightness.
+pub fn hsl_to_rgb(hsl: u16) -> u32 {
+    let hue = ((hsl >> 10) & 0x3F) as f64 / 64.0 + 0.0078125;
+    let saturation = ((hsl >> 7) & 0x7) as f64 / 8.0 + 0.0625;
+    let lightness = (hsl & 0x7F) as f64 / 128.0;
+
+    let q = if lightness < 0.5 {
+        lightness * (1.0 + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2.0 * lightness - q;
+
+    let r = hue_to_channel(p, q, hue + 1.0 / 3.0);
+    let g = hue_to_channel(p, q, hue);
+    let b = hue_to_channel(p, q, hue - 1.0 / 3.0);
+
+    let [r, g, b] = [r, g, b].map(|channel| ((channel * 256.0) as u32).min(255));
+
+    (r << 16) | (g << 8) | b
+}
+
+fn hue_to_channel(p: f64, q: f64, t: f64) -> f64 {
+    let t = if t < 0.0 {
+        t + 1.0
+    } else if t > 1.0 {
+        t - 1.0
+    } else {
+        t
+    };
+
+    if 6.0 * t < 1.0 {
+        p + (q - p) * 6.0 * t
+    } else if 2.0 * t < 1.0 {
+        q
+    } else if 3.0 * t < 2.0 {
+        p + (q - p) * (2.0 / 3.0 - t) * 6.0
+    } else {
+        p
+    }
+}
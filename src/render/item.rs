@@ -0,0 +1,448 @@
+use super::{hsl_to_rgb, Image};
+use crate::{
+    definition::osrs::{DecodeContext, Definition, ItemDefinition, ModelDefinition},
+    loader::osrs::TextureLoader,
+    Cache,
+};
+
+/// The width of an inventory icon in pixels.
+pub const ICON_WIDTH: u32 = 36;
+/// The height of an inventory icon in pixels.
+pub const ICON_HEIGHT: u32 = 32;
+
+const MODEL_INDEX_ID: u8 = 7;
+const ZOOM: i32 = 512;
+const NEAR_PLANE: i32 = 50;
+const LIGHT: [i32; 3] = [-50, -10, -50];
+const OUTLINE_RGB: u32 = 0x000001;
+const SHADOW_RGB: u32 = 0x302020;
+
+/// Renders the inventory icons of items like the client does.
+///
+/// The inventory model of an item is recolored, scaled, lit and rotated with
+/// the 2d fields of the item and drawn into a [`ICON_WIDTH`] by
+/// [`ICON_HEIGHT`] image, followed by the black outline and the drop shadow of
+/// the client. Textured faces are drawn with the average color of their
+/// texture.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::{loader::osrs::ItemLoader, render::ItemIconRenderer};
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let item_loader = ItemLoader::new(&cache)?;
+/// let renderer = ItemIconRenderer::new(&cache)?;
+///
+/// let blue_partyhat = item_loader.load(1042).unwrap();
+/// let icon = renderer.render(blue_partyhat)?;
+///
+/// assert_eq!((icon.width(), icon.height()), (36, 32));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ItemIconRenderer<'cache> {
+    cache: &'cache Cache,
+    textures: TextureLoader,
+}
+
+impl<'cache> ItemIconRenderer<'cache> {
+    /// Make a new `ItemIconRenderer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the texture definitions could not be loaded.
+    pub fn new(cache: &'cache Cache) -> crate::Result<Self> {
+        Ok(Self {
+            cache,
+            textures: TextureLoader::new(cache)?,
+        })
+    }
+
+    /// Renders the inventory icon of an item.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the inventory model could not be read, decoded or
+    /// parsed.
+    pub fn render(&self, item: &ItemDefinition) -> crate::Result<Image> {
+        let data = &item.inventory_model_data;
+        let model_id = data.inventory_model as u32;
+        let buffer = self.cache.read(MODEL_INDEX_ID, model_id)?.decode()?;
+        let ctx = DecodeContext::new(MODEL_INDEX_ID, model_id);
+        let mut model = ModelDefinition::from_buffer(model_id, &buffer, &ctx)?;
+
+        for (find, replace) in data.color_find.iter().zip(&data.color_replace) {
+            for color in model.face_colors.iter_mut() {
+                if *color == *find {
+                    *color = *replace;
+                }
+            }
+        }
+        for (find, replace) in data.texture_find.iter().zip(&data.texture_replace) {
+            for texture in model.face_textures.iter_mut().flatten() {
+                if *texture == *find {
+                    *texture = *replace;
+                }
+            }
+        }
+        scale(&mut model, data.resize_x, data.resize_y, data.resize_z);
+
+        let colors = self.light(&model, data.ambient as i32 + 64, data.contrast as i32 + 768);
+
+        let height = model
+            .vertices_y
+            .iter()
+            .map(|y| -y)
+            .max()
+            .unwrap_or(0)
+            .max(0);
+        let zoom = data.zoom2d as i32;
+        let x_offset = data.x_offset2d as i16 as i32;
+        let y_offset = data.y_offset2d as i16 as i32;
+        let projected = project(
+            &model,
+            [data.x_an2d, data.y_an2d, data.z_an2d],
+            [
+                x_offset,
+                height / 2 + ((zoom * sine(data.x_an2d)) >> 16) + y_offset,
+                ((zoom * cosine(data.x_an2d)) >> 16) + y_offset,
+            ],
+        );
+
+        let mut raster = Raster::new(ICON_WIDTH as usize, ICON_HEIGHT as usize);
+        for face in 0..model.face_count() {
+            if let Some(colors) = colors[face] {
+                let alpha = model.face_alphas.get(face).copied().unwrap_or(0);
+                let vertices = [
+                    model.faces_a[face] as usize,
+                    model.faces_b[face] as usize,
+                    model.faces_c[face] as usize,
+                ];
+                raster.draw_face(&projected, vertices, colors, alpha);
+            }
+        }
+
+        raster.outline(OUTLINE_RGB);
+        raster.shadow(SHADOW_RGB);
+
+        Ok(raster.into_image())
+    }
+
+    /// Computes the HSL color of every vertex of every face, `None` for faces
+    /// that are hidden.
+    fn light(&self, model: &ModelDefinition, ambient: i32, contrast: i32) -> Vec<Option<[u16; 3]>> {
+        let magnitude = ((LIGHT[0] * LIGHT[0] + LIGHT[1] * LIGHT[1] + LIGHT[2] * LIGHT[2]) as f64)
+            .sqrt() as i32;
+        let intensity = ((contrast * magnitude) >> 8).max(1);
+
+        let face_normals: Vec<[i32; 3]> = (0..model.face_count())
+            .map(|face| face_normal(model, face))
+            .collect();
+        let mut vertex_normals = vec![[0; 4]; model.vertex_count()];
+        for (face, normal) in face_normals.iter().enumerate() {
+            if model.face_render_types.get(face).copied().unwrap_or(0) & 1 == 0 {
+                for vertex in [
+                    model.faces_a[face],
+                    model.faces_b[face],
+                    model.faces_c[face],
+                ] {
+                    if let Some(vertex_normal) = vertex_normals.get_mut(vertex as usize) {
+                        vertex_normal[0] += normal[0];
+                        vertex_normal[1] += normal[1];
+                        vertex_normal[2] += normal[2];
+                        vertex_normal[3] += 1;
+                    }
+                }
+            }
+        }
+
+        (0..model.face_count())
+            .map(|face| {
+                // Faces with an alpha of 255 are not drawn at all.
+                if model.face_alphas.get(face) == Some(&255) {
+                    return None;
+                }
+
+                let color = match model.face_textures.get(face).copied().flatten() {
+                    Some(texture) => self.textures.load(texture as u32)?.average_hsl,
+                    None => model.face_colors[face],
+                };
+
+                if model.face_render_types.get(face).copied().unwrap_or(0) & 1 == 1 {
+                    let lightness =
+                        ambient + dot(&face_normals[face]) / (intensity + intensity / 2);
+                    let color = shade(color, lightness);
+                    return Some([color; 3]);
+                }
+
+                let mut colors = [0; 3];
+                let vertices = [
+                    model.faces_a[face],
+                    model.faces_b[face],
+                    model.faces_c[face],
+                ];
+                for (color_out, vertex) in colors.iter_mut().zip(vertices) {
+                    let normal = vertex_normals.get(vertex as usize)?;
+                    let lightness = ambient + dot(normal) / (intensity * normal[3].max(1));
+                    *color_out = shade(color, lightness);
+                }
+
+                Some(colors)
+            })
+            .collect()
+    }
+}
+
+fn scale(model: &mut ModelDefinition, x: u16, y: u16, z: u16) {
+    for (vertices, scale) in [
+        (&mut model.vertices_x, x),
+        (&mut model.vertices_y, y),
+        (&mut model.vertices_z, z),
+    ] {
+        if scale != 128 {
+            for vertex in vertices.iter_mut() {
+                *vertex = *vertex * scale as i32 / 128;
+            }
+        }
+    }
+}
+
+fn sine(angle: u16) -> i32 {
+    (65536.0 * ((angle & 2047) as f64 * std::f64::consts::TAU / 2048.0).sin()) as i32
+}
+
+fn cosine(angle: u16) -> i32 {
+    (65536.0 * ((angle & 2047) as f64 * std::f64::consts::TAU / 2048.0).cos()) as i32
+}
+
+/// Returns the normal of a face with a length of 256.
+fn face_normal(model: &ModelDefinition, face: usize) -> [i32; 3] {
+    let vertex = |index: u16| {
+        let index = index as usize;
+        [
+            model.vertices_x.get(index).copied().unwrap_or(0),
+            model.vertices_y.get(index).copied().unwrap_or(0),
+            model.vertices_z.get(index).copied().unwrap_or(0),
+        ]
+    };
+    let a = vertex(model.faces_a[face]);
+    let b = vertex(model.faces_b[face]);
+    let c = vertex(model.faces_c[face]);
+
+    let (dx1, dy1, dz1) = (b[0] - a[0], b[1] - a[1], b[2] - a[2]);
+    let (dx2, dy2, dz2) = (c[0] - a[0], c[1] - a[1], c[2] - a[2]);
+    let mut normal = [
+        dy1 * dz2 - dy2 * dz1,
+        dz1 * dx2 - dz2 * dx1,
+        dx1 * dy2 - dx2 * dy1,
+    ];
+    while normal.iter().any(|n| n.abs() > 8192) {
+        normal = normal.map(|n| n >> 1);
+    }
+
+    let len = (normal.iter().map(|n| n * n).sum::<i32>() as f64).sqrt() as i32;
+    normal.map(|n| n * 256 / len.max(1))
+}
+
+fn dot(normal: &[i32]) -> i32 {
+    normal[0] * LIGHT[0] + normal[1] * LIGHT[1] + normal[2] * LIGHT[2]
+}
+
+/// Applies a lightness to the HSL color, just like the client does.
+fn shade(hsl: u16, lightness: i32) -> u16 {
+    let lightness = (((hsl & 0x7F) as i32 * lightness) >> 7).clamp(2, 126);
+
+    (hsl & 0xFF80) | lightness as u16
+}
+
+/// Rotates and translates the vertices and projects them onto the icon.
+fn project(
+    model: &ModelDefinition,
+    [x_an, y_an, z_an]: [u16; 3],
+    offset: [i32; 3],
+) -> Vec<[i32; 3]> {
+    let (sin_x, cos_x) = (sine(x_an), cosine(x_an));
+    let (sin_y, cos_y) = (sine(y_an), cosine(y_an));
+    let (sin_z, cos_z) = (sine(z_an), cosine(z_an));
+    let center_x = ICON_WIDTH as i32 / 2;
+    let center_y = ICON_HEIGHT as i32 / 2;
+
+    (0..model.vertex_count())
+        .map(|vertex| {
+            let mut x = model.vertices_x[vertex];
+            let mut y = model.vertices_y[vertex];
+            let mut z = model.vertices_z[vertex];
+
+            if z_an != 0 {
+                let rotated = (y * sin_z + x * cos_z) >> 16;
+                y = (y * cos_z - x * sin_z) >> 16;
+                x = rotated;
+            }
+            if y_an != 0 {
+                let rotated = (z * sin_y + x * cos_y) >> 16;
+                z = (z * cos_y - x * sin_y) >> 16;
+                x = rotated;
+            }
+
+            x += offset[0];
+            y += offset[1];
+            z += offset[2];
+
+            let rotated = (y * cos_x - z * sin_x) >> 16;
+            z = (y * sin_x + z * cos_x) >> 16;
+            y = rotated;
+
+            if z < NEAR_PLANE {
+                return [0, 0, z];
+            }
+
+            [center_x + x * ZOOM / z, center_y + y * ZOOM / z, z]
+        })
+        .collect()
+}
+
+/// A depth buffered ARGB canvas, pixels that are `0` are transparent.
+struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<u32>,
+    depth: Vec<i32>,
+}
+
+impl Raster {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+            depth: vec![i32::MAX; width * height],
+        }
+    }
+
+    /// Draws a Gouraud shaded triangle, back faces are culled.
+    fn draw_face(
+        &mut self,
+        projected: &[[i32; 3]],
+        vertices: [usize; 3],
+        colors: [u16; 3],
+        alpha: u8,
+    ) {
+        let (Some(a), Some(b), Some(c)) = (
+            projected.get(vertices[0]),
+            projected.get(vertices[1]),
+            projected.get(vertices[2]),
+        ) else {
+            return;
+        };
+        if a[2] < NEAR_PLANE || b[2] < NEAR_PLANE || c[2] < NEAR_PLANE {
+            return;
+        }
+
+        let area = (a[0] - b[0]) * (c[1] - b[1]) - (a[1] - b[1]) * (c[0] - b[0]);
+        if area <= 0 {
+            return;
+        }
+
+        let min_x = a[0].min(b[0]).min(c[0]).max(0);
+        let max_x = a[0].max(b[0]).max(c[0]).min(self.width as i32 - 1);
+        let min_y = a[1].min(b[1]).min(c[1]).max(0);
+        let max_y = a[1].max(b[1]).max(c[1]).min(self.height as i32 - 1);
+
+        let edge = |p: &[i32; 3], q: &[i32; 3], x: i32, y: i32| {
+            (p[0] - q[0]) * (y - q[1]) - (p[1] - q[1]) * (x - q[0])
+        };
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let wa = edge(b, c, x, y);
+                let wb = edge(c, a, x, y);
+                let wc = edge(a, b, x, y);
+                if wa < 0 || wb < 0 || wc < 0 {
+                    continue;
+                }
+
+                let interpolate = |values: [i32; 3]| {
+                    ((values[0] as i64 * wa as i64
+                        + values[1] as i64 * wb as i64
+                        + values[2] as i64 * wc as i64)
+                        / area as i64) as i32
+                };
+                let z = interpolate([a[2], b[2], c[2]]);
+                let i = y as usize * self.width + x as usize;
+                if z >= self.depth[i] {
+                    continue;
+                }
+                self.depth[i] = z;
+
+                let hsl = interpolate(colors.map(|color| color as i32));
+                let rgb = hsl_to_rgb(hsl as u16);
+                let rgb = if alpha == 0 {
+                    rgb
+                } else {
+                    blend(rgb, self.pixels[i], alpha)
+                };
+                self.pixels[i] = 0xFF00_0000 | rgb.max(1);
+            }
+        }
+    }
+
+    /// Gives every transparent pixel next to a drawn pixel the color `rgb`.
+    fn outline(&mut self, rgb: u32) {
+        let (width, height) = (self.width, self.height);
+        let pixels = self.pixels.clone();
+
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                if pixels[i] != 0 {
+                    continue;
+                }
+
+                let next_to_drawn = (x > 0 && pixels[i - 1] != 0)
+                    || (y > 0 && pixels[i - width] != 0)
+                    || (x < width - 1 && pixels[i + 1] != 0)
+                    || (y < height - 1 && pixels[i + width] != 0);
+                if next_to_drawn {
+                    self.pixels[i] = 0xFF00_0000 | rgb;
+                }
+            }
+        }
+    }
+
+    /// Gives every transparent pixel with a drawn pixel to its top left the
+    /// color `rgb`.
+    fn shadow(&mut self, rgb: u32) {
+        for y in (1..self.height).rev() {
+            for x in (1..self.width).rev() {
+                let i = y * self.width + x;
+                if self.pixels[i] == 0 && self.pixels[i - 1 - self.width] != 0 {
+                    self.pixels[i] = 0xFF00_0000 | rgb;
+                }
+            }
+        }
+    }
+
+    fn into_image(self) -> Image {
+        let mut image = Image::new(self.width as u32, self.height as u32);
+        for (i, &argb) in self.pixels.iter().enumerate() {
+            image.set_argb((i % self.width) as i32, (i / self.width) as i32, argb);
+        }
+
+        image
+    }
+}
+
+/// Blends `rgb` over `background` with the given transparency.
+fn blend(rgb: u32, background: u32, alpha: u8) -> u32 {
+    let alpha = alpha as u32;
+    let channel = |shift: u32| {
+        let src = (rgb >> shift) & 0xFF;
+        let dst = (background >> shift) & 0xFF;
+        ((src * (256 - alpha) + dst * alpha) >> 8) << shift
+    };
+
+    channel(16) | channel(8) | channel(0)
+}
@@ -0,0 +1,309 @@
+use super::{hsl_to_rgb, Image};
+use crate::{
+    definition::osrs::{
+        DecodeContext, Definition, OverlayDefinition, SpriteDefinition, SpriteFrame,
+        TRANSPARENT_RGB,
+    },
+    loader::osrs::{
+        DefaultsLoader, OverlayLoader, Region, RegionLoader, TextureLoader, UnderlayLoader,
+        REGION_SIZE,
+    },
+    Cache,
+};
+
+/// The width and height of a single tile in pixels, the same as on the minimap.
+pub const TILE_SIZE: usize = 4;
+/// The amount of surrounding tiles in each direction that underlay colors are
+/// blended with.
+pub const BLEND_RADIUS: usize = 5;
+
+const OPAQUE: u32 = 0xFF00_0000;
+const WALL_RGB: u32 = 0xEEEEEE;
+const DOOR_RGB: u32 = 0xEE0000;
+const SPRITE_INDEX_ID: u8 = 8;
+
+/// Renders regions into map images like the minimap of the client.
+///
+/// Every tile is drawn as a [`TILE_SIZE`] square with the color of its overlay,
+/// or the blended color of its underlay when it has no overlay. Overlay shapes
+/// are not taken into account. Walls are drawn as lines on the edges of their
+/// tile and locations with a map scene are drawn as icons on top.
+///
+/// # Examples
+///
+/// ```
+/// # use rscache::Cache;
+/// use rscache::render::MapImageRenderer;
+///
+/// # fn main() -> Result<(), rscache::Error> {
+/// # let cache = Cache::new("./data/osrs_cache")?;
+/// let renderer = MapImageRenderer::new(&cache)?;
+///
+/// let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+/// let image = renderer.render(12850, &keys, 0)?;
+///
+/// assert_eq!(image.width(), 256);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MapImageRenderer<'cache> {
+    regions: RegionLoader<'cache>,
+    underlays: UnderlayLoader,
+    overlays: OverlayLoader,
+    textures: TextureLoader,
+    map_scenes: Option<SpriteDefinition>,
+}
+
+impl<'cache> MapImageRenderer<'cache> {
+    /// Make a new `MapImageRenderer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the definitions needed for rendering could not
+    /// be loaded.
+    pub fn new(cache: &'cache Cache) -> crate::Result<Self> {
+        let map_scenes = match DefaultsLoader::new(cache)?.graphics().map_scenes {
+            Some(id) => {
+                let buffer = cache.read(SPRITE_INDEX_ID, id)?.decode()?;
+                let ctx = DecodeContext::new(SPRITE_INDEX_ID, id);
+                Some(SpriteDefinition::from_buffer(id, &buffer, &ctx)?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            regions: RegionLoader::new(cache)?,
+            underlays: UnderlayLoader::new(cache)?,
+            overlays: OverlayLoader::new(cache)?,
+            textures: TextureLoader::new(cache)?,
+            map_scenes,
+        })
+    }
+
+    /// Loads a region and renders a single plane of it.
+    ///
+    /// The location archive is encrypted with XTEA, so `keys` are required.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the region could not be loaded.
+    pub fn render(&self, id: u16, keys: &[u32; 4], plane: usize) -> crate::Result<Image> {
+        let region = self.regions.load(id, keys)?;
+
+        Ok(self.render_region(&region, plane))
+    }
+
+    /// Renders a single plane of an already loaded region.
+    ///
+    /// The image is `REGION_SIZE * TILE_SIZE` pixels wide and high with north
+    /// at the top.
+    pub fn render_region(&self, region: &Region, plane: usize) -> Image {
+        let size = (REGION_SIZE * TILE_SIZE) as u32;
+        let mut image = Image::new(size, size);
+
+        let underlays = self.blended_underlays(region, plane);
+        for x in 0..REGION_SIZE {
+            for y in 0..REGION_SIZE {
+                let overlay_id = region.overlay_id(x, y, plane) as u8;
+                let overlay = match overlay_id {
+                    0 => None,
+                    id => self.overlays.load(id as u32 - 1),
+                };
+
+                let rgb = overlay
+                    .and_then(|overlay| self.overlay_rgb(overlay))
+                    .or(underlays[x * REGION_SIZE + y]);
+                if let Some(rgb) = rgb {
+                    let (px, py) = tile_origin(x, y);
+                    for dx in 0..TILE_SIZE as i32 {
+                        for dy in 0..TILE_SIZE as i32 {
+                            image.set_argb(px + dx, py + dy, OPAQUE | rgb);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.draw_locations(&mut image, region, plane);
+
+        image
+    }
+
+    /// Averages the underlay colors around every tile, just like the client
+    /// smooths the borders between underlays.
+    fn blended_underlays(&self, region: &Region, plane: usize) -> Vec<Option<u32>> {
+        let colors: Vec<Option<u32>> = (0..REGION_SIZE * REGION_SIZE)
+            .map(|i| {
+                let (x, y) = (i / REGION_SIZE, i % REGION_SIZE);
+                match region.underlay_id(x, y, plane) {
+                    0 => None,
+                    id => self
+                        .underlays
+                        .load(id as u32 - 1)
+                        .map(|underlay| underlay.rgb),
+                }
+            })
+            .collect();
+
+        (0..REGION_SIZE * REGION_SIZE)
+            .map(|i| {
+                let (x, y) = (i / REGION_SIZE, i % REGION_SIZE);
+                colors[i]?;
+
+                let xs = x.saturating_sub(BLEND_RADIUS)..(x + BLEND_RADIUS + 1).min(REGION_SIZE);
+                let ys = y.saturating_sub(BLEND_RADIUS)..(y + BLEND_RADIUS + 1).min(REGION_SIZE);
+
+                let (mut r, mut g, mut b, mut count) = (0, 0, 0, 0);
+                for bx in xs {
+                    for by in ys.clone() {
+                        if let Some(rgb) = colors[bx * REGION_SIZE + by] {
+                            r += (rgb >> 16) & 0xFF;
+                            g += (rgb >> 8) & 0xFF;
+                            b += rgb & 0xFF;
+                            count += 1;
+                        }
+                    }
+                }
+
+                Some(((r / count) << 16) | ((g / count) << 8) | (b / count))
+            })
+            .collect()
+    }
+
+    fn overlay_rgb(&self, overlay: &OverlayDefinition) -> Option<u32> {
+        if let Some(rgb) = overlay.secondary_rgb {
+            return Some(rgb);
+        }
+
+        match overlay.texture {
+            Some(id) => self
+                .textures
+                .load(id as u32)
+                .map(|texture| hsl_to_rgb(texture.average_hsl)),
+            None if overlay.rgb == TRANSPARENT_RGB => None,
+            None => Some(overlay.rgb),
+        }
+    }
+
+    fn draw_locations(&self, image: &mut Image, region: &Region, plane: usize) {
+        let objects = self.regions.objects();
+
+        for entry in region.locations.entries() {
+            let (x, y) = (entry.x as usize, entry.y as usize);
+            if region.collision_plane(x, y, entry.plane as usize) != Some(plane) {
+                continue;
+            }
+            let object = match objects.load(entry.id) {
+                Some(object) => object,
+                None => continue,
+            };
+
+            // Objects without a map scene have an id of 0.
+            let map_scene = self
+                .map_scenes
+                .as_ref()
+                .filter(|_| object.map_scene_id != 0)
+                .and_then(|sprite| {
+                    Some((sprite, sprite.frames.get(object.map_scene_id as usize)?))
+                });
+            if let Some((sprite, frame)) = map_scene {
+                let (size_x, size_y) = if entry.rotation & 1 == 1 {
+                    (object.model_data.size_y, object.model_data.size_x)
+                } else {
+                    (object.model_data.size_x, object.model_data.size_y)
+                };
+                draw_map_scene(image, sprite, frame, x, y, size_x, size_y);
+                continue;
+            }
+
+            let rgb = if object.actions.iter().any(|action| !action.is_empty()) {
+                DOOR_RGB
+            } else {
+                WALL_RGB
+            };
+            draw_wall(image, x, y, entry.loc_type, entry.rotation, OPAQUE | rgb);
+        }
+    }
+}
+
+/// The top left pixel of a tile, tiles with a higher `y` are further north.
+///
+/// Tiles north of the region, which large map scenes can reach into, are
+/// above the image.
+fn tile_origin(x: usize, y: usize) -> (i32, i32) {
+    (
+        (x * TILE_SIZE) as i32,
+        (REGION_SIZE as i32 - 1 - y as i32) * TILE_SIZE as i32,
+    )
+}
+
+fn draw_map_scene(
+    image: &mut Image,
+    sprite: &SpriteDefinition,
+    frame: &SpriteFrame,
+    x: usize,
+    y: usize,
+    size_x: u8,
+    size_y: u8,
+) {
+    // Map scenes are centered on the tiles the location covers.
+    let (px, _) = tile_origin(x, y);
+    let (_, py) = tile_origin(x, y + size_y.max(1) as usize - 1);
+    let px = px + (size_x as i32 * TILE_SIZE as i32 - sprite.max_width as i32) / 2;
+    let py = py + (size_y as i32 * TILE_SIZE as i32 - sprite.max_height as i32) / 2;
+
+    for dx in 0..frame.width {
+        for dy in 0..frame.height {
+            let argb = frame.pixel(dx, dy).unwrap_or(0);
+            if argb >> 24 != 0 {
+                image.set_argb(
+                    px + (frame.offset_x + dx) as i32,
+                    py + (frame.offset_y + dy) as i32,
+                    argb,
+                );
+            }
+        }
+    }
+}
+
+fn draw_wall(image: &mut Image, x: usize, y: usize, loc_type: u8, rotation: u8, argb: u32) {
+    let (px, py) = tile_origin(x, y);
+    let last = TILE_SIZE as i32 - 1;
+
+    let mut edge = |rotation: u8| {
+        for i in 0..TILE_SIZE as i32 {
+            let (dx, dy) = match rotation & 3 {
+                0 => (0, i),
+                1 => (i, 0),
+                2 => (last, i),
+                _ => (i, last),
+            };
+            image.set_argb(px + dx, py + dy, argb);
+        }
+    };
+
+    match loc_type {
+        0 => edge(rotation),
+        2 => {
+            edge(rotation);
+            edge(rotation + 1);
+        }
+        3 => {
+            let (dx, dy) = match rotation & 3 {
+                0 => (0, 0),
+                1 => (last, 0),
+                2 => (last, last),
+                _ => (0, last),
+            };
+            image.set_argb(px + dx, py + dy, argb);
+        }
+        9 => {
+            for i in 0..TILE_SIZE as i32 {
+                let dy = if rotation & 1 == 0 { last - i } else { i };
+                image.set_argb(px + i, py + dy, argb);
+            }
+        }
+        _ => {}
+    }
+}
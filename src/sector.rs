@@ -0,0 +1,526 @@
+//! Sector-level access into the main data file.
+//!
+//! Every archive in `main_file_cache.dat2` is stored as a chain of fixed-size
+//! sectors. Each sector starts with a small header which points to the next
+//! sector in the chain, followed by a block of archive data.
+
+use std::io::{self, Read};
+
+use crate::error::SectorError;
+use runefs::{
+    codec::{Buffer, Encoded},
+    ArchiveRef,
+};
+
+/// Total size of a single sector, header included.
+pub const SECTOR_SIZE: usize = 520;
+/// Size of a sector header for archives with an id that fits in a `u16`.
+pub const SECTOR_HEADER_SIZE: usize = 8;
+/// Size of a sector header for archives with an id larger than `u16::MAX`.
+pub const SECTOR_EXPANDED_HEADER_SIZE: usize = 10;
+/// Size of the data block that follows a normal sector header.
+pub const SECTOR_DATA_SIZE: usize = SECTOR_SIZE - SECTOR_HEADER_SIZE;
+/// Size of the data block that follows an expanded sector header.
+pub const SECTOR_EXPANDED_DATA_SIZE: usize = SECTOR_SIZE - SECTOR_EXPANDED_HEADER_SIZE;
+
+/// The last sector that can be addressed, sector numbers are stored in 24 bits
+/// by both index entries and sector headers.
+///
+/// Data files can therefore grow up to `(MAX_SECTOR + 1) * SECTOR_SIZE` bytes,
+/// which is well past 4GB, so offsets are always computed as `u64`.
+///
+/// RS3 caches use the same 6 byte index entries, which are still parsed by
+/// `ArchiveRef::from_buffer` of `rune-fs`. Caches that need sectors past this
+/// limit can't be read.
+pub const MAX_SECTOR: usize = 0xFF_FFFF;
+
+/// The offset of `sector` in the main data file.
+#[inline]
+pub const fn sector_offset(sector: usize) -> u64 {
+    sector as u64 * SECTOR_SIZE as u64
+}
+
+/// The header layout of a sector, determined by the id of the archive it belongs to.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum SectorHeaderSize {
+    Normal,
+    Expanded,
+}
+
+impl SectorHeaderSize {
+    /// Archives with an id larger than `u16::MAX` use the expanded header.
+    #[inline]
+    pub const fn from_archive_id(archive_id: u32) -> Self {
+        if archive_id > u16::MAX as u32 {
+            Self::Expanded
+        } else {
+            Self::Normal
+        }
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub const fn header_len(self) -> usize {
+        match self {
+            Self::Normal => SECTOR_HEADER_SIZE,
+            Self::Expanded => SECTOR_EXPANDED_HEADER_SIZE,
+        }
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub const fn data_len(self) -> usize {
+        match self {
+            Self::Normal => SECTOR_DATA_SIZE,
+            Self::Expanded => SECTOR_EXPANDED_DATA_SIZE,
+        }
+    }
+}
+
+/// The lengths of the data blocks an archive is split into, one per sector.
+///
+/// Every block is full except for the last one.
+///
+/// # Examples
+///
+/// ```
+/// use rscache::fs::consts::DataBlocks;
+///
+/// let blocks: Vec<_> = DataBlocks::new(10, 1200).collect();
+/// assert_eq!(blocks, [512, 512, 176]);
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct DataBlocks {
+    remaining: usize,
+    block_len: usize,
+}
+
+impl DataBlocks {
+    /// The blocks of an archive of `length` bytes, the archive id determines
+    /// the header size and with that the size of a full block.
+    #[inline]
+    pub const fn new(archive_id: u32, length: usize) -> Self {
+        Self {
+            remaining: length,
+            block_len: SectorHeaderSize::from_archive_id(archive_id).data_len(),
+        }
+    }
+}
+
+impl From<&ArchiveRef> for DataBlocks {
+    #[inline]
+    fn from(archive: &ArchiveRef) -> Self {
+        Self::new(archive.id, archive.length)
+    }
+}
+
+impl Iterator for DataBlocks {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let len = self.remaining.min(self.block_len);
+        self.remaining -= len;
+
+        Some(len)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining.div_ceil(self.block_len);
+
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for DataBlocks {}
+
+/// Header which precedes every block of archive data in the main data file.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SectorHeader {
+    pub archive_id: u32,
+    pub chunk: usize,
+    pub next: usize,
+    pub index_id: u8,
+}
+
+impl SectorHeader {
+    /// Parses a sector header from the start of `buffer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is shorter than the header length.
+    pub fn new(buffer: &[u8], header_size: SectorHeaderSize) -> Self {
+        let (archive_id, buffer) = match header_size {
+            SectorHeaderSize::Normal => (
+                u16::from_be_bytes([buffer[0], buffer[1]]) as u32,
+                &buffer[2..],
+            ),
+            SectorHeaderSize::Expanded => (
+                u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]),
+                &buffer[4..],
+            ),
+        };
+
+        Self {
+            archive_id,
+            chunk: u16::from_be_bytes([buffer[0], buffer[1]]) as usize,
+            next: ((buffer[2] as usize) << 16) | ((buffer[3] as usize) << 8) | buffer[4] as usize,
+            index_id: buffer[5],
+        }
+    }
+
+    /// Parses a sector header from the start of `buffer`, or returns `None` if
+    /// `buffer` is shorter than the header length.
+    #[inline]
+    pub fn try_new(buffer: &[u8], header_size: SectorHeaderSize) -> Option<Self> {
+        (buffer.len() >= header_size.header_len()).then(|| Self::new(buffer, header_size))
+    }
+
+    /// Encodes the header into the start of `buffer`, the inverse of [`new`](SectorHeader::new).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is shorter than the header length.
+    pub fn encode(&self, buffer: &mut [u8], header_size: SectorHeaderSize) {
+        let buffer = match header_size {
+            SectorHeaderSize::Normal => {
+                buffer[..2].copy_from_slice(&(self.archive_id as u16).to_be_bytes());
+                &mut buffer[2..]
+            }
+            SectorHeaderSize::Expanded => {
+                buffer[..4].copy_from_slice(&self.archive_id.to_be_bytes());
+                &mut buffer[4..]
+            }
+        };
+
+        buffer[..2].copy_from_slice(&(self.chunk as u16).to_be_bytes());
+        buffer[2..5].copy_from_slice(&(self.next as u32).to_be_bytes()[1..]);
+        buffer[5] = self.index_id;
+    }
+
+    /// Returns `true` if this header holds an earlier chunk of the given
+    /// archive, the chain that led to it loops back onto itself.
+    pub(crate) const fn loops_back(&self, archive_id: u32, chunk: usize, index_id: u8) -> bool {
+        self.archive_id == archive_id && self.index_id == index_id && self.chunk < chunk
+    }
+
+    /// Checks if this header belongs to the given archive at the given position in its chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SectorError` describing the first field that doesn't match.
+    pub const fn validate(
+        &self,
+        archive_id: u32,
+        chunk: usize,
+        index_id: u8,
+    ) -> Result<(), SectorError> {
+        if self.archive_id != archive_id {
+            return Err(SectorError::ArchiveMismatch {
+                expected: archive_id,
+                actual: self.archive_id,
+            });
+        }
+        if self.chunk != chunk {
+            return Err(SectorError::ChunkMismatch {
+                expected: chunk,
+                actual: self.chunk,
+            });
+        }
+        if self.index_id != index_id {
+            return Err(SectorError::IndexMismatch {
+                expected: index_id,
+                actual: self.index_id,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// A single sector with its header and the block of data it carries.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Sector<'a> {
+    pub header: SectorHeader,
+    pub data_block: &'a [u8],
+}
+
+impl<'a> Sector<'a> {
+    /// Splits `buffer` into a header and its data block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is shorter than the header length.
+    pub fn new(buffer: &'a [u8], header_size: SectorHeaderSize) -> Self {
+        Self {
+            header: SectorHeader::new(buffer, header_size),
+            data_block: &buffer[header_size.header_len()..],
+        }
+    }
+}
+
+/// Encodes `buffer` as a chain of consecutive sectors, the first one being
+/// `sector`.
+///
+/// Every sector is padded to the full sector size, so the chain can be written
+/// at `sector * SECTOR_SIZE` and the sector after it is free again.
+pub(crate) fn encode_chain(sector: usize, index_id: u8, archive_id: u32, buffer: &[u8]) -> Vec<u8> {
+    let header_size = SectorHeaderSize::from_archive_id(archive_id);
+    let blocks = buffer.chunks(header_size.data_len());
+    let block_count = blocks.len();
+
+    let mut data = vec![0; block_count * SECTOR_SIZE];
+    let sectors = data.chunks_exact_mut(SECTOR_SIZE);
+    for ((chunk, block), sector_data) in blocks.enumerate().zip(sectors) {
+        let next = if chunk + 1 == block_count {
+            0
+        } else {
+            sector + chunk + 1
+        };
+
+        let header = SectorHeader {
+            archive_id,
+            chunk,
+            next,
+            index_id,
+        };
+        header.encode(sector_data, header_size);
+        sector_data[header_size.header_len()..][..block.len()].copy_from_slice(block);
+    }
+
+    data
+}
+
+/// Walks the sector chain of an archive, yielding the offset of every sector in
+/// the data file together with its header.
+///
+/// Created with [`Cache::sectors`](crate::Cache::sectors). Headers are yielded
+/// as they are stored without validating them, so a corrupt chain can be
+/// inspected. The walk stops after as many sectors as the archive length needs,
+/// or at the first sector that is out of bounds or holds an earlier chunk of
+/// the archive.
+#[derive(Clone, Debug)]
+pub struct Sectors<'a> {
+    data: &'a [u8],
+    archive_id: u32,
+    index_id: u8,
+    header_size: SectorHeaderSize,
+    next: usize,
+    chunk: usize,
+    remaining: usize,
+}
+
+impl<'a> Sectors<'a> {
+    pub(crate) fn new(data: &'a [u8], archive: &ArchiveRef) -> Self {
+        let header_size = SectorHeaderSize::from_archive_id(archive.id);
+
+        Self {
+            data,
+            archive_id: archive.id,
+            index_id: archive.index_id,
+            header_size,
+            next: archive.sector,
+            chunk: 0,
+            remaining: DataBlocks::from(archive).len(),
+        }
+    }
+}
+
+impl Iterator for Sectors<'_> {
+    type Item = Result<(u64, SectorHeader), SectorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let sector = self.next;
+        let offset = sector_offset(sector);
+        let buffer = usize::try_from(offset)
+            .ok()
+            .and_then(|start| self.data.get(start..start + self.header_size.header_len()));
+        let Some(buffer) = buffer else {
+            self.remaining = 0;
+            return Some(Err(SectorError::SectorOutOfBounds { sector, offset }));
+        };
+        let header = SectorHeader::new(buffer, self.header_size);
+        if header.loops_back(self.archive_id, self.chunk, self.index_id) {
+            self.remaining = 0;
+            return Some(Err(SectorError::CyclicChain { sector }));
+        }
+        self.next = header.next;
+        self.chunk += 1;
+
+        Some(Ok((offset, header)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+/// Streams the data of a single archive by walking its sector chain on demand.
+#[derive(Debug)]
+pub(crate) struct ArchiveReader<'a> {
+    data: &'a [u8],
+    archive: &'a ArchiveRef,
+    header_size: SectorHeaderSize,
+    next: usize,
+    chunk: usize,
+    remaining: usize,
+    block: &'a [u8],
+}
+
+impl<'a> ArchiveReader<'a> {
+    pub(crate) fn new(data: &'a [u8], archive: &'a ArchiveRef) -> Self {
+        Self {
+            data,
+            archive,
+            header_size: SectorHeaderSize::from_archive_id(archive.id),
+            next: archive.sector,
+            chunk: 0,
+            remaining: archive.length,
+            block: &[],
+        }
+    }
+
+    /// Borrows the archive data straight from the data file when the whole
+    /// archive fits in a single sector.
+    pub(crate) fn single_block(&mut self) -> Result<Option<&'a [u8]>, SectorError> {
+        if self.chunk != 0 || self.remaining > self.header_size.data_len() {
+            return Ok(None);
+        }
+        if self.remaining == 0 {
+            return Ok(Some(&[]));
+        }
+
+        self.next_block()?;
+
+        Ok(Some(std::mem::take(&mut self.block)))
+    }
+
+    /// Walks the entire sector chain and collects the archive data.
+    pub(crate) fn read_to_vec(mut self) -> Result<Vec<u8>, SectorError> {
+        let mut buffer = Vec::with_capacity(self.remaining);
+        while self.remaining > 0 {
+            self.next_block()?;
+            buffer.extend_from_slice(self.block);
+        }
+
+        Ok(buffer)
+    }
+
+    fn next_block(&mut self) -> Result<(), SectorError> {
+        let data_len = self.remaining.min(self.header_size.data_len());
+        let offset = sector_offset(self.next);
+        let offset = match usize::try_from(offset) {
+            Ok(start) if start < self.data.len() => start,
+            _ => {
+                return Err(SectorError::SectorOutOfBounds {
+                    sector: self.next,
+                    offset,
+                })
+            }
+        };
+        // The sector starts in the data file but is cut off, the cache is
+        // truncated.
+        let buffer = self
+            .data
+            .get(offset..offset + self.header_size.header_len() + data_len)
+            .ok_or(SectorError::LengthMismatch {
+                expected: self.archive.length,
+                actual: self.archive.length - self.remaining,
+            })?;
+        let sector = Sector::new(buffer, self.header_size);
+
+        let (archive_id, index_id) = (self.archive.id, self.archive.index_id);
+        if sector.header.loops_back(archive_id, self.chunk, index_id) {
+            return Err(SectorError::CyclicChain { sector: self.next });
+        }
+        sector.header.validate(archive_id, self.chunk, index_id)?;
+
+        self.next = sector.header.next;
+        self.chunk += 1;
+        self.remaining -= data_len;
+        self.block = sector.data_block;
+
+        Ok(())
+    }
+}
+
+impl Read for ArchiveReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.block.is_empty() {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+            self.next_block()?;
+        }
+
+        let len = buf.len().min(self.block.len());
+        buf[..len].copy_from_slice(&self.block[..len]);
+        self.block = &self.block[len..];
+
+        Ok(len)
+    }
+}
+
+/// Streams archive data from either an in-memory overlay or its sector chain.
+#[derive(Debug)]
+pub(crate) enum Reader<'a> {
+    Overlay(&'a [u8]),
+    Archive(ArchiveReader<'a>),
+}
+
+impl Read for Reader<'_> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Overlay(data) => data.read(buf),
+            Self::Archive(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Archive data that is either borrowed from the data file or copied out of it.
+///
+/// Returned by [`read_borrowed`](crate::Cache::read_borrowed).
+#[derive(Debug)]
+pub enum ReadResult<'a> {
+    /// The archive fits in a single sector so its data is borrowed without copying.
+    Borrowed(&'a [u8]),
+    /// The archive spans multiple sectors so its data had to be copied into a buffer.
+    Owned(Buffer<Encoded>),
+}
+
+impl ReadResult<'_> {
+    /// Returns `true` if the archive data was borrowed without copying.
+    #[inline]
+    pub const fn is_borrowed(&self) -> bool {
+        matches!(self, Self::Borrowed(_))
+    }
+
+    /// Converts the result into an owned buffer, copying the data if it was borrowed.
+    pub fn into_owned(self) -> Buffer<Encoded> {
+        match self {
+            Self::Borrowed(data) => Buffer::from(data.to_vec()),
+            Self::Owned(buffer) => buffer,
+        }
+    }
+}
+
+impl std::ops::Deref for ReadResult<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(data) => data,
+            Self::Owned(buffer) => buffer.as_slice(),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Statistics about the contents of the cache.
+//!
+//! # Example
+//!
+//! ```
+//! # use rscache::Cache;
+//! # fn main() -> Result<(), rscache::Error> {
+//! # let cache = Cache::new("./data/osrs_cache")?;
+//! let stats = cache.stats()?;
+//!
+//! for index in stats.iter() {
+//!     println!(
+//!         "index {}: {} archives, {} bytes",
+//!         index.index_id, index.archive_count, index.total_bytes
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{io::Read, slice::Iter};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{codec::Compression, sector::SectorHeaderSize, Cache};
+
+/// How many archives of an index use each compression type.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct CompressionStats {
+    pub none: usize,
+    pub bzip2: usize,
+    pub gzip: usize,
+    pub lzma: usize,
+    pub deflate: usize,
+    /// Archives that are empty or have an unknown compression type.
+    pub unknown: usize,
+}
+
+/// Statistics for a single index.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct IndexStats {
+    pub index_id: u8,
+    pub archive_count: usize,
+    /// Total size of all encoded archives, sector headers excluded.
+    pub total_bytes: usize,
+    /// Total amount of sectors the archives occupy.
+    pub sector_count: usize,
+    /// Unused bytes at the end of the last sector of every archive.
+    pub wasted_bytes: usize,
+    pub compression: CompressionStats,
+}
+
+/// Statistics for every index in the cache, see [`Cache::stats`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct CacheStats {
+    indices: Vec<IndexStats>,
+}
+
+impl CacheStats {
+    /// Returns the statistics of the given index, if it exists.
+    pub fn index(&self, index_id: u8) -> Option<&IndexStats> {
+        self.indices.iter().find(|stats| stats.index_id == index_id)
+    }
+
+    /// Total size of all encoded archives in the cache.
+    pub fn total_bytes(&self) -> usize {
+        self.indices.iter().map(|stats| stats.total_bytes).sum()
+    }
+
+    /// Total amount of unused bytes at the end of archive sectors.
+    pub fn wasted_bytes(&self) -> usize {
+        self.indices.iter().map(|stats| stats.wasted_bytes).sum()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, IndexStats> {
+        self.indices.iter()
+    }
+}
+
+impl IntoIterator for CacheStats {
+    type Item = IndexStats;
+    type IntoIter = std::vec::IntoIter<IndexStats>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.indices.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CacheStats {
+    type Item = &'a IndexStats;
+    type IntoIter = Iter<'a, IndexStats>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.indices.iter()
+    }
+}
+
+impl Cache {
+    /// Gathers statistics for every index in the cache.
+    ///
+    /// Only the first sector of every archive is read to determine its
+    /// compression type, the sizes are taken from the index files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the first sector of an archive can't be read.
+    pub fn stats(&self) -> crate::Result<CacheStats> {
+        let mut indices = Vec::new();
+
+        for index_id in 0..=u8::MAX {
+            let index = match self.indices.get(&index_id) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let mut stats = IndexStats {
+                index_id,
+                ..IndexStats::default()
+            };
+
+            for archive in index.archive_refs.values() {
+                let data_len = SectorHeaderSize::from_archive_id(archive.id).data_len();
+                let sectors = archive.length.div_ceil(data_len);
+
+                stats.archive_count += 1;
+                stats.total_bytes += archive.length;
+                stats.sector_count += sectors;
+                stats.wasted_bytes += sectors * data_len - archive.length;
+
+                if archive.length == 0 {
+                    stats.compression.unknown += 1;
+                    continue;
+                }
+
+                let mut compression = [0; 1];
+                self.archive_reader(archive).read_exact(&mut compression)?;
+                match Compression::try_from(compression[0]) {
+                    Ok(Compression::None) => stats.compression.none += 1,
+                    Ok(Compression::Bzip2) => stats.compression.bzip2 += 1,
+                    Ok(Compression::Gzip) => stats.compression.gzip += 1,
+                    Ok(Compression::Lzma) => stats.compression.lzma += 1,
+                    Ok(Compression::Deflate) => stats.compression.deflate += 1,
+                    Err(_) => stats.compression.unknown += 1,
+                }
+            }
+
+            indices.push(stats);
+        }
+
+        Ok(CacheStats { indices })
+    }
+}
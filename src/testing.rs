@@ -0,0 +1,247 @@
+//! Synthesizes tiny caches for tests.
+//!
+//! The caches are valid dat2/idx caches with reference tables, written to a
+//! directory in [`std::env::temp_dir`] which is removed again when the
+//! [`TestCache`] is dropped. Crates that build on this one can run their tests
+//! against them instead of shipping a full cache, by enabling the `testing`
+//! feature in their dev-dependencies.
+//!
+//! # Example
+//!
+//! ```
+//! use rscache::testing::TestCache;
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! let test_cache = TestCache::builder()
+//!     .archive(0, 0, b"hello".to_vec())
+//!     .group(2, 10, [vec![1, 2], vec![3]])
+//!     .build()?;
+//!
+//! let cache = test_cache.open()?;
+//! assert_eq!(cache.read_decoded(0, 0)?.as_slice(), b"hello");
+//! assert_eq!(cache.index_metadata(2)?.get(10).unwrap().entry_count, 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use runefs::REFERENCE_TABLE_ID;
+
+use crate::{
+    archive::ArchiveFileGroup,
+    codec::{Compression, EncodeOptions, Header},
+    definition::{osrs::ItemDefinition, ArchiveFileData},
+    metadata::{ArchiveMetadata, IndexMetadata, FLAG_LENGTHS},
+    writer::CacheFiles,
+    Cache,
+};
+
+/// Version of the reference tables and the trailer of every archive.
+const VERSION: u16 = 1;
+
+/// A cache in a temporary directory, which is removed on drop.
+///
+/// Drop every [`Cache`] that was opened from it first, the files can't be
+/// removed on Windows while they are mapped.
+#[derive(Debug)]
+pub struct TestCache {
+    path: PathBuf,
+}
+
+impl TestCache {
+    /// Creates a [`TestCacheBuilder`] without any archives.
+    #[inline]
+    pub fn builder() -> TestCacheBuilder {
+        TestCacheBuilder::default()
+    }
+
+    /// Builds a cache with a couple of indices and a handful of archives.
+    ///
+    /// Index 0 has a single file archive 0 and a group archive 1 with three
+    /// files, index 1 has an archive that spans two sectors and index 2 has
+    /// the item archive 10 with two items, so an
+    /// [`ItemLoader`](crate::loader::osrs::ItemLoader) can be created for it.
+    ///
+    /// # Errors
+    ///
+    /// See [`TestCacheBuilder::build`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::{loader::osrs::ItemLoader, testing::TestCache};
+    ///
+    /// # fn main() -> Result<(), rscache::Error> {
+    /// let test_cache = TestCache::tiny()?;
+    /// let cache = test_cache.open()?;
+    ///
+    /// let item_loader = ItemLoader::new(&cache)?;
+    /// assert_eq!(item_loader.load(1).unwrap().name, "Bronze dagger");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tiny() -> crate::Result<Self> {
+        let items = ["Dwarf remains", "Bronze dagger"]
+            .iter()
+            .enumerate()
+            .map(|(id, name)| {
+                let item = ItemDefinition {
+                    id: id as u32,
+                    name: name.to_string(),
+                    ..ItemDefinition::default()
+                };
+
+                item.encode()
+            });
+
+        Self::builder()
+            .archive(0, 0, b"rscache".to_vec())
+            .group(0, 1, [vec![1], vec![2, 3], vec![4, 5, 6]])
+            .archive(1, 0, vec![0; 1024])
+            .group(2, 10, items)
+            .build()
+    }
+
+    /// The directory the cache is in.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the cache, see [`Cache::new`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Cache::new`].
+    pub fn open(&self) -> crate::Result<Cache> {
+        Cache::new(&self.path)
+    }
+}
+
+impl Drop for TestCache {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Collects the archives of a [`TestCache`].
+///
+/// Every archive is encoded with the same compression and gets version 1.
+/// The files of groups get the ids `0..n`.
+#[derive(Clone, Debug, Default)]
+pub struct TestCacheBuilder {
+    indices: BTreeMap<u8, BTreeMap<u32, Vec<Vec<u8>>>>,
+    compression: Compression,
+}
+
+impl TestCacheBuilder {
+    /// Adds an archive with a single file, replacing the archive if it was
+    /// already added.
+    pub fn archive(mut self, index_id: u8, archive_id: u32, data: Vec<u8>) -> Self {
+        self.indices
+            .entry(index_id)
+            .or_default()
+            .insert(archive_id, vec![data]);
+        self
+    }
+
+    /// Adds an archive with multiple files, replacing the archive if it was
+    /// already added.
+    pub fn group<I>(mut self, index_id: u8, archive_id: u32, files: I) -> Self
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        self.indices
+            .entry(index_id)
+            .or_default()
+            .insert(archive_id, files.into_iter().collect());
+        self
+    }
+
+    /// Sets the compression of the archives and reference tables, defaults to
+    /// [`Compression::None`].
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Writes the cache to a new temporary directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encoding an archive or writing the files fails.
+    pub fn build(self) -> crate::Result<TestCache> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "rscache-test-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let test_cache = TestCache { path };
+        self.build_in(test_cache.path())?;
+
+        Ok(test_cache)
+    }
+
+    /// Writes the cache to `path`, which is created if it doesn't exist.
+    ///
+    /// Unlike [`build`](TestCacheBuilder::build) the cache is not removed
+    /// afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encoding an archive or writing the files fails.
+    pub fn build_in<P: AsRef<Path>>(self, path: P) -> crate::Result<()> {
+        let options = EncodeOptions::new(self.compression);
+
+        let mut files = CacheFiles::default();
+        for (index_id, archives) in self.indices {
+            let mut metadata = Vec::with_capacity(archives.len());
+            for (archive_id, data) in archives {
+                let entry_count = data.len();
+                let group = data
+                    .into_iter()
+                    .enumerate()
+                    .map(|(id, data)| ArchiveFileData {
+                        id: id as u32,
+                        data,
+                    })
+                    .collect();
+                let decoded = ArchiveFileGroup::new(group).encode();
+                let buffer = options.version(VERSION).encode(&decoded)?;
+                let header = Header::parse(buffer.as_slice())?;
+
+                metadata.push(ArchiveMetadata {
+                    id: archive_id,
+                    crc: crc32fast::hash(&buffer[..header.encoded_len()]),
+                    compressed_len: Some(header.len as u32),
+                    decompressed_len: Some(decoded.len() as u32),
+                    version: VERSION as u32,
+                    entry_count,
+                    valid_ids: (0..entry_count as u32).collect(),
+                    ..ArchiveMetadata::default()
+                });
+                files.insert(index_id, archive_id, &buffer)?;
+            }
+
+            // Ids that don't fit in two bytes need the smart ids of protocol 7.
+            let protocol = match metadata.last() {
+                Some(archive) if archive.id > u16::MAX as u32 => 7,
+                _ => 6,
+            };
+            let table = IndexMetadata::new(protocol, VERSION as u32, FLAG_LENGTHS, metadata);
+            let table = options.encode(&table.encode())?;
+            files.insert(REFERENCE_TABLE_ID, index_id as u32, &table)?;
+        }
+
+        files.write(path.as_ref())
+    }
+}
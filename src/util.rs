@@ -1,9 +1,13 @@
 //! Helpful utility functions, macros and structs.
 
+pub mod base37;
+pub mod charset;
+pub mod coords;
 #[allow(unused_assignments)]
 mod huffman;
 #[allow(clippy::many_single_char_names, clippy::too_many_lines)]
 mod isaac_rand;
+pub mod xtea;
 
 pub use huffman::Huffman;
 pub use isaac_rand::IsaacRand;
@@ -13,41 +17,102 @@ use std::{
     io::{self, BufReader},
 };
 
-use crate::extension::ReadExt;
+use crate::extension::{ReadExt, WriteExt};
 
 macro_rules! impl_osrs_loader {
+    (@fetch_map $def:ty, $cache:ident, $revision:ident, $idx_id:expr, $arc_id:expr) => {
+        <$def>::fetch_from_archive_with_revision($cache, $idx_id, $arc_id, $revision)
+    };
+    (@fetch_map $def:ty, $cache:ident, $revision:ident, $idx_id:expr) => {
+        <$def>::fetch_from_index_with_revision($cache, $idx_id, $revision)
+    };
+    (@fetch_one $cache:ident, $id:ident, $revision:ident, $idx_id:expr, $arc_id:expr) => {
+        crate::definition::osrs::fetch_file($cache, $idx_id, $arc_id, $id, $revision)
+    };
+    (@fetch_one $cache:ident, $id:ident, $revision:ident, $idx_id:expr) => {
+        crate::definition::osrs::fetch_archive($cache, $idx_id, $id, $revision)
+    };
     ($ldr:ident, $def:ty, index_id: $idx_id:expr $(, archive_id: $arc_id:expr)?) => {
         impl $ldr {
-            #[allow(unreachable_code)]
             pub fn new(cache: &Cache) -> crate::Result<Self> {
-                $(
-                    let map = <$def>::fetch_from_archive(cache, $idx_id, $arc_id)?;
-
-                    return Ok(Self(map));
-                )?
+                Self::with_revision(cache, crate::definition::Revision::default())
+            }
 
-                let map = <$def>::fetch_from_index(cache, $idx_id)?;
+            /// Loads the definitions the way the given client revision wrote
+            /// them, see [`Revision`](crate::definition::Revision).
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if reading, decoding or parsing a definition
+            /// fails.
+            pub fn with_revision(
+                cache: &Cache,
+                revision: crate::definition::Revision,
+            ) -> crate::Result<Self> {
+                let map = impl_osrs_loader!(
+                    @fetch_map $def, cache, revision, $idx_id $(, $arc_id)?
+                )?;
 
-                Ok(Self(map))
+                Ok(Self(map.into_iter().collect()))
             }
 
-            pub fn load(&self, id: u16) -> Option<&$def> {
+            pub fn load(&self, id: u32) -> Option<&$def> {
                 self.0.get(&id)
             }
         }
 
-        impl_iter_for_loader!($ldr, u16, $def);
+        impl $def {
+            /// Reads and decodes a single definition without building a loader,
+            /// or returns `None` if there is no definition with the id.
+            ///
+            /// Only the archive the definition is in is read, and only the
+            /// definition itself is decoded. When fetching many definitions a
+            /// loader is faster.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if reading, decoding or parsing the definition
+            /// fails.
+            pub fn fetch(cache: &Cache, id: u32) -> crate::Result<Option<Self>> {
+                Self::fetch_with_revision(cache, id, crate::definition::Revision::default())
+            }
+
+            /// Like [`fetch`](Self::fetch), but decodes the definition the way
+            /// the given client revision wrote it.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if reading, decoding or parsing the definition
+            /// fails.
+            pub fn fetch_with_revision(
+                cache: &Cache,
+                id: u32,
+                revision: crate::definition::Revision,
+            ) -> crate::Result<Option<Self>> {
+                impl_osrs_loader!(@fetch_one cache, id, revision, $idx_id $(, $arc_id)?)
+            }
+        }
+
+        $(impl_group_constructors!($ldr, $def, $idx_id, $arc_id);)?
+        impl_loader_trait!($ldr, u32, $def);
+        impl_iter_for_loader!($ldr, u32, $def);
     };
 }
 
 #[cfg(feature = "rs3")]
 macro_rules! impl_rs3_loader {
-    ($ldr:ident, $def:ty, index_id: $idx_id:expr) => {
+    (@fetch_map $def:ty, $cache:ident, $idx_id:expr, $arc_id:expr) => {
+        <$def>::fetch_from_archive($cache, $idx_id, $arc_id)
+    };
+    (@fetch_map $def:ty, $cache:ident, $idx_id:expr) => {
+        <$def>::fetch_from_index($cache, $idx_id)
+    };
+    ($ldr:ident, $def:ty, index_id: $idx_id:expr $(, archive_id: $arc_id:expr)?) => {
         impl $ldr {
             pub fn new(cache: &Cache) -> crate::Result<Self> {
-                let map = <$def>::fetch_from_index(cache, $idx_id)?;
+                let map = impl_rs3_loader!(@fetch_map $def, cache, $idx_id $(, $arc_id)?)?;
 
-                Ok(Self(map))
+                Ok(Self(map.into_iter().collect()))
             }
 
             pub fn load(&self, id: u32) -> Option<&$def> {
@@ -55,27 +120,98 @@ macro_rules! impl_rs3_loader {
             }
         }
 
+        $(impl_group_constructors!($ldr, $def, $idx_id, $arc_id);)?
+        impl_loader_trait!($ldr, u32, $def);
         impl_iter_for_loader!($ldr, u32, $def);
     };
 }
 
+macro_rules! impl_group_constructors {
+    ($ldr:ident, $def:ty, $idx_id:expr, $arc_id:expr) => {
+        impl $ldr {
+            /// Decodes the definitions from the files of their archive, which
+            /// can be obtained without a [`Cache`], e.g. from a JS5 download or
+            /// a flat file export.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if parsing a definition fails.
+            pub fn from_group(group: crate::archive::ArchiveFileGroup) -> crate::Result<Self> {
+                let ctx = crate::definition::DecodeContext::new($idx_id, $arc_id);
+
+                group
+                    .files()
+                    .iter()
+                    .map(|file| {
+                        let definition =
+                            <$def as crate::definition::Definition>::new(file.id, file, &ctx)?;
+
+                        Ok((file.id, definition))
+                    })
+                    .collect::<crate::Result<_>>()
+                    .map(Self)
+            }
+
+            /// Decodes the definitions from their decoded archive buffer, see
+            /// [`from_group`](Self::from_group).
+            ///
+            /// `entry_count` is the amount of files in the archive, as stored in
+            /// its reference table.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the archive can't be split into its files or
+            /// if parsing a definition fails.
+            pub fn from_bytes(buffer: &[u8], entry_count: usize) -> crate::Result<Self> {
+                let group = crate::archive::ArchiveFileGroup::try_from_buffer(buffer, entry_count)?;
+
+                Self::from_group(group)
+            }
+        }
+    };
+}
+
+macro_rules! impl_loader_trait {
+    ($ldr:ident, $id:ty, $def:ty) => {
+        impl crate::loader::Loader for $ldr {
+            type Id = $id;
+            type Definition = $def;
+
+            #[inline]
+            fn new(cache: &Cache) -> crate::Result<Self> {
+                Self::new(cache)
+            }
+
+            #[inline]
+            fn load(&self, id: $id) -> Option<&$def> {
+                self.load(id)
+            }
+
+            #[inline]
+            fn iter(&self) -> impl Iterator<Item = (&$id, &$def)> {
+                self.0.iter()
+            }
+        }
+    };
+}
+
 macro_rules! impl_iter_for_loader {
     ($ldr:ident, $id:ty, $def:ty) => {
         impl $ldr {
             #[inline]
-            pub fn iter(&self) -> hash_map::Iter<'_, $id, $def> {
+            pub fn iter(&self) -> btree_map::Iter<'_, $id, $def> {
                 self.0.iter()
             }
 
             #[inline]
-            pub fn iter_mut(&mut self) -> hash_map::IterMut<'_, $id, $def> {
+            pub fn iter_mut(&mut self) -> btree_map::IterMut<'_, $id, $def> {
                 self.0.iter_mut()
             }
         }
 
         impl IntoIterator for $ldr {
             type Item = ($id, $def);
-            type IntoIter = hash_map::IntoIter<$id, $def>;
+            type IntoIter = btree_map::IntoIter<$id, $def>;
 
             #[inline]
             fn into_iter(self) -> Self::IntoIter {
@@ -85,7 +221,7 @@ macro_rules! impl_iter_for_loader {
 
         impl<'a> IntoIterator for &'a $ldr {
             type Item = (&'a $id, &'a $def);
-            type IntoIter = hash_map::Iter<'a, $id, $def>;
+            type IntoIter = btree_map::Iter<'a, $id, $def>;
             #[inline]
             fn into_iter(self) -> Self::IntoIter {
                 self.0.iter()
@@ -94,7 +230,7 @@ macro_rules! impl_iter_for_loader {
 
         impl<'a> IntoIterator for &'a mut $ldr {
             type Item = (&'a $id, &'a mut $def);
-            type IntoIter = hash_map::IterMut<'a, $id, $def>;
+            type IntoIter = btree_map::IterMut<'a, $id, $def>;
 
             #[inline]
             fn into_iter(self) -> Self::IntoIter {
@@ -106,12 +242,77 @@ macro_rules! impl_iter_for_loader {
 
 /// djd2 module for string hashing
 pub mod djd2 {
+    use std::collections::HashMap;
 
-    /// Hashes the string
+    /// Reverse lookup table from djd2 hashes back to the names they were made from.
     ///
-    /// # Errors
+    /// Archives only store the hash of their name, a dictionary of known names
+    /// makes it possible to show human-readable archive listings.
     ///
-    /// Can panic if `nth(n)` returns `None` if n >= strings iter length.
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::util::djd2::Dictionary;
+    ///
+    /// let dictionary: Dictionary = ["huffman", "m50_50"].into_iter().collect();
+    /// assert_eq!(dictionary.resolve(1258058669), Some("huffman"));
+    /// ```
+    #[derive(Clone, Eq, PartialEq, Debug, Default)]
+    pub struct Dictionary(HashMap<i32, String>);
+
+    impl Dictionary {
+        #[allow(missing_docs)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Hashes the name and stores it, returning its hash.
+        pub fn insert<T: Into<String>>(&mut self, name: T) -> i32 {
+            let name = name.into();
+            let hash = self::hash(&name);
+            self.0.insert(hash, name);
+
+            hash
+        }
+
+        /// Returns the name that produced the given hash, if it is known.
+        pub fn resolve(&self, hash: i32) -> Option<&str> {
+            self.0.get(&hash).map(String::as_str)
+        }
+
+        #[allow(missing_docs)]
+        #[inline]
+        pub fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        #[allow(missing_docs)]
+        #[inline]
+        pub fn is_empty(&self) -> bool {
+            self.0.is_empty()
+        }
+    }
+
+    impl<T: Into<String>> Extend<T> for Dictionary {
+        fn extend<I: IntoIterator<Item = T>>(&mut self, names: I) {
+            for name in names {
+                self.insert(name);
+            }
+        }
+    }
+
+    impl<T: Into<String>> FromIterator<T> for Dictionary {
+        fn from_iter<I: IntoIterator<Item = T>>(names: I) -> Self {
+            let mut dictionary = Self::new();
+            dictionary.extend(names);
+
+            dictionary
+        }
+    }
+
+
+    /// Hashes the string like the client does, the characters are encoded with
+    /// [`charset`](super::charset) first.
     ///
     /// # Examples
     ///
@@ -120,17 +321,27 @@ pub mod djd2 {
     /// assert_eq!(hash, 1258058669);
     /// ```
     pub fn hash<T: AsRef<str>>(string: T) -> i32 {
-        let string = string.as_ref();
-        let mut hash = 0;
+        hash_bytes(&super::charset::encode(string.as_ref()))
+    }
 
-        for index in 0..string.len() {
-            hash =
-                string.chars().nth(index).unwrap_or_else(|| {
-                    panic!("index {} not valid in str len {}", index, string.len())
-                }) as i32
-                    + ((hash << 5) - hash);
-        }
-        hash
+    /// Hashes the bytes of an already encoded string.
+    ///
+    /// The bytes are signed in the client, so bytes of `0x80` and up lower
+    /// the hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rscache::util::djd2;
+    ///
+    /// assert_eq!(djd2::hash_bytes(b"huffman"), djd2::hash("huffman"));
+    /// ```
+    pub fn hash_bytes(bytes: &[u8]) -> i32 {
+        bytes.iter().fold(0_i32, |hash, &byte| {
+            (hash << 5)
+                .wrapping_sub(hash)
+                .wrapping_add(i32::from(byte as i8))
+        })
     }
 }
 
@@ -157,3 +368,26 @@ pub fn read_parameters(reader: &mut BufReader<&[u8]>) -> io::Result<HashMap<u32,
 
     Ok(map)
 }
+
+/// Encodes parameters in the format [`read_parameters`] decodes.
+///
+/// Values that are the string form of an `i32` are written as integers, all
+/// other values as strings.
+pub fn write_parameters(buffer: &mut Vec<u8>, params: &HashMap<u32, String>) {
+    let mut params: Vec<_> = params.iter().collect();
+    params.sort_unstable_by_key(|&(&key, _)| key);
+
+    buffer.write_u8(params.len() as u8);
+    for (&key, value) in params {
+        let int = value
+            .parse::<i32>()
+            .ok()
+            .filter(|int| int.to_string() == *value);
+        buffer.write_u8(int.is_none() as u8);
+        buffer.write_u24(key);
+        match int {
+            Some(int) => buffer.write_i32(int),
+            None => buffer.write_string(value),
+        }
+    }
+}
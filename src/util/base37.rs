@@ -0,0 +1,63 @@
+//! Base37 encoding of usernames.
+//!
+//! The client packs usernames of up to 12 characters into a single number,
+//! used for friend and ignore lists and during login.
+//!
+//! # Examples
+//!
+//! ```
+//! use rscache::util::base37;
+//!
+//! let encoded = base37::encode("Zezima");
+//! assert_eq!(encoded, 1_813_643_468);
+//! assert_eq!(base37::decode(encoded).as_deref(), Some("zezima"));
+//! ```
+
+/// The characters of the base, characters that aren't in here are encoded as
+/// `_`.
+const CHARS: [u8; 37] = *b"_abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// The largest value that fits 12 characters, exclusive.
+const MAX: u64 = 0x5B5B_57F8_A98A_5DD1;
+
+/// Encodes the first 12 characters of a name, ignoring case.
+///
+/// Trailing underscores and other characters that aren't in the base are
+/// dropped, like in the client.
+pub fn encode<T: AsRef<str>>(name: T) -> u64 {
+    let mut value = 0_u64;
+    for c in name.as_ref().chars().take(12) {
+        value *= 37;
+        value += match c {
+            'A'..='Z' => c as u64 - 'A' as u64 + 1,
+            'a'..='z' => c as u64 - 'a' as u64 + 1,
+            '0'..='9' => c as u64 - '0' as u64 + 27,
+            _ => 0,
+        };
+    }
+
+    while value != 0 && value.is_multiple_of(37) {
+        value /= 37;
+    }
+
+    value
+}
+
+/// Decodes a name, in lowercase with `_` for the characters that weren't in
+/// the base.
+///
+/// Returns `None` if the value can't be produced by [`encode`].
+pub fn decode(mut value: u64) -> Option<String> {
+    if value == 0 || value >= MAX || value.is_multiple_of(37) {
+        return None;
+    }
+
+    let mut name = Vec::with_capacity(12);
+    while value != 0 {
+        name.push(CHARS[(value % 37) as usize]);
+        value /= 37;
+    }
+    name.reverse();
+
+    String::from_utf8(name).ok()
+}
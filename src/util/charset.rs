@@ -0,0 +1,84 @@
+//! The charset of the strings in the cache.
+//!
+//! Strings are encoded with cp1252, a superset of Latin-1 that uses the bytes
+//! `0x80..0xA0` for characters like `€` and `™`. Characters that can't be
+//! encoded become `?`, just like in the client.
+//!
+//! # Examples
+//!
+//! ```
+//! use rscache::util::charset;
+//!
+//! let bytes = charset::encode("Café™");
+//! assert_eq!(bytes, [b'C', b'a', b'f', 0xE9, 0x99]);
+//! assert_eq!(charset::decode(&bytes), "Café™");
+//! ```
+
+/// The characters of the bytes `0x80..0xA0`, `None` for the bytes that cp1252
+/// leaves undefined.
+const EXTENDED: [Option<char>; 32] = [
+    Some('\u{20AC}'),
+    None,
+    Some('\u{201A}'),
+    Some('\u{0192}'),
+    Some('\u{201E}'),
+    Some('\u{2026}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{02C6}'),
+    Some('\u{2030}'),
+    Some('\u{0160}'),
+    Some('\u{2039}'),
+    Some('\u{0152}'),
+    None,
+    Some('\u{017D}'),
+    None,
+    None,
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2022}'),
+    Some('\u{2013}'),
+    Some('\u{2014}'),
+    Some('\u{02DC}'),
+    Some('\u{2122}'),
+    Some('\u{0161}'),
+    Some('\u{203A}'),
+    Some('\u{0153}'),
+    None,
+    Some('\u{017E}'),
+    Some('\u{0178}'),
+];
+
+/// Decodes a single byte, undefined bytes become `?`.
+pub const fn decode_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => match EXTENDED[byte as usize - 0x80] {
+            Some(c) => c,
+            None => '?',
+        },
+        _ => byte as char,
+    }
+}
+
+/// Encodes a single character, characters outside of the charset become `?`.
+pub fn encode_char(c: char) -> u8 {
+    match c as u32 {
+        0x01..=0x7F | 0xA0..=0xFF => c as u8,
+        _ => EXTENDED
+            .iter()
+            .position(|&extended| extended == Some(c))
+            .map_or(b'?', |index| 0x80 + index as u8),
+    }
+}
+
+/// Decodes a string, the bytes should not include the terminating `0`.
+pub fn decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| decode_char(byte)).collect()
+}
+
+/// Encodes a string without a terminating `0`.
+pub fn encode(string: &str) -> Vec<u8> {
+    string.chars().map(encode_char).collect()
+}
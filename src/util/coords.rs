@@ -0,0 +1,136 @@
+//! Conversions between region ids and tile coordinates.
+//!
+//! The world is split into regions of 64 by 64 tiles, a region id packs the
+//! region coordinates as `x << 8 | y`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rscache::util::coords::{Coordinate, RegionId};
+//!
+//! let lumbridge = RegionId::from(12850);
+//! assert_eq!((lumbridge.x(), lumbridge.y()), (50, 50));
+//! assert_eq!(lumbridge.base(), Coordinate::new(3200, 3200, 0));
+//!
+//! let tile = Coordinate::new(3222, 3218, 0);
+//! assert_eq!(tile.region(), lumbridge);
+//! assert_eq!(tile.local(), (22, 18));
+//! assert_eq!(Coordinate::from_local(lumbridge, 22, 18, 0), tile);
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The width and height of a region in tiles.
+pub const REGION_SIZE: u16 = 64;
+
+/// The id of a region, as used by the [`MapLoader`](crate::loader::osrs::MapLoader)
+/// and [`LocationLoader`](crate::loader::osrs::LocationLoader).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct RegionId(pub u16);
+
+impl RegionId {
+    /// Creates the id of the region at the given region coordinates.
+    #[inline]
+    pub const fn new(x: u8, y: u8) -> Self {
+        Self(((x as u16) << 8) | y as u16)
+    }
+
+    /// The x coordinate of the region, in regions.
+    #[inline]
+    pub const fn x(self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    /// The y coordinate of the region, in regions.
+    #[inline]
+    pub const fn y(self) -> u8 {
+        self.0 as u8
+    }
+
+    /// The south-west tile of the region on plane 0.
+    #[inline]
+    pub const fn base(self) -> Coordinate {
+        Coordinate::new(
+            self.x() as u16 * REGION_SIZE,
+            self.y() as u16 * REGION_SIZE,
+            0,
+        )
+    }
+
+    /// Returns `true` if the tile lies within this region.
+    #[inline]
+    pub const fn contains(self, coord: Coordinate) -> bool {
+        coord.region().0 == self.0
+    }
+}
+
+impl From<u16> for RegionId {
+    #[inline]
+    fn from(id: u16) -> Self {
+        Self(id)
+    }
+}
+
+impl From<RegionId> for u16 {
+    #[inline]
+    fn from(region: RegionId) -> Self {
+        region.0
+    }
+}
+
+/// An absolute tile coordinate.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Coordinate {
+    pub x: u16,
+    pub y: u16,
+    /// The height level, `0..4`.
+    pub plane: u8,
+}
+
+impl Coordinate {
+    #[inline]
+    pub const fn new(x: u16, y: u16, plane: u8) -> Self {
+        Self { x, y, plane }
+    }
+
+    /// Converts a tile within a region to an absolute coordinate.
+    #[inline]
+    pub const fn from_local(region: RegionId, x: u8, y: u8, plane: u8) -> Self {
+        let base = region.base();
+
+        Self::new(base.x + x as u16, base.y + y as u16, plane)
+    }
+
+    /// The region the tile lies in.
+    #[inline]
+    pub const fn region(self) -> RegionId {
+        RegionId::new((self.x / REGION_SIZE) as u8, (self.y / REGION_SIZE) as u8)
+    }
+
+    /// The `(x, y)` coordinates of the tile within its region, `0..64`.
+    #[inline]
+    pub const fn local(self) -> (u8, u8) {
+        ((self.x % REGION_SIZE) as u8, (self.y % REGION_SIZE) as u8)
+    }
+
+    /// Packs the coordinate like client scripts do, `plane << 28 | x << 14 | y`.
+    #[inline]
+    pub const fn pack(self) -> u32 {
+        ((self.plane as u32 & 0x3) << 28)
+            | ((self.x as u32 & 0x3FFF) << 14)
+            | (self.y as u32 & 0x3FFF)
+    }
+
+    /// Unpacks a coordinate packed with [`pack`](Coordinate::pack).
+    #[inline]
+    pub const fn unpack(packed: u32) -> Self {
+        Self::new(
+            (packed >> 14 & 0x3FFF) as u16,
+            (packed & 0x3FFF) as u16,
+            (packed >> 28 & 0x3) as u8,
+        )
+    }
+}
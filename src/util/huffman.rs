@@ -1,3 +1,5 @@
+use crate::error::HuffmanError;
+
 /// Decompresses chat messages.
 /// 
 /// # Examples
@@ -14,7 +16,7 @@
 /// let compressed_msg = &[174, 128, 35, 32, 208, 96];
 /// let decompressed_len = 8; // client will include this in the chat packet.
 ///
-/// let decompressed_msg = huffman.decompress(compressed_msg, decompressed_len);
+/// let decompressed_msg = huffman.decompress(compressed_msg, decompressed_len)?;
 ///
 /// if let Ok(msg) = String::from_utf8(decompressed_msg) {
 ///     assert_eq!(msg, "rs-cache");
@@ -88,7 +90,7 @@ impl Huffman {
 
                 i_11 = 0;
                 while i_11 < b_6 as i32 {
-                    i_12_1 = ((i32::min_value()) as u32 >> i_11) as i32;
+                    i_12_1 = (i32::MIN as u32 >> i_11) as i32;
                     if i_8 & i_12_1 != 0 {
                         if keys[i_10 as usize] == 0 {
                             keys[i_10 as usize] = i_4;
@@ -125,109 +127,51 @@ impl Huffman {
     /// The buffer is normally an encoded chat message which will be decoded into
     /// the original message. This helps limit chat packet sizes.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the decompressed length == 0
-    pub fn decompress(&self, compressed: &[u8], decompressed_len: usize) -> Vec<u8> {
-        let mut decompressed = vec![0; decompressed_len];
-
-        let i_2 = 0;
-        let mut i_4 = 0;
+    /// Returns a `HuffmanError` if the buffer doesn't contain a valid message of
+    /// `decompressed_len` bytes, which makes it safe to use on untrusted input.
+    pub fn decompress(
+        &self,
+        compressed: &[u8],
+        decompressed_len: usize,
+    ) -> Result<Vec<u8>, HuffmanError> {
+        let mut decompressed = Vec::with_capacity(decompressed_len.min(compressed.len() * 8));
         if decompressed_len == 0 {
-            panic!("Huffman decompressed message length can't be 0.");
+            return Ok(decompressed);
         }
-        let mut i_7 = 0;
-        let mut i_8 = i_2;
-
-        loop {
-            if i_4 >= decompressed_len {
-                break;
-            }
-
-            let b_9 = compressed[i_8 as usize];
-            if b_9 > 127 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
-            }
-
-            let mut i_10 = 0;
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            if b_9 & 0x40 != 0 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
-            }
-
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            if b_9 & 0x20 != 0 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
-            }
-
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            if b_9 & 0x10 != 0 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
-            }
 
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            if b_9 & 0x8 != 0 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
-            }
-
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            if b_9 & 0x4 != 0 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
-            }
-
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            if b_9 & 0x2 != 0 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
-            }
-
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            if b_9 & 0x1 != 0 {
-                i_7 = self.keys[i_7 as usize];
-            } else {
-                i_7 += 1;
+        let mut node = 0;
+        for &byte in compressed {
+            for bit in (0..8).rev() {
+                node = if byte & (1 << bit) != 0 {
+                    let key = self.key(node)?;
+                    usize::try_from(key).map_err(|_| HuffmanError::InvalidKey(node))?
+                } else {
+                    node + 1
+                };
+
+                let key = self.key(node)?;
+                if key < 0 {
+                    decompressed.push((!key) as u8);
+                    if decompressed.len() == decompressed_len {
+                        return Ok(decompressed);
+                    }
+                    node = 0;
+                }
             }
-
-            i_10_keys(&mut i_10, &self.keys, &mut i_7, &mut i_4, &mut decompressed);
-
-            i_8 += 1;
         }
 
-        decompressed
+        Err(HuffmanError::Truncated {
+            expected: decompressed_len,
+            actual: decompressed.len(),
+        })
     }
-}
 
-fn i_10_keys(
-    i_10: &mut i32,
-    keys: &[i32],
-    i_7: &mut i32,
-    i_4: &mut usize,
-    decompressed: &mut [u8],
-) {
-    *i_10 = keys[*i_7 as usize];
-    if *i_10 < 0 {
-        decompressed[*i_4] = (!*i_10) as u8;
-        *i_4 += 1;
-        *i_7 = 0;
+    fn key(&self, node: usize) -> Result<i32, HuffmanError> {
+        self.keys
+            .get(node)
+            .copied()
+            .ok_or(HuffmanError::InvalidKey(node))
     }
 }
@@ -8,7 +8,8 @@ const MASK: u32 = (SIZE as u32 - 1) << 2;
 /// Can be used to encode and decode game packet id's.
 ///
 /// Note: The client will only send one set of keys, the decoder keys.
-/// To get the encoder keys, simply add 50 to every decoder key.
+/// To get the encoder keys, simply add 50 to every decoder key, or use
+/// [`pair_from_client_keys`](IsaacRand::pair_from_client_keys) to create both.
 /// ```
 /// # let xtea_keys: Vec<u32> = vec![0; 4];
 /// let mut isaac_decoder_keys = Vec::with_capacity(4);
@@ -70,6 +71,56 @@ impl IsaacRand {
         isaac.init();
         isaac
     }
+
+    /// Creates the decoder and encoder from the keys the client sends on login.
+    ///
+    /// The decoder is seeded with the keys as-is, the encoder with every key
+    /// incremented by 50.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rscache::util::IsaacRand;
+    /// let client_keys = [0x1234, 0x5678, 0x9abc, 0xdef0];
+    /// let (mut decoder, mut encoder) = IsaacRand::pair_from_client_keys(&client_keys);
+    ///
+    /// let opcode = 42_u8;
+    /// let encoded = opcode.wrapping_add(encoder.next_u8());
+    /// # let _ = (encoded, decoder.next_u8());
+    /// ```
+    pub fn pair_from_client_keys(keys: &[u32; 4]) -> (Self, Self) {
+        let decoder = Self::new(keys);
+        let encoder = Self::new(&keys.map(|key| key.wrapping_add(50)));
+
+        (decoder, encoder)
+    }
+
+    /// Returns the lowest byte of the next value, which is what packet opcodes are offset by.
+    #[inline]
+    pub fn next_u8(&mut self) -> u8 {
+        self.next_u32() as u8
+    }
+
+    /// Returns the next value without advancing the randomizer.
+    pub fn peek(&mut self) -> u32 {
+        self.refill();
+
+        self.rsl[self.count - 1]
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.refill();
+        self.count -= 1;
+
+        self.rsl[self.count]
+    }
+
+    fn refill(&mut self) {
+        if self.count == 0 {
+            self.isaac();
+            self.count = SIZE;
+        }
+    }
     fn init(&mut self) {
         let mut h = GOLDEN_RATIO;
         let mut g = h;
@@ -319,12 +370,6 @@ impl Iterator for IsaacRand {
     type Item = u32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.count == 0 {
-            self.isaac();
-            self.count = SIZE;
-        }
-        self.count -= 1;
-
-        Some(self.rsl[self.count])
+        Some(self.next_u32())
     }
 }
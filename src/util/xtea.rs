@@ -0,0 +1,241 @@
+//! XTEA encryption as it is used by the cache and the login protocol.
+//!
+//! [`decipher`] is re-exported from `rune-fs`. Its `encipher` doesn't reverse
+//! the decipher, so [`encipher`] is implemented here. The helpers below are
+//! built on top of both.
+//!
+//! Data is processed in blocks of 8 bytes, trailing bytes that don't fill a
+//! whole block are left as-is, just like the client does.
+//!
+//! # Examples
+//!
+//! ```
+//! use rscache::util::xtea;
+//!
+//! let keys = [0x1234, 0x5678, 0x9abc, 0xdef0];
+//! let mut data = b"login block data".to_vec();
+//!
+//! xtea::encipher_in_place(&mut data, &keys);
+//! assert_ne!(data, b"login block data");
+//!
+//! xtea::decipher_in_place(&mut data, &keys);
+//! assert_eq!(data, b"login block data");
+//! ```
+
+use std::collections::{hash_map, HashMap};
+#[cfg(feature = "serde")]
+use std::io::Read;
+use std::slice::ChunksExact;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use runefs::xtea::decipher;
+
+/// Size of a single block in bytes.
+pub const BLOCK_SIZE: usize = 8;
+
+const ROUNDS: u32 = 32;
+const RATIO: u32 = 0x9E37_79B9;
+
+/// Enciphers every whole block of `data` in place with the given keys.
+pub fn encipher(data: &mut [u8], keys: &[u32; 4]) {
+    for block in data.chunks_exact_mut(BLOCK_SIZE) {
+        let mut v0 = u32::from_be_bytes([block[0], block[1], block[2], block[3]]);
+        let mut v1 = u32::from_be_bytes([block[4], block[5], block[6], block[7]]);
+        let mut sum = 0_u32;
+        for _ in 0..ROUNDS {
+            v0 = v0.wrapping_add(
+                (((v1 << 4) ^ (v1 >> 5)).wrapping_add(v1))
+                    ^ sum.wrapping_add(keys[(sum & 3) as usize]),
+            );
+            sum = sum.wrapping_add(RATIO);
+            v1 = v1.wrapping_add(
+                (((v0 << 4) ^ (v0 >> 5)).wrapping_add(v0))
+                    ^ sum.wrapping_add(keys[((sum >> 11) & 3) as usize]),
+            );
+        }
+        block[..4].copy_from_slice(&v0.to_be_bytes());
+        block[4..].copy_from_slice(&v1.to_be_bytes());
+    }
+}
+
+/// Enciphers a single block in place.
+pub fn encipher_block(block: &mut [u8; BLOCK_SIZE], keys: &[u32; 4]) {
+    encipher(block, keys);
+}
+
+/// Deciphers a single block in place.
+pub fn decipher_block(block: &mut [u8; BLOCK_SIZE], keys: &[u32; 4]) {
+    decipher(block, keys);
+}
+
+/// Enciphers every whole block of `data` in place.
+pub fn encipher_in_place(data: &mut [u8], keys: &[u32; 4]) {
+    let len = data.len() - data.len() % BLOCK_SIZE;
+    encipher(&mut data[..len], keys);
+}
+
+/// Deciphers every whole block of `data` in place.
+pub fn decipher_in_place(data: &mut [u8], keys: &[u32; 4]) {
+    let len = data.len() - data.len() % BLOCK_SIZE;
+    decipher(&mut data[..len], keys);
+}
+
+/// Lazily deciphers `data` one block at a time, see [`Blocks`].
+pub fn decipher_blocks<'a>(data: &'a [u8], keys: &'a [u32; 4]) -> Blocks<'a> {
+    Blocks {
+        chunks: data.chunks_exact(BLOCK_SIZE),
+        keys,
+        decipher: true,
+    }
+}
+
+/// Lazily enciphers `data` one block at a time, see [`Blocks`].
+pub fn encipher_blocks<'a>(data: &'a [u8], keys: &'a [u32; 4]) -> Blocks<'a> {
+    Blocks {
+        chunks: data.chunks_exact(BLOCK_SIZE),
+        keys,
+        decipher: false,
+    }
+}
+
+/// Iterator over the enciphered or deciphered blocks of a buffer.
+///
+/// Only whole blocks are yielded, the trailing bytes are available through
+/// [`remainder`](Blocks::remainder).
+#[derive(Clone, Debug)]
+pub struct Blocks<'a> {
+    chunks: ChunksExact<'a, u8>,
+    keys: &'a [u32; 4],
+    decipher: bool,
+}
+
+impl<'a> Blocks<'a> {
+    /// The trailing bytes that don't fill a whole block, these are never encrypted.
+    #[inline]
+    pub fn remainder(&self) -> &'a [u8] {
+        self.chunks.remainder()
+    }
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = [u8; BLOCK_SIZE];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block: [u8; BLOCK_SIZE] = self.chunks.next()?.try_into().ok()?;
+        if self.decipher {
+            decipher_block(&mut block, self.keys);
+        } else {
+            encipher_block(&mut block, self.keys);
+        }
+
+        Some(block)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Blocks<'_> {}
+
+/// XTEA keys of every region, used to decrypt location archives.
+///
+/// # Examples
+///
+/// ```
+/// use rscache::util::xtea::XteaKeyStore;
+///
+/// let mut keys = XteaKeyStore::new();
+/// keys.insert(12850, [3030157619, 2364842415, 3297319647, 1973582566]);
+///
+/// assert!(keys.get(12850).is_some());
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct XteaKeyStore(HashMap<u16, [u32; 4]>);
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct KeyEntry {
+    mapsquare: u16,
+    key: [i32; 4],
+}
+
+impl XteaKeyStore {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses keys in the JSON format that is commonly used to distribute them.
+    ///
+    /// The JSON is a list of objects which each have a `mapsquare` (the region id)
+    /// and a `key` of four signed integers, other fields are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reader doesn't contain valid JSON in the expected format.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn from_json<R: Read>(reader: R) -> crate::Result<Self> {
+        let entries: Vec<KeyEntry> = serde_json::from_reader(reader)?;
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| (entry.mapsquare, entry.key.map(|key| key as u32)))
+            .collect())
+    }
+
+    /// Stores the keys of a region, returning the keys it previously had.
+    pub fn insert(&mut self, region_id: u16, keys: [u32; 4]) -> Option<[u32; 4]> {
+        self.0.insert(region_id, keys)
+    }
+
+    #[allow(missing_docs)]
+    pub fn get(&self, region_id: u16) -> Option<&[u32; 4]> {
+        self.0.get(&region_id)
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn iter(&self) -> hash_map::Iter<'_, u16, [u32; 4]> {
+        self.0.iter()
+    }
+}
+
+impl Extend<(u16, [u32; 4])> for XteaKeyStore {
+    fn extend<I: IntoIterator<Item = (u16, [u32; 4])>>(&mut self, keys: I) {
+        self.0.extend(keys);
+    }
+}
+
+impl FromIterator<(u16, [u32; 4])> for XteaKeyStore {
+    fn from_iter<I: IntoIterator<Item = (u16, [u32; 4])>>(keys: I) -> Self {
+        Self(keys.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a XteaKeyStore {
+    type Item = (&'a u16, &'a [u32; 4]);
+    type IntoIter = hash_map::Iter<'a, u16, [u32; 4]>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
@@ -0,0 +1,169 @@
+//! Integrity verification for the whole cache.
+//!
+//! # Example
+//!
+//! ```
+//! # use rscache::Cache;
+//! # fn main() -> Result<(), rscache::Error> {
+//! # let cache = Cache::new("./data/osrs_cache")?;
+//! let report = cache.verify();
+//!
+//! for issue in report.issues() {
+//!     println!("{}/{}: {}", issue.index_id, issue.archive_id, issue.kind);
+//! }
+//! # assert!(report.is_ok());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{error::SectorError, Cache};
+use runefs::{codec::Buffer, IndexMetadata, REFERENCE_TABLE_ID};
+
+/// Describes what is wrong with a single archive.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum IssueKind {
+    /// The reference table lists the archive but the index has no entry for it.
+    Missing,
+    /// The sector chain of the archive is broken.
+    Sector(SectorError),
+    /// The archive data doesn't match the crc stored in the reference table.
+    CrcMismatch { expected: u32, actual: u32 },
+    /// The reference table itself could not be decoded.
+    Corrupt(String),
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "archive is missing"),
+            Self::Sector(err) => write!(f, "{}", err),
+            Self::CrcMismatch { expected, actual } => {
+                write!(f, "expected crc {} but was {}", expected, actual)
+            }
+            Self::Corrupt(reason) => write!(f, "reference table is corrupt: {}", reason),
+        }
+    }
+}
+
+/// A single corrupt or missing archive.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Issue {
+    pub index_id: u8,
+    pub archive_id: u32,
+    pub kind: IssueKind,
+}
+
+/// The outcome of [`Cache::verify`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct VerifyReport {
+    archives_checked: usize,
+    issues: Vec<Issue>,
+}
+
+impl VerifyReport {
+    /// Returns `true` if no issues were found.
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The amount of archives that were checked, including the ones with issues.
+    #[inline]
+    pub const fn archives_checked(&self) -> usize {
+        self.archives_checked
+    }
+
+    #[allow(missing_docs)]
+    #[inline]
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+}
+
+impl Cache {
+    /// Walks every archive of every index and checks it against its reference table.
+    ///
+    /// Every sector header in the archive's chain is validated and the crc of
+    /// the archive data is compared with the crc stored in the reference table.
+    /// Verification doesn't stop at the first issue, all issues are collected
+    /// into the returned [`VerifyReport`].
+    pub fn verify(&self) -> VerifyReport {
+        let mut report = VerifyReport::default();
+
+        for index_id in self.present_indices() {
+            let archive = match self.archive_ref(REFERENCE_TABLE_ID, index_id as u32) {
+                Ok(archive) => archive,
+                Err(_) => continue,
+            };
+
+            report.archives_checked += 1;
+            let metadata = self
+                .archive_reader(archive)
+                .read_to_vec()
+                .map_err(IssueKind::Sector)
+                .and_then(|buffer| {
+                    let buffer = Buffer::from(buffer)
+                        .decode()
+                        .map_err(|err| IssueKind::Corrupt(err.to_string()))?;
+                    IndexMetadata::from_buffer(buffer)
+                        .map_err(|err| IssueKind::Corrupt(err.to_string()))
+                });
+
+            let metadata = match metadata {
+                Ok(metadata) => metadata,
+                Err(kind) => {
+                    report.issues.push(Issue {
+                        index_id: REFERENCE_TABLE_ID,
+                        archive_id: index_id as u32,
+                        kind,
+                    });
+                    continue;
+                }
+            };
+
+            for archive_metadata in &metadata {
+                report.archives_checked += 1;
+                let result =
+                    self.verify_archive(index_id, archive_metadata.id, archive_metadata.crc);
+                if let Err(kind) = result {
+                    report.issues.push(Issue {
+                        index_id,
+                        archive_id: archive_metadata.id,
+                        kind,
+                    });
+                }
+            }
+        }
+
+        report
+    }
+
+    fn verify_archive(&self, index_id: u8, archive_id: u32, crc: u32) -> Result<(), IssueKind> {
+        let archive = self
+            .archive_ref(index_id, archive_id)
+            .map_err(|_| IssueKind::Missing)?;
+        let buffer = self
+            .archive_reader(archive)
+            .read_to_vec()
+            .map_err(IssueKind::Sector)?;
+
+        // The version trailer is not included in the crc.
+        let actual = crc32fast::hash(&buffer[..buffer.len().saturating_sub(2)]);
+        if actual != crc {
+            return Err(IssueKind::CrcMismatch {
+                expected: crc,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
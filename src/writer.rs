@@ -0,0 +1,308 @@
+//! Write support for the dat2/idx file system.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use rscache::Cache;
+//! use rscache::writer::CacheWriter;
+//!
+//! # fn main() -> Result<(), rscache::Error> {
+//! let path = "./data/osrs_cache";
+//! let buffer = Cache::new(path)?.read(2, 10)?;
+//!
+//! let mut writer = CacheWriter::open(path)?;
+//! writer.write(2, 10, &buffer)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use runefs::{
+    codec::{Buffer, Encoded},
+    MAIN_DATA, REFERENCE_TABLE_ID,
+};
+
+use crate::{
+    codec::{EncodedExt, Header},
+    error::{SectorError, WriteError},
+    index::{ARCHIVE_REF_LEN, IDX_PREFIX},
+    metadata::{self, ArchivePatch},
+    sector::{self, sector_offset, SectorHeader, SectorHeaderSize, MAX_SECTOR, SECTOR_SIZE},
+    MUSIC_DATA, MUSIC_INDICES,
+};
+
+/// The largest archive an index entry can point to, its length is stored in
+/// 24 bits.
+const MAX_ARCHIVE_LEN: usize = 0xFF_FFFF;
+
+/// Writes archives into the dat2/idx files of a cache.
+///
+/// Every write appends a new sector chain to the data file, points the idx
+/// entry of the archive to it and updates the crc, version and lengths of the
+/// archive in its reference table. The old chain is left in place and is
+/// simply no longer referenced. Whirlpool digests and the hash flag in
+/// reference tables are not updated.
+///
+/// Archives of the [`MUSIC_INDICES`] are written to [`MUSIC_DATA`] when the
+/// cache has one, just like [`Cache`](crate::Cache) reads them from it.
+///
+/// Do not write to a cache that is mapped by a [`Cache`](crate::Cache), open
+/// a new one after writing instead.
+#[derive(Debug)]
+pub struct CacheWriter {
+    path: PathBuf,
+    data: File,
+    music_data: Option<File>,
+}
+
+impl CacheWriter {
+    /// Opens the data files of the cache in `path` for writing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data files can't be opened.
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut options = OpenOptions::new();
+        options.read(true).write(true);
+
+        let data = options.open(path.join(MAIN_DATA))?;
+        let music_data = match options.open(path.join(MUSIC_DATA)) {
+            Ok(file) => Some(file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path,
+            data,
+            music_data,
+        })
+    }
+
+    /// Writes an encoded archive into the cache.
+    ///
+    /// The buffer is stored as-is, archives outside of the reference table need
+    /// their version trailer. The version in the reference table is taken from
+    /// that trailer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `UnknownArchive` if the archive is not in the reference table of
+    /// its index, new archives can't be added yet, and `MissingVersion` if it
+    /// has no version trailer. Returns `ArchiveTooLarge` or `SectorOverflow` if
+    /// the archive can't be addressed by its index entry.
+    /// I/O errors and errors while decoding the reference table are returned as
+    /// well.
+    pub fn write(&mut self, index_id: u8, archive_id: u32, buffer: &[u8]) -> crate::Result<()> {
+        if index_id != REFERENCE_TABLE_ID {
+            self.patch_reference_table(index_id, archive_id, buffer)?;
+        }
+
+        self.write_archive(index_id, archive_id, buffer)
+    }
+
+    fn patch_reference_table(
+        &mut self,
+        index_id: u8,
+        archive_id: u32,
+        buffer: &[u8],
+    ) -> crate::Result<()> {
+        let table = self.read_archive(REFERENCE_TABLE_ID, index_id as u32)?;
+        let table = patch_reference_table(table, index_id, archive_id, buffer)?;
+
+        self.write_archive(REFERENCE_TABLE_ID, index_id as u32, &table)
+    }
+
+    fn write_archive(&mut self, index_id: u8, archive_id: u32, buffer: &[u8]) -> crate::Result<()> {
+        let data = self.data_file(index_id);
+        let len = data.seek(SeekFrom::End(0))?;
+        // The first sector is never used by the client.
+        let sector = check_entry(len.div_ceil(SECTOR_SIZE as u64).max(1), archive_id, buffer)?;
+
+        let chain = sector::encode_chain(sector, index_id, archive_id, buffer);
+        data.seek(SeekFrom::Start(sector_offset(sector)))?;
+        data.write_all(&chain)?;
+
+        let mut entry = [0; ARCHIVE_REF_LEN];
+        entry[..3].copy_from_slice(&(buffer.len() as u32).to_be_bytes()[1..]);
+        entry[3..].copy_from_slice(&(sector as u32).to_be_bytes()[1..]);
+
+        let mut index = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.index_path(index_id))?;
+        index.seek(SeekFrom::Start(
+            (archive_id as usize * ARCHIVE_REF_LEN) as u64,
+        ))?;
+        index.write_all(&entry)?;
+
+        Ok(())
+    }
+
+    fn read_archive(&mut self, index_id: u8, archive_id: u32) -> crate::Result<Vec<u8>> {
+        let mut entry = [0; ARCHIVE_REF_LEN];
+        let mut index = File::open(self.index_path(index_id))?;
+        index.seek(SeekFrom::Start(
+            (archive_id as usize * ARCHIVE_REF_LEN) as u64,
+        ))?;
+        index.read_exact(&mut entry)?;
+
+        let length = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize;
+        let mut next = u32::from_be_bytes([0, entry[3], entry[4], entry[5]]) as usize;
+
+        let data = self.data_file(index_id);
+        let header_size = SectorHeaderSize::from_archive_id(archive_id);
+        let mut buffer = Vec::with_capacity(length);
+        let mut sector = vec![0; SECTOR_SIZE];
+        let mut chunk = 0;
+        while buffer.len() < length {
+            let data_len = (length - buffer.len()).min(header_size.data_len());
+            let sector = &mut sector[..header_size.header_len() + data_len];
+            data.seek(SeekFrom::Start(sector_offset(next)))?;
+            data.read_exact(sector)?;
+
+            let header = SectorHeader::new(sector, header_size);
+            header.validate(archive_id, chunk, index_id)?;
+            buffer.extend(&sector[header_size.header_len()..]);
+
+            next = header.next;
+            chunk += 1;
+        }
+
+        Ok(buffer)
+    }
+
+    /// The data file the archives of the index are stored in.
+    fn data_file(&mut self, index_id: u8) -> &mut File {
+        match &mut self.music_data {
+            Some(music_data) if MUSIC_INDICES.contains(&index_id) => music_data,
+            _ => &mut self.data,
+        }
+    }
+
+    fn index_path(&self, index_id: u8) -> PathBuf {
+        self.path.join(format!("{}{}", IDX_PREFIX, index_id))
+    }
+}
+
+/// The data file and index files of a cache that is being written.
+#[derive(Debug)]
+pub(crate) struct CacheFiles {
+    data: Vec<u8>,
+    pub(crate) indices: BTreeMap<u8, Vec<u8>>,
+}
+
+impl Default for CacheFiles {
+    fn default() -> Self {
+        // The first sector is never used by the client.
+        Self {
+            data: vec![0; SECTOR_SIZE],
+            indices: BTreeMap::new(),
+        }
+    }
+}
+
+impl CacheFiles {
+    /// Appends the archive to the data file and adds its index entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ArchiveTooLarge` or `SectorOverflow` if the archive can't be
+    /// addressed by its index entry.
+    pub(crate) fn insert(
+        &mut self,
+        index_id: u8,
+        archive_id: u32,
+        buffer: &[u8],
+    ) -> crate::Result<()> {
+        let sector = check_entry((self.data.len() / SECTOR_SIZE) as u64, archive_id, buffer)?;
+        let chain = sector::encode_chain(sector, index_id, archive_id, buffer);
+        self.data.extend(chain);
+
+        let index = self.indices.entry(index_id).or_default();
+        let offset = archive_id as usize * ARCHIVE_REF_LEN;
+        if index.len() < offset + ARCHIVE_REF_LEN {
+            index.resize(offset + ARCHIVE_REF_LEN, 0);
+        }
+        index[offset..offset + 3].copy_from_slice(&(buffer.len() as u32).to_be_bytes()[1..]);
+        index[offset + 3..offset + 6].copy_from_slice(&(sector as u32).to_be_bytes()[1..]);
+
+        Ok(())
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> crate::Result<()> {
+        fs::create_dir_all(path)?;
+        fs::write(path.join(MAIN_DATA), &self.data)?;
+        for (index_id, index) in &self.indices {
+            fs::write(path.join(format!("{}{}", IDX_PREFIX, index_id)), index)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that an archive stored from `sector` onwards can be addressed by an
+/// index entry, which holds both its length and its first sector in 24 bits,
+/// and returns the sector.
+fn check_entry(sector: u64, archive_id: u32, buffer: &[u8]) -> crate::Result<usize> {
+    if buffer.len() > MAX_ARCHIVE_LEN {
+        return Err(WriteError::ArchiveTooLarge { len: buffer.len() }.into());
+    }
+
+    let data_len = SectorHeaderSize::from_archive_id(archive_id).data_len();
+    let last_sector = sector + buffer.len().div_ceil(data_len).max(1) as u64 - 1;
+    if last_sector > MAX_SECTOR as u64 {
+        return Err(SectorError::SectorOverflow {
+            sector: last_sector,
+        }
+        .into());
+    }
+
+    Ok(sector as usize)
+}
+
+/// Updates the entry of an archive in an encoded reference table to match
+/// `buffer` and returns the re-encoded table.
+pub(crate) fn patch_reference_table(
+    table: Vec<u8>,
+    index_id: u8,
+    archive_id: u32,
+    buffer: &[u8],
+) -> crate::Result<Buffer<Encoded>> {
+    let header = Header::parse(buffer)?;
+    let version = header.version.ok_or(WriteError::MissingVersion {
+        index_id,
+        archive_id,
+    })?;
+    let encoded = Buffer::from(buffer.to_vec());
+    let decompressed_len = encoded.decode_ref()?.len();
+
+    let table = Buffer::from(table).decode_with_metadata()?;
+    let options = table.encode_options();
+    let mut table = table.to_vec();
+
+    let patch = ArchivePatch {
+        // The version trailer is not included in the crc.
+        crc: crc32fast::hash(encoded.without_version()),
+        version: version as u32,
+        compressed_len: header.len as u32,
+        decompressed_len: decompressed_len as u32,
+    };
+    if !metadata::patch_archive(&mut table, archive_id, &patch)? {
+        return Err(WriteError::UnknownArchive {
+            index_id,
+            archive_id,
+        }
+        .into());
+    }
+
+    options.encode(&table)
+}
@@ -4,7 +4,11 @@ mod test_util;
 #[cfg(test)]
 mod osrs {
     use super::test_util;
-    use rscache::Cache;
+    use rscache::{
+        error::HuffmanError,
+        util::{xtea, Huffman, IsaacRand},
+        Cache,
+    };
 
     #[test]
     fn new() {
@@ -16,6 +20,37 @@ mod osrs {
         assert!(Cache::new("./wrong/path").is_err());
     }
 
+    #[test]
+    fn io_error_source() {
+        use std::error::Error as _;
+
+        let err = Cache::new("./wrong/path").unwrap_err();
+        let source = err.source().unwrap().downcast_ref::<std::io::Error>();
+
+        assert!(matches!(err, rscache::Error::Io(_)));
+        assert_eq!(source.unwrap().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn name_hash_mismatch() {
+        let cache = test_util::osrs_cache();
+
+        match cache.archive_by_name(10, "unknown") {
+            Err(rscache::Error::NameHash(err)) => {
+                assert_eq!(err.name(), "unknown");
+                assert_eq!(err.index_id(), 10);
+                assert_eq!(err.hash(), rscache::util::djd2::hash("unknown"));
+            }
+            _ => panic!("expected a name hash mismatch"),
+        }
+    }
+
+    #[test]
+    fn no_music_data() {
+        let cache = test_util::osrs_cache();
+        assert!(!cache.has_music_data());
+    }
+
     #[test]
     fn huffman_table() {
         let cache = test_util::osrs_cache();
@@ -25,6 +60,363 @@ mod osrs {
         assert_eq!(&hash, "664e89cf25a0af7da138dd0f3904ca79cd1fe767");
         assert_eq!(buffer.len(), 256);
     }
+
+    #[test]
+    fn xtea_decipher_location_archive() {
+        let cache = test_util::osrs_cache();
+        let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+
+        let archive = cache.archive_by_name(5, "l50_50").unwrap();
+        let mut buffer = cache.read(5, archive.id).unwrap().to_vec();
+        let expected = cache.read(5, archive.id).unwrap().with_xtea_keys(keys).decode().unwrap();
+
+        let len = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+        let end = 5 + len + if buffer[0] == 0 { 0 } else { 4 };
+        xtea::decipher_in_place(&mut buffer[5..end], &keys);
+
+        let decoded = runefs::codec::Buffer::from(buffer.clone()).decode().unwrap();
+        assert_eq!(decoded.len(), 11113);
+        assert_eq!(decoded.as_slice(), expected.as_slice());
+
+        xtea::encipher_in_place(&mut buffer[5..end], &keys);
+        assert_eq!(buffer, cache.read(5, archive.id).unwrap().as_slice());
+    }
+
+    #[test]
+    fn xtea_blocks() {
+        let keys = [1, 2, 3, 4];
+        let data: Vec<u8> = (0..20).collect();
+
+        let mut enciphered = data.clone();
+        xtea::encipher(&mut enciphered, &keys);
+        assert_eq!(&enciphered[16..], &data[16..]);
+
+        let blocks = xtea::decipher_blocks(&enciphered, &keys);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks.remainder(), &data[16..]);
+        assert_eq!(blocks.flatten().collect::<Vec<_>>(), &data[..16]);
+
+        xtea::decipher(&mut enciphered, &keys);
+        assert_eq!(enciphered, data);
+    }
+
+    #[test]
+    fn djd2_hash() {
+        use rscache::util::djd2;
+
+        assert_eq!(djd2::hash("huffman"), 1258058669);
+        assert_eq!(djd2::hash("€"), -128);
+        assert_eq!(djd2::hash("aé"), 97 * 31 - 23);
+        assert_eq!(djd2::hash_bytes(&[b'a', 0xE9]), djd2::hash("aé"));
+        assert_eq!(djd2::hash("a".repeat(100)), djd2::hash_bytes(&[b'a'; 100]));
+    }
+
+    #[test]
+    fn base37() {
+        use rscache::util::base37;
+
+        assert_eq!(base37::encode("mod ash"), 34_402_101_396);
+        assert_eq!(base37::encode("Mod_Ash__"), 34_402_101_396);
+        assert_eq!(base37::decode(34_402_101_396).unwrap(), "mod_ash");
+        let truncated = base37::encode("a1234567890zz");
+        assert_eq!(base37::decode(truncated).unwrap(), "a1234567890z");
+        assert_eq!(base37::decode(0), None);
+        assert_eq!(base37::decode(37), None);
+        assert_eq!(base37::decode(u64::MAX), None);
+    }
+
+    #[test]
+    fn coords() {
+        use rscache::util::coords::{Coordinate, RegionId};
+
+        let region = RegionId::new(50, 50);
+        assert_eq!(u16::from(region), 12850);
+        assert_eq!(region.base(), Coordinate::new(3200, 3200, 0));
+        assert!(region.contains(Coordinate::new(3263, 3263, 3)));
+        assert!(!region.contains(Coordinate::new(3264, 3200, 0)));
+
+        let coord = Coordinate::new(3222, 3218, 2);
+        assert_eq!(coord.local(), (22, 18));
+        assert_eq!(Coordinate::from_local(coord.region(), 22, 18, 2), coord);
+        assert_eq!(coord.pack(), (2 << 28) | (3222 << 14) | 3218);
+        assert_eq!(Coordinate::unpack(coord.pack()), coord);
+    }
+
+    #[cfg(feature = "rsa")]
+    #[test]
+    fn rsa_round_trip() {
+        use rscache::{
+            crypto::{self, RsaKeys},
+            error::RsaError,
+            Error,
+        };
+
+        let public_keys = RsaKeys::new(b"17", b"3233");
+        let private_keys = RsaKeys::new(b"2753", b"3233");
+
+        let encrypted = crypto::rsa_encrypt(&[65], &public_keys).unwrap();
+        assert_eq!(encrypted, [0x0A, 0xE6]);
+        assert_eq!(crypto::rsa_decrypt(&encrypted, &private_keys).unwrap(), [65]);
+
+        assert!(matches!(
+            crypto::rsa_encrypt(&[0xFF, 0xFF], &public_keys),
+            Err(Error::Rsa(RsaError::BlockTooLarge))
+        ));
+        assert!(matches!(
+            crypto::rsa_encrypt(&[65], &RsaKeys::new(b"17", b"abc")),
+            Err(Error::Rsa(RsaError::InvalidModulus))
+        ));
+    }
+
+    #[test]
+    fn isaac_pair() {
+        let keys = [1, 2, 3, 4];
+        let (mut decoder, mut encoder) = IsaacRand::pair_from_client_keys(&keys);
+        let mut expected_decoder = IsaacRand::new(&keys);
+        let mut expected_encoder = IsaacRand::new(&[51, 52, 53, 54]);
+
+        for _ in 0..1000 {
+            let peeked = decoder.peek();
+            assert_eq!(decoder.peek(), peeked);
+            assert_eq!(decoder.next(), Some(peeked));
+            assert_eq!(Some(peeked), expected_decoder.next());
+
+            let expected = expected_encoder.next().unwrap();
+            assert_eq!(encoder.next_u8(), expected as u8);
+        }
+    }
+
+    #[test]
+    fn huffman_decompress() {
+        let cache = test_util::osrs_cache();
+        let huffman = Huffman::new(&cache.huffman_table().unwrap());
+
+        let compressed = &[174, 128, 35, 32, 208, 96];
+
+        assert_eq!(huffman.decompress(compressed, 8).unwrap(), b"rs-cache");
+        assert!(huffman.decompress(compressed, 0).unwrap().is_empty());
+        assert_eq!(
+            huffman.decompress(&compressed[..3], 8),
+            Err(HuffmanError::Truncated {
+                expected: 8,
+                actual: 3
+            })
+        );
+        assert!(huffman.decompress(compressed, 9).is_err());
+    }
+
+    #[test]
+    fn archive_by_name() {
+        let cache = test_util::osrs_cache();
+        let archive = cache.archive_by_name(5, "m50_50").unwrap();
+
+        assert_eq!(archive.index_id, 5);
+        assert_eq!(cache.archive_by_name(5, "m50_50").unwrap().id, archive.id);
+        assert!(cache.archive_by_name(5, "not_a_map").is_err());
+        assert!(cache.archive_by_name(42, "m50_50").is_err());
+    }
+
+    #[test]
+    fn resolve_name() {
+        let mut cache = test_util::osrs_cache();
+        cache.register_names(["huffman", "m50_50"]);
+
+        let hash = rscache::util::djd2::hash("m50_50");
+        assert_eq!(cache.resolve_name(hash), Some("m50_50"));
+        assert_eq!(cache.resolve_name(1258058669), Some("huffman"));
+        assert_eq!(cache.resolve_name(0), None);
+    }
+
+    #[test]
+    fn file_name_hashes() {
+        use rscache::metadata::IndexMetadata;
+
+        let cache = test_util::osrs_cache();
+        let buffer = cache.read(255, 16).unwrap().decode().unwrap();
+        let metadata = IndexMetadata::from_buffer(&buffer).unwrap();
+        let archive = metadata.get(0).unwrap();
+
+        assert_eq!(metadata.len(), 36);
+        assert_eq!(archive.entry_count, 33);
+        assert_eq!(archive.file_name_hashes.len(), 33);
+        assert_eq!(archive.file_name_hashes[0], 3343801);
+        assert_eq!(archive.file_by_name("main"), Some(0));
+    }
+
+    #[test]
+    fn no_file_name_hashes() {
+        use rscache::metadata::IndexMetadata;
+
+        let cache = test_util::osrs_cache();
+        let buffer = cache.read(255, 2).unwrap().decode().unwrap();
+        let metadata = IndexMetadata::from_buffer(&buffer).unwrap();
+
+        assert_eq!(metadata.len(), 32);
+        assert!(metadata.iter().all(|archive| archive.file_name_hashes.is_empty()));
+    }
+
+    #[test]
+    fn verify_archive() {
+        let cache = test_util::osrs_cache();
+        let archive = cache.index_metadata(2).unwrap().get(10).unwrap();
+        let mut buffer = cache.read(2, 10).unwrap().to_vec();
+
+        assert!(archive.verify(&buffer));
+        assert!(archive.verify(&buffer[..buffer.len() - 2]));
+
+        buffer[20] ^= 0xFF;
+        assert!(!archive.verify(&buffer));
+        assert!(!archive.verify(&[]));
+    }
+
+    #[test]
+    fn archive_lengths() {
+        use rscache::metadata::{IndexMetadata, FLAG_LENGTHS};
+
+        #[rustfmt::skip]
+        let buffer = [
+            6, 0, 0, 0, 1, FLAG_LENGTHS, 0, 1, 0, 4,
+            0, 0, 0, 42, 0, 0, 0, 10, 0, 0, 0, 20, 0, 0, 0, 3,
+            0, 1, 0, 0,
+        ];
+        let metadata = IndexMetadata::from_buffer(&buffer).unwrap();
+        let archive = metadata.get(4).unwrap();
+
+        assert_eq!(archive.crc, 42);
+        assert_eq!(archive.compressed_len, Some(10));
+        assert_eq!(archive.decompressed_len, Some(20));
+        assert_eq!(archive.version, 3);
+
+        let cache = test_util::osrs_cache();
+        let metadata = cache.index_metadata(2).unwrap();
+        assert!(metadata.iter().all(|archive| archive.compressed_len.is_none()));
+    }
+
+    #[test]
+    fn corrupt_archive_count() {
+        use rscache::metadata::IndexMetadata;
+
+        // Protocol 7 with an archive count of i32::MAX and no archives.
+        let buffer = [7, 0, 0, 0, 1, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 1];
+
+        assert!(IndexMetadata::from_buffer(&buffer).is_err());
+    }
+
+    #[test]
+    fn encode_metadata() {
+        use rscache::metadata::{ArchiveMetadata, IndexMetadata, FLAG_LENGTHS, FLAG_NAMES};
+
+        let cache = test_util::osrs_cache();
+        for index_id in cache.present_indices() {
+            let buffer = cache.read(255, index_id as u32).unwrap().decode().unwrap();
+            let metadata = IndexMetadata::from_buffer(&buffer).unwrap();
+            assert_eq!(metadata.encode(), buffer.as_slice(), "index {}", index_id);
+        }
+
+        let archives = vec![
+            ArchiveMetadata {
+                id: 70_000,
+                valid_ids: vec![0, 40_000],
+                file_name_hashes: vec![1, 2],
+                ..Default::default()
+            },
+            ArchiveMetadata {
+                id: 3,
+                crc: 42,
+                compressed_len: Some(10),
+                decompressed_len: Some(20),
+                valid_ids: vec![0],
+                ..Default::default()
+            },
+        ];
+        let metadata = IndexMetadata::new(7, 1, FLAG_NAMES | FLAG_LENGTHS, archives);
+        let decoded = IndexMetadata::from_buffer(&metadata.encode()).unwrap();
+
+        assert_eq!(decoded.iter().next().unwrap().id, 3);
+        assert_eq!(decoded.get(70_000).unwrap().valid_ids, [0, 40_000]);
+        assert_eq!(decoded.get(70_000).unwrap().entry_count, 2);
+        assert_eq!(decoded.get(3).unwrap().entry_count, 1);
+        assert_eq!(decoded.get(3).unwrap().file_name_hashes, [0]);
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        use rscache::{
+            error::MetadataError,
+            metadata::{ArchiveMetadata, IndexMetadata},
+        };
+
+        let cache = test_util::osrs_cache();
+        for index_id in cache.present_indices() {
+            let buffer = cache.read(255, index_id as u32).unwrap().decode().unwrap();
+            let metadata = cache.index_metadata(index_id).unwrap();
+
+            let encoded = Vec::<u8>::try_from(metadata).unwrap();
+            assert_eq!(encoded, buffer.as_slice(), "index {}", index_id);
+            assert_eq!(&IndexMetadata::from_buffer(&encoded).unwrap(), metadata);
+        }
+
+        let mut metadata = cache.index_metadata(2).unwrap().clone();
+        metadata.protocol = 8;
+        assert_eq!(
+            Vec::<u8>::try_from(&metadata),
+            Err(MetadataError::UnsupportedProtocol(8))
+        );
+
+        let archive = ArchiveMetadata {
+            id: 70_000,
+            ..Default::default()
+        };
+        let metadata = IndexMetadata::new(6, 0, 0, vec![archive]);
+        assert_eq!(
+            Vec::<u8>::try_from(&metadata),
+            Err(MetadataError::IdOverflow {
+                protocol: 6,
+                value: 70_000
+            })
+        );
+    }
+
+    #[test]
+    fn metadata_snapshot() {
+        use rscache::Protocol;
+
+        let cache = test_util::osrs_cache();
+        let snapshot = cache.metadata_snapshot().unwrap();
+
+        assert_eq!(snapshot.protocol, Protocol::Osrs);
+        assert!(snapshot.indices.keys().copied().eq(cache.present_indices()));
+        assert_eq!(&snapshot.indices[&2], cache.index_metadata(2).unwrap());
+
+        let items = snapshot.archive(2, 10).unwrap();
+        assert_eq!(items.crc, cache.archive_crc(2, 10).unwrap());
+        assert!(snapshot.archive(2, 100_000).is_none());
+        assert!(snapshot.archive(255, 2).is_none());
+    }
+
+    #[test]
+    fn verify() {
+        let cache = test_util::osrs_cache();
+        let report = cache.verify();
+
+        assert!(report.is_ok());
+        assert_eq!(report.archives_checked(), 58_655);
+    }
+
+    #[test]
+    fn stats() {
+        let cache = test_util::osrs_cache();
+        let stats = cache.stats().unwrap();
+        let reference_tables = stats.index(255).unwrap();
+
+        assert_eq!(reference_tables.archive_count, 21);
+        assert_eq!(reference_tables.total_bytes, 292_819);
+        assert_eq!(reference_tables.wasted_bytes, 5165);
+        assert_eq!(reference_tables.compression.none, 2);
+        assert_eq!(reference_tables.compression.bzip2, 3);
+        assert_eq!(reference_tables.compression.gzip, 16);
+        assert!(stats.index(42).is_none());
+    }
 }
 
 #[cfg(all(test, feature = "rs3"))]
@@ -51,4 +443,20 @@ mod rs3 {
         assert_eq!(&hash, "664e89cf25a0af7da138dd0f3904ca79cd1fe767");
         assert_eq!(buffer.len(), 256);
     }
+
+    #[test]
+    fn metadata_round_trip() {
+        let cache = test_util::rs3_cache();
+        for index_id in cache.present_indices() {
+            let buffer = cache.read(255, index_id as u32).unwrap();
+            if buffer.is_empty() {
+                continue;
+            }
+            let buffer = buffer.decode().unwrap();
+            let metadata = cache.index_metadata(index_id).unwrap();
+
+            let encoded = Vec::<u8>::try_from(metadata).unwrap();
+            assert_eq!(encoded, buffer.as_slice(), "index {}", index_id);
+        }
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 mod test_util;
 
-use rscache::checksum::Checksum;
+use rscache::{checksum::Checksum, Cache};
 
 #[test]
 fn new() {
@@ -34,6 +34,46 @@ fn validate() {
     assert!(checksum.validate(&crcs).is_ok());
 }
 
+#[test]
+fn encode_by_ref() {
+    let cache = test_util::osrs_cache();
+    let checksum = Checksum::new(&cache).unwrap();
+
+    let buffer = checksum.encode().unwrap();
+    assert_eq!(buffer.len(), 173);
+    assert_eq!(checksum.encode().unwrap().as_slice(), buffer.as_slice());
+}
+
+#[test]
+fn entry() {
+    let cache = test_util::osrs_cache();
+    let checksum = Checksum::new(&cache).unwrap();
+
+    let entry = checksum.entry(3).unwrap();
+    assert_eq!(entry.crc(), 4209099954);
+    assert_eq!(checksum.iter().nth(3), Some(entry));
+    assert!(checksum.entry(255).is_none());
+}
+
+#[test]
+fn missing_index() {
+    let path = std::path::Path::new("./data/osrs_cache");
+    let indices = (0..=20)
+        .filter(|&index_id| index_id != 3)
+        .chain([255])
+        .map(|index_id| path.join(format!("main_file_cache.idx{}", index_id)));
+    let cache = Cache::from_files(path.join("main_file_cache.dat2"), indices).unwrap();
+
+    assert!(!cache.present_indices().contains(&3));
+    assert_eq!(cache.present_indices().len(), 20);
+
+    let checksum = Checksum::new(&cache).unwrap();
+    assert_eq!(checksum.index_count(), 22);
+    assert_eq!(checksum.entry(3).unwrap().crc(), 0);
+    assert_eq!(checksum.entry(4).unwrap().crc(), 3716821437);
+    assert_eq!(checksum.encode().unwrap().len(), 173);
+}
+
 #[test]
 fn invalid_crc() {
     use rscache::error::ValidateError;
@@ -83,8 +123,8 @@ fn invalid_len() {
 mod rsa {
     use rscache::checksum::{RsaChecksum, RsaKeys};
     use super::test_util;
-    pub const EXPONENT: &'static [u8] = b"5206580307236375668350588432916871591810765290737810323990754121164270399789630501436083337726278206128394461017374810549461689174118305784406140446740993";
-    pub const MODULUS: &'static [u8] = b"6950273013450460376345707589939362735767433035117300645755821424559380572176824658371246045200577956729474374073582306250298535718024104420271215590565201";
+    pub const EXPONENT: &[u8] = b"5206580307236375668350588432916871591810765290737810323990754121164270399789630501436083337726278206128394461017374810549461689174118305784406140446740993";
+    pub const MODULUS: &[u8] = b"6950273013450460376345707589939362735767433035117300645755821424559380572176824658371246045200577956729474374073582306250298535718024104420271215590565201";
 
     #[test]
     fn with_keys() {
@@ -96,4 +136,65 @@ mod rsa {
         assert_eq!(&hash, "118e0146af6cf288630357eec6298c34a2430065");
         assert_eq!(buffer.len(), 4681);
     }
-}
\ No newline at end of file
+}
+#[cfg(feature = "whirlpool")]
+#[test]
+fn new_with_whirlpool() {
+    let cache = test_util::osrs_cache();
+    let checksum = Checksum::new_with_whirlpool(&cache).unwrap();
+
+    assert_eq!(checksum.iter().count(), 21);
+    assert!(checksum.iter().all(|entry| entry.hash().len() == 64));
+    assert_ne!(checksum.iter().next().unwrap().hash(), &[0; 64]);
+}
+
+#[test]
+fn with_options() {
+    use rscache::checksum::ChecksumOptions;
+
+    let cache = test_util::osrs_cache();
+    let checksum = ChecksumOptions::new().skip_index(3).build(&cache).unwrap();
+    let default = Checksum::new(&cache).unwrap();
+
+    assert_eq!(checksum.iter().count(), 21);
+    for (index_id, (entry, default)) in checksum.iter().zip(default.iter()).enumerate() {
+        if index_id == 3 {
+            assert_ne!(entry, default);
+        } else {
+            assert_eq!(entry, default);
+        }
+    }
+
+    let checksum = ChecksumOptions::new().skip_indices([]).build(&cache).unwrap();
+    assert_eq!(checksum, Checksum::new(&cache).unwrap());
+}
+
+#[test]
+fn validate_report() {
+    use rscache::error::ValidateError;
+
+    let cache = test_util::osrs_cache();
+    let checksum = Checksum::new(&cache).unwrap();
+
+    let crcs = [
+        1593884597, 1029608590, 16840364, 4209098954, 3716821437, 165713182, 686540367, 4262755489,
+        2208636505, 3047082366, 586413816, 2890424900, 3411535427, 3178880569, 153718440,
+        3849392898, 3628627685, 2813112885, 1461700456, 2751169400,
+    ];
+
+    assert_eq!(
+        checksum.validate_report(&crcs),
+        Err(vec![
+            ValidateError::InvalidLength {
+                expected: 21,
+                actual: 20
+            },
+            ValidateError::InvalidCrc {
+                idx: 3,
+                external: 4209098954,
+                internal: 4209099954
+            },
+        ])
+    );
+    assert!(checksum.validate_report(&[0; 21]).unwrap_err().len() == 21);
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+fn rscache_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rscache-cli"))
+        .arg("./data/osrs_cache")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn info() {
+    let output = rscache_cli(&["info"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.starts_with("protocol: "));
+}
+
+#[test]
+fn read() {
+    let output = rscache_cli(&["read", "2", "10", "--decode"]);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 886_570);
+}
+
+#[test]
+fn item() {
+    let output = rscache_cli(&["item", "1042"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("\"name\": \"Blue partyhat\""));
+    assert!(!rscache_cli(&["item", "65535"]).status.success());
+}
+
+#[test]
+fn invalid_command() {
+    let output = rscache_cli(&["unpack"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("invalid command"));
+}
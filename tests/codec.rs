@@ -0,0 +1,104 @@
+use proptest::prelude::*;
+use rscache::{
+    codec::{self, Compression, EncodeOptions, EncodedExt},
+    error::CodecError,
+    Error,
+};
+
+fn compression() -> impl Strategy<Value = Compression> {
+    prop_oneof![
+        Just(Compression::None),
+        Just(Compression::Bzip2),
+        Just(Compression::Gzip),
+        Just(Compression::Deflate),
+    ]
+}
+
+fn options() -> impl Strategy<Value = EncodeOptions> {
+    (
+        compression(),
+        any::<Option<u16>>(),
+        any::<Option<[u32; 4]>>(),
+    )
+        .prop_map(|(compression, version, keys)| {
+            let mut options = EncodeOptions::new(compression);
+            if let Some(version) = version {
+                options = options.version(version);
+            }
+            if let Some(keys) = keys {
+                options = options.xtea_keys(keys);
+            }
+            options
+        })
+}
+
+proptest! {
+    #[test]
+    fn roundtrip(options in options(), data in prop::collection::vec(any::<u8>(), 0..2048)) {
+        codec::roundtrip_check(&options, &data).unwrap();
+    }
+
+    #[test]
+    fn roundtrip_levels(
+        compression in compression(),
+        level in 0..=9_u32,
+        data in prop::collection::vec(any::<u8>(), 0..512),
+    ) {
+        let options = EncodeOptions::new(compression).level(level);
+        match compression.levels() {
+            Some(levels) if !levels.contains(&level) => {
+                prop_assert!(codec::roundtrip_check(&options, &data).is_err());
+            }
+            _ => codec::roundtrip_check(&options, &data).unwrap(),
+        }
+    }
+
+    #[test]
+    fn decode_with_metadata(
+        compression in compression(),
+        version in any::<Option<u16>>(),
+        data in prop::collection::vec(any::<u8>(), 0..512),
+    ) {
+        let mut options = EncodeOptions::new(compression);
+        if let Some(version) = version {
+            options = options.version(version);
+        }
+
+        let decoded = options.encode(&data).unwrap().decode_with_metadata().unwrap();
+        prop_assert_eq!(decoded.compression(), compression);
+        prop_assert_eq!(decoded.version(), version);
+        prop_assert_eq!(&*decoded, data.as_slice());
+        let reencoded = decoded.encode().unwrap();
+        let encoded = options.encode(&data).unwrap();
+        prop_assert_eq!(reencoded.as_slice(), encoded.as_slice());
+    }
+
+    #[test]
+    fn decode_ref_with_keys(
+        compression in compression(),
+        keys in any::<[u32; 4]>(),
+        data in prop::collection::vec(any::<u8>(), 0..512),
+    ) {
+        let encoded = EncodeOptions::new(compression).xtea_keys(keys).encode(&data).unwrap();
+
+        let decoded = encoded.decode_ref_with_keys(&keys).unwrap();
+        prop_assert_eq!(decoded.as_slice(), data.as_slice());
+    }
+}
+
+#[test]
+fn kept_bzip2_header() {
+    let options = EncodeOptions::new(Compression::Bzip2).keep_header(true);
+
+    assert!(codec::roundtrip_check(&options, b"BZh1 is added by the decoder").is_err());
+}
+
+#[test]
+fn lzma_is_unsupported() {
+    let options = EncodeOptions::new(Compression::Lzma);
+
+    match codec::roundtrip_check(&options, &[1, 2, 3]) {
+        Err(Error::Codec(CodecError::Unsupported(Compression::Lzma))) => {}
+        result => panic!("expected Unsupported, got {:?}", result),
+    }
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "serde")]
+
+mod test_util;
+
+use rscache::{export::Export, loader::osrs::ItemLoader};
+
+#[test]
+fn export_json() {
+    let item_loader = ItemLoader::new(&test_util::osrs_cache()).unwrap();
+
+    let mut buffer = Vec::new();
+    item_loader.export_json(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+
+    assert_eq!(output.lines().count(), item_loader.iter().count());
+    assert!(output.starts_with("{\"id\":0,"));
+    assert!(output.contains("\"name\":\"Blue partyhat\""));
+}
+
+#[test]
+fn export_csv() {
+    let item_loader = ItemLoader::new(&test_util::osrs_cache()).unwrap();
+
+    let mut buffer = Vec::new();
+    item_loader.export_csv(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+
+    assert!(output.starts_with("id,name,examine,stackable,cost,"));
+    assert!(output.lines().any(|line| line.starts_with("1042,Blue partyhat,,false,")));
+}
+
+#[test]
+fn metadata_snapshot_json() {
+    use rscache::metadata::MetadataSnapshot;
+
+    let snapshot = test_util::osrs_cache().metadata_snapshot().unwrap();
+
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let parsed: MetadataSnapshot = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(parsed, snapshot);
+}
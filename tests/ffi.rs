@@ -0,0 +1,48 @@
+#![cfg(feature = "ffi")]
+
+mod test_util;
+
+use std::{ffi::CString, ptr};
+
+use rscache::ffi::{rscache_free, rscache_open, rscache_read, rscache_read_decoded, RSCACHE_ERROR};
+
+#[test]
+fn read_into_buffer() {
+    let path = CString::new("./data/osrs_cache").unwrap();
+
+    unsafe {
+        let cache = rscache_open(path.as_ptr());
+        assert!(!cache.is_null());
+
+        let len = rscache_read(cache, 2, 10, ptr::null_mut(), 0);
+        assert!(len > 0);
+
+        let mut buffer = vec![0; len as usize];
+        assert_eq!(
+            rscache_read(cache, 2, 10, buffer.as_mut_ptr(), buffer.len()),
+            len
+        );
+        assert_eq!(
+            &test_util::hash(&buffer),
+            "c6ee1518e9a39a42ecaf946c6c84a942cb3102f4"
+        );
+
+        assert!(rscache_read_decoded(cache, 2, 10, ptr::null_mut(), 0) > 0);
+        assert_eq!(
+            rscache_read(cache, 2, u32::MAX, ptr::null_mut(), 0),
+            RSCACHE_ERROR
+        );
+
+        rscache_free(cache);
+    }
+}
+
+#[test]
+fn open_invalid_path() {
+    let path = CString::new("./data/missing").unwrap();
+
+    unsafe {
+        assert!(rscache_open(path.as_ptr()).is_null());
+        assert!(rscache_open(ptr::null()).is_null());
+    }
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "fuzzing")]
+
+mod test_util;
+
+use rscache::{
+    fuzzing,
+    metadata::IndexMetadata,
+    sector::{SectorHeader, SectorHeaderSize},
+};
+
+#[test]
+fn index_metadata_try_from() {
+    let cache = test_util::osrs_cache();
+    let buffer = cache.read(255, 2).unwrap().decode().unwrap();
+    let buffer = buffer.as_slice();
+
+    let metadata = IndexMetadata::try_from(buffer).unwrap();
+    assert_eq!(&metadata, cache.index_metadata(2).unwrap());
+    assert!(IndexMetadata::try_from(&buffer[..buffer.len() / 2]).is_err());
+}
+
+#[test]
+fn sector_header_try_new() {
+    let buffer = [0, 10, 0, 1, 0, 0, 2, 2];
+
+    let header = SectorHeader::try_new(&buffer, SectorHeaderSize::Normal).unwrap();
+    assert_eq!((header.archive_id, header.chunk, header.next), (10, 1, 2));
+    assert_eq!(header.index_id, 2);
+    assert!(SectorHeader::try_new(&buffer, SectorHeaderSize::Expanded).is_none());
+    assert!(SectorHeader::try_new(&[], SectorHeaderSize::Normal).is_none());
+}
+
+#[test]
+fn truncated_input() {
+    let cache = test_util::osrs_cache();
+    let metadata = cache.read(255, 2).unwrap().decode().unwrap();
+    let metadata = metadata.as_slice();
+    let items = cache.read_decoded(2, 10).unwrap();
+    let items = items.as_slice();
+
+    for len in 0..metadata.len().min(512) {
+        fuzzing::index_metadata(&metadata[..len]);
+        fuzzing::sector_header(&metadata[..len]);
+    }
+    for len in 0..items.len().min(512) {
+        fuzzing::definitions(&items[..len]);
+    }
+}
+
+#[test]
+fn oversized_counts() {
+    use rscache::definition::osrs::{DecodeContext, Definition, SpriteDefinition};
+
+    // A reference table with i32::MAX archives.
+    let metadata = [7, 0, 0, 0, 1, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+    assert!(IndexMetadata::try_from(&metadata[..]).is_err());
+
+    // A single 65535x65535 frame without any pixels.
+    #[rustfmt::skip]
+    let sprite = [
+        0,
+        0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF,
+        0, 1,
+    ];
+    let ctx = DecodeContext::new(8, 0);
+    assert!(SpriteDefinition::from_buffer(0, &sprite, &ctx).is_err());
+}
+
+/// Runs every target on random input and on randomly corrupted archives, a
+/// short deterministic stand-in for a fuzzing session.
+#[test]
+fn smoke_run() {
+    let cache = test_util::osrs_cache();
+    let sprite = cache.index_metadata(8).unwrap().iter().next().unwrap().id;
+    let seeds = [
+        cache.read(255, 2).unwrap().decode().unwrap().to_vec(),
+        cache.read_decoded(2, 10).unwrap().to_vec(),
+        cache.read_decoded(8, sprite).unwrap().to_vec(),
+    ];
+
+    // xorshift64, any fixed seed works.
+    let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..2_000 {
+        let len = next() as usize % 64;
+        let random: Vec<_> = (0..len).map(|_| next() as u8).collect();
+
+        let seed = &seeds[next() as usize % seeds.len()];
+        let mut corrupted = seed[..seed.len().min(512)].to_vec();
+        for _ in 0..4 {
+            let i = next() as usize % corrupted.len();
+            corrupted[i] = next() as u8;
+        }
+
+        for data in [&random, &corrupted] {
+            fuzzing::index_metadata(data);
+            fuzzing::sector_header(data);
+            fuzzing::definitions(data);
+        }
+    }
+}
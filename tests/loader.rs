@@ -22,6 +22,19 @@ mod osrs {
             assert!(!item.members_only);
         }
 
+        #[test]
+        fn sorted_by_id() {
+            use rscache::loader::Loader;
+
+            let item_loader = item_loader();
+            let ids: Vec<u32> = item_loader.iter().map(|(&id, _)| id).collect();
+
+            assert_eq!(ids.first(), Some(&0));
+            assert!(ids.windows(2).all(|ids| ids[0] < ids[1]));
+            assert!(Loader::iter(&item_loader).map(|(&id, _)| id).eq(ids.iter().copied()));
+            assert!(item_loader.into_iter().map(|(id, _)| id).eq(ids));
+        }
+
         #[test]
         fn magic_logs() {
             let item_loader = item_loader();
@@ -32,6 +45,14 @@ mod osrs {
             assert!(item.members_only);
         }
 
+        #[test]
+        fn cp1252_name() {
+            let item_loader = item_loader();
+            let item = item_loader.load(10966).unwrap();
+
+            assert_eq!(item.name, "Grubs à la mode");
+        }
+
         #[test]
         fn noted() {
             let item_loader = item_loader();
@@ -41,6 +62,132 @@ mod osrs {
             assert!(!item.members_only);
         }
 
+        #[test]
+        fn noted_of() {
+            let item_loader = item_loader();
+            let noted = item_loader.noted_of(1042).unwrap();
+
+            assert_eq!(noted.id, 1043);
+            assert_eq!(noted.name, "Blue partyhat");
+            assert!(noted.stackable);
+            assert_eq!(noted.inventory_model_data.inventory_model, 2429);
+            assert_eq!(item_loader.unnoted_of(1043).unwrap().id, 1042);
+            assert!(item_loader.unnoted_of(1042).is_none());
+            assert!(item_loader.noted_of(1043).is_none());
+        }
+
+        #[test]
+        fn placeholder_of() {
+            let item_loader = item_loader();
+            let placeholder = item_loader.placeholder_of(1042).unwrap();
+
+            assert_eq!(placeholder.id, 14389);
+            assert_eq!(placeholder.name, "Blue partyhat");
+            assert_eq!(placeholder.cost, 0);
+            assert!(item_loader.placeholder_of(14389).is_none());
+        }
+
+        #[test]
+        fn equipment_stats() {
+            use rscache::definition::osrs::equipment::{self, EquipmentSlot};
+
+            let item_loader = item_loader();
+            let mut item = item_loader.load(4151).unwrap().clone();
+            assert!(item.equipment_stats().is_none());
+
+            item.wear_pos1 = Some(3);
+            item.params.extend([
+                (equipment::SLASH_ATTACK, "82".to_owned()),
+                (equipment::MELEE_STRENGTH, "82".to_owned()),
+                (equipment::MAGIC_DEFENCE, "-6".to_owned()),
+                (equipment::ATTACK_SPEED, "4".to_owned()),
+            ]);
+            let stats = item.equipment_stats().unwrap();
+
+            assert_eq!(stats.slot, Some(EquipmentSlot::Weapon));
+            assert_eq!(stats.attack.slash, 82);
+            assert_eq!(stats.attack.stab, 0);
+            assert_eq!(stats.defence.magic, -6);
+            assert_eq!(stats.melee_strength, 82);
+            assert_eq!(stats.attack_speed, Some(4));
+            assert_eq!(EquipmentSlot::from_wear_pos(6), None);
+        }
+
+        #[test]
+        fn current_opcodes() {
+            use rscache::definition::osrs::{DecodeContext, Definition, ItemDefinition};
+
+            let mut buffer = vec![2];
+            buffer.extend_from_slice(b"Amulet\0");
+            buffer.push(3);
+            buffer.extend_from_slice(b"Shiny.\0");
+            buffer.push(9);
+            buffer.extend_from_slice(b"amulet\0");
+            buffer.extend_from_slice(&[13, 2, 14, 8, 27, 11]);
+            buffer.extend_from_slice(&[43, 2, 1]);
+            buffer.extend_from_slice(b"Edgeville\0");
+            buffer.push(3);
+            buffer.extend_from_slice(b"Draynor\0");
+            buffer.extend_from_slice(&[0, 75, 0x00, 0x0A, 94, 0x01, 0x2C, 0]);
+
+            let ctx = DecodeContext::new(2, 10);
+            let item = ItemDefinition::from_buffer(1712, &buffer, &ctx).unwrap();
+
+            assert_eq!(item.name, "Amulet");
+            assert_eq!(item.examine, "Shiny.");
+            assert_eq!(item.wear_pos1, Some(2));
+            assert_eq!(item.wear_pos2, Some(8));
+            assert_eq!(item.wear_pos3, Some(11));
+            assert_eq!(item.interface_sub_options[2][&0], "Edgeville");
+            assert_eq!(item.interface_sub_options[2][&2], "Draynor");
+            assert_eq!(item.weight, 10);
+            assert_eq!(item.category, Some(300));
+        }
+
+        #[test]
+        fn encode_round_trip() {
+            use rscache::definition::osrs::ItemDefinition;
+
+            test_util::assert_encode_round_trip(10, ItemDefinition::encode);
+        }
+
+        #[test]
+        fn from_bytes() {
+            let cache = test_util::osrs_cache();
+            let archive = cache.index_metadata(2).unwrap().get(10).unwrap();
+            let entry_count = archive.entry_count;
+            let buffer = cache.read_decoded(2, 10).unwrap();
+
+            let item_loader = ItemLoader::from_bytes(&buffer, entry_count).unwrap();
+            assert_eq!(item_loader, ItemLoader::new(&cache).unwrap());
+            assert!(ItemLoader::from_bytes(&buffer[..16], entry_count).is_err());
+        }
+
+        #[test]
+        fn fetch() {
+            use rscache::definition::osrs::ItemDefinition;
+
+            let cache = test_util::osrs_cache();
+            let item = ItemDefinition::fetch(&cache, 1042).unwrap().unwrap();
+
+            assert_eq!(&item, item_loader().load(1042).unwrap());
+            assert!(ItemDefinition::fetch(&cache, 65_535).unwrap().is_none());
+        }
+
+        #[test]
+        fn stack_variant() {
+            let item_loader = item_loader();
+            let coins = item_loader.load(995).unwrap();
+
+            assert_eq!(coins.stack_variant(0), 995);
+            assert_eq!(coins.stack_variant(1), 995);
+            assert_eq!(coins.stack_variant(2), 996);
+            assert_eq!(coins.stack_variant(24), 999);
+            assert_eq!(coins.stack_variant(100), 1001);
+            assert_eq!(coins.stack_variant(1_000_000), 1004);
+            assert_eq!(item_loader.load(1042).unwrap().stack_variant(5), 1042);
+        }
+
         #[test]
         fn non_existent() {
             let item_loader = item_loader();
@@ -63,6 +210,57 @@ mod osrs {
 
             assert_eq!(npc.name, "Woodsman tutor");
             assert!(npc.interactable);
+            assert!(npc.visible_on_minimap);
+        }
+
+        #[test]
+        fn with_revision() {
+            use rscache::definition::Revision;
+
+            let cache = test_util::osrs_cache();
+            let npc_loader_180 = NpcLoader::with_revision(&cache, Revision(180)).unwrap();
+
+            assert_eq!(npc_loader_180, npc_loader());
+        }
+
+        #[test]
+        fn head_icon_sprites() {
+            use rscache::definition::osrs::{DecodeContext, Definition, NpcDefinition, Revision};
+
+            // Icons in slot 0 and 2, from sprite archive 440.
+            let buffer = [102, 0b101, 0x01, 0xB8, 0x03, 0x01, 0xB8, 0x81, 0x2C, 0];
+            let ctx = DecodeContext {
+                revision: Revision::NPC_HEAD_ICON_SPRITES,
+                ..DecodeContext::new(2, 9)
+            };
+            let npc = NpcDefinition::from_buffer(1, &buffer, &ctx).unwrap();
+
+            let sprites = [Some((440, 2)), None, Some((440, 299))];
+            assert_eq!(npc.model_data.head_icon_sprites, sprites);
+            assert_eq!(npc.model_data.head_icon, None);
+            assert_eq!(npc.encode(), buffer);
+
+            let ctx = DecodeContext::new(2, 9);
+            let npc = NpcDefinition::from_buffer(1, &[102, 0x00, 0x05, 0], &ctx).unwrap();
+            assert_eq!(npc.model_data.head_icon, Some(5));
+        }
+
+        #[test]
+        fn hidden_on_minimap() {
+            use rscache::definition::osrs::{DecodeContext, Definition, NpcDefinition};
+
+            // Opcode 93 used to set the flag to true, which is its default.
+            let ctx = DecodeContext::new(2, 9);
+            let npc = NpcDefinition::from_buffer(1, &[0], &ctx).unwrap();
+            assert!(npc.visible_on_minimap);
+            let npc = NpcDefinition::from_buffer(1, &[93, 0], &ctx).unwrap();
+            assert!(!npc.visible_on_minimap);
+
+            let npc_loader = npc_loader();
+            let npc = npc_loader.load(101).unwrap();
+
+            assert_eq!(npc.name, "Rocks");
+            assert!(!npc.visible_on_minimap);
         }
 
         #[test]
@@ -74,6 +272,61 @@ mod osrs {
             assert!(npc.interactable);
         }
 
+        #[test]
+        fn resolve() {
+            use rscache::definition::osrs::TransformVar;
+
+            let npc_loader = npc_loader();
+            assert_eq!(npc_loader.resolve(3226, |_| 0).unwrap().id, 3226);
+            assert_eq!(npc_loader.resolve(119, |_| 0).unwrap().id, 6773);
+            assert!(npc_loader.resolve(119, |_| 2).is_none());
+
+            let veos = |value| {
+                npc_loader.resolve(1063, |var| {
+                    assert_eq!(var, TransformVar::Varbit(4897));
+                    value
+                })
+            };
+            assert_eq!(veos(0).unwrap().id, 8484);
+            assert_eq!(veos(1).unwrap().id, 8630);
+            assert_eq!(veos(-1).unwrap().id, 8630);
+        }
+
+        #[test]
+        fn current_opcodes() {
+            use rscache::definition::osrs::{DecodeContext, Definition, NpcDefinition};
+
+            #[rustfmt::skip]
+            let buffer = [
+                18, 0x00, 0x2A,
+                74, 0x00, 0x4B, 77, 0x00, 0xFF,
+                114, 0x03, 0x00,
+                117, 0x03, 0x01, 0x03, 0x02, 0x03, 0x03, 0x03, 0x04,
+                122, 123,
+                124, 0x00, 0xC8,
+                0,
+            ];
+
+            let ctx = DecodeContext::new(2, 9);
+            let npc = NpcDefinition::from_buffer(1, &buffer, &ctx).unwrap();
+
+            assert_eq!(npc.category, Some(42));
+            assert_eq!(npc.stats, [75, 0, 0, 255, 0, 0]);
+            assert_eq!(npc.animation_data.running, Some(0x300));
+            assert_eq!(npc.animation_data.crawling, Some(0x301));
+            assert_eq!(npc.animation_data.crawling_rotate_90_left, Some(0x304));
+            assert!(npc.follower);
+            assert!(npc.low_priority_follower_options);
+            assert_eq!(npc.model_data.height, Some(200));
+        }
+
+        #[test]
+        fn encode_round_trip() {
+            use rscache::definition::osrs::NpcDefinition;
+
+            test_util::assert_encode_round_trip(9, NpcDefinition::encode);
+        }
+
         #[test]
         fn non_existent() {
             let npc_loader = npc_loader();
@@ -121,6 +374,39 @@ mod osrs {
             assert!(!obj.obstruct_ground);
         }
 
+        #[test]
+        fn ambient_sounds() {
+            let obj_loader = obj_loader();
+            let beehive = obj_loader.load(68).unwrap();
+            assert_eq!(beehive.ambient_sound_id, 3045);
+            assert_eq!(beehive.ambient_sound_distance, 3);
+
+            let obj = obj_loader.load(7390).unwrap();
+            assert_eq!(obj.ambient_sound_ids, [3938, 3940, 3937, 3939, 3941]);
+            assert_eq!(obj.ambient_sound_min_ticks, 150);
+            assert_eq!(obj.ambient_sound_max_ticks, 250);
+            assert_eq!(obj.ambient_sound_distance, 12);
+        }
+
+        #[test]
+        fn sound_retain() {
+            use rscache::definition::osrs::{
+                DecodeContext, Definition, ObjectDefinition, Revision,
+            };
+
+            let buffer = [78, 0x00, 0x64, 5, 1, 0];
+            let ctx = DecodeContext {
+                revision: Revision::OBJECT_SOUND_RETAIN,
+                ..DecodeContext::new(2, 6)
+            };
+            let obj = ObjectDefinition::from_buffer(1, &buffer, &ctx).unwrap();
+
+            assert_eq!(obj.ambient_sound_id, 100);
+            assert_eq!(obj.ambient_sound_distance, 5);
+            assert_eq!(obj.ambient_sound_retain, Some(1));
+            assert_eq!(obj.encode(), buffer);
+        }
+
         #[test]
         fn dungeon_door() {
             let obj_loader = obj_loader();
@@ -132,6 +418,343 @@ mod osrs {
             assert!(obj.solid);
             assert!(!obj.obstruct_ground);
         }
+
+        #[test]
+        fn resolve() {
+            use rscache::definition::osrs::TransformVar;
+
+            let obj_loader = obj_loader();
+            assert_eq!(obj_loader.resolve(2030, |_| 0).unwrap().id, 2030);
+
+            let varp = |value| {
+                obj_loader.resolve(0, |var| {
+                    assert_eq!(var, TransformVar::Varp(0));
+                    value
+                })
+            };
+            assert!(varp(0).is_none());
+            assert_eq!(varp(3).unwrap().id, 15596);
+
+            let varbit = |value| {
+                obj_loader.resolve(1729, |var| {
+                    assert_eq!(var, TransformVar::Varbit(4621));
+                    value
+                })
+            };
+            assert_eq!(varbit(1).unwrap().id, 33343);
+            assert_eq!(varbit(5).unwrap().id, 1405);
+        }
+
+        #[test]
+        fn encode_round_trip() {
+            use rscache::definition::osrs::ObjectDefinition;
+
+            test_util::assert_encode_round_trip(6, ObjectDefinition::encode);
+        }
+    }
+
+    mod font_metrics {
+        use super::test_util;
+        use rscache::loader::osrs::FontMetricsLoader;
+
+        #[test]
+        fn p11_full() {
+            let font_loader = FontMetricsLoader::new(&test_util::osrs_cache()).unwrap();
+            let font = font_loader.load(494).unwrap();
+
+            assert_eq!(font.advances.len(), 256);
+            assert_eq!(font.ascent, 10);
+            assert_eq!(font.advance(b'A'), 7);
+            assert_eq!(font.text_width(b"Hello"), 25);
+        }
+    }
+
+    mod sprites {
+        use super::test_util;
+        use rscache::loader::osrs::SpriteLoader;
+
+        #[test]
+        fn compass() {
+            let sprite_loader = SpriteLoader::new(&test_util::osrs_cache()).unwrap();
+            let sprite = sprite_loader.load(169).unwrap();
+
+            assert_eq!((sprite.max_width, sprite.max_height), (51, 51));
+            assert_eq!(sprite.frames.len(), 1);
+
+            let frame = &sprite.frames[0];
+            assert_eq!(frame.pixels.len(), 51 * 51);
+            assert_eq!(frame.pixel(25, 25), Some(0xFFF6F5F1));
+            assert_eq!(frame.pixel(51, 0), None);
+            assert_eq!(&frame.to_rgba()[..4], &[0x39, 0x34, 0x25, 0xFF]);
+        }
+
+        #[test]
+        fn fetch() {
+            use rscache::definition::osrs::SpriteDefinition;
+
+            let cache = test_util::osrs_cache();
+            let sprite = SpriteDefinition::fetch(&cache, 169).unwrap().unwrap();
+
+            assert_eq!((sprite.id, sprite.max_width), (169, 51));
+            assert!(SpriteDefinition::fetch(&cache, 65_535).unwrap().is_none());
+        }
+    }
+
+    mod fonts {
+        use super::test_util;
+        use rscache::loader::osrs::FontLoader;
+
+        #[test]
+        fn p11_full() {
+            let font_loader = FontLoader::new(&test_util::osrs_cache()).unwrap();
+            let font = font_loader.load(494).unwrap();
+
+            assert_eq!(font.ascent(), 10);
+            assert_eq!(font.glyphs().frames.len(), 256);
+            assert_eq!(font.text_width("Hello"), 25);
+            assert_eq!(font.advance('\u{4E2D}'), font.advance('?'));
+            // cp1252 puts the euro sign at 0x80.
+            assert_eq!(font.glyph('\u{20AC}'), font.glyphs().frames.get(0x80));
+
+            let glyph = font.glyph('A').unwrap();
+            assert_eq!((glyph.offset_x, glyph.offset_y), (1, 1));
+            assert_eq!((glyph.width, glyph.height), (5, 8));
+            assert_eq!(glyph.pixels.iter().filter(|&&pixel| pixel != 0).count(), 18);
+        }
+    }
+
+    mod models {
+        use super::test_util;
+        use rscache::definition::osrs::{DecodeContext, Definition, ModelDefinition};
+
+        fn model(id: u32) -> ModelDefinition {
+            let buffer = test_util::osrs_cache()
+                .read(7, id)
+                .unwrap()
+                .decode()
+                .unwrap();
+            let ctx = DecodeContext::new(7, id);
+
+            ModelDefinition::from_buffer(id, &buffer, &ctx).unwrap()
+        }
+
+        #[test]
+        fn old_format() {
+            let model = model(0);
+
+            assert_eq!(model.vertex_count(), 176);
+            assert_eq!(model.face_count(), 327);
+            assert_eq!(model.vertices_x[0], -40);
+            assert_eq!(model.vertices_y[0], -10);
+            assert_eq!(model.vertices_z[0], -16);
+            assert_eq!(model.faces_a[1], 0);
+            assert_eq!(model.faces_b[1], 2);
+            assert_eq!(model.faces_c[1], 3);
+            assert_eq!(model.face_colors[0], 4550);
+        }
+
+        #[test]
+        fn new_format() {
+            let model = model(30000);
+
+            assert_eq!(model.vertex_count(), 104);
+            assert_eq!(model.face_count(), 176);
+            assert_eq!(model.vertices_x[0], -8);
+            assert_eq!(model.vertices_y[0], -98);
+            assert_eq!(model.vertices_z[0], -25);
+            assert_eq!(model.face_colors[0], 5012);
+            assert_eq!(model.priority, 2);
+        }
+
+        #[test]
+        fn unsupported_format() {
+            use rscache::{error::DefinitionError, Error};
+
+            let ctx = DecodeContext::new(7, 0);
+            for (footer, format) in [(0xFE, 2), (0xFD, 3)] {
+                let buffer = [0, 0, 0xFF, footer];
+                let err = ModelDefinition::from_buffer(0, &buffer, &ctx).unwrap_err();
+
+                assert!(matches!(
+                    err,
+                    Error::Definition(DefinitionError::UnsupportedModelFormat { id: 0, format: f })
+                        if f == format
+                ));
+            }
+        }
+    }
+
+    mod defaults {
+        use super::test_util;
+        use rscache::loader::osrs::DefaultsLoader;
+
+        #[test]
+        fn graphics() {
+            let defaults = DefaultsLoader::new(&test_util::osrs_cache()).unwrap();
+            let graphics = defaults.graphics();
+
+            assert_eq!(graphics.compass, Some(169));
+            assert_eq!(graphics.map_scenes, Some(317));
+            assert_eq!(graphics.mod_icons, Some(423));
+        }
+    }
+
+    mod unknown_opcodes {
+        use rscache::{
+            definition::osrs::{DecodeContext, Definition, UnderlayDefinition},
+            error::DefinitionError,
+            Error,
+        };
+
+        const BUFFER: [u8; 6] = [1, 0x12, 0x34, 0x56, 200, 0];
+
+        #[test]
+        fn strict() {
+            let ctx = DecodeContext::new(2, 1);
+            let err = UnderlayDefinition::from_buffer(7, &BUFFER, &ctx).unwrap_err();
+
+            assert!(matches!(
+                err,
+                Error::Definition(DefinitionError::UnknownOpcode {
+                    kind: "underlay",
+                    id: 7,
+                    opcode: 200,
+                })
+            ));
+        }
+
+        #[test]
+        fn lenient() {
+            let ctx = DecodeContext {
+                lenient: true,
+                ..DecodeContext::new(2, 1)
+            };
+            let underlay = UnderlayDefinition::from_buffer(7, &BUFFER, &ctx).unwrap();
+
+            assert_eq!(underlay.rgb, 0x123456);
+        }
+    }
+
+    mod generic {
+        use super::test_util;
+        use rscache::loader::{
+            osrs::{ItemLoader, NpcLoader},
+            Loader,
+        };
+
+        fn name_of<L: Loader>(id: L::Id) -> Option<String>
+        where
+            L::Definition: std::fmt::Debug,
+        {
+            let loader = L::new(&test_util::osrs_cache()).unwrap();
+            loader.load(id).map(|def| format!("{:?}", def))
+        }
+
+        #[test]
+        fn load_through_trait() {
+            assert!(name_of::<ItemLoader>(1042).unwrap().contains("Blue partyhat"));
+            assert!(name_of::<NpcLoader>(3226).unwrap().contains("Woodsman tutor"));
+            assert!(name_of::<ItemLoader>(65_535).is_none());
+        }
+
+        #[test]
+        fn iter_through_trait() {
+            let cache = test_util::osrs_cache();
+            let loader = <ItemLoader as Loader>::new(&cache).unwrap();
+
+            assert_eq!(Loader::iter(&loader).count(), loader.iter().count());
+        }
+    }
+
+    mod fetch_definition {
+        use super::test_util;
+        use rscache::definition::osrs::{DecodeContext, FetchDefinition, ItemDefinition};
+
+        #[test]
+        fn decode_group() {
+            let cache = test_util::osrs_cache();
+            let group = ItemDefinition::fetch_group(&cache, 2, 10).unwrap();
+            let items = ItemDefinition::decode_group(&group, &DecodeContext::new(2, 10)).unwrap();
+
+            assert_eq!(items[&1042].name, "Blue partyhat");
+            assert_eq!(items.len(), group.files().len());
+        }
+
+        #[test]
+        fn from_file() {
+            let cache = test_util::osrs_cache();
+            let item = ItemDefinition::fetch_from_file(&cache, 2, 10, 1042).unwrap();
+
+            assert_eq!(item.unwrap().name, "Blue partyhat");
+            assert!(ItemDefinition::fetch_from_file(&cache, 2, 10, 65_535)
+                .unwrap()
+                .is_none());
+            assert!(ItemDefinition::fetch_from_file(&cache, 2, 65_535, 0).is_err());
+        }
+
+        #[test]
+        fn by_name_and_keys() {
+            let cache = test_util::osrs_cache();
+            let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+            let archive_id = cache.archive_by_name(5, "l50_50").unwrap().id;
+
+            let group =
+                ItemDefinition::fetch_group_with_keys(&cache, 5, archive_id, &keys).unwrap();
+            assert_eq!(group.files()[0].data.len(), 11113);
+            assert!(ItemDefinition::fetch_group(&cache, 5, archive_id).is_err());
+
+            let group = ItemDefinition::fetch_group_by_name(&cache, 5, "m50_50").unwrap();
+            assert_eq!(group.files().len(), 1);
+            assert!(ItemDefinition::fetch_group_by_name(&cache, 5, "x50_50").is_err());
+        }
+    }
+
+    mod protocol {
+        use super::test_util;
+        use rscache::{
+            definition::{osrs::ItemDefinition, DecodeContext, Definition},
+            Cache, Protocol,
+        };
+
+        #[test]
+        fn detected() {
+            assert_eq!(test_util::osrs_cache().protocol(), Protocol::Osrs);
+        }
+
+        #[test]
+        fn model_id_format() {
+            let cache = Cache::builder()
+                .protocol(Protocol::Rs3)
+                .open("./data/osrs_cache")
+                .unwrap();
+            let osrs_cache = test_util::osrs_cache();
+
+            // Model ids below 32768 are stored the same way by both protocols.
+            assert_eq!(
+                ItemDefinition::fetch(&cache, 1042).unwrap(),
+                ItemDefinition::fetch(&osrs_cache, 1042).unwrap()
+            );
+
+            // Model 256 as a big smart, followed by the end of the definition.
+            let buffer = [1, 0x80, 0, 1, 0, 0];
+            let ctx = DecodeContext {
+                protocol: Some(Protocol::Rs3),
+                ..DecodeContext::new(2, 10)
+            };
+            let item = ItemDefinition::from_buffer(0, &buffer, &ctx).unwrap();
+            assert_eq!(item.inventory_model_data.inventory_model, 256);
+
+            let ctx = DecodeContext {
+                protocol: Some(Protocol::Osrs),
+                ..DecodeContext::new(2, 10)
+            };
+            assert_ne!(
+                ItemDefinition::from_buffer(0, &buffer, &ctx)
+                    .map(|item| item.inventory_model_data.inventory_model)
+                    .ok(),
+                Some(256)
+            );
+        }
     }
 
     mod locations {
@@ -151,6 +774,161 @@ mod osrs {
             assert_eq!(location_def.region_y, 50);
             assert_eq!(location_def.region_base_coords(), (3200, 3200));
             assert_eq!(location_def.data.len(), 4730);
+            assert_eq!(location_def.entries().count(), 4730);
+        }
+
+        #[test]
+        fn lumbridge_entries() {
+            let cache = test_util::osrs_cache();
+
+            let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+
+            let location_loader = LocationLoader::new(&cache);
+            let location_def = location_loader.load_uncached(12850, &keys).unwrap();
+
+            for (entry, location) in location_def.entries().zip(&location_def.data) {
+                assert_eq!(entry.id, location.id);
+                assert!(entry.x < 64 && entry.y < 64 && entry.plane < 4);
+                assert_eq!(entry.loc_type, location.loc_type);
+                assert_eq!(entry.rotation, location.orientation);
+
+                let (x, y) = entry.absolute_coords(location_def.region_base_coords());
+                assert!((3200..3264).contains(&x) && (3200..3264).contains(&y));
+            }
+        }
+
+        #[test]
+        fn lumbridge_uncached() {
+            let cache = test_util::osrs_cache();
+
+            let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+
+            let location_loader = LocationLoader::new(&cache);
+            let location_def = location_loader.load_uncached(12850, &keys).unwrap();
+
+            assert_eq!(location_def.region_base_coords(), (3200, 3200));
+            assert_eq!(location_def.data.len(), 4730);
+        }
+    }
+
+    mod regions {
+        use super::test_util;
+        use rscache::loader::osrs::{flags, RegionLoader};
+
+        #[test]
+        fn lumbridge() {
+            let cache = test_util::osrs_cache();
+
+            let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+
+            let region_loader = RegionLoader::new(&cache).unwrap();
+            let region = region_loader.load(12850, &keys).unwrap();
+
+            assert_eq!(region.id, 12850);
+            assert_eq!(region.region_base_coords(), (3200, 3200));
+            assert_eq!(region.locations.data.len(), 4730);
+
+            let blocked = (0..64)
+                .flat_map(|x| (0..64).map(move |y| (x, y)))
+                .filter(|&(x, y)| region.is_blocked(x, y, 0))
+                .count();
+            assert!(blocked > 0 && blocked < 64 * 64);
+            assert!((0..64).any(|x| (0..64)
+                .any(|y| region.collision_flags(x, y, 0) & flags::WALL_WEST != 0)));
+        }
+    }
+
+    mod collision {
+        use super::test_util;
+        use rscache::{
+            loader::osrs::{CollisionMap, RegionLoader},
+            util::xtea::XteaKeyStore,
+        };
+
+        #[test]
+        fn lumbridge() {
+            let cache = test_util::osrs_cache();
+
+            let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+            let key_store: XteaKeyStore = [(12850, keys), (0, [0; 4])].into_iter().collect();
+
+            let collision_map = CollisionMap::build(&cache, &key_store).unwrap();
+            let region = RegionLoader::new(&cache).unwrap().load(12850, &keys).unwrap();
+
+            assert_eq!(collision_map.len(), 1);
+            assert!(collision_map.contains_region(12850));
+            assert_eq!(collision_map.skipped(), &[0]);
+
+            for x in 0..64 {
+                for y in 0..64 {
+                    assert_eq!(
+                        collision_map.flags(3200 + x as u16, 3200 + y as u16, 0),
+                        Some(region.collision_flags(x, y, 0))
+                    );
+                }
+            }
+            assert!(collision_map.flags(0, 0, 0).is_none());
+            assert_eq!(collision_map.is_blocked(0, 0, 0), None);
+        }
+
+        #[test]
+        fn wall_on_region_border() {
+            use rscache::{
+                definition::osrs::{Location, LocationDefinition},
+                loader::osrs::{flags, MapLoader, Region},
+            };
+
+            let cache = test_util::osrs_cache();
+            let region_loader = RegionLoader::new(&cache).unwrap();
+            let objects = region_loader.objects();
+            let (&wall_id, _) = objects
+                .iter()
+                .find(|(_, object)| object.interact_type != 0)
+                .unwrap();
+
+            let map_loader = MapLoader::new(&cache);
+            let lumbridge_map = map_loader.load_uncached(12850).unwrap();
+            let west_map = map_loader.load_uncached(12594).unwrap();
+            let y = (0..64)
+                .find(|&y| lumbridge_map.map_data(0, y, 1).settings & 2 == 0)
+                .unwrap();
+
+            // A wall on the west edge of lumbridge, facing west.
+            let locations = LocationDefinition {
+                id: 12850,
+                region_x: 50,
+                region_y: 50,
+                data: vec![Location {
+                    id: wall_id,
+                    loc_type: 0,
+                    orientation: 0,
+                    pos: (50, 50 + y as u16, 0),
+                }],
+            };
+            let lumbridge = Region::new(lumbridge_map, locations, objects);
+            let west = Region::new(
+                west_map,
+                LocationDefinition {
+                    id: 12594,
+                    region_x: 49,
+                    region_y: 50,
+                    ..Default::default()
+                },
+                objects,
+            );
+
+            let border_y = 3200 + y as u16;
+            assert!(lumbridge.border_flags().iter().any(|border| {
+                (border.coords.x, border.coords.y) == (3199, border_y)
+                    && border.flag & flags::WALL_EAST != 0
+            }));
+            assert_eq!(west.collision_flags(63, y, 0) & flags::WALL_EAST, 0);
+
+            let collision_map = CollisionMap::from_regions([lumbridge, west]);
+            let east_of_west = collision_map.flags(3199, border_y, 0).unwrap();
+            let west_of_lumbridge = collision_map.flags(3200, border_y, 0).unwrap();
+            assert_ne!(east_of_west & flags::WALL_EAST, 0);
+            assert_ne!(west_of_lumbridge & flags::WALL_WEST, 0);
         }
     }
 
@@ -169,6 +947,40 @@ mod osrs {
             assert_eq!(map_def.region_y, 50);
             assert_eq!(map_def.region_base_coords(), (3200, 3200));
         }
+
+        #[test]
+        fn lumbridge_tiles() {
+            let cache = test_util::osrs_cache();
+
+            let map_loader = MapLoader::new(&cache);
+            let map_def = map_loader.load_uncached(12850).unwrap();
+
+            assert_eq!(map_def.tiles().count(), 4 * 64 * 64);
+            for (x, y, plane, tile) in map_def.tiles() {
+                let map_data = map_def.map_data(x, y, plane);
+
+                assert_eq!(map_def.tile(x, y, plane), tile);
+                assert_eq!(tile.settings, map_data.settings);
+                assert_eq!(tile.underlay_id.unwrap_or(0), map_data.underlay_id);
+                assert_eq!(tile.is_blocked(), map_data.settings & 1 == 1);
+            }
+            assert!(map_def.tiles().any(|(_, _, _, tile)| tile.overlay.is_some()));
+        }
+
+        #[test]
+        fn shared_across_threads() {
+            let cache = test_util::osrs_cache();
+            let map_loader = MapLoader::new(&cache);
+
+            std::thread::scope(|scope| {
+                for _ in 0..2 {
+                    scope.spawn(|| {
+                        let map_def = map_loader.load_uncached(12850).unwrap();
+                        assert_eq!(map_def.region_base_coords(), (3200, 3200));
+                    });
+                }
+            });
+        }
     }
 }
 
@@ -246,4 +1058,36 @@ mod rs3 {
             assert!(item.is_none());
         }
     }
+
+    mod quests {
+        use super::test_util;
+        use rscache::loader::rs3::QuestLoader;
+
+        #[test]
+        fn cooks_assistant() {
+            let quest_loader = QuestLoader::new(&test_util::rs3_cache()).unwrap();
+            let (_, quest) = quest_loader
+                .iter()
+                .find(|(_, quest)| quest.name == "Cook's Assistant")
+                .unwrap();
+
+            assert!(!quest.members_only);
+            assert!(quest.quest_points > 0);
+        }
+    }
+
+    mod config_groups {
+        use super::test_util;
+        use rscache::{definition::rs3::ItemDefinition, loader::rs3::ConfigGroupLoader};
+
+        #[test]
+        fn items() {
+            let cache = test_util::rs3_cache();
+            let item_loader = ConfigGroupLoader::<ItemDefinition>::new(&cache, 19).unwrap();
+
+            let item = item_loader.load(1042).unwrap();
+            assert_eq!(item.name, "Blue partyhat");
+            assert_eq!(item.id, 1042);
+        }
+    }
 }
@@ -0,0 +1,219 @@
+mod test_util;
+
+use rscache::net::{self, BLOCK_LEN, BLOCK_MARKER};
+
+#[test]
+fn encode_small_response() {
+    let cache = test_util::osrs_cache();
+    let buffer = cache.read(255, 10).unwrap();
+
+    let response = net::encode_response(255, 10, &buffer).unwrap();
+
+    assert_eq!(&response[..3], &[255, 0, 10]);
+    assert_eq!(&response[3..], buffer.as_slice());
+    assert_eq!(response.len(), 80);
+}
+
+#[test]
+fn encode_block_markers() {
+    let buffer = vec![1; 1200];
+    let response = net::encode_response(2, 10, &buffer).unwrap();
+
+    assert_eq!(response[BLOCK_LEN], BLOCK_MARKER);
+    assert_eq!(response[BLOCK_LEN * 2], BLOCK_MARKER);
+    assert_eq!(response.iter().filter(|&&byte| byte == BLOCK_MARKER).count(), 2);
+    assert_eq!(response.len(), 3 + 1200 + 2);
+}
+
+#[test]
+fn encode_archive_id_overflow() {
+    assert!(net::encode_response(2, u16::MAX as u32 + 1, &[1, 2, 3]).is_err());
+}
+
+mod downloader {
+    use super::test_util;
+    use rscache::net::{self, Js5Downloader};
+    use std::io::{self, Cursor, Read, Write};
+
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn request_all() {
+        let cache = test_util::osrs_cache();
+        let large = cache.read(2, 10).unwrap();
+        let large = &large[..large.len() - 2];
+        let small = cache.read(255, 10).unwrap();
+
+        // Responses arrive in a different order than they were requested.
+        let mut input = vec![0];
+        input.extend(net::encode_response(255, 10, &small).unwrap());
+        input.extend(net::encode_response(2, 10, large).unwrap());
+        let stream = MockStream {
+            input: Cursor::new(input),
+            output: Vec::new(),
+        };
+
+        let mut downloader = Js5Downloader::new(stream, 215).unwrap();
+        let buffers = downloader.request_all(&[(2, 10), (255, 10)]).unwrap();
+
+        assert_eq!(buffers[0].as_slice(), large);
+        assert_eq!(buffers[1].as_slice(), small.as_slice());
+    }
+
+    #[test]
+    fn update_from_remote() {
+        use rscache::codec::{Compression, EncodeOptions};
+
+        let cache = test_util::osrs_cache();
+        let mut checksum = cache.checksum().unwrap().encode().unwrap().decode().unwrap().to_vec();
+        // Pretend the reference table of index 2 changed on the server.
+        checksum[2 * 8] ^= 0xFF;
+        let checksum = EncodeOptions::new(Compression::None).encode(&checksum).unwrap();
+        let reference_table = cache.read(255, 2).unwrap();
+
+        let mut input = vec![0];
+        input.extend(net::encode_response(255, 255, &checksum).unwrap());
+        input.extend(net::encode_response(255, 2, &reference_table).unwrap());
+        let stream = MockStream {
+            input: Cursor::new(input),
+            output: Vec::new(),
+        };
+
+        let mut downloader = Js5Downloader::new(stream, 215).unwrap();
+        let update = cache.update_from_remote(&mut downloader).unwrap();
+
+        assert_eq!(update.len(), 1);
+        let archive = update.iter().next().unwrap();
+        assert_eq!((archive.index_id, archive.archive_id), (255, 2));
+        assert_eq!(archive.buffer.as_slice(), reference_table.as_slice());
+    }
+
+    #[test]
+    fn request_archive_id_overflow() {
+        let stream = MockStream {
+            input: Cursor::new(vec![0]),
+            output: Vec::new(),
+        };
+
+        let mut downloader = Js5Downloader::new(stream, 215).unwrap();
+        assert!(downloader.request(2, u16::MAX as u32 + 1).is_err());
+    }
+
+    #[test]
+    fn handshake_rejected() {
+        let stream = MockStream {
+            input: Cursor::new(vec![6]),
+            output: Vec::new(),
+        };
+
+        assert!(Js5Downloader::new(stream, 1).is_err());
+    }
+}
+
+mod session {
+    use super::test_util;
+    use rscache::net::{self, Js5Session, PREFETCH_FLAG};
+
+    #[test]
+    fn urgent_before_prefetch() {
+        let cache = test_util::osrs_cache();
+        let mut session = Js5Session::new(&cache).unwrap();
+
+        // Prefetch 255/2 and 255/10, then urgent 255/10 twice.
+        let requests = [0, 255, 0, 2, 0, 255, 0, 10, 1, 255, 0, 10, 1, 255, 0, 10];
+        session.receive(&requests[..10]).unwrap();
+        session.receive(&requests[10..]).unwrap();
+        assert_eq!(session.pending(), 2);
+
+        let small = cache.read(255, 10).unwrap();
+        let response = session.poll_response().unwrap().unwrap();
+        assert_eq!(response, net::encode_response(255, 10, &small).unwrap());
+
+        let buffer = cache.read(255, 2).unwrap();
+        let mut expected = net::encode_response(255, 2, &buffer).unwrap();
+        expected[3] |= PREFETCH_FLAG;
+        assert_eq!(session.poll_response().unwrap().unwrap(), expected);
+        assert!(session.poll_response().unwrap().is_none());
+    }
+
+    #[test]
+    fn encryption_key() {
+        let cache = test_util::osrs_cache();
+        let mut session = Js5Session::new(&cache).unwrap();
+
+        session.receive(&[4, 0x5A, 0, 0, 1, 2, 0, 10]).unwrap();
+        let response = session.poll_response().unwrap().unwrap();
+
+        let buffer = cache.read(2, 10).unwrap();
+        let expected = net::encode_response(2, 10, &buffer[..buffer.len() - 2]).unwrap();
+        let decrypted: Vec<_> = response.iter().map(|byte| byte ^ 0x5A).collect();
+        assert_eq!(decrypted, expected);
+    }
+
+    #[test]
+    fn logged_in_and_disconnect() {
+        let cache = test_util::osrs_cache();
+        let mut session = Js5Session::new(&cache).unwrap();
+
+        session.receive(&[2, 0, 0, 0, 1, 255, 0, 10]).unwrap();
+        assert!(session.is_logged_in());
+
+        session.receive(&[7, 0, 0, 0, 1, 255, 0, 2]).unwrap();
+        assert!(session.is_closed());
+        assert!(session.poll_response().unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_opcode() {
+        let cache = test_util::osrs_cache();
+        let mut session = Js5Session::new(&cache).unwrap();
+
+        assert!(session.receive(&[9, 0, 0, 0]).is_err());
+    }
+}
+
+#[cfg(feature = "async")]
+mod js5 {
+    use super::test_util;
+    use rscache::net::{self, Js5Responder};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn respond() {
+        let cache = Arc::new(test_util::osrs_cache());
+        let responder = Js5Responder::new(Arc::clone(&cache)).await.unwrap();
+
+        let mut stream = Vec::new();
+        responder.respond(255, 10, &mut stream).await.unwrap();
+
+        let buffer = cache.read(255, 10).unwrap();
+        assert_eq!(stream, net::encode_response(255, 10, &buffer).unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_async() {
+        let cache = Arc::new(test_util::osrs_cache());
+
+        let buffer = cache.read_async(2, 10).await.unwrap();
+        assert_eq!(buffer.as_slice(), cache.read(2, 10).unwrap().as_slice());
+    }
+}
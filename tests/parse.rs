@@ -0,0 +1,97 @@
+use rscache::{
+    extension::ReadExt,
+    parse::{be_i16_smart, be_u16_smart, be_u32_smart, be_u32_smart_compat, var_u32, var_u64},
+    util::charset,
+};
+
+#[test]
+fn u16_smart() {
+    assert_eq!(be_u16_smart(&[0x7F]), Ok((&[][..], 127)));
+    assert_eq!(be_u16_smart(&[0x80, 0x80, 0x01]), Ok((&[0x01][..], 128)));
+    assert_eq!(be_u16_smart(&[0xFF, 0xFF]), Ok((&[][..], 32767)));
+    assert!(be_u16_smart(&[0x80]).is_err());
+    assert!(be_u16_smart(&[]).is_err());
+}
+
+#[test]
+fn i16_smart() {
+    assert_eq!(be_i16_smart(&[0x00]), Ok((&[][..], -64)));
+    assert_eq!(be_i16_smart(&[0x7F]), Ok((&[][..], 63)));
+    assert_eq!(be_i16_smart(&[0x80, 0x00]), Ok((&[][..], -16384)));
+    assert_eq!(be_i16_smart(&[0xFF, 0xFF]), Ok((&[][..], 16383)));
+}
+
+#[test]
+fn u32_smart() {
+    assert_eq!(be_u32_smart(&[0x7F, 0xFF]), Ok((&[][..], 32767)));
+    assert_eq!(
+        be_u32_smart(&[0x80, 0x00, 0x80, 0x00]),
+        Ok((&[][..], 32768))
+    );
+    assert_eq!(
+        be_u32_smart(&[0xFF, 0xFF, 0xFF, 0xFF]),
+        Ok((&[][..], i32::MAX as u32))
+    );
+    assert!(be_u32_smart(&[0x80, 0x00]).is_err());
+}
+
+#[test]
+fn u32_smart_compat() {
+    assert_eq!(be_u32_smart_compat(&[0x05]), Ok((&[][..], 5)));
+    assert_eq!(
+        be_u32_smart_compat(&[0xFF, 0xFF, 0xFF, 0xFF, 0x02]),
+        Ok((&[][..], 32767 * 2 + 2))
+    );
+}
+
+#[test]
+fn var_int() {
+    assert_eq!(var_u32(&[0x7F]), Ok((&[][..], 127)));
+    assert_eq!(var_u32(&[0x81, 0x00]), Ok((&[][..], 128)));
+    assert_eq!(
+        var_u64(&[0x81, 0x80, 0x80, 0x00, 0x01]),
+        Ok((&[0x01][..], 1 << 21))
+    );
+    assert!(var_u32(&[0x81]).is_err());
+}
+
+#[test]
+fn read_smart() {
+    let mut reader: &[u8] = &[0x7F, 0xFF, 0x80, 0x00, 0x80, 0x00];
+
+    assert_eq!(reader.read_smart().unwrap(), 32767);
+    assert_eq!(reader.read_smart().unwrap(), 32768);
+    assert!(reader.read_smart().is_err());
+}
+
+#[test]
+fn read_signed_and_nullable_smarts() {
+    let mut reader: &[u8] = &[
+        0x41, 0xC0, 0x05, 0x80, 0x80, 0x00, 0x00, 0x05, 0x7F, 0xFF, 0x00, 0x07,
+    ];
+
+    assert_eq!(reader.read_smart_u16().unwrap(), 1);
+    assert_eq!(reader.read_smart_u16().unwrap(), 5);
+    assert_eq!(reader.read_usmart().unwrap(), 128);
+    assert_eq!(reader.read_i16_smart().unwrap(), -64);
+    assert_eq!(reader.read_nullable_smart_u16().unwrap(), None);
+    assert_eq!(reader.read_nullable_smart_u16().unwrap(), Some(4));
+    assert_eq!(reader.read_nullable_smart().unwrap(), None);
+    assert_eq!(reader.read_nullable_smart().unwrap(), Some(7));
+}
+
+#[test]
+fn read_smart_compat() {
+    let mut reader: &[u8] = &[0xFF, 0xFF, 0x03];
+
+    assert_eq!(reader.read_smart_compat().unwrap(), 32770);
+}
+
+#[test]
+fn read_string() {
+    let mut reader: &[u8] = &[b'5', 0x80, 0x00, 0xE0, 0x81, 0x00];
+
+    assert_eq!(reader.read_string().unwrap(), "5€");
+    assert_eq!(reader.read_string().unwrap(), "à?");
+    assert_eq!(charset::encode("5€ ✓"), [b'5', 0x80, b' ', b'?']);
+}
@@ -68,6 +68,96 @@ mod osrs {
         let cache = test_util::osrs_cache();
         assert!(cache.read(2, 25_000).is_err());
     }
+
+    #[test]
+    fn streamed_read() {
+        use std::io::Read;
+
+        let cache = test_util::osrs_cache();
+        let mut reader = cache.reader(2, 10).unwrap();
+
+        let mut buffer = Vec::new();
+        let mut chunk = [0; 100];
+        loop {
+            let len = reader.read(&mut chunk).unwrap();
+            if len == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..len]);
+        }
+
+        let hash = test_util::hash(&buffer);
+        assert_eq!(&hash, "c6ee1518e9a39a42ecaf946c6c84a942cb3102f4");
+        assert_eq!(buffer.len(), 260_537);
+    }
+
+    #[test]
+    fn borrowed_read() {
+        let cache = test_util::osrs_cache();
+        let result = cache.read_borrowed(3, 278).unwrap();
+
+        assert!(result.is_borrowed());
+        let hash = test_util::hash(&result);
+        assert_eq!(&hash, "036abb64d3f1734d892f69b1253a87639b7bcb44");
+        assert_eq!(result.len(), 512);
+    }
+
+    #[test]
+    fn borrowed_read_falls_back_to_owned() {
+        let cache = test_util::osrs_cache();
+        let result = cache.read_borrowed(2, 10).unwrap();
+
+        assert!(!result.is_borrowed());
+        assert_eq!(
+            result.into_owned().as_slice(),
+            cache.read(2, 10).unwrap().as_slice()
+        );
+    }
+
+    #[test]
+    fn sectors() {
+        let cache = test_util::osrs_cache();
+        let len = cache.read(2, 10).unwrap().len();
+        let sectors: Vec<_> = cache
+            .sectors(2, 10)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(sectors.len(), len.div_ceil(512));
+        for (chunk, (offset, header)) in sectors.iter().enumerate() {
+            assert_eq!(offset % 520, 0);
+            assert_eq!((header.archive_id, header.index_id), (10, 2));
+            assert_eq!(header.chunk, chunk);
+            if let Some((next_offset, _)) = sectors.get(chunk + 1) {
+                assert_eq!(header.next as u64 * 520, *next_offset);
+            }
+        }
+        assert!(cache.sectors(2, 25_000).is_err());
+    }
+
+    #[test]
+    fn archive_crc_and_version() {
+        let cache = test_util::osrs_cache();
+        let archive = cache.index_metadata(2).unwrap().get(10).unwrap();
+
+        assert_eq!(cache.archive_crc(2, 10).unwrap(), 0xE871_2D78);
+        assert_eq!(cache.archive_crc(2, 10).unwrap(), archive.crc);
+        assert_eq!(cache.archive_version(2, 10).unwrap(), Some(918));
+        assert_eq!(archive.version, 918);
+
+        let checksum = cache.checksum().unwrap();
+        let entry = checksum.iter().nth(2).unwrap();
+        assert_eq!(cache.archive_crc(255, 2).unwrap(), entry.crc());
+        assert_eq!(cache.archive_version(255, 2).unwrap(), None);
+        assert!(cache.archive_crc(2, 25_000).is_err());
+    }
+
+    #[test]
+    fn streamed_read_fails() {
+        let cache = test_util::osrs_cache();
+        assert!(cache.reader(2, 25_000).is_err());
+    }
 }
 
 #[cfg(all(test, feature = "rs3"))]
@@ -100,3 +190,518 @@ mod rs3 {
         assert!(cache.read(2, 25_000).is_err());
     }
 }
+
+#[test]
+fn decode_ref() {
+    use rscache::codec::EncodedExt;
+
+    let cache = test_util::osrs_cache();
+    let encoded = cache.read(2, 10).unwrap();
+
+    let decoded = encoded.decode_ref().unwrap();
+    let expected = cache.read(2, 10).unwrap().decode().unwrap();
+
+    assert_eq!(decoded.as_slice(), expected.as_slice());
+    assert_eq!(encoded.as_slice(), cache.read(2, 10).unwrap().as_slice());
+}
+
+#[test]
+fn decode_with_metadata() {
+    use rscache::codec::{Compression, EncodedExt};
+
+    let cache = test_util::osrs_cache();
+
+    let decoded = cache.read(2, 10).unwrap().decode_with_metadata().unwrap();
+    assert_eq!(decoded.compression(), Compression::Bzip2);
+    assert_eq!(decoded.version(), Some(918));
+    assert_eq!(decoded.len(), 886_570);
+
+    let decoded = cache.read(255, 2).unwrap().decode_with_metadata().unwrap();
+    assert_eq!(decoded.compression(), Compression::Bzip2);
+    assert_eq!(decoded.version(), None);
+}
+
+#[test]
+fn strip_version() {
+    use rscache::codec::EncodedExt;
+
+    let cache = test_util::osrs_cache();
+    let mut buffer = cache.read(2, 10).unwrap();
+    let len = buffer.len();
+    assert_eq!(buffer.without_version().len(), len - 2);
+
+    assert_eq!(buffer.strip_version(), Some(918));
+    assert_eq!(buffer.len(), len - 2);
+    assert_eq!(buffer.strip_version(), None);
+    assert_eq!(buffer.len(), len - 2);
+
+    let table = cache.read(255, 2).unwrap();
+    assert_eq!(table.without_version(), table.as_slice());
+}
+
+#[test]
+fn encode_identical() {
+    use rscache::codec::EncodedExt;
+
+    let cache = test_util::osrs_cache();
+    let original = cache.read(2, 10).unwrap();
+    let encoded = original.decode_with_metadata().unwrap().encode().unwrap();
+
+    assert_eq!(encoded.as_slice(), original.as_slice());
+}
+
+#[test]
+fn encode_gzip() {
+    use rscache::codec::{Compression, EncodeOptions, EncodedExt};
+
+    let cache = test_util::osrs_cache();
+    let decoded = cache.read(0, 0).unwrap().decode_with_metadata().unwrap();
+    assert_eq!(decoded.compression(), Compression::Gzip);
+
+    let encoded = EncodeOptions::new(Compression::Gzip).encode(&decoded).unwrap();
+    assert_eq!(&encoded[9..19], &[0x1F, 0x8B, 0x08, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(encoded.decode().unwrap().as_slice(), &*decoded);
+}
+
+#[test]
+fn encode_deflate() {
+    use rscache::codec::{Compression, EncodeOptions, EncodedExt};
+
+    let data = b"raw deflate streams go through the same pipeline".repeat(8);
+    let encoded = EncodeOptions::new(Compression::Deflate)
+        .version(3)
+        .encode(&data)
+        .unwrap();
+
+    let decoded = encoded.decode_with_metadata().unwrap();
+    assert_eq!(decoded.compression(), Compression::Deflate);
+    assert_eq!(decoded.version(), Some(3));
+    assert_eq!(&*decoded, data.as_slice());
+}
+
+#[test]
+fn deflate_length_mismatch() {
+    use rscache::{
+        codec::{Buffer, Compression, EncodeOptions, Encoded, EncodedExt},
+        error::CodecError,
+        Error,
+    };
+
+    let data = b"the header decides how much gets inflated".repeat(8);
+    let encoded = EncodeOptions::new(Compression::Deflate)
+        .encode(&data)
+        .unwrap();
+
+    for len in [data.len() - 1, data.len() + 1] {
+        let mut buffer = encoded.to_vec();
+        buffer[5..9].copy_from_slice(&(len as u32).to_be_bytes());
+        let buffer: Buffer<Encoded> = Buffer::from(buffer);
+
+        let err = buffer.decode_ref().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Codec(CodecError::LengthMismatch { expected }) if expected == len
+        ));
+    }
+}
+
+#[test]
+fn lzma_support() {
+    use rscache::{
+        codec::{Buffer, Compression, Encoded, EncodedExt},
+        error::CodecError,
+        Error,
+    };
+
+    assert!(Compression::Bzip2.is_supported());
+    assert_eq!(Compression::Lzma.is_supported(), cfg!(feature = "lzma"));
+
+    let buffer: Buffer<Encoded> = Buffer::from(vec![3, 0, 0, 0, 1, 0, 0, 0, 1, 0]);
+    if !cfg!(feature = "lzma") {
+        let err = buffer.decode_ref().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Codec(CodecError::DecodeUnsupported(Compression::Lzma))
+        ));
+    }
+}
+
+#[test]
+fn encode_levels() {
+    use rscache::{
+        codec::{Compression, EncodeOptions},
+        error::CodecError,
+        Error,
+    };
+
+    let data = b"higher levels trade encoding time for size".repeat(64);
+    for level in 1..=9 {
+        let encoded = EncodeOptions::new(Compression::Bzip2)
+            .level(level)
+            .encode(&data)
+            .unwrap();
+        assert_eq!(encoded.decode().unwrap().as_slice(), data.as_slice());
+    }
+
+    let err = EncodeOptions::new(Compression::Gzip)
+        .level(10)
+        .encode(&data)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Codec(CodecError::InvalidLevel { level: 10, .. })
+    ));
+}
+
+#[test]
+fn index_metadata() {
+    let cache = test_util::osrs_cache();
+    let metadata = cache.index_metadata(2).unwrap();
+
+    assert_eq!(metadata.get(10).unwrap().version, 918);
+    assert!(std::ptr::eq(metadata, cache.index_metadata(2).unwrap()));
+    assert!(cache.index_metadata(100).is_err());
+}
+
+#[test]
+fn from_buffers() {
+    let path = "./data/osrs_cache";
+    let data = std::fs::read(format!("{}/main_file_cache.dat2", path)).unwrap();
+    let indices = (0..=20).chain([255]).map(|index_id| {
+        let index = std::fs::read(format!("{}/main_file_cache.idx{}", path, index_id)).unwrap();
+        (index_id, index)
+    });
+
+    let cache = rscache::Cache::from_buffers(data, indices).unwrap();
+    let buffer = cache.read(2, 10).unwrap();
+
+    assert_eq!(&test_util::hash(&buffer), "c6ee1518e9a39a42ecaf946c6c84a942cb3102f4");
+    assert!(cache.archive_by_name(10, "huffman").is_ok());
+}
+
+#[test]
+fn cyclic_sector_chain() {
+    use rscache::error::SectorError;
+
+    // Sector 1 is the first chunk of archive 0 in index 255 and points back to
+    // itself.
+    let mut data = vec![0; 1040];
+    data[520..528].copy_from_slice(&[0, 0, 0, 0, 0, 0, 1, 255]);
+    let index = [0, 0x03, 0xE8, 0, 0, 1];
+
+    let cache = rscache::Cache::from_buffers(data, [(255, index)]).unwrap();
+    let err = cache.read(255, 0).unwrap_err();
+    assert!(matches!(
+        err,
+        rscache::Error::Sector(SectorError::CyclicChain { sector: 1 })
+    ));
+
+    let mut reader = cache.reader(255, 0).unwrap();
+    let err = std::io::copy(&mut reader, &mut std::io::sink()).unwrap_err();
+    assert!(matches!(
+        err.into_inner().unwrap().downcast_ref(),
+        Some(SectorError::CyclicChain { sector: 1 })
+    ));
+}
+
+#[test]
+fn cyclic_sectors() {
+    use rscache::error::SectorError;
+
+    let mut data = vec![0; 1040];
+    data[520..528].copy_from_slice(&[0, 0, 0, 0, 0, 0, 1, 255]);
+    let index = [0, 0x03, 0xE8, 0, 0, 1];
+
+    let cache = rscache::Cache::from_buffers(data, [(255, index)]).unwrap();
+    let mut sectors = cache.sectors(255, 0).unwrap();
+    let (offset, header) = sectors.next().unwrap().unwrap();
+    assert_eq!((offset, header.next), (520, 1));
+    assert!(matches!(
+        sectors.next(),
+        Some(Err(SectorError::CyclicChain { sector: 1 }))
+    ));
+    assert!(sectors.next().is_none());
+}
+
+#[test]
+fn data_blocks() {
+    use rscache::fs::consts::{DataBlocks, SECTOR_DATA_SIZE, SECTOR_EXPANDED_DATA_SIZE};
+
+    let blocks: Vec<_> = DataBlocks::new(10, 1200).collect();
+    assert_eq!(blocks, [SECTOR_DATA_SIZE, SECTOR_DATA_SIZE, 176]);
+
+    let blocks: Vec<_> = DataBlocks::new(70_000, 1020).collect();
+    assert_eq!(blocks, [SECTOR_EXPANDED_DATA_SIZE; 2]);
+    assert_eq!(DataBlocks::new(10, 0).len(), 0);
+
+    let cache = test_util::osrs_cache();
+    let len = cache.read(2, 10).unwrap().len();
+    let sectors = cache.sectors(2, 10).unwrap().count();
+    assert_eq!(DataBlocks::new(10, len).len(), sectors);
+}
+
+#[test]
+fn heap_backend() {
+    use rscache::{Backend, Cache};
+
+    let cache = Cache::new_with_backend("./data/osrs_cache", Backend::Heap).unwrap();
+    let buffer = cache.read(2, 10).unwrap();
+
+    assert_eq!(
+        &test_util::hash(&buffer),
+        "c6ee1518e9a39a42ecaf946c6c84a942cb3102f4"
+    );
+}
+
+#[test]
+fn truncated_data() {
+    use rscache::error::SectorError;
+
+    let path = "./data/osrs_cache";
+    let data = std::fs::read(format!("{}/main_file_cache.dat2", path)).unwrap();
+    let index = std::fs::read(format!("{}/main_file_cache.idx255", path)).unwrap();
+
+    // Cut the data file off halfway through the first sector of archive 2.
+    let sector = u32::from_be_bytes([0, index[15], index[16], index[17]]) as usize;
+    let data = data[..sector * 520 + 100].to_vec();
+
+    let err = rscache::Cache::from_buffers(data, [(255, &index)])
+        .and_then(|cache| cache.read(255, 2))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        rscache::Error::Sector(SectorError::LengthMismatch { .. })
+    ));
+}
+
+#[test]
+fn sector_out_of_bounds() {
+    use rscache::error::SectorError;
+
+    let index = std::fs::read("./data/osrs_cache/main_file_cache.idx255").unwrap();
+
+    let err = rscache::Cache::from_buffers(Vec::<u8>::new(), [(255, &index)])
+        .and_then(|cache| cache.read(255, 2))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        rscache::Error::Sector(SectorError::SectorOutOfBounds { offset, .. }) if offset > 0
+    ));
+}
+
+#[test]
+fn large_sector_offset() {
+    use rscache::error::SectorError;
+
+    // Archive 255/0 is 10 bytes long and starts at the last addressable sector.
+    let index = [0, 0, 10, 255, 255, 255];
+
+    let err = rscache::Cache::from_buffers(vec![0; 520], [(255, &index)])
+        .and_then(|cache| cache.read(255, 0))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        rscache::Error::Sector(SectorError::SectorOutOfBounds {
+            sector: 0xFF_FFFF,
+            offset: 8_724_151_800,
+        })
+    ));
+}
+
+#[test]
+fn fs_reexport() {
+    use rscache::fs::{Dat2, Indices, MAIN_DATA};
+
+    let path = std::path::Path::new("./data/osrs_cache");
+    let data = Dat2::new(path.join(MAIN_DATA)).unwrap();
+    let indices = Indices::new(path).unwrap();
+
+    let archive = &indices.get(&2).unwrap().archive_refs[&10];
+    let cache = rscache::Cache::new(path).unwrap();
+    assert_eq!(
+        data.read(archive).unwrap().as_slice(),
+        cache.read(2, 10).unwrap().as_slice()
+    );
+}
+
+#[test]
+fn builder() {
+    use rscache::{util::xtea::XteaKeyStore, Backend, Cache, Protocol};
+
+    let mut keys = XteaKeyStore::new();
+    keys.insert(12850, [3030157619, 2364842415, 3297319647, 1973582566]);
+
+    let cache = Cache::builder()
+        .backend(Backend::Heap)
+        .decoded_cache(1024 * 1024)
+        .lazy_metadata(false)
+        .xtea_keys(keys)
+        .protocol(Protocol::Rs3)
+        .open("./data/osrs_cache")
+        .unwrap();
+
+    assert_eq!(cache.protocol(), Protocol::Rs3);
+    assert!(cache.xtea_keys().get(12850).is_some());
+    assert_eq!(
+        cache.read(2, 10).unwrap().as_slice(),
+        test_util::osrs_cache().read(2, 10).unwrap().as_slice()
+    );
+    assert_eq!(test_util::osrs_cache().protocol(), Protocol::Osrs);
+}
+
+#[test]
+fn from_files() {
+    let path = std::path::Path::new("./data/osrs_cache");
+    let data = path.join("main_file_cache.dat2");
+    let indices: Vec<_> = (0..=20)
+        .chain([255])
+        .map(|index_id| path.join(format!("main_file_cache.idx{}", index_id)))
+        .collect();
+
+    let cache = rscache::Cache::from_files(&data, &indices).unwrap();
+    let buffer = cache.read(2, 10).unwrap();
+    assert_eq!(
+        &test_util::hash(&buffer),
+        "c6ee1518e9a39a42ecaf946c6c84a942cb3102f4"
+    );
+
+    let err = rscache::Cache::from_files(&data, [path.join("main_file_cache.dat2")]).unwrap_err();
+    assert!(matches!(err, rscache::Error::InvalidIndexFile { .. }));
+
+    let indices = (0..=20).chain([255]).zip(&indices);
+    let cache = rscache::Cache::from_files_with_ids(&data, indices).unwrap();
+    assert!(cache.read(2, 10).is_ok());
+}
+
+#[test]
+fn decoded_cache() {
+    use rscache::codec::{Compression, EncodeOptions};
+
+    let mut cache = test_util::osrs_cache().with_decoded_cache(1024 * 1024);
+    let expected = cache.read(0, 191).unwrap().decode().unwrap();
+
+    for _ in 0..2 {
+        let buffer = cache.read_decoded(0, 191).unwrap();
+        assert_eq!(buffer.as_slice(), expected.as_slice());
+    }
+    let shared = cache.read_decoded_shared(0, 191).unwrap();
+    assert!(std::sync::Arc::ptr_eq(
+        &shared,
+        &cache.read_decoded_shared(0, 191).unwrap()
+    ));
+    assert_eq!(&*shared, expected.as_slice());
+
+    let buffer = EncodeOptions::new(Compression::None)
+        .version(1)
+        .encode(b"overlay")
+        .unwrap();
+    cache.overlay(0, 191, buffer.as_slice()).unwrap();
+    assert_eq!(cache.read_decoded(0, 191).unwrap().as_slice(), b"overlay");
+}
+
+#[test]
+fn observer() {
+    use rscache::{
+        observer::{CacheObserver, DecodeEvent, ReadEvent},
+        Cache,
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Events {
+        reads: Mutex<Vec<ReadEvent>>,
+        decodes: Mutex<Vec<DecodeEvent>>,
+    }
+
+    impl CacheObserver for Events {
+        fn on_read(&self, event: &ReadEvent) {
+            self.reads.lock().unwrap().push(*event);
+        }
+
+        fn on_decode(&self, event: &DecodeEvent) {
+            self.decodes.lock().unwrap().push(*event);
+        }
+    }
+
+    let events = Arc::new(Events::default());
+    let cache = Cache::builder()
+        .decoded_cache(1024 * 1024)
+        .observer(Arc::clone(&events))
+        .open("./data/osrs_cache")
+        .unwrap();
+
+    let encoded = cache.read(2, 10).unwrap();
+    let decoded = cache.read_decoded(2, 10).unwrap();
+    cache.read_decoded(2, 10).unwrap();
+    assert!(cache.read(2, 100_000).is_err());
+
+    let reads = events.reads.lock().unwrap();
+    let archives: Vec<_> = reads
+        .iter()
+        .map(|read| (read.index_id, read.archive_id))
+        .collect();
+    assert_eq!(archives, [(2, 10), (2, 10)]);
+    assert_eq!(reads[0].bytes, encoded.len());
+    assert!(!reads[0].overlay);
+
+    let decodes = events.decodes.lock().unwrap();
+    assert_eq!(decodes.len(), 2);
+    assert_eq!(decodes[0].bytes, decoded.len());
+    assert!(!decodes[0].cached);
+    assert!(decodes[1].cached);
+}
+
+#[test]
+fn overlay() {
+    use rscache::{codec::EncodedExt, metadata::IndexMetadata};
+    use std::io::Read;
+
+    let mut cache = test_util::osrs_cache();
+    let checksum = cache.checksum().unwrap().encode().unwrap();
+    let decoded = cache.read(0, 191).unwrap().decode_with_metadata().unwrap();
+    let buffer = decoded.encode_options().version(1337).encode(&decoded).unwrap();
+
+    cache.overlay(0, 191, buffer.as_slice()).unwrap();
+    assert_eq!(cache.read(0, 191).unwrap().as_slice(), buffer.as_slice());
+    assert!(cache.read_borrowed(0, 191).unwrap().is_borrowed());
+
+    let mut streamed = Vec::new();
+    cache.reader(0, 191).unwrap().read_to_end(&mut streamed).unwrap();
+    assert_eq!(streamed, buffer.as_slice());
+
+    let table = cache.read(255, 0).unwrap().decode().unwrap();
+    let metadata = IndexMetadata::from_buffer(&table).unwrap();
+    assert_eq!(metadata.get(191).unwrap().version, 1337);
+    assert_ne!(cache.checksum().unwrap().encode().unwrap().as_slice(), checksum.as_slice());
+
+    cache.clear_overlays();
+    assert_eq!(cache.checksum().unwrap().encode().unwrap().as_slice(), checksum.as_slice());
+}
+
+#[test]
+fn archive_file_group() {
+    use rscache::{
+        archive::ArchiveFileGroup, error::ArchiveGroupError, metadata::IndexMetadata, Error,
+    };
+
+    let cache = test_util::osrs_cache();
+    let table = cache.read(255, 2).unwrap().decode().unwrap();
+    let metadata = IndexMetadata::from_buffer(&table).unwrap();
+    let entry_count = metadata.get(10).unwrap().entry_count;
+
+    let buffer = cache.read(2, 10).unwrap().decode().unwrap();
+    let group = ArchiveFileGroup::try_from_buffer(&buffer, entry_count).unwrap();
+    assert_eq!(group.files().len(), entry_count);
+
+    let err = ArchiveFileGroup::try_from_buffer(&[0, 0, 1], 2).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ArchiveGroup(ArchiveGroupError::InvalidChunkCount { .. })
+    ));
+
+    let err = ArchiveFileGroup::try_from_buffer(&[1, 0, 0, 0, 2, 0, 0, 0, 0, 1], 2).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ArchiveGroup(ArchiveGroupError::OutOfRange { .. })
+    ));
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "render")]
+
+mod test_util;
+
+use rscache::{
+    loader::osrs::{ItemLoader, OverlayLoader, TextureLoader, UnderlayLoader},
+    render::{self, ItemIconRenderer, MapImageRenderer},
+};
+
+#[test]
+fn hsl_to_rgb() {
+    assert_eq!(render::hsl_to_rgb(0), 0x000000);
+    assert_eq!(render::hsl_to_rgb(0x7F), 0xFEFDFD);
+    assert_eq!(render::hsl_to_rgb(0x9D4D), 0x7A8DB9);
+}
+
+#[test]
+fn floor_definitions() {
+    let cache = test_util::osrs_cache();
+    let underlays = UnderlayLoader::new(&cache).unwrap();
+    let overlays = OverlayLoader::new(&cache).unwrap();
+    let textures = TextureLoader::new(&cache).unwrap();
+
+    assert_eq!(underlays.load(0).unwrap().rgb, 0x282820);
+    assert_eq!(overlays.load(0).unwrap().rgb, 0xAAAAAA);
+    assert_eq!(overlays.load(4).unwrap().texture, Some(3));
+    assert_eq!(textures.load(1).unwrap().average_hsl, 0x9D4D);
+    assert_eq!(textures.load(0).unwrap().sprite_ids, vec![447]);
+}
+
+#[test]
+fn render_region() {
+    let cache = test_util::osrs_cache();
+    let renderer = MapImageRenderer::new(&cache).unwrap();
+
+    let keys: [u32; 4] = [3030157619, 2364842415, 3297319647, 1973582566];
+    let image = renderer.render(12850, &keys, 0).unwrap();
+
+    assert_eq!((image.width(), image.height()), (256, 256));
+    assert_eq!(image.pixels().len(), 256 * 256 * 4);
+    assert!(image.pixel(256, 0).is_none());
+
+    let pixels: Vec<_> = image.pixels().chunks_exact(4).collect();
+    assert!(pixels.iter().filter(|pixel| pixel[3] == 0xFF).count() > pixels.len() / 2);
+    assert!(pixels.contains(&&[0xEE, 0xEE, 0xEE, 0xFF][..]));
+}
+
+#[test]
+fn render_item_icon() {
+    let cache = test_util::osrs_cache();
+    let item_loader = ItemLoader::new(&cache).unwrap();
+    let renderer = ItemIconRenderer::new(&cache).unwrap();
+
+    let icon = renderer.render(item_loader.load(1042).unwrap()).unwrap();
+
+    assert_eq!((icon.width(), icon.height()), (36, 32));
+    let pixels: Vec<_> = icon.pixels().chunks_exact(4).collect();
+    assert!(pixels.iter().any(|pixel| pixel[3] == 0));
+    assert!(pixels.contains(&&[0x00, 0x00, 0x01, 0xFF][..]));
+    assert!(pixels.contains(&&[0x30, 0x20, 0x20, 0xFF][..]));
+}
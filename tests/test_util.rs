@@ -1,4 +1,10 @@
-use rscache::Cache;
+use std::fmt::Debug;
+
+use rscache::{
+    archive::ArchiveFileGroup,
+    definition::{DecodeContext, Definition},
+    Cache,
+};
 use sha1_smol::Sha1;
 
 pub fn osrs_cache() -> Cache {
@@ -6,6 +12,7 @@ pub fn osrs_cache() -> Cache {
 }
 
 #[cfg(all(test, feature = "rs3"))]
+#[allow(dead_code)]
 pub fn rs3_cache() -> Cache {
     Cache::new("./data/rs3_cache").unwrap()
 }
@@ -17,3 +24,26 @@ pub fn hash(buffer: &[u8]) -> String {
     m.update(buffer);
     m.digest().to_string()
 }
+
+/// Decodes every definition in config archive `archive_id`, encodes it again
+/// and checks that it decodes into the same definition without growing.
+#[allow(dead_code)]
+pub fn assert_encode_round_trip<D>(archive_id: u32, encode: fn(&D) -> Vec<u8>)
+where
+    D: Definition + PartialEq + Debug,
+{
+    let cache = osrs_cache();
+    let archive = cache.index_metadata(2).unwrap().get(archive_id).unwrap();
+    let buffer = cache.read_decoded(2, archive_id).unwrap();
+    let group = ArchiveFileGroup::try_from_buffer(&buffer, archive.entry_count).unwrap();
+    let ctx = DecodeContext::new(2, archive_id);
+
+    for (file, &id) in group.into_iter().zip(&archive.valid_ids) {
+        let definition = D::from_buffer(id, &file.data, &ctx).unwrap();
+        let encoded = encode(&definition);
+        let decoded = D::from_buffer(id, &encoded, &ctx).unwrap();
+
+        assert!(encoded.len() <= file.data.len(), "definition {}", id);
+        assert_eq!(decoded, definition);
+    }
+}
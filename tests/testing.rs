@@ -0,0 +1,62 @@
+#![cfg(feature = "testing")]
+
+use rscache::{codec::Compression, loader::osrs::ItemLoader, testing::TestCache};
+
+#[test]
+fn tiny_cache() {
+    let test_cache = TestCache::tiny().unwrap();
+    let cache = test_cache.open().unwrap();
+
+    assert!(cache.verify().is_ok());
+    assert_eq!(cache.read_decoded(0, 0).unwrap().as_slice(), b"rscache");
+    assert_eq!(cache.read_decoded(1, 0).unwrap().len(), 1024);
+
+    let metadata = cache.index_metadata(0).unwrap();
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(metadata.get(1).unwrap().entry_count, 3);
+
+    let item_loader = ItemLoader::new(&cache).unwrap();
+    assert_eq!(item_loader.load(0).unwrap().name, "Dwarf remains");
+    assert_eq!(item_loader.load(1).unwrap().name, "Bronze dagger");
+}
+
+#[test]
+fn compression() {
+    for compression in [Compression::None, Compression::Bzip2, Compression::Gzip] {
+        let test_cache = TestCache::builder()
+            .compression(compression)
+            .group(0, 70_000, [vec![1, 2, 3], vec![4; 600]])
+            .build()
+            .unwrap();
+        let cache = test_cache.open().unwrap();
+
+        assert!(cache.verify().is_ok());
+        let archive = cache.index_metadata(0).unwrap().get(70_000).unwrap();
+        assert_eq!(archive.entry_count, 2);
+        assert_eq!(archive.decompressed_len, Some(603 + 9));
+        assert_eq!(cache.read_decoded(0, 70_000).unwrap().len(), 603 + 9);
+    }
+}
+
+#[test]
+fn verify_with_missing_index() {
+    let test_cache = TestCache::builder()
+        .archive(0, 0, vec![1])
+        .archive(2, 10, vec![2])
+        .build()
+        .unwrap();
+    let report = test_cache.open().unwrap().verify();
+
+    assert!(report.is_ok());
+    assert_eq!(report.archives_checked(), 4);
+}
+
+#[test]
+fn removed_on_drop() {
+    let test_cache = TestCache::tiny().unwrap();
+    let path = test_cache.path().to_path_buf();
+    assert!(path.join("main_file_cache.dat2").exists());
+
+    drop(test_cache);
+    assert!(!path.exists());
+}
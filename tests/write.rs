@@ -0,0 +1,226 @@
+use std::{fs, path::PathBuf};
+
+use rscache::{
+    codec::EncodedExt,
+    definition::osrs::{DecodeContext, Definition, ItemDefinition},
+    error::WriteError,
+    metadata::IndexMetadata,
+    sector::SECTOR_SIZE,
+    util::xtea::XteaKeyStore,
+    writer::CacheWriter,
+    Cache, MUSIC_DATA,
+};
+
+fn copy_cache(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(name);
+    fs::create_dir_all(&path).unwrap();
+    for entry in fs::read_dir("./data/osrs_cache").unwrap() {
+        let entry = entry.unwrap();
+        fs::copy(entry.path(), path.join(entry.file_name())).unwrap();
+    }
+
+    path
+}
+
+#[test]
+fn write_archive() {
+    let path = copy_cache("rscache_write_archive");
+    let cache = Cache::new(&path).unwrap();
+    let decoded = cache.read(0, 191).unwrap().decode_with_metadata().unwrap();
+    let checksum = cache.checksum().unwrap().encode().unwrap();
+
+    let buffer = decoded.encode_options().version(1337).encode(&decoded).unwrap();
+    drop(cache);
+
+    let mut writer = CacheWriter::open(&path).unwrap();
+    writer.write(0, 191, &buffer).unwrap();
+
+    let cache = Cache::new(&path).unwrap();
+    assert_eq!(cache.read(0, 191).unwrap().as_slice(), buffer.as_slice());
+    assert!(cache.verify().is_ok());
+
+    let buffer = cache.read(255, 0).unwrap().decode().unwrap();
+    let metadata = IndexMetadata::from_buffer(&buffer).unwrap();
+    assert_eq!(metadata.get(191).unwrap().version, 1337);
+    assert_ne!(cache.checksum().unwrap().encode().unwrap().as_slice(), checksum.as_slice());
+
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn write_music_archive() {
+    let path = copy_cache("rscache_write_music_archive");
+    let cache = Cache::new(&path).unwrap();
+    let archive_id = cache.index_metadata(6).unwrap().iter().next().unwrap().id;
+    let buffer = cache.read(6, archive_id).unwrap();
+    drop(cache);
+
+    // The first sector is never used by the client.
+    fs::write(path.join(MUSIC_DATA), [0; SECTOR_SIZE]).unwrap();
+    let mut writer = CacheWriter::open(&path).unwrap();
+    writer.write(6, archive_id, &buffer).unwrap();
+
+    assert!(fs::metadata(path.join(MUSIC_DATA)).unwrap().len() > SECTOR_SIZE as u64);
+    let cache = Cache::new(&path).unwrap();
+    assert_eq!(
+        cache.read(6, archive_id).unwrap().as_slice(),
+        buffer.as_slice()
+    );
+
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn extract() {
+    let path = std::env::temp_dir().join("rscache_extract");
+    let cache = Cache::new("./data/osrs_cache").unwrap();
+    cache.extract(&path, &[2, 5]).unwrap();
+
+    let extracted = Cache::new(&path).unwrap();
+    assert_eq!(extracted.present_indices(), [2, 5]);
+    assert_eq!(
+        extracted.read(2, 10).unwrap().as_slice(),
+        cache.read(2, 10).unwrap().as_slice()
+    );
+    assert_eq!(
+        extracted.read(255, 5).unwrap().as_slice(),
+        cache.read(255, 5).unwrap().as_slice()
+    );
+    assert!(extracted.read(0, 191).is_err());
+    assert!(cache.extract(&path, &[200]).is_err());
+
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn dump_all() {
+    let path = std::env::temp_dir().join("rscache_dump_all");
+    let cache = Cache::new("./data/osrs_cache").unwrap();
+    cache.extract(path.join("cache"), &[2]).unwrap();
+
+    let extracted = Cache::new(path.join("cache")).unwrap();
+    let mut progress = Vec::new();
+    let skipped = extracted
+        .dump_all(path.join("dump"), |p| progress.push(p))
+        .unwrap();
+    assert!(skipped.is_empty());
+
+    let metadata = cache.index_metadata(2).unwrap();
+    let last = progress.last().unwrap();
+    assert_eq!(progress.len(), metadata.len());
+    assert_eq!((last.dumped, last.total), (metadata.len(), metadata.len()));
+
+    let decoded = cache.read(2, 10).unwrap().decode().unwrap();
+    let dumped = fs::read(path.join("dump/2/10.bin")).unwrap();
+    assert_eq!(dumped, decoded.as_slice());
+
+    let item = fs::read(path.join("dump/2/10/1042.bin")).unwrap();
+    let item_def = ItemDefinition::from_buffer(1042, &item, &DecodeContext::new(2, 10)).unwrap();
+    assert_eq!(item_def.name, "Blue partyhat");
+
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn dump_locations() {
+    let path = std::env::temp_dir().join("rscache_dump_locations");
+    let cache = Cache::new("./data/osrs_cache").unwrap();
+    cache.extract(path.join("cache"), &[5]).unwrap();
+    let location = cache.archive_by_name(5, "l50_50").unwrap().id;
+    let keys = [3030157619, 2364842415, 3297319647, 1973582566];
+
+    let skipped = Cache::new(path.join("cache"))
+        .unwrap()
+        .dump_all(path.join("dump"), |_| ())
+        .unwrap();
+    let skipped = skipped
+        .iter()
+        .find(|archive| archive.archive_id == location)
+        .unwrap();
+    assert!(matches!(
+        skipped.error,
+        rscache::Error::MissingXteaKeys { region_id: 12850 }
+    ));
+    assert!(!path.join(format!("dump/5/{}.bin", location)).exists());
+
+    let mut key_store = XteaKeyStore::new();
+    key_store.insert(12850, keys);
+    let skipped = Cache::builder()
+        .xtea_keys(key_store)
+        .open(path.join("cache"))
+        .unwrap()
+        .dump_all(path.join("dump"), |_| ())
+        .unwrap();
+    assert!(skipped.iter().all(|archive| archive.archive_id != location));
+
+    let dumped = fs::read(path.join(format!("dump/5/{}.bin", location))).unwrap();
+    let decoded = cache.read_decoded_with_keys(5, location, &keys).unwrap();
+    assert_eq!(dumped, decoded.as_slice());
+
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn import() {
+    let path = std::env::temp_dir().join("rscache_import");
+    let cache = Cache::new("./data/osrs_cache").unwrap();
+
+    for (index_id, archive_id) in [(255, 2), (2, 10), (2, 8)] {
+        let dir = path.join("flat").join(index_id.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let buffer = cache.read(index_id, archive_id).unwrap();
+        fs::write(dir.join(format!("{}.dat", archive_id)), buffer.as_slice()).unwrap();
+    }
+    fs::write(path.join("flat/2/10.bin"), b"ignored").unwrap();
+    fs::write(path.join("flat/README"), b"ignored").unwrap();
+
+    let imported = Cache::import(path.join("flat"), path.join("cache")).unwrap();
+    assert_eq!(imported.present_indices(), [2]);
+    for archive_id in [10, 8] {
+        assert_eq!(
+            imported.read(2, archive_id).unwrap().as_slice(),
+            cache.read(2, archive_id).unwrap().as_slice()
+        );
+    }
+    assert!(imported.read(2, 9).unwrap().is_empty());
+    assert!(imported.read(2, 11).is_err());
+
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn import_archive_too_large() {
+    let path = std::env::temp_dir().join("rscache_import_archive_too_large");
+    let dir = path.join("flat/2");
+    fs::create_dir_all(&dir).unwrap();
+    // One byte more than the 24 bit length of an index entry can hold.
+    fs::write(dir.join("10.dat"), vec![0; 0x100_0000]).unwrap();
+
+    assert!(matches!(
+        Cache::import(path.join("flat"), path.join("cache")),
+        Err(rscache::Error::Write(WriteError::ArchiveTooLarge {
+            len: 0x100_0000
+        }))
+    ));
+
+    fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn overlay_without_version() {
+    use rscache::codec::EncodeOptions;
+
+    let mut cache = Cache::new("./data/osrs_cache").unwrap();
+    let decoded = cache.read(0, 191).unwrap().decode_with_metadata().unwrap();
+    let buffer = EncodeOptions::new(decoded.compression())
+        .encode(&decoded)
+        .unwrap();
+
+    assert!(matches!(
+        cache.overlay(0, 191, buffer.as_slice()),
+        Err(rscache::Error::Write(WriteError::MissingVersion {
+            index_id: 0,
+            archive_id: 191
+        }))
+    ));
+}